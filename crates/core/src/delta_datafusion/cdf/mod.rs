@@ -4,7 +4,7 @@ use arrow_schema::{DataType, Field, TimeUnit};
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 
-pub(crate) use scan::*;
+pub use scan::*;
 pub(crate) use scan_utils::*;
 
 use crate::kernel::{Add, AddCDCFile};
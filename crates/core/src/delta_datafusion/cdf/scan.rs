@@ -1,10 +1,37 @@
 use std::any::Any;
+use std::collections::HashSet;
 use std::fmt::Formatter;
 use std::sync::Arc;
 
-use arrow_schema::SchemaRef;
+use arrow_cast::parse::string_to_timestamp_nanos;
+use arrow_schema::{DataType, SchemaRef};
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use datafusion::catalog::CatalogProviderList;
+use datafusion::datasource::function::TableFunctionImpl;
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::execution::context::{SessionContext, SessionState};
 use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::physical_plan::empty::EmptyExec;
+use datafusion::physical_plan::projection::ProjectionExec;
 use datafusion::physical_plan::{DisplayAs, DisplayFormatType, ExecutionPlan};
+use datafusion_common::{
+    DataFusionError, OwnedTableReference, Result as DataFusionResult, ScalarValue, TableReference,
+};
+use datafusion_expr::expr::InList;
+use datafusion_expr::{Between, BinaryExpr, Expr, Operator, TableProviderFilterPushDown};
+use datafusion_physical_expr::expressions::Column;
+use datafusion_physical_expr::PhysicalExpr;
+use futures::FutureExt;
+
+use crate::delta_datafusion::cdf::{CHANGE_TYPE_COL, COMMIT_VERSION_COL};
+use crate::delta_datafusion::DeltaTableProvider;
+use crate::errors::DeltaResult;
+use crate::operations::load_cdf::CdfLoadBuilder;
+use crate::DeltaTable;
+
+/// Name the [`TableChangesFunction`] is registered under in a [`DeltaSessionContext`](crate::delta_datafusion::DeltaSessionContext)
+pub const TABLE_CHANGES_FUNCTION: &str = "table_changes";
 
 /// Physical execution of a scan
 #[derive(Debug, Clone)]
@@ -39,14 +66,20 @@ impl ExecutionPlan for DeltaCdfScan {
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
-        vec![]
+        vec![self.plan.clone()]
     }
 
     fn with_new_children(
         self: Arc<Self>,
-        _children: Vec<Arc<dyn ExecutionPlan>>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> datafusion_common::Result<Arc<dyn ExecutionPlan>> {
-        self.plan.clone().with_new_children(_children)
+        if children.len() != 1 {
+            return Err(DataFusionError::Plan(format!(
+                "DeltaCdfScan wrong number of children {}",
+                children.len()
+            )));
+        }
+        Ok(Arc::new(Self::new(children[0].clone())))
     }
 
     fn execute(
@@ -57,3 +90,401 @@ impl ExecutionPlan for DeltaCdfScan {
         self.plan.execute(partition, context)
     }
 }
+
+/// A table provider for the change data feed of a delta table
+///
+/// Filters on `_commit_version` narrow the range of commits that are read, and filters on
+/// `_change_type` which exclude `insert` changes skip the data files of commits without change
+/// data files.
+pub struct DeltaCdfTableProvider {
+    cdf_builder: CdfLoadBuilder,
+    schema: SchemaRef,
+}
+
+impl DeltaCdfTableProvider {
+    /// Build a DeltaCdfTableProvider
+    pub fn try_new(cdf_builder: CdfLoadBuilder) -> DeltaResult<Self> {
+        Ok(DeltaCdfTableProvider {
+            schema: cdf_builder.cdf_schema()?,
+            cdf_builder,
+        })
+    }
+}
+
+#[async_trait]
+impl TableProvider for DeltaCdfTableProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        session: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let bounds = CdfFilterBounds::from_filters(filters);
+
+        let start = bounds
+            .min_version
+            .map_or(self.cdf_builder.starting_version(), |v| {
+                v.max(self.cdf_builder.starting_version())
+            });
+        let end = self.cdf_builder.resolve_ending_version().await?;
+        let end = bounds.max_version.map_or(end, |v| v.min(end));
+
+        let projected_schema = match projection {
+            Some(p) => Arc::new(self.schema.project(p)?),
+            None => self.schema.clone(),
+        };
+        if start > end || bounds.change_types.as_ref().is_some_and(|t| t.is_empty()) {
+            return Ok(Arc::new(EmptyExec::new(projected_schema)));
+        }
+
+        let read_add_files = bounds
+            .change_types
+            .as_ref()
+            .map_or(true, |types| types.contains("insert"));
+        let scan: Arc<dyn ExecutionPlan> = Arc::new(
+            self.cdf_builder
+                .clone()
+                .with_session_ctx(SessionContext::new_with_state(session.clone()))
+                .with_starting_version(start)
+                .with_ending_version(end)
+                .with_add_files(read_add_files)
+                .build()
+                .await?,
+        );
+
+        match projection {
+            Some(projection) => {
+                let scan_schema = scan.schema();
+                let exprs = projection
+                    .iter()
+                    .map(|i| {
+                        let name = self.schema.field(*i).name();
+                        let idx = scan_schema.index_of(name)?;
+                        Ok((
+                            Arc::new(Column::new(name, idx)) as Arc<dyn PhysicalExpr>,
+                            name.to_owned(),
+                        ))
+                    })
+                    .collect::<DataFusionResult<Vec<_>>>()?;
+                Ok(Arc::new(ProjectionExec::try_new(exprs, scan)?))
+            }
+            None => Ok(scan),
+        }
+    }
+
+    fn supports_filter_pushdown(
+        &self,
+        filter: &Expr,
+    ) -> DataFusionResult<TableProviderFilterPushDown> {
+        if CdfFilterBounds::from_filters(std::slice::from_ref(filter)).is_empty() {
+            Ok(TableProviderFilterPushDown::Unsupported)
+        } else {
+            Ok(TableProviderFilterPushDown::Inexact)
+        }
+    }
+}
+
+/// Bounds on the change data to read, derived from the filters of a query
+#[derive(Debug, Default)]
+struct CdfFilterBounds {
+    min_version: Option<i64>,
+    max_version: Option<i64>,
+    change_types: Option<HashSet<String>>,
+}
+
+impl CdfFilterBounds {
+    fn from_filters(filters: &[Expr]) -> Self {
+        let mut bounds = Self::default();
+        for filter in filters {
+            bounds.visit(filter);
+        }
+        bounds
+    }
+
+    fn is_empty(&self) -> bool {
+        self.min_version.is_none() && self.max_version.is_none() && self.change_types.is_none()
+    }
+
+    fn visit(&mut self, expr: &Expr) {
+        match expr {
+            Expr::BinaryExpr(BinaryExpr { left, op, right }) => match op {
+                Operator::And => {
+                    self.visit(left);
+                    self.visit(right);
+                }
+                _ => {
+                    if let Some((column, value)) = column_and_literal(left, right) {
+                        self.visit_comparison(column, *op, value);
+                    } else if let Some((column, value)) = column_and_literal(right, left) {
+                        if let Some(op) = op.swap() {
+                            self.visit_comparison(column, op, value);
+                        }
+                    }
+                }
+            },
+            Expr::Between(Between {
+                expr,
+                negated: false,
+                low,
+                high,
+            }) => {
+                if let (Some((column, low)), Some((_, high))) = (
+                    column_and_literal(expr, low),
+                    column_and_literal(expr, high),
+                ) {
+                    self.visit_comparison(column, Operator::GtEq, low);
+                    self.visit_comparison(column, Operator::LtEq, high);
+                }
+            }
+            Expr::InList(InList {
+                expr,
+                list,
+                negated: false,
+            }) if is_column(expr, CHANGE_TYPE_COL) => {
+                let types: Option<HashSet<String>> = list
+                    .iter()
+                    .map(|e| match e {
+                        Expr::Literal(ScalarValue::Utf8(Some(s))) => Some(s.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                if let Some(types) = types {
+                    self.restrict_change_types(types);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_comparison(&mut self, column: &str, op: Operator, value: &ScalarValue) {
+        if column == COMMIT_VERSION_COL {
+            let Some(version) = scalar_to_i64(value) else {
+                return;
+            };
+            match op {
+                Operator::Eq => {
+                    self.restrict_min_version(version);
+                    self.restrict_max_version(version);
+                }
+                Operator::Gt => match version.checked_add(1) {
+                    Some(version) => self.restrict_min_version(version),
+                    None => self.exclude_all_versions(),
+                },
+                Operator::GtEq => self.restrict_min_version(version),
+                Operator::Lt => match version.checked_sub(1) {
+                    Some(version) => self.restrict_max_version(version),
+                    None => self.exclude_all_versions(),
+                },
+                Operator::LtEq => self.restrict_max_version(version),
+                _ => {}
+            }
+        } else if column == CHANGE_TYPE_COL && op == Operator::Eq {
+            if let ScalarValue::Utf8(Some(change_type)) = value {
+                self.restrict_change_types(HashSet::from([change_type.clone()]));
+            }
+        }
+    }
+
+    fn restrict_min_version(&mut self, version: i64) {
+        self.min_version = Some(self.min_version.map_or(version, |v| v.max(version)));
+    }
+
+    fn restrict_max_version(&mut self, version: i64) {
+        self.max_version = Some(self.max_version.map_or(version, |v| v.min(version)));
+    }
+
+    /// Restrict the bounds to an empty range of versions, for comparisons no version satisfies
+    fn exclude_all_versions(&mut self) {
+        self.restrict_min_version(i64::MAX);
+        self.restrict_max_version(i64::MIN);
+    }
+
+    fn restrict_change_types(&mut self, types: HashSet<String>) {
+        self.change_types = Some(match self.change_types.take() {
+            Some(current) => current.intersection(&types).cloned().collect(),
+            None => types,
+        });
+    }
+}
+
+fn is_column(expr: &Expr, name: &str) -> bool {
+    matches!(expr, Expr::Column(c) if c.name == name)
+}
+
+fn column_and_literal<'a>(
+    column: &'a Expr,
+    literal: &'a Expr,
+) -> Option<(&'a str, &'a ScalarValue)> {
+    match (column, literal) {
+        (Expr::Column(c), Expr::Literal(value)) => Some((c.name.as_str(), value)),
+        _ => None,
+    }
+}
+
+fn scalar_to_i64(value: &ScalarValue) -> Option<i64> {
+    match value.cast_to(&DataType::Int64).ok()? {
+        ScalarValue::Int64(v) => v,
+        _ => None,
+    }
+}
+
+/// A table function reading the change data feed of a delta table registered in a session
+///
+/// The function takes the name of the table followed by the start and an optional end of the
+/// range of changes to read, e.g. `table_changes('my_table', 3, 10)`. Integers denote table
+/// versions and strings or timestamps denote commit timestamps, both bounds are inclusive.
+///
+/// Table functions are called while planning, which cannot wait on I/O, so only tables
+/// returned by their schema without waiting are supported. These include all tables
+/// registered in a session, but not those of schemas loading their tables asynchronously,
+/// e.g. from a remote catalog.
+pub struct TableChangesFunction {
+    catalog_list: Arc<dyn CatalogProviderList>,
+    default_catalog: String,
+    default_schema: String,
+}
+
+impl TableChangesFunction {
+    /// Create a function resolving tables against the catalogs of the given session
+    pub fn new(state: &SessionState) -> Self {
+        let options = &state.config_options().catalog;
+        Self {
+            catalog_list: state.catalog_list(),
+            default_catalog: options.default_catalog.clone(),
+            default_schema: options.default_schema.clone(),
+        }
+    }
+
+    fn resolve_table(&self, table_ref: OwnedTableReference) -> DataFusionResult<CdfLoadBuilder> {
+        let table = table_ref.resolve(&self.default_catalog, &self.default_schema);
+        let schema = self
+            .catalog_list
+            .catalog(&table.catalog)
+            .and_then(|catalog| catalog.schema(&table.schema))
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!("{TABLE_CHANGES_FUNCTION}: table {table} not found"))
+            })?;
+        // Table functions are resolved while planning, which is synchronous, so the table is
+        // only looked up if the schema returns it without waiting, e.g. for tables registered
+        // in the in-memory schemas of a session.
+        let provider = schema
+            .table(&table.table)
+            .now_or_never()
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!(
+                    "{TABLE_CHANGES_FUNCTION}: table {table} is provided by a schema loading tables asynchronously, register the table in the session instead"
+                ))
+            })??
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!("{TABLE_CHANGES_FUNCTION}: table {table} not found"))
+            })?;
+
+        let provider = provider.as_any();
+        if let Some(table) = provider.downcast_ref::<DeltaTable>() {
+            Ok(CdfLoadBuilder::new(
+                table.log_store(),
+                table.snapshot()?.clone(),
+            ))
+        } else if let Some(table) = provider.downcast_ref::<DeltaTableProvider>() {
+            Ok(CdfLoadBuilder::new(
+                table.log_store.clone(),
+                table.snapshot.clone(),
+            ))
+        } else {
+            Err(DataFusionError::Plan(format!(
+                "{TABLE_CHANGES_FUNCTION}: table {table} is not a delta table"
+            )))
+        }
+    }
+}
+
+impl TableFunctionImpl for TableChangesFunction {
+    fn call(&self, args: &[Expr]) -> DataFusionResult<Arc<dyn TableProvider>> {
+        if args.len() < 2 || args.len() > 3 {
+            return Err(DataFusionError::Plan(format!(
+                "{TABLE_CHANGES_FUNCTION} expects a table name, a start and an optional end, got {} arguments",
+                args.len()
+            )));
+        }
+
+        let table_ref = match &args[0] {
+            Expr::Literal(ScalarValue::Utf8(Some(name))) => {
+                TableReference::from(name.as_str()).to_owned_reference()
+            }
+            Expr::Column(column) => OwnedTableReference::from(column.flat_name()),
+            other => {
+                return Err(DataFusionError::Plan(format!(
+                    "{TABLE_CHANGES_FUNCTION}: expected a table name, got {other}"
+                )))
+            }
+        };
+        let mut cdf_builder = self.resolve_table(table_ref)?;
+
+        cdf_builder = match ChangeBound::try_from(&args[1])? {
+            ChangeBound::Version(version) => cdf_builder.with_starting_version(version),
+            ChangeBound::Timestamp(timestamp) => cdf_builder.with_starting_timestamp(timestamp),
+        };
+        if let Some(end) = args.get(2) {
+            cdf_builder = match ChangeBound::try_from(end)? {
+                ChangeBound::Version(version) => cdf_builder.with_ending_version(version),
+                ChangeBound::Timestamp(timestamp) => cdf_builder.with_ending_timestamp(timestamp),
+            };
+        }
+
+        Ok(Arc::new(DeltaCdfTableProvider::try_new(cdf_builder)?))
+    }
+}
+
+/// A start or end of the range of changes passed to [`TableChangesFunction`]
+enum ChangeBound {
+    Version(i64),
+    Timestamp(DateTime<Utc>),
+}
+
+impl TryFrom<&Expr> for ChangeBound {
+    type Error = DataFusionError;
+
+    fn try_from(expr: &Expr) -> DataFusionResult<Self> {
+        let invalid = || {
+            DataFusionError::Plan(format!(
+                "{TABLE_CHANGES_FUNCTION}: expected a version or timestamp, got {expr}"
+            ))
+        };
+        match expr {
+            Expr::Cast(cast) => ChangeBound::try_from(cast.expr.as_ref()),
+            Expr::TryCast(cast) => ChangeBound::try_from(cast.expr.as_ref()),
+            Expr::Literal(ScalarValue::Utf8(Some(s))) => {
+                let nanos = string_to_timestamp_nanos(s)?;
+                Ok(ChangeBound::Timestamp(Utc.timestamp_nanos(nanos)))
+            }
+            Expr::Literal(value) if value.data_type().is_integer() => scalar_to_i64(value)
+                .map(ChangeBound::Version)
+                .ok_or_else(invalid),
+            Expr::Literal(value) if matches!(value.data_type(), DataType::Timestamp(_, _)) => {
+                match value.cast_to(&DataType::Timestamp(
+                    arrow_schema::TimeUnit::Nanosecond,
+                    None,
+                ))? {
+                    ScalarValue::TimestampNanosecond(Some(nanos), _) => {
+                        Ok(ChangeBound::Timestamp(Utc.timestamp_nanos(nanos)))
+                    }
+                    _ => Err(invalid()),
+                }
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
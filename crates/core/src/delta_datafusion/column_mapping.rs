@@ -0,0 +1,225 @@
+//! Support for scanning tables with column mapping enabled.
+//!
+//! With column mapping enabled, columns are stored in the data files using their physical names
+//! rather than the logical names of the table schema. In `id` mode, columns are additionally
+//! matched by the field ids stored in the parquet schema of the data files.
+//!
+//! See [`crate::kernel::arrow::column_mapping`] for the conversion of schemas and data.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use arrow_schema::FieldRef;
+use bytes::Bytes;
+use datafusion::datasource::physical_plan::parquet::DefaultParquetFileReaderFactory;
+use datafusion::datasource::physical_plan::{FileMeta, ParquetFileReaderFactory};
+use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion_common::tree_node::{Transformed, TreeNode};
+use datafusion_common::Result as DataFusionResult;
+use datafusion_physical_expr::expressions::{CastExpr, Column};
+use datafusion_physical_expr::PhysicalExpr;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use object_store::ObjectStore;
+use parquet::arrow::async_reader::AsyncFileReader;
+use parquet::arrow::ARROW_SCHEMA_META_KEY;
+use parquet::file::metadata::{FileMetaData, ParquetMetaData, RowGroupMetaData};
+use parquet::schema::types::{from_thrift, to_thrift, SchemaDescriptor};
+
+use crate::errors::DeltaResult;
+use crate::kernel::{DataType, StructField, StructType};
+
+/// Rewrite the columns referenced in a predicate to the physical column names.
+pub(crate) fn physical_predicate(
+    predicate: Arc<dyn PhysicalExpr>,
+    physical_names: &HashMap<String, String>,
+) -> DataFusionResult<Arc<dyn PhysicalExpr>> {
+    Ok(predicate
+        .transform(&|expr| {
+            if let Some(column) = expr.as_any().downcast_ref::<Column>() {
+                if let Some(name) = physical_names.get(column.name()) {
+                    return Ok(Transformed::yes(
+                        Arc::new(Column::new(name, column.index())) as Arc<dyn PhysicalExpr>,
+                    ));
+                }
+            }
+            Ok(Transformed::no(expr))
+        })?
+        .data)
+}
+
+/// Project the output of a scan using physical column names back to the logical schema.
+///
+/// `logical_fields` maps the physical names of the columns read from the data files to the
+/// corresponding field of the logical schema.
+pub(crate) fn logical_projection(
+    input: Arc<dyn ExecutionPlan>,
+    logical_fields: &HashMap<String, FieldRef>,
+) -> DeltaResult<Arc<dyn ExecutionPlan>> {
+    let schema = input.schema();
+    let exprs = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| {
+            let column: Arc<dyn PhysicalExpr> = Arc::new(Column::new(field.name(), idx));
+            match logical_fields.get(field.name()) {
+                Some(logical) if logical.data_type() != field.data_type() => (
+                    Arc::new(CastExpr::new(column, logical.data_type().clone(), None))
+                        as Arc<dyn PhysicalExpr>,
+                    logical.name().clone(),
+                ),
+                Some(logical) => (column, logical.name().clone()),
+                None => (column, field.name().clone()),
+            }
+        })
+        .collect::<Vec<_>>();
+    Ok(Arc::new(ProjectionExec::try_new(exprs, input)?))
+}
+
+/// Reader factory that names the columns of the data files by the physical column name
+/// associated with their field id.
+///
+/// Used for tables with column mapping mode `id`, where columns need to be matched by
+/// the field ids stored in the parquet schema, rather than by name.
+#[derive(Debug)]
+pub(crate) struct FieldIdReaderFactory {
+    inner: DefaultParquetFileReaderFactory,
+    physical_names: Arc<HashMap<i32, String>>,
+}
+
+impl FieldIdReaderFactory {
+    pub(crate) fn try_new(
+        store: Arc<dyn ObjectStore>,
+        table_schema: &StructType,
+    ) -> DeltaResult<Self> {
+        let mut physical_names = HashMap::new();
+        collect_physical_names(table_schema.fields(), &mut physical_names)?;
+        Ok(Self {
+            inner: DefaultParquetFileReaderFactory::new(store),
+            physical_names: Arc::new(physical_names),
+        })
+    }
+}
+
+fn collect_physical_names<'a>(
+    fields: impl IntoIterator<Item = &'a StructField>,
+    physical_names: &mut HashMap<i32, String>,
+) -> DeltaResult<()> {
+    for field in fields {
+        if let Some(id) = field.column_mapping_id() {
+            physical_names.insert(id, field.physical_name()?.to_string());
+        }
+        let mut data_type = field.data_type();
+        loop {
+            match data_type {
+                DataType::Struct(inner) => {
+                    collect_physical_names(inner.fields(), physical_names)?;
+                    break;
+                }
+                DataType::Array(inner) => data_type = inner.element_type(),
+                DataType::Map(inner) => {
+                    if let DataType::Struct(key) = inner.key_type() {
+                        collect_physical_names(key.fields(), physical_names)?;
+                    }
+                    data_type = inner.value_type();
+                }
+                DataType::Primitive(_) => break,
+            }
+        }
+    }
+    Ok(())
+}
+
+impl ParquetFileReaderFactory for FieldIdReaderFactory {
+    fn create_reader(
+        &self,
+        partition_index: usize,
+        file_meta: FileMeta,
+        metadata_size_hint: Option<usize>,
+        metrics: &ExecutionPlanMetricsSet,
+    ) -> DataFusionResult<Box<dyn AsyncFileReader + Send>> {
+        let inner =
+            self.inner
+                .create_reader(partition_index, file_meta, metadata_size_hint, metrics)?;
+        Ok(Box::new(FieldIdReader {
+            inner,
+            physical_names: self.physical_names.clone(),
+        }))
+    }
+}
+
+struct FieldIdReader {
+    inner: Box<dyn AsyncFileReader + Send>,
+    physical_names: Arc<HashMap<i32, String>>,
+}
+
+impl AsyncFileReader for FieldIdReader {
+    fn get_bytes(&mut self, range: Range<usize>) -> BoxFuture<'_, parquet::errors::Result<Bytes>> {
+        self.inner.get_bytes(range)
+    }
+
+    fn get_byte_ranges(
+        &mut self,
+        ranges: Vec<Range<usize>>,
+    ) -> BoxFuture<'_, parquet::errors::Result<Vec<Bytes>>> {
+        self.inner.get_byte_ranges(ranges)
+    }
+
+    fn get_metadata(&mut self) -> BoxFuture<'_, parquet::errors::Result<Arc<ParquetMetaData>>> {
+        let physical_names = self.physical_names.clone();
+        let metadata = self.inner.get_metadata();
+        async move { rename_by_field_id(metadata.await?, &physical_names) }.boxed()
+    }
+}
+
+/// Rename all fields in the parquet schema which have a known field id.
+fn rename_by_field_id(
+    metadata: Arc<ParquetMetaData>,
+    physical_names: &HashMap<i32, String>,
+) -> parquet::errors::Result<Arc<ParquetMetaData>> {
+    let file_metadata = metadata.file_metadata();
+    let mut elements = to_thrift(file_metadata.schema())?;
+    let mut renamed = false;
+    // the first element is the root of the schema
+    for element in elements.iter_mut().skip(1) {
+        let name = element.field_id.and_then(|id| physical_names.get(&id));
+        if let Some(name) = name.filter(|name| **name != element.name) {
+            element.name = name.clone();
+            renamed = true;
+        }
+    }
+    if !renamed {
+        return Ok(metadata);
+    }
+
+    let schema_descr = Arc::new(SchemaDescriptor::new(from_thrift(&elements)?));
+    let row_groups = metadata
+        .row_groups()
+        .iter()
+        .map(|rg| RowGroupMetaData::from_thrift(schema_descr.clone(), rg.to_thrift()))
+        .collect::<parquet::errors::Result<Vec<_>>>()?;
+    let file_metadata = FileMetaData::new(
+        file_metadata.version(),
+        file_metadata.num_rows(),
+        file_metadata.created_by().map(ToString::to_string),
+        // the embedded arrow schema still refers to the original column names
+        file_metadata.key_value_metadata().map(|kv| {
+            kv.iter()
+                .filter(|kv| kv.key != ARROW_SCHEMA_META_KEY)
+                .cloned()
+                .collect()
+        }),
+        schema_descr,
+        file_metadata.column_orders().cloned(),
+    );
+    Ok(Arc::new(ParquetMetaData::new_with_page_index(
+        file_metadata,
+        row_groups,
+        metadata.column_index().cloned(),
+        metadata.offset_index().cloned(),
+    )))
+}
@@ -0,0 +1,464 @@
+//! Physical operator applying deletion vectors to scanned data files
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use arrow::compute::filter_record_batch;
+use arrow_array::cast::AsArray;
+use arrow_array::types::UInt64Type;
+use arrow_array::{Array, ArrayAccessor, ArrayRef, BooleanArray, RecordBatch, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::EquivalenceProperties;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionMode, ExecutionPlan, PlanProperties, RecordBatchStream,
+    SendableRecordBatchStream, Statistics,
+};
+use datafusion_common::{DFSchemaRef, DataFusionError, Result as DataFusionResult};
+use datafusion_expr::{Expr, LogicalPlan, UserDefinedLogicalNodeCore};
+use futures::{Stream, StreamExt};
+use roaring::RoaringTreemap;
+
+use crate::delta_datafusion::get_path_column;
+
+/// Removes rows marked as deleted by a deletion vector from a parquet scan.
+///
+/// The wrapped scan must produce exactly one partition per data file and must read
+/// every row of the file in order, since row positions are tracked to match the
+/// indexes stored in the deletion vector. For that reason the operator does not benefit
+/// from repartitioning its input, which prevents the optimizer from splitting the scan
+/// into byte ranges.
+///
+/// Optionally the position of each row within its data file is exposed as an additional
+/// column, which allows operations to create deletion vectors for the rows they modify.
+pub(crate) struct DeletionVectorExec {
+    scan: Arc<dyn ExecutionPlan>,
+    /// Deletion vectors, one per partition of the wrapped scan
+    deletion_vectors: Vec<Arc<RoaringTreemap>>,
+    /// Index and name of the row index column inserted into the scanned batches
+    row_index_column: Option<(usize, String)>,
+    schema: SchemaRef,
+    properties: PlanProperties,
+}
+
+impl DeletionVectorExec {
+    pub fn try_new(
+        scan: Arc<dyn ExecutionPlan>,
+        deletion_vectors: Vec<Arc<RoaringTreemap>>,
+        row_index_column: Option<(usize, &str)>,
+    ) -> DataFusionResult<Self> {
+        let partitions = scan.properties().output_partitioning().partition_count();
+        if partitions != deletion_vectors.len() {
+            return Err(DataFusionError::Internal(format!(
+                "DeletionVectorExec expects one deletion vector per partition, got {} for {} partitions",
+                deletion_vectors.len(),
+                partitions
+            )));
+        }
+
+        let (schema, properties) = match row_index_column {
+            Some((idx, name)) => {
+                let mut fields = scan.schema().fields().to_vec();
+                fields.insert(idx, Arc::new(Field::new(name, DataType::UInt64, false)));
+                let schema = Arc::new(Schema::new(fields));
+                let properties = PlanProperties::new(
+                    EquivalenceProperties::new(schema.clone()),
+                    scan.properties().output_partitioning().clone(),
+                    ExecutionMode::Bounded,
+                );
+                (schema, properties)
+            }
+            None => (scan.schema(), scan.properties().clone()),
+        };
+
+        Ok(Self {
+            scan,
+            deletion_vectors,
+            row_index_column: row_index_column.map(|(idx, name)| (idx, name.to_string())),
+            schema,
+            properties,
+        })
+    }
+}
+
+impl fmt::Debug for DeletionVectorExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeletionVectorExec")
+            .field("scan", &self.scan)
+            .field("files", &self.deletion_vectors.len())
+            .finish()
+    }
+}
+
+impl DisplayAs for DeletionVectorExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "DeletionVectorExec files={}",
+            self.deletion_vectors.len()
+        )
+    }
+}
+
+impl ExecutionPlan for DeletionVectorExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.scan.clone()]
+    }
+
+    fn benefits_from_input_partitioning(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![true]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        if children.len() != 1 {
+            return Err(DataFusionError::Plan(
+                "DeletionVectorExec wrong number of children".to_string(),
+            ));
+        }
+        // deletion vectors are matched to the partitions of the scan by index, which is
+        // checked when creating the operator
+        Ok(Arc::new(Self::try_new(
+            children[0].clone(),
+            self.deletion_vectors.clone(),
+            self.row_index_column
+                .as_ref()
+                .map(|(idx, name)| (*idx, name.as_str())),
+        )?))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<SendableRecordBatchStream> {
+        let deletion_vector = self
+            .deletion_vectors
+            .get(partition)
+            .cloned()
+            .ok_or_else(|| DataFusionError::Internal(format!("Invalid partition {partition}")))?;
+        Ok(Box::pin(DeletionVectorStream {
+            schema: self.schema(),
+            input: self.scan.execute(partition, context)?,
+            deletion_vector,
+            row_index: self.row_index_column.as_ref().map(|(idx, _)| *idx),
+            row_offset: 0,
+        }))
+    }
+
+    fn statistics(&self) -> DataFusionResult<Statistics> {
+        Ok(Statistics::new_unknown(&self.schema()))
+    }
+}
+
+struct DeletionVectorStream {
+    schema: SchemaRef,
+    input: SendableRecordBatchStream,
+    deletion_vector: Arc<RoaringTreemap>,
+    row_index: Option<usize>,
+    /// Position of the first row of the next batch within the data file
+    row_offset: u64,
+}
+
+impl DeletionVectorStream {
+    fn apply(&mut self, batch: RecordBatch) -> DataFusionResult<RecordBatch> {
+        let start = self.row_offset;
+        let end = start + batch.num_rows() as u64;
+        self.row_offset = end;
+
+        let batch = match self.row_index {
+            Some(idx) => {
+                let mut columns = batch.columns().to_vec();
+                columns.insert(
+                    idx,
+                    Arc::new(UInt64Array::from_iter_values(start..end)) as ArrayRef,
+                );
+                RecordBatch::try_new(self.schema.clone(), columns)?
+            }
+            None => batch,
+        };
+
+        let deleted_in_batch = self.deletion_vector.rank(end.saturating_sub(1))
+            - start
+                .checked_sub(1)
+                .map(|idx| self.deletion_vector.rank(idx))
+                .unwrap_or_default();
+        if batch.num_rows() == 0 || deleted_in_batch == 0 {
+            return Ok(batch);
+        }
+
+        let mask = (start..end)
+            .map(|idx| Some(!self.deletion_vector.contains(idx)))
+            .collect::<BooleanArray>();
+        Ok(filter_record_batch(&batch, &mask)?)
+    }
+}
+
+impl Stream for DeletionVectorStream {
+    type Item = DataFusionResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.input.poll_next_unpin(cx).map(|x| match x {
+            Some(Ok(batch)) => Some(self.apply(batch)),
+            other => other,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl RecordBatchStream for DeletionVectorStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Positions of rows within their data files, keyed by the path of the data file.
+pub(crate) type DeletedRows = Arc<Mutex<HashMap<String, RoaringTreemap>>>;
+
+/// Records the position of rows within their data files, so deletion vectors can be
+/// written for them once the plan has been executed.
+///
+/// The input must contain the file column and row index column of a [`DeltaScan`](super::DeltaScan).
+/// If a predicate column is given, only rows where it is true are recorded. Rows without
+/// a file, e.g. rows inserted by a merge, are never recorded. Batches are passed through unchanged.
+#[derive(Debug)]
+pub(crate) struct DeletedRowsCollectorExec {
+    input: Arc<dyn ExecutionPlan>,
+    file_column: Arc<String>,
+    row_index_column: Arc<String>,
+    predicate_column: Option<Arc<String>>,
+    deleted_rows: DeletedRows,
+}
+
+impl DeletedRowsCollectorExec {
+    pub fn new(
+        input: Arc<dyn ExecutionPlan>,
+        file_column: Arc<String>,
+        row_index_column: Arc<String>,
+        predicate_column: Option<Arc<String>>,
+    ) -> Self {
+        Self {
+            input,
+            file_column,
+            row_index_column,
+            predicate_column,
+            deleted_rows: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Rows recorded while executing the plan
+    pub fn deleted_rows(&self) -> DeletedRows {
+        self.deleted_rows.clone()
+    }
+}
+
+impl DisplayAs for DeletedRowsCollectorExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DeletedRowsCollector")
+    }
+}
+
+impl ExecutionPlan for DeletedRowsCollectorExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        self.input.properties()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        if children.len() != 1 {
+            return Err(DataFusionError::Plan(
+                "DeletedRowsCollectorExec wrong number of children".to_string(),
+            ));
+        }
+        Ok(Arc::new(Self {
+            input: children[0].clone(),
+            file_column: self.file_column.clone(),
+            row_index_column: self.row_index_column.clone(),
+            predicate_column: self.predicate_column.clone(),
+            deleted_rows: self.deleted_rows.clone(),
+        }))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<SendableRecordBatchStream> {
+        Ok(Box::pin(DeletedRowsCollectorStream {
+            schema: self.schema(),
+            input: self.input.execute(partition, context)?,
+            file_column: self.file_column.clone(),
+            row_index_column: self.row_index_column.clone(),
+            predicate_column: self.predicate_column.clone(),
+            deleted_rows: self.deleted_rows.clone(),
+        }))
+    }
+}
+
+struct DeletedRowsCollectorStream {
+    schema: SchemaRef,
+    input: SendableRecordBatchStream,
+    file_column: Arc<String>,
+    row_index_column: Arc<String>,
+    predicate_column: Option<Arc<String>>,
+    deleted_rows: DeletedRows,
+}
+
+impl DeletedRowsCollectorStream {
+    fn record(&self, batch: &RecordBatch) -> DataFusionResult<()> {
+        let missing = |name: &str| DataFusionError::Internal(format!("Missing column {name}"));
+        let files = get_path_column(batch, &self.file_column)?;
+        let row_indexes = batch
+            .column_by_name(&self.row_index_column)
+            .ok_or_else(|| missing(&self.row_index_column))?
+            .as_primitive_opt::<UInt64Type>()
+            .ok_or_else(|| {
+                DataFusionError::Internal("Row index column must be of type UInt64".to_string())
+            })?;
+        let predicate = match &self.predicate_column {
+            Some(name) => Some(
+                batch
+                    .column_by_name(name)
+                    .ok_or_else(|| missing(name))?
+                    .as_boolean_opt()
+                    .ok_or_else(|| {
+                        DataFusionError::Internal(format!("Column {name} must be a boolean"))
+                    })?,
+            ),
+            None => None,
+        };
+
+        let mut deleted_rows = self.deleted_rows.lock().unwrap();
+        for idx in 0..batch.num_rows() {
+            if files.is_null(idx) || row_indexes.is_null(idx) {
+                continue;
+            }
+            if let Some(predicate) = predicate {
+                if !predicate.is_valid(idx) || !predicate.value(idx) {
+                    continue;
+                }
+            }
+            deleted_rows
+                .entry(files.value(idx).to_string())
+                .or_default()
+                .insert(row_indexes.value(idx));
+        }
+        Ok(())
+    }
+}
+
+impl Stream for DeletedRowsCollectorStream {
+    type Item = DataFusionResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.input.poll_next_unpin(cx).map(|x| match x {
+            Some(Ok(batch)) => Some(self.record(&batch).map(|_| batch)),
+            other => other,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl RecordBatchStream for DeletedRowsCollectorStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Logical node for the [`DeletedRowsCollectorExec`]
+#[derive(Debug, Hash, Eq, PartialEq)]
+pub(crate) struct DeletedRowsCollector {
+    pub input: LogicalPlan,
+    pub file_column: Arc<String>,
+    pub row_index_column: Arc<String>,
+    pub predicate_column: Option<Arc<String>>,
+}
+
+impl UserDefinedLogicalNodeCore for DeletedRowsCollector {
+    fn name(&self) -> &str {
+        "DeletedRowsCollector"
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    fn schema(&self) -> &DFSchemaRef {
+        self.input.schema()
+    }
+
+    fn expressions(&self) -> Vec<Expr> {
+        vec![]
+    }
+
+    fn fmt_for_explain(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DeletedRowsCollector")
+    }
+
+    fn from_template(&self, _exprs: &[Expr], inputs: &[LogicalPlan]) -> Self {
+        Self {
+            input: inputs[0].clone(),
+            file_column: self.file_column.clone(),
+            row_index_column: self.row_index_column.clone(),
+            predicate_column: self.predicate_column.clone(),
+        }
+    }
+}
+
+/// Locate the physical [`DeletedRowsCollectorExec`] after the planner converted the logical node
+/// and return the rows recorded by it.
+pub(crate) fn find_deleted_rows(parent: &Arc<dyn ExecutionPlan>) -> Option<DeletedRows> {
+    if let Some(collector) = parent.as_any().downcast_ref::<DeletedRowsCollectorExec>() {
+        return Some(collector.deleted_rows());
+    }
+
+    for child in &parent.children() {
+        let res = find_deleted_rows(child);
+        if res.is_some() {
+            return res;
+        }
+    }
+
+    None
+}
@@ -40,18 +40,21 @@ use arrow_cast::display::array_value_to_string;
 use arrow_schema::Field;
 use async_trait::async_trait;
 use chrono::{DateTime, TimeZone, Utc};
-use datafusion::datasource::file_format::{parquet::ParquetFormat, FileFormat};
 use datafusion::datasource::physical_plan::{
-    wrap_partition_type_in_dict, wrap_partition_value_in_dict, FileScanConfig,
+    wrap_partition_type_in_dict, wrap_partition_value_in_dict, FileScanConfig, ParquetExec,
+    ParquetFileReaderFactory,
 };
 use datafusion::datasource::provider::TableProviderFactory;
 use datafusion::datasource::{listing::PartitionedFile, MemTable, TableProvider, TableType};
 use datafusion::execution::context::{SessionConfig, SessionContext, SessionState, TaskContext};
+use datafusion::execution::object_store::ObjectStoreUrl;
 use datafusion::execution::runtime_env::RuntimeEnv;
 use datafusion::execution::FunctionRegistry;
 use datafusion::physical_optimizer::pruning::PruningPredicate;
 use datafusion::physical_plan::filter::FilterExec;
 use datafusion::physical_plan::limit::LocalLimitExec;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::union::UnionExec;
 use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, PlanProperties, SendableRecordBatchStream,
     Statistics,
@@ -72,6 +75,7 @@ use datafusion_expr::{
 use datafusion_functions::expr_fn::get_field;
 use datafusion_functions_array::extract::{array_element, array_slice};
 use datafusion_physical_expr::execution_props::ExecutionProps;
+use datafusion_physical_expr::expressions::Column as PhysicalColumn;
 use datafusion_physical_expr::PhysicalExpr;
 use datafusion_proto::logical_plan::LogicalExtensionCodec;
 use datafusion_proto::physical_plan::PhysicalExtensionCodec;
@@ -81,26 +85,43 @@ use futures::TryStreamExt;
 
 use itertools::Itertools;
 use object_store::ObjectMeta;
+use roaring::RoaringTreemap;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+use crate::delta_datafusion::deletion_vector::DeletionVectorExec;
 use crate::delta_datafusion::expr::parse_predicate_expression;
+use crate::delta_datafusion::row_tracking::{
+    row_tracking_data_type, RowTrackingColumns, RowTrackingExec,
+};
 use crate::errors::{DeltaResult, DeltaTableError};
-use crate::kernel::{Add, DataCheck, EagerSnapshot, Invariant, Snapshot};
-use crate::logstore::LogStoreRef;
+use crate::kernel::arrow::column_mapping::physical_arrow_schema;
+use crate::kernel::{Add, DataCheck, EagerSnapshot, GeneratedColumn, Invariant, Snapshot};
+use crate::logstore::{object_store_url, LogStoreRef};
+use crate::operations::transaction::row_tracking::{
+    MATERIALIZED_ROW_COMMIT_VERSION_COLUMN_NAME, MATERIALIZED_ROW_ID_COLUMN_NAME,
+};
+use crate::storage::utils::is_absolute_path;
+use crate::storage::{root_store_for, store_root, ObjectStoreRef};
 use crate::table::builder::ensure_table_uri;
+use crate::table::config::ColumnMappingMode;
 use crate::table::state::DeltaTableState;
 use crate::table::Constraint;
 use crate::{open_table, open_table_with_storage_options, DeltaTable};
 
 const PATH_COLUMN: &str = "__delta_rs_path";
+const ROW_INDEX_COLUMN: &str = "__delta_rs_row_index";
+const ROW_TRACKING_COLUMN: &str = "_metadata";
 
 pub mod cdf;
 pub mod expr;
 pub mod logical;
 pub mod physical;
 
+mod column_mapping;
+pub(crate) mod deletion_vector;
 mod find_files;
+mod row_tracking;
 
 impl From<DeltaTableError> for DataFusionError {
     fn from(err: DeltaTableError) -> Self {
@@ -296,6 +317,55 @@ impl DeltaTableState {
     }
 }
 
+/// The object stores the data files of a table are read from.
+///
+/// Files are usually located relative to the table root and read from the table's store. Files
+/// referenced by absolute URIs, e.g. the files of shallow clones, are read from stores for the
+/// roots of these URIs, which are registered with the runtime environment of the session.
+struct DataFileStores {
+    log_store: LogStoreRef,
+    env: Arc<RuntimeEnv>,
+    stores: HashMap<ObjectStoreUrl, (ObjectStoreRef, Url)>,
+}
+
+impl DataFileStores {
+    fn try_new(log_store: LogStoreRef, env: Arc<RuntimeEnv>) -> DeltaResult<Self> {
+        let table_root = ensure_table_uri(log_store.root_uri())?;
+        let stores = HashMap::from([(
+            log_store.object_store_url(),
+            (log_store.object_store(), table_root),
+        )]);
+        Ok(Self {
+            log_store,
+            env,
+            stores,
+        })
+    }
+
+    /// Get the url of the store a file is read from, registering the store if needed
+    fn store_url(&mut self, action: &Add) -> DeltaResult<ObjectStoreUrl> {
+        if !is_absolute_path(&action.path)? {
+            return Ok(self.log_store.object_store_url());
+        }
+        let location = Url::parse(&action.path)
+            .map_err(|_| DeltaTableError::InvalidTableLocation(action.path.clone()))?;
+        let root = store_root(&location);
+        let store_url = object_store_url(&root);
+        if !self.stores.contains_key(&store_url) {
+            let store = root_store_for(&root, &self.log_store.config().options)?;
+            self.env
+                .register_object_store(store_url.as_ref(), store.clone());
+            self.stores.insert(store_url.clone(), (store, root));
+        }
+        Ok(store_url)
+    }
+
+    /// Get a registered store and the url of its root
+    fn get(&self, url: &ObjectStoreUrl) -> (ObjectStoreRef, Url) {
+        self.stores[url].clone()
+    }
+}
+
 // each delta table must register a specific object store, since paths are internally
 // handled relative to the table root.
 pub(crate) fn register_store(store: LogStoreRef, env: Arc<RuntimeEnv>) {
@@ -334,6 +404,22 @@ pub(crate) fn df_logical_schema(
         fields.push(Arc::new(Field::new(file_column_name, DataType::Utf8, true)));
     }
 
+    if let Some(row_index_column_name) = &scan_config.row_index_column_name {
+        fields.push(Arc::new(Field::new(
+            row_index_column_name,
+            DataType::UInt64,
+            false,
+        )));
+    }
+
+    if let Some(row_tracking_column_name) = &scan_config.row_tracking_column_name {
+        fields.push(Arc::new(Field::new(
+            row_tracking_column_name,
+            row_tracking_data_type(),
+            false,
+        )));
+    }
+
     Ok(Arc::new(ArrowSchema::new(fields)))
 }
 
@@ -347,6 +433,16 @@ pub struct DeltaScanConfigBuilder {
     /// If include_file_column is true and the name is None then it will be auto-generated
     /// Otherwise the user provided name will be used
     file_column_name: Option<String>,
+    /// Include the position of each record within its data file.
+    /// The name of this column is determined by `row_index_column_name`
+    include_row_index_column: bool,
+    /// Column name that contains the position of a record within its data file.
+    row_index_column_name: Option<String>,
+    /// Include the row id and row commit version of each record of a table with row tracking.
+    /// The name of this column is determined by `row_tracking_column_name`
+    include_row_tracking_column: bool,
+    /// Column name that contains the row tracking metadata of a record.
+    row_tracking_column_name: Option<String>,
     /// Whether to wrap partition values in a dictionary encoding to potentially save space
     wrap_partition_values: Option<bool>,
     enable_parquet_pushdown: bool,
@@ -357,6 +453,10 @@ impl Default for DeltaScanConfigBuilder {
         DeltaScanConfigBuilder {
             include_file_column: false,
             file_column_name: None,
+            include_row_index_column: false,
+            row_index_column_name: None,
+            include_row_tracking_column: false,
+            row_tracking_column_name: None,
             wrap_partition_values: None,
             enable_parquet_pushdown: true,
         }
@@ -384,6 +484,38 @@ impl DeltaScanConfigBuilder {
         self
     }
 
+    /// Indicate that a column containing the position of a record within its data file is included.
+    /// Column name is generated and can be determined once this Config is built
+    pub fn with_row_index_column(mut self, include: bool) -> Self {
+        self.include_row_index_column = include;
+        self.row_index_column_name = None;
+        self
+    }
+
+    /// Indicate that a column containing the position of a record within its data file is included
+    /// and column name is user defined.
+    pub fn with_row_index_column_name<S: ToString>(mut self, name: &S) -> Self {
+        self.row_index_column_name = Some(name.to_string());
+        self.include_row_index_column = true;
+        self
+    }
+
+    /// Indicate that a struct column containing the `row_id` and `row_commit_version` of a record
+    /// is included. The column is named `_metadata` unless that conflicts with a table column.
+    pub fn with_row_tracking_column(mut self, include: bool) -> Self {
+        self.include_row_tracking_column = include;
+        self.row_tracking_column_name = None;
+        self
+    }
+
+    /// Indicate that a struct column containing the `row_id` and `row_commit_version` of a record
+    /// is included and column name is user defined.
+    pub fn with_row_tracking_column_name<S: ToString>(mut self, name: &S) -> Self {
+        self.row_tracking_column_name = Some(name.to_string());
+        self.include_row_tracking_column = true;
+        self
+    }
+
     /// Whether to wrap partition values in a dictionary encoding
     pub fn wrap_partition_values(mut self, wrap: bool) -> Self {
         self.wrap_partition_values = Some(wrap);
@@ -400,52 +532,107 @@ impl DeltaScanConfigBuilder {
     /// Build a DeltaScanConfig and ensure no column name conflicts occur during downstream processing
     pub fn build(&self, snapshot: &DeltaTableState) -> DeltaResult<DeltaScanConfig> {
         let input_schema = snapshot.input_schema()?;
-        let mut file_column_name = None;
-        let mut column_names: HashSet<&String> = HashSet::new();
+        let mut column_names: HashSet<String> = HashSet::new();
         for field in input_schema.fields.iter() {
-            column_names.insert(field.name());
+            column_names.insert(field.name().to_owned());
         }
 
-        if self.include_file_column {
-            match &self.file_column_name {
-                Some(name) => {
-                    if column_names.contains(name) {
-                        return Err(DeltaTableError::Generic(format!(
-                            "Unable to add file path column since column with name {} exits",
-                            name
-                        )));
-                    }
-
-                    file_column_name = Some(name.to_owned())
-                }
-                None => {
-                    let prefix = PATH_COLUMN;
-                    let mut idx = 0;
-                    let mut name = prefix.to_owned();
+        let file_column_name = if self.include_file_column {
+            let name = metadata_column_name(
+                &column_names,
+                self.file_column_name.as_ref(),
+                PATH_COLUMN,
+                "file path",
+            )?;
+            column_names.insert(name.clone());
+            Some(name)
+        } else {
+            None
+        };
 
-                    while column_names.contains(&name) {
-                        idx += 1;
-                        name = format!("{}_{}", prefix, idx);
-                    }
+        let row_index_column_name = if self.include_row_index_column {
+            let name = metadata_column_name(
+                &column_names,
+                self.row_index_column_name.as_ref(),
+                ROW_INDEX_COLUMN,
+                "row index",
+            )?;
+            column_names.insert(name.clone());
+            Some(name)
+        } else {
+            None
+        };
 
-                    file_column_name = Some(name);
-                }
+        let row_tracking_column_name = if self.include_row_tracking_column {
+            if !snapshot.table_config().enable_row_tracking() {
+                return Err(DeltaTableError::Generic(
+                    "Row tracking columns require row tracking to be enabled on the table"
+                        .to_string(),
+                ));
             }
-        }
+            Some(metadata_column_name(
+                &column_names,
+                self.row_tracking_column_name.as_ref(),
+                ROW_TRACKING_COLUMN,
+                "row tracking",
+            )?)
+        } else {
+            None
+        };
 
         Ok(DeltaScanConfig {
             file_column_name,
+            row_index_column_name,
+            row_tracking_column_name,
             wrap_partition_values: self.wrap_partition_values.unwrap_or(true),
             enable_parquet_pushdown: self.enable_parquet_pushdown,
         })
     }
 }
 
+/// Determine the name of a metadata column, either using the user provided name or
+/// generating a name based on `prefix` that does not conflict with existing columns.
+fn metadata_column_name(
+    column_names: &HashSet<String>,
+    name: Option<&String>,
+    prefix: &str,
+    description: &str,
+) -> DeltaResult<String> {
+    match name {
+        Some(name) => {
+            if column_names.contains(name) {
+                return Err(DeltaTableError::Generic(format!(
+                    "Unable to add {} column since column with name {} exits",
+                    description, name
+                )));
+            }
+            Ok(name.to_owned())
+        }
+        None => {
+            let mut idx = 0;
+            let mut name = prefix.to_owned();
+
+            while column_names.contains(&name) {
+                idx += 1;
+                name = format!("{}_{}", prefix, idx);
+            }
+
+            Ok(name)
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 /// Include additional metadata columns during a [`DeltaScan`]
 pub struct DeltaScanConfig {
     /// Include the source path for each record
     pub file_column_name: Option<String>,
+    /// Include the position of each record within its data file
+    #[serde(default)]
+    pub row_index_column_name: Option<String>,
+    /// Include the row id and row commit version of each record
+    #[serde(default)]
+    pub row_tracking_column_name: Option<String>,
     /// Wrap partition values in a dictionary encoding
     pub wrap_partition_values: bool,
     /// Allow pushdown of the scan filter
@@ -510,12 +697,147 @@ impl<'a> DeltaScanBuilder<'a> {
     }
 
     pub async fn build(self) -> DeltaResult<DeltaScan> {
+        match self.config.row_tracking_column_name.clone() {
+            Some(column_name) => self.build_with_row_tracking(column_name).await,
+            None => self.build_scan().await,
+        }
+    }
+
+    /// Scan the table including the metadata columns row ids are derived from, and compute
+    /// the row tracking column on top of that scan.
+    async fn build_with_row_tracking(self, column_name: String) -> DeltaResult<DeltaScan> {
+        let table_config = self.snapshot.table_config();
+        if !table_config.enable_row_tracking() {
+            return Err(DeltaTableError::Generic(
+                "Row tracking columns require row tracking to be enabled on the table".to_string(),
+            ));
+        }
+        let materialized_column = |key: &str| table_config.0.get(key).cloned().flatten();
+        let materialized_row_id_column = materialized_column(MATERIALIZED_ROW_ID_COLUMN_NAME);
+        let materialized_row_commit_version_column =
+            materialized_column(MATERIALIZED_ROW_COMMIT_VERSION_COLUMN_NAME);
+
+        let logical_schema = df_logical_schema(self.snapshot, &self.config)?;
+        let logical_schema = match self.projection {
+            Some(used_columns) => Arc::new(logical_schema.project(used_columns)?),
+            None => logical_schema,
+        };
+
+        let mut column_names = df_logical_schema(self.snapshot, &self.config)?
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect::<HashSet<_>>();
+        let mut config = self.config.clone();
+        config.row_tracking_column_name = None;
+        let file_column = match &config.file_column_name {
+            Some(name) => name.clone(),
+            None => {
+                let name = metadata_column_name(&column_names, None, PATH_COLUMN, "file path")?;
+                column_names.insert(name.clone());
+                config.file_column_name = Some(name.clone());
+                name
+            }
+        };
+        let row_index_column = match &config.row_index_column_name {
+            Some(name) => name.clone(),
+            None => {
+                let name =
+                    metadata_column_name(&column_names, None, ROW_INDEX_COLUMN, "row index")?;
+                config.row_index_column_name = Some(name.clone());
+                name
+            }
+        };
+
+        // Materialized row ids are read from the data files alongside the table columns.
+        let schema = match self.schema {
+            Some(schema) => schema,
+            None => {
+                self.snapshot
+                    .physical_arrow_schema_for(self.log_store.as_ref())
+                    .await?
+            }
+        };
+        let mut fields = schema.fields().to_vec();
+        for name in materialized_row_id_column
+            .iter()
+            .chain(materialized_row_commit_version_column.iter())
+        {
+            if schema.field_with_name(name).is_err() {
+                fields.push(Arc::new(Field::new(name, DataType::Int64, true)));
+            }
+        }
+        let schema = Arc::new(ArrowSchema::new_with_metadata(
+            fields,
+            schema.metadata().clone(),
+        ));
+
+        // The row tracking column can not be used to prune files.
+        let filter = self.filter.filter(|expr| {
+            expr.to_columns()
+                .map(|columns| columns.iter().all(|c| c.name != column_name))
+                .unwrap_or_default()
+        });
+
+        let files = match self.files {
+            Some(files) => Either::Left(files.iter().cloned()),
+            None => Either::Right(self.snapshot.file_actions()?.into_iter()),
+        }
+        .map(|add| (add.path, (add.base_row_id, add.default_row_commit_version)))
+        .collect::<HashMap<_, _>>();
+
+        let scan = DeltaScanBuilder {
+            snapshot: self.snapshot,
+            log_store: self.log_store.clone(),
+            filter,
+            state: self.state,
+            projection: None,
+            limit: self.limit,
+            files: self.files,
+            config,
+            schema: Some(schema),
+        }
+        .build_scan()
+        .await?;
+
+        let row_tracking = Arc::new(RowTrackingExec::new(
+            scan.parquet_scan,
+            RowTrackingColumns {
+                file_column,
+                row_index_column,
+                materialized_row_id_column,
+                materialized_row_commit_version_column,
+            },
+            Arc::new(files),
+            &column_name,
+        ));
+        let projection = logical_schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let column = PhysicalColumn::new_with_schema(field.name(), &row_tracking.schema())?;
+                Ok((
+                    Arc::new(column) as Arc<dyn PhysicalExpr>,
+                    field.name().clone(),
+                ))
+            })
+            .collect::<DataFusionResult<Vec<_>>>()?;
+
+        Ok(DeltaScan {
+            table_uri: scan.table_uri,
+            parquet_scan: Arc::new(ProjectionExec::try_new(projection, row_tracking)?),
+            config: self.config,
+            logical_schema,
+        })
+    }
+
+    async fn build_scan(self) -> DeltaResult<DeltaScan> {
         let config = self.config;
         let schema = match self.schema {
             Some(schema) => schema,
             None => {
                 self.snapshot
-                    .physical_arrow_schema(self.log_store.object_store())
+                    .physical_arrow_schema_for(self.log_store.as_ref())
                     .await?
             }
         };
@@ -563,15 +885,45 @@ impl<'a> DeltaScanBuilder<'a> {
             }
         };
 
+        // Files with deletion vectors are scanned separately, since all of their rows need
+        // to be read in order to determine which rows have been deleted. The same holds
+        // for every file if the position of each row within its file is requested.
+        let (dv_files, files): (Vec<Add>, Vec<Add>) = files.into_iter().partition(|action| {
+            action.deletion_vector.is_some() || config.row_index_column_name.is_some()
+        });
+
         // TODO we group files together by their partition values. If the table is partitioned
         // and partitions are somewhat evenly distributed, probably not the worst choice ...
         // However we may want to do some additional balancing in case we are far off from the above.
-        let mut file_groups: HashMap<Vec<ScalarValue>, Vec<PartitionedFile>> = HashMap::new();
+        // Files can be located in different stores, so they are grouped by their store first.
+        let mut file_groups: HashMap<
+            ObjectStoreUrl,
+            HashMap<Vec<ScalarValue>, Vec<PartitionedFile>>,
+        > = HashMap::new();
+        let mut stores =
+            DataFileStores::try_new(self.log_store.clone(), self.state.runtime_env().clone())?;
 
         let table_partition_cols = &self.snapshot.metadata().partition_columns;
 
-        for action in files.iter() {
-            let mut part = partitioned_file_from_action(action, table_partition_cols, &schema);
+        // With column mapping enabled, data files and partition values refer to columns
+        // by their physical names.
+        let table_schema = self.snapshot.schema();
+        let column_mapping_mode = self.snapshot.table_config().column_mapping_mode();
+        let physical_schema = match column_mapping_mode {
+            ColumnMappingMode::None => schema.clone(),
+            mode => Arc::new(physical_arrow_schema(&schema, table_schema, mode)?),
+        };
+        let physical_partition_cols = table_partition_cols
+            .iter()
+            .map(|name| {
+                let field = table_schema.field_with_name(name)?;
+                Ok(field.physical_name_for(column_mapping_mode)?.to_string())
+            })
+            .collect::<DeltaResult<Vec<_>>>()?;
+
+        let to_partitioned_file = |action: &Add| {
+            let mut part =
+                partitioned_file_from_action(action, &physical_partition_cols, &physical_schema);
 
             if config.file_column_name.is_some() {
                 let partition_value = if config.wrap_partition_values {
@@ -581,22 +933,50 @@ impl<'a> DeltaScanBuilder<'a> {
                 };
                 part.partition_values.push(partition_value);
             }
+            part
+        };
 
+        for action in files.iter() {
+            let part = to_partitioned_file(action);
             file_groups
+                .entry(stores.store_url(action)?)
+                .or_default()
                 .entry(part.partition_values.clone())
                 .or_default()
                 .push(part);
         }
 
+        let mut dv_groups: HashMap<ObjectStoreUrl, Vec<Add>> = HashMap::new();
+        for action in dv_files {
+            dv_groups
+                .entry(stores.store_url(&action)?)
+                .or_default()
+                .push(action);
+        }
+        if dv_groups.is_empty() && config.row_index_column_name.is_some() {
+            dv_groups.insert(self.log_store.object_store_url(), vec![]);
+        }
+
         let file_schema = Arc::new(ArrowSchema::new(
-            schema
+            physical_schema
                 .fields()
                 .iter()
-                .filter(|f| !table_partition_cols.contains(f.name()))
+                .filter(|f| !physical_partition_cols.contains(f.name()))
                 .cloned()
                 .collect::<Vec<arrow::datatypes::FieldRef>>(),
         ));
 
+        // Columns read from the data files which are named differently in the logical schema.
+        let logical_fields = schema
+            .fields()
+            .iter()
+            .zip(physical_schema.fields().iter())
+            .filter(|(logical, physical)| {
+                logical != physical && !table_partition_cols.contains(logical.name())
+            })
+            .map(|(logical, physical)| (physical.name().clone(), logical.clone()))
+            .collect::<HashMap<_, _>>();
+
         let mut table_partition_cols = table_partition_cols
             .iter()
             .map(|name| schema.field_with_name(name).map(|f| f.to_owned()))
@@ -628,23 +1008,147 @@ impl<'a> DeltaScanBuilder<'a> {
         } else {
             None
         };
+        let parquet_pushdown = match parquet_pushdown {
+            Some(predicate) if !logical_fields.is_empty() => {
+                let physical_names = logical_fields
+                    .iter()
+                    .map(|(physical, logical)| (logical.name().clone(), physical.clone()))
+                    .collect();
+                Some(column_mapping::physical_predicate(
+                    predicate,
+                    &physical_names,
+                )?)
+            }
+            predicate => predicate,
+        };
+
+        let parquet_options = self.state.default_table_options().parquet;
+        let parquet_scan = |scan_config: FileScanConfig,
+                            predicate: Option<&Arc<dyn PhysicalExpr>>|
+         -> DeltaResult<Arc<dyn ExecutionPlan>> {
+            let reader_factory: Option<Arc<dyn ParquetFileReaderFactory>> =
+                match column_mapping_mode {
+                    ColumnMappingMode::Id => {
+                        let (object_store, _) = stores.get(&scan_config.object_store_url);
+                        Some(Arc::new(column_mapping::FieldIdReaderFactory::try_new(
+                            object_store,
+                            table_schema,
+                        )?))
+                    }
+                    _ => None,
+                };
+            let scan = ParquetExec::new(
+                scan_config,
+                predicate.cloned(),
+                None,
+                parquet_options.clone(),
+            );
+            Ok(match reader_factory {
+                Some(factory) => Arc::new(scan.with_parquet_file_reader_factory(factory)),
+                None => Arc::new(scan),
+            })
+        };
+
+        // The row index column is not read from the data files, but appended after the file column.
+        let row_index_column = config.row_index_column_name.as_ref().map(|name| {
+            let idx = file_schema.fields().len() + table_partition_cols.len();
+            let position = match self.projection {
+                Some(projection) => projection.iter().position(|i| *i == idx),
+                None => Some(idx),
+            };
+            (idx, position, name.as_str())
+        });
+        let projection = match (self.projection, row_index_column) {
+            (Some(projection), Some((idx, _, _))) => Some(
+                projection
+                    .iter()
+                    .filter(|i| **i != idx)
+                    .cloned()
+                    .collect::<Vec<_>>(),
+            ),
+            (projection, _) => projection.cloned(),
+        };
+
+        let dv_row_index_column = row_index_column
+            .and_then(|(_, position, name)| position.map(|position| (position, name)));
+        let mut dv_scans: Vec<Arc<dyn ExecutionPlan>> = Vec::with_capacity(dv_groups.len());
+        for (object_store_url, dv_files) in dv_groups {
+            let (object_store, root) = stores.get(&object_store_url);
+            let deletion_vectors = futures::future::try_join_all(dv_files.iter().map(|action| {
+                let root = &root;
+                let object_store = object_store.clone();
+                async move {
+                    let bitmap = match &action.deletion_vector {
+                        Some(dv) => dv.read(object_store.as_ref(), root).await?,
+                        None => RoaringTreemap::new(),
+                    };
+                    Ok::<_, DeltaTableError>(Arc::new(bitmap))
+                }
+            }))
+            .await?;
+
+            // Every file gets its own group so each partition maps to exactly one deletion vector.
+            // Neither a limit nor the filter can be pushed down, since that would invalidate
+            // the row positions the deletion vector refers to.
+            let scan = parquet_scan(
+                FileScanConfig {
+                    object_store_url,
+                    file_schema: file_schema.clone(),
+                    file_groups: dv_files
+                        .iter()
+                        .map(|action| vec![to_partitioned_file(action)])
+                        .collect(),
+                    statistics: Statistics::new_unknown(&schema),
+                    projection: projection.clone(),
+                    limit: None,
+                    table_partition_cols: table_partition_cols.clone(),
+                    output_ordering: vec![],
+                },
+                None,
+            )?;
+            dv_scans.push(Arc::new(DeletionVectorExec::try_new(
+                scan,
+                deletion_vectors,
+                dv_row_index_column,
+            )?));
+        }
 
-        let scan = ParquetFormat::new()
-            .create_physical_plan(
-                self.state,
+        if file_groups.is_empty() && dv_scans.is_empty() {
+            file_groups.insert(self.log_store.object_store_url(), HashMap::new());
+        }
+        // The table statistics only apply if all files are read by a single scan
+        let statistics = if file_groups.len() == 1 {
+            stats
+        } else {
+            Statistics::new_unknown(&schema)
+        };
+        let mut scans = Vec::with_capacity(file_groups.len() + dv_scans.len());
+        for (object_store_url, file_groups) in file_groups {
+            scans.push(parquet_scan(
                 FileScanConfig {
-                    object_store_url: self.log_store.object_store_url(),
-                    file_schema,
+                    object_store_url,
+                    file_schema: file_schema.clone(),
                     file_groups: file_groups.into_values().collect(),
-                    statistics: stats,
-                    projection: self.projection.cloned(),
+                    statistics: statistics.clone(),
+                    projection: projection.clone(),
                     limit: self.limit,
-                    table_partition_cols,
+                    table_partition_cols: table_partition_cols.clone(),
                     output_ordering: vec![],
                 },
                 parquet_pushdown.as_ref(),
-            )
-            .await?;
+            )?);
+        }
+        scans.extend(dv_scans);
+
+        let scan: Arc<dyn ExecutionPlan> = match scans.len() {
+            1 => scans.remove(0),
+            _ => Arc::new(UnionExec::new(scans)),
+        };
+        let scan = if logical_fields.is_empty() {
+            scan
+        } else {
+            column_mapping::logical_projection(scan, &logical_fields)?
+        };
 
         Ok(DeltaScan {
             table_uri: ensure_table_uri(self.log_store.root_uri())?.as_str().into(),
@@ -732,6 +1236,21 @@ impl DeltaTableProvider {
             config,
         })
     }
+
+    /// The snapshot of the table scanned by the provider
+    pub fn snapshot(&self) -> &DeltaTableState {
+        &self.snapshot
+    }
+
+    /// The log store of the table scanned by the provider
+    pub fn log_store(&self) -> LogStoreRef {
+        self.log_store.clone()
+    }
+
+    /// The configuration of the scan
+    pub fn config(&self) -> &DeltaScanConfig {
+        &self.config
+    }
 }
 
 #[async_trait]
@@ -791,6 +1310,9 @@ impl TableProvider for DeltaTableProvider {
 
 // TODO: this will likely also need to perform column mapping later when we support reader protocol v2
 /// A wrapper for parquet scans
+///
+/// Files carrying a deletion vector are read through a separate scan which removes deleted rows,
+/// in which case `parquet_scan` is the union of both scans.
 #[derive(Debug)]
 pub struct DeltaScan {
     /// The URL of the ObjectStore root
@@ -1150,6 +1672,7 @@ pub(crate) async fn execute_plan_to_batch(
 pub struct DeltaDataChecker {
     constraints: Vec<Constraint>,
     invariants: Vec<Invariant>,
+    generated_columns: Vec<GeneratedColumn>,
     ctx: SessionContext,
 }
 
@@ -1159,6 +1682,7 @@ impl DeltaDataChecker {
         Self {
             invariants: vec![],
             constraints: vec![],
+            generated_columns: vec![],
             ctx: DeltaSessionContext::default().into(),
         }
     }
@@ -1168,6 +1692,7 @@ impl DeltaDataChecker {
         Self {
             invariants,
             constraints: vec![],
+            generated_columns: vec![],
             ctx: DeltaSessionContext::default().into(),
         }
     }
@@ -1177,6 +1702,17 @@ impl DeltaDataChecker {
         Self {
             constraints,
             invariants: vec![],
+            generated_columns: vec![],
+            ctx: DeltaSessionContext::default().into(),
+        }
+    }
+
+    /// Create a new DeltaDataChecker with a specified set of generated columns
+    pub fn new_with_generated_columns(generated_columns: Vec<GeneratedColumn>) -> Self {
+        Self {
+            constraints: vec![],
+            invariants: vec![],
+            generated_columns,
             ctx: DeltaSessionContext::default().into(),
         }
     }
@@ -1197,9 +1733,11 @@ impl DeltaDataChecker {
     pub fn new(snapshot: &DeltaTableState) -> Self {
         let invariants = snapshot.schema().get_invariants().unwrap_or_default();
         let constraints = snapshot.table_config().get_constraints();
+        let generated_columns = snapshot.schema().get_generated_columns();
         Self {
             invariants,
             constraints,
+            generated_columns,
             ctx: DeltaSessionContext::default().into(),
         }
     }
@@ -1210,7 +1748,9 @@ impl DeltaDataChecker {
     /// of values that violated each invariant.
     pub async fn check_batch(&self, record_batch: &RecordBatch) -> Result<(), DeltaTableError> {
         self.enforce_checks(record_batch, &self.invariants).await?;
-        self.enforce_checks(record_batch, &self.constraints).await
+        self.enforce_checks(record_batch, &self.constraints).await?;
+        self.enforce_checks(record_batch, &self.generated_columns)
+            .await
     }
 
     async fn enforce_checks<C: DataCheck>(
@@ -1713,15 +2253,21 @@ impl From<DeltaSessionConfig> for SessionConfig {
 }
 
 /// A wrapper for Deltafusion's SessionContext to capture sane default table defaults
+///
+/// The context provides the [`table_changes`](cdf::TableChangesFunction) table function to
+/// query the change data feed of registered delta tables.
 pub struct DeltaSessionContext {
     inner: SessionContext,
 }
 
 impl Default for DeltaSessionContext {
     fn default() -> Self {
-        DeltaSessionContext {
-            inner: SessionContext::new_with_config(DeltaSessionConfig::default().into()),
-        }
+        let inner = SessionContext::new_with_config(DeltaSessionConfig::default().into());
+        inner.register_udtf(
+            cdf::TABLE_CHANGES_FUNCTION,
+            Arc::new(cdf::TableChangesFunction::new(&inner.state())),
+        );
+        DeltaSessionContext { inner }
     }
 }
 
@@ -1976,6 +2522,44 @@ mod tests {
         assert!(matches!(result, Err(DeltaTableError::Generic { .. })));
     }
 
+    #[tokio::test]
+    async fn test_enforce_generated_columns() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(arrow::array::Int32Array::from(vec![Some(1), Some(2), None])),
+                Arc::new(arrow::array::Int32Array::from(vec![Some(2), Some(4), None])),
+            ],
+        )
+        .unwrap();
+
+        let generated_columns = vec![GeneratedColumn::new(
+            "b",
+            &crate::kernel::DataType::INTEGER,
+            "a * 2",
+        )];
+        assert!(
+            DeltaDataChecker::new_with_generated_columns(generated_columns)
+                .check_batch(&batch)
+                .await
+                .is_ok()
+        );
+
+        let generated_columns = vec![GeneratedColumn::new(
+            "b",
+            &crate::kernel::DataType::INTEGER,
+            "a + 1",
+        )];
+        let result = DeltaDataChecker::new_with_generated_columns(generated_columns)
+            .check_batch(&batch)
+            .await;
+        assert!(matches!(result, Err(DeltaTableError::InvalidData { .. })));
+    }
+
     #[test]
     fn roundtrip_test_delta_exec_plan() {
         let ctx = SessionContext::new();
@@ -2175,4 +2759,135 @@ mod tests {
         assert_batches_sorted_eq!(&expected, &actual);
         */
     }
+
+    #[tokio::test]
+    async fn delta_scan_row_tracking_columns() {
+        let table = crate::DeltaOps::new_in_memory()
+            .create()
+            .with_column("value", crate::kernel::DataType::STRING, true, None)
+            .with_configuration_property(crate::DeltaConfigKey::EnableRowTracking, Some("true"))
+            .await
+            .unwrap();
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "value",
+            DataType::Utf8,
+            true,
+        )]));
+        let mut table = table;
+        for values in [vec!["A", "B", "C"], vec!["D", "E"]] {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(arrow::array::StringArray::from(values))],
+            )
+            .unwrap();
+            table = crate::DeltaOps(table).write(vec![batch]).await.unwrap();
+        }
+
+        let query = |table: DeltaTable| async move {
+            let config = DeltaScanConfigBuilder::new()
+                .with_row_tracking_column(true)
+                .build(table.snapshot().unwrap())
+                .unwrap();
+            let provider = DeltaTableProvider::try_new(
+                table.snapshot().unwrap().clone(),
+                table.log_store(),
+                config,
+            )
+            .unwrap();
+            let ctx = SessionContext::new();
+            ctx.register_table("test", Arc::new(provider)).unwrap();
+            ctx.sql("select value, _metadata['row_id'] as row_id, _metadata['row_commit_version'] as version from test")
+                .await
+                .unwrap()
+                .collect()
+                .await
+                .unwrap()
+        };
+        let expected = vec![
+            "+-------+--------+---------+",
+            "| value | row_id | version |",
+            "+-------+--------+---------+",
+            "| A     | 0      | 1       |",
+            "| B     | 1      | 1       |",
+            "| C     | 2      | 1       |",
+            "| D     | 3      | 2       |",
+            "| E     | 4      | 2       |",
+            "+-------+--------+---------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &query(table.clone()).await);
+
+        // row ids and commit versions are materialized when files are compacted
+        let (table, metrics) = crate::DeltaOps(table).optimize().await.unwrap();
+        assert_eq!(metrics.num_files_removed, 2);
+        assert_eq!(table.snapshot().unwrap().files_count(), 1);
+        assert_batches_sorted_eq!(&expected, &query(table.clone()).await);
+
+        // the columns can only be requested for tables with row tracking enabled
+        let table = crate::DeltaOps::new_in_memory()
+            .create()
+            .with_column("value", crate::kernel::DataType::STRING, true, None)
+            .await
+            .unwrap();
+        assert!(DeltaScanConfigBuilder::new()
+            .with_row_tracking_column(true)
+            .build(table.snapshot().unwrap())
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn delta_scan_deletion_vectors() {
+        use crate::writer::test_utils::{get_record_batch, setup_table_with_deletion_vectors};
+        use datafusion_expr::{col, lit};
+
+        fn find_dv_exec(plan: &Arc<dyn ExecutionPlan>) -> Option<Arc<dyn ExecutionPlan>> {
+            if plan.as_any().is::<DeletionVectorExec>() {
+                return Some(plan.clone());
+            }
+            plan.children().iter().find_map(find_dv_exec)
+        }
+
+        let table = setup_table_with_deletion_vectors(None).await;
+        let table = crate::DeltaOps(table)
+            .write(vec![get_record_batch(None, false)])
+            .await
+            .unwrap();
+        let (table, _) = crate::DeltaOps(table)
+            .delete()
+            .with_predicate(col("value").eq(lit(10)))
+            .await
+            .unwrap();
+
+        let ctx = SessionContext::new_with_config(SessionConfig::new().with_target_partitions(4));
+        ctx.register_table("test", Arc::new(table)).unwrap();
+        let plan = ctx
+            .sql("select count(*) from test")
+            .await
+            .unwrap()
+            .create_physical_plan()
+            .await
+            .unwrap();
+
+        // the scan is a child of the operator, and is not split into byte ranges
+        let dv_exec = find_dv_exec(&plan).unwrap();
+        let children = dv_exec.children();
+        assert_eq!(children.len(), 1);
+        assert!(children[0].as_any().is::<ParquetExec>());
+        assert_eq!(
+            children[0]
+                .properties()
+                .output_partitioning()
+                .partition_count(),
+            1
+        );
+
+        // deletion vectors are matched to the partitions of the scan
+        let schema = children[0].schema();
+        assert!(dv_exec
+            .clone()
+            .with_new_children(vec![Arc::new(EmptyExec::new(schema.clone()))])
+            .is_ok());
+        assert!(dv_exec
+            .with_new_children(vec![Arc::new(EmptyExec::new(schema).with_partitions(2))])
+            .is_err());
+    }
 }
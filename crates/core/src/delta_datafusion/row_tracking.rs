@@ -0,0 +1,255 @@
+//! Physical operator exposing the row ids and row commit versions of scanned rows
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use arrow::compute::cast;
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Int64Type, UInt64Type};
+use arrow_array::{Array, ArrayRef, Int64Array, RecordBatch, StructArray};
+use arrow_schema::{DataType, Field, Fields, Schema, SchemaRef};
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_expr::EquivalenceProperties;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionMode, ExecutionPlan, PlanProperties, RecordBatchStream,
+    SendableRecordBatchStream, Statistics,
+};
+use datafusion_common::{DataFusionError, Result as DataFusionResult};
+use futures::{Stream, StreamExt};
+
+use crate::errors::DeltaTableError;
+
+/// Name of the field holding the row id within the row tracking column
+pub(crate) const ROW_ID_FIELD: &str = "row_id";
+/// Name of the field holding the row commit version within the row tracking column
+pub(crate) const ROW_COMMIT_VERSION_FIELD: &str = "row_commit_version";
+
+/// The struct type of the row tracking metadata column
+pub(crate) fn row_tracking_data_type() -> DataType {
+    DataType::Struct(Fields::from(vec![
+        Field::new(ROW_ID_FIELD, DataType::Int64, true),
+        Field::new(ROW_COMMIT_VERSION_FIELD, DataType::Int64, true),
+    ]))
+}
+
+/// `baseRowId` and `defaultRowCommitVersion` of the scanned files, keyed by file path
+pub(crate) type RowTrackingFiles = HashMap<String, (Option<i64>, Option<i64>)>;
+
+/// Columns of the scanned batches the row tracking metadata is derived from
+#[derive(Debug, Clone)]
+pub(crate) struct RowTrackingColumns {
+    /// Column containing the path of the data file a row was read from
+    pub file_column: String,
+    /// Column containing the position of a row within its data file
+    pub row_index_column: String,
+    /// Column containing row ids materialized in the data files
+    pub materialized_row_id_column: Option<String>,
+    /// Column containing row commit versions materialized in the data files
+    pub materialized_row_commit_version_column: Option<String>,
+}
+
+/// Appends a struct column containing the row id and row commit version of each scanned row.
+///
+/// Rows without a materialized row id get the `baseRowId` of their file plus their position
+/// within the file, rows without a materialized commit version get the
+/// `defaultRowCommitVersion` of their file.
+pub(crate) struct RowTrackingExec {
+    input: Arc<dyn ExecutionPlan>,
+    columns: RowTrackingColumns,
+    files: Arc<RowTrackingFiles>,
+    schema: SchemaRef,
+    properties: PlanProperties,
+}
+
+impl RowTrackingExec {
+    pub fn new(
+        input: Arc<dyn ExecutionPlan>,
+        columns: RowTrackingColumns,
+        files: Arc<RowTrackingFiles>,
+        column_name: &str,
+    ) -> Self {
+        let mut fields = input.schema().fields().to_vec();
+        fields.push(Arc::new(Field::new(
+            column_name,
+            row_tracking_data_type(),
+            false,
+        )));
+        let schema = Arc::new(Schema::new(fields));
+        let properties = PlanProperties::new(
+            EquivalenceProperties::new(schema.clone()),
+            input.properties().output_partitioning().clone(),
+            ExecutionMode::Bounded,
+        );
+        Self {
+            input,
+            columns,
+            files,
+            schema,
+            properties,
+        }
+    }
+}
+
+impl fmt::Debug for RowTrackingExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RowTrackingExec")
+            .field("input", &self.input)
+            .field("columns", &self.columns)
+            .finish()
+    }
+}
+
+impl DisplayAs for RowTrackingExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RowTrackingExec")
+    }
+}
+
+impl ExecutionPlan for RowTrackingExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        match children.as_slice() {
+            [input] => Ok(Arc::new(Self::new(
+                input.clone(),
+                self.columns.clone(),
+                self.files.clone(),
+                self.schema.fields().last().unwrap().name(),
+            ))),
+            _ => Err(DataFusionError::External(Box::new(
+                DeltaTableError::Generic("RowTrackingExec expects only one child".into()),
+            ))),
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<SendableRecordBatchStream> {
+        Ok(Box::pin(RowTrackingStream {
+            schema: self.schema(),
+            input: self.input.execute(partition, context)?,
+            columns: self.columns.clone(),
+            files: self.files.clone(),
+        }))
+    }
+
+    fn statistics(&self) -> DataFusionResult<Statistics> {
+        Ok(Statistics::new_unknown(&self.schema()))
+    }
+}
+
+struct RowTrackingStream {
+    schema: SchemaRef,
+    input: SendableRecordBatchStream,
+    columns: RowTrackingColumns,
+    files: Arc<RowTrackingFiles>,
+}
+
+impl RowTrackingStream {
+    fn apply(&self, batch: RecordBatch) -> DataFusionResult<RecordBatch> {
+        let column = |name: &str| {
+            batch.column_by_name(name).ok_or_else(|| {
+                DataFusionError::Internal(format!("Missing column {name} to compute row ids"))
+            })
+        };
+        let paths = cast(column(&self.columns.file_column)?, &DataType::Utf8)?;
+        let paths = paths.as_string::<i32>();
+        let row_indexes = column(&self.columns.row_index_column)?.as_primitive::<UInt64Type>();
+        let materialized = |name: &Option<String>| {
+            name.as_ref()
+                .and_then(|name| batch.column_by_name(name))
+                .map(|arr| cast(arr, &DataType::Int64))
+                .transpose()
+        };
+        let materialized_row_ids = materialized(&self.columns.materialized_row_id_column)?;
+        let materialized_row_ids = materialized_row_ids
+            .as_ref()
+            .map(|arr| arr.as_primitive::<Int64Type>());
+        let materialized_versions =
+            materialized(&self.columns.materialized_row_commit_version_column)?;
+        let materialized_versions = materialized_versions
+            .as_ref()
+            .map(|arr| arr.as_primitive::<Int64Type>());
+
+        let mut row_ids = Vec::with_capacity(batch.num_rows());
+        let mut versions = Vec::with_capacity(batch.num_rows());
+        for idx in 0..batch.num_rows() {
+            let (base_row_id, default_version) = if paths.is_valid(idx) {
+                self.files
+                    .get(paths.value(idx))
+                    .copied()
+                    .unwrap_or_default()
+            } else {
+                (None, None)
+            };
+            let row_id = match materialized_row_ids {
+                Some(arr) if arr.is_valid(idx) => Some(arr.value(idx)),
+                _ => base_row_id.map(|base| base + row_indexes.value(idx) as i64),
+            };
+            let version = match materialized_versions {
+                Some(arr) if arr.is_valid(idx) => Some(arr.value(idx)),
+                _ => default_version,
+            };
+            row_ids.push(row_id);
+            versions.push(version);
+        }
+
+        let DataType::Struct(fields) = row_tracking_data_type() else {
+            unreachable!()
+        };
+        let metadata = StructArray::try_new(
+            fields,
+            vec![
+                Arc::new(Int64Array::from(row_ids)) as ArrayRef,
+                Arc::new(Int64Array::from(versions)) as ArrayRef,
+            ],
+            None,
+        )?;
+        let mut columns = batch.columns().to_vec();
+        columns.push(Arc::new(metadata));
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+}
+
+impl Stream for RowTrackingStream {
+    type Item = DataFusionResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.input.poll_next_unpin(cx).map(|x| match x {
+            Some(Ok(batch)) => Some(self.apply(batch)),
+            other => other,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl RecordBatchStream for RowTrackingStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
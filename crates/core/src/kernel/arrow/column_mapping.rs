@@ -0,0 +1,330 @@
+//! Conversions between the logical and physical representation of tables with column mapping.
+//!
+//! With column mapping enabled, columns are stored in the data files using their physical names
+//! rather than the logical names of the table schema. Data files written with column mapping
+//! additionally carry the column mapping id of each column as parquet field id.
+
+use std::sync::Arc;
+
+use arrow::array::ArrayData;
+use arrow_array::{make_array, Array, ArrayRef, RecordBatch};
+use arrow_schema::{
+    ArrowError, DataType as ArrowDataType, Field, FieldRef, Schema as ArrowSchema,
+    SchemaRef as ArrowSchemaRef,
+};
+use indexmap::IndexMap;
+use parquet::arrow::PARQUET_FIELD_ID_META_KEY;
+
+use crate::errors::DeltaResult;
+use crate::kernel::{DataType, Scalar, StructField, StructType};
+use crate::table::config::ColumnMappingMode;
+
+/// Rename the fields of an arrow schema to the physical names used in the data files.
+///
+/// Fields not contained in the table schema - e.g. metadata columns - are not renamed.
+#[cfg(feature = "datafusion")]
+pub(crate) fn physical_arrow_schema(
+    schema: &ArrowSchema,
+    table_schema: &StructType,
+    mode: ColumnMappingMode,
+) -> DeltaResult<ArrowSchema> {
+    rename_schema(schema, table_schema, mode, false)
+}
+
+fn rename_schema(
+    schema: &ArrowSchema,
+    table_schema: &StructType,
+    mode: ColumnMappingMode,
+    field_ids: bool,
+) -> DeltaResult<ArrowSchema> {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| match table_schema.field_with_name(field.name()) {
+            Ok(table_field) => physical_arrow_field(field, table_field, mode, field_ids),
+            Err(_) => Ok(field.as_ref().clone()),
+        })
+        .collect::<DeltaResult<Vec<_>>>()?;
+    Ok(ArrowSchema::new_with_metadata(
+        fields,
+        schema.metadata().clone(),
+    ))
+}
+
+fn physical_arrow_field(
+    field: &Field,
+    table_field: &StructField,
+    mode: ColumnMappingMode,
+    field_ids: bool,
+) -> DeltaResult<Field> {
+    let data_type =
+        physical_arrow_type(field.data_type(), table_field.data_type(), mode, field_ids)?;
+    let mut field = field
+        .clone()
+        .with_name(table_field.physical_name_for(mode)?)
+        .with_data_type(data_type);
+    if let Some(id) = table_field.column_mapping_id().filter(|_| field_ids) {
+        let mut metadata = field.metadata().clone();
+        metadata.insert(PARQUET_FIELD_ID_META_KEY.to_string(), id.to_string());
+        field = field.with_metadata(metadata);
+    }
+    Ok(field)
+}
+
+fn physical_arrow_type(
+    data_type: &ArrowDataType,
+    table_type: &DataType,
+    mode: ColumnMappingMode,
+    field_ids: bool,
+) -> DeltaResult<ArrowDataType> {
+    let rename_child = |field: &FieldRef, table_type: &DataType| -> DeltaResult<FieldRef> {
+        let data_type = physical_arrow_type(field.data_type(), table_type, mode, field_ids)?;
+        Ok(Arc::new(field.as_ref().clone().with_data_type(data_type)))
+    };
+    Ok(match (data_type, table_type) {
+        (ArrowDataType::Struct(fields), DataType::Struct(table_struct)) => ArrowDataType::Struct(
+            fields
+                .iter()
+                .map(|field| match table_struct.field_with_name(field.name()) {
+                    Ok(table_field) => Ok(Arc::new(physical_arrow_field(
+                        field,
+                        table_field,
+                        mode,
+                        field_ids,
+                    )?)),
+                    Err(_) => Ok(field.clone()),
+                })
+                .collect::<DeltaResult<Vec<_>>>()?
+                .into(),
+        ),
+        (ArrowDataType::List(element), DataType::Array(table_array)) => {
+            ArrowDataType::List(rename_child(element, table_array.element_type())?)
+        }
+        (ArrowDataType::LargeList(element), DataType::Array(table_array)) => {
+            ArrowDataType::LargeList(rename_child(element, table_array.element_type())?)
+        }
+        (ArrowDataType::Map(entries, sorted), DataType::Map(table_map)) => {
+            let entries = match entries.data_type() {
+                ArrowDataType::Struct(fields) if fields.len() == 2 => {
+                    let key = rename_child(&fields[0], table_map.key_type())?;
+                    let value = rename_child(&fields[1], table_map.value_type())?;
+                    Arc::new(
+                        entries
+                            .as_ref()
+                            .clone()
+                            .with_data_type(ArrowDataType::Struct(vec![key, value].into())),
+                    )
+                }
+                _ => entries.clone(),
+            };
+            ArrowDataType::Map(entries, *sorted)
+        }
+        _ => data_type.clone(),
+    })
+}
+
+/// Converts data using the logical column names of a table into data using the
+/// physical column names and field ids with which it is stored in the data files.
+#[derive(Debug, Clone)]
+pub(crate) struct PhysicalMapper {
+    table_schema: StructType,
+    mode: ColumnMappingMode,
+    /// The physical schema of the data
+    schema: ArrowSchemaRef,
+}
+
+impl PhysicalMapper {
+    /// Create a new mapper for data with the given logical schema.
+    ///
+    /// Returns `None` if column mapping is not enabled.
+    pub(crate) fn try_new(
+        schema: &ArrowSchema,
+        table_schema: &StructType,
+        mode: ColumnMappingMode,
+    ) -> DeltaResult<Option<Self>> {
+        if mode == ColumnMappingMode::None {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            table_schema: table_schema.clone(),
+            mode,
+            schema: Arc::new(rename_schema(schema, table_schema, mode, true)?),
+        }))
+    }
+
+    /// The physical schema of the data
+    pub(crate) fn schema(&self) -> ArrowSchemaRef {
+        self.schema.clone()
+    }
+
+    /// Get the physical name of a top level column.
+    pub(crate) fn physical_name(&self, name: &str) -> DeltaResult<String> {
+        Ok(match self.table_schema.field_with_name(name) {
+            Ok(field) => field.physical_name_for(self.mode)?.to_string(),
+            Err(_) => name.to_string(),
+        })
+    }
+
+    /// Get the physical path of a (nested) column given as dot separated path.
+    pub(crate) fn physical_path(&self, path: &str) -> DeltaResult<String> {
+        let mut segments = Vec::new();
+        let mut current = Some(&self.table_schema);
+        for segment in path.split('.') {
+            match current.and_then(|schema| schema.field_with_name(segment).ok()) {
+                Some(field) => {
+                    segments.push(field.physical_name_for(self.mode)?.to_string());
+                    current = match field.data_type() {
+                        DataType::Struct(inner) => Some(inner.as_ref()),
+                        _ => None,
+                    };
+                }
+                None => {
+                    segments.push(segment.to_string());
+                    current = None;
+                }
+            }
+        }
+        Ok(segments.join("."))
+    }
+
+    /// Key the partition values by the physical names of the partition columns.
+    pub(crate) fn map_partition_values(
+        &self,
+        partition_values: &IndexMap<String, Scalar>,
+    ) -> DeltaResult<IndexMap<String, Scalar>> {
+        partition_values
+            .iter()
+            .map(|(name, value)| Ok((self.physical_name(name)?, value.clone())))
+            .collect()
+    }
+
+    /// Rename the (nested) columns of a record batch to their physical names.
+    pub(crate) fn map_batch(&self, batch: &RecordBatch) -> DeltaResult<RecordBatch> {
+        let (fields, columns): (Vec<_>, Vec<_>) = batch
+            .schema()
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .map(|(field, column)| {
+                let physical_name = self.physical_name(field.name())?;
+                match self.schema.field_with_name(&physical_name) {
+                    Ok(physical) if physical.data_type() == field.data_type() => {
+                        Ok((Arc::new(physical.clone()), column.clone()))
+                    }
+                    Ok(physical) => Ok((
+                        Arc::new(physical.clone()),
+                        with_data_type(column, physical.data_type())?,
+                    )),
+                    Err(_) => Ok((field.clone(), column.clone())),
+                }
+            })
+            .collect::<DeltaResult<Vec<(FieldRef, ArrayRef)>>>()?
+            .into_iter()
+            .unzip();
+        Ok(RecordBatch::try_new(
+            Arc::new(ArrowSchema::new_with_metadata(
+                fields,
+                batch.schema().metadata().clone(),
+            )),
+            columns,
+        )?)
+    }
+}
+
+/// Change the data type of an array to a type which only differs in the names or
+/// metadata of its (nested) fields.
+fn with_data_type(array: &ArrayRef, data_type: &ArrowDataType) -> Result<ArrayRef, ArrowError> {
+    Ok(make_array(retype(array.to_data(), data_type)?))
+}
+
+fn retype(data: ArrayData, data_type: &ArrowDataType) -> Result<ArrayData, ArrowError> {
+    if data.data_type() == data_type {
+        return Ok(data);
+    }
+    let child_types: Vec<&ArrowDataType> = match data_type {
+        ArrowDataType::Struct(fields) => fields.iter().map(|f| f.data_type()).collect(),
+        ArrowDataType::List(field)
+        | ArrowDataType::LargeList(field)
+        | ArrowDataType::FixedSizeList(field, _)
+        | ArrowDataType::Map(field, _) => vec![field.data_type()],
+        _ => vec![],
+    };
+    let child_data = data
+        .child_data()
+        .iter()
+        .zip(child_types)
+        .map(|(child, data_type)| retype(child.clone(), data_type))
+        .collect::<Result<Vec<_>, _>>()?;
+    data.into_builder()
+        .data_type(data_type.clone())
+        .child_data(child_data)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::{ColumnMetadataKey, MetadataValue};
+    use arrow_array::{Int32Array, StructArray};
+
+    fn mapped_field(name: &str, data_type: DataType, id: i32) -> StructField {
+        StructField::new(name, data_type, true).with_metadata([
+            (
+                ColumnMetadataKey::ColumnMappingId.as_ref(),
+                MetadataValue::Number(id.into()),
+            ),
+            (
+                ColumnMetadataKey::ColumnMappingPhysicalName.as_ref(),
+                MetadataValue::String(format!("col-{id}")),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_map_batch() {
+        let table_schema = StructType::new(vec![
+            mapped_field("id", DataType::INTEGER, 1),
+            mapped_field(
+                "nested",
+                DataType::struct_type(vec![mapped_field("value", DataType::INTEGER, 3)]),
+                2,
+            ),
+        ]);
+        let schema: ArrowSchema = (&table_schema).try_into().unwrap();
+        let nested = StructArray::from(vec![(
+            Arc::new(Field::new("value", ArrowDataType::Int32, true)),
+            Arc::new(Int32Array::from(vec![3, 4])) as ArrayRef,
+        )]);
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                Field::new("id", ArrowDataType::Int32, true),
+                Field::new("nested", nested.data_type().clone(), true),
+            ])),
+            vec![Arc::new(Int32Array::from(vec![1, 2])), Arc::new(nested)],
+        )
+        .unwrap();
+
+        let mapper = PhysicalMapper::try_new(&schema, &table_schema, ColumnMappingMode::Name)
+            .unwrap()
+            .unwrap();
+        let physical = mapper.map_batch(&batch).unwrap();
+
+        let id = physical.schema().field_with_name("col-1").unwrap().clone();
+        assert_eq!(
+            id.metadata().get(PARQUET_FIELD_ID_META_KEY),
+            Some(&"1".to_string())
+        );
+        let nested = physical.column_by_name("col-2").unwrap();
+        match nested.data_type() {
+            ArrowDataType::Struct(fields) => assert_eq!(fields[0].name(), "col-3"),
+            _ => panic!("expected struct type"),
+        }
+        assert_eq!(mapper.physical_path("nested.value").unwrap(), "col-2.col-3");
+
+        assert!(
+            PhysicalMapper::try_new(&schema, &table_schema, ColumnMappingMode::None)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
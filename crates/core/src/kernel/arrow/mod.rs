@@ -13,6 +13,7 @@ use super::{
     DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE,
 };
 
+pub(crate) mod column_mapping;
 pub(crate) mod extract;
 pub(crate) mod json;
 
@@ -429,6 +430,33 @@ macro_rules! arrow_defs {
     }
 }
 
+/// Returns an arrow schema for the top level file of a V2 checkpoint
+///
+/// In addition to the fields of a classic checkpoint, it contains the
+/// `checkpointMetadata` and `sidecar` actions.
+pub(crate) fn delta_log_schema_for_v2_checkpoint(
+    checkpoint_schema: &ArrowSchema,
+) -> ArrowSchemaRef {
+    lazy_static! {
+        static ref V2_FIELDS: Vec<ArrowField> = arrow_defs![
+            checkpointMetadata[
+                version:Int64 not_null,
+                tags
+            ],
+            sidecar[
+                path:Utf8 not_null,
+                sizeInBytes:Int64 not_null,
+                modificationTime:Int64 not_null,
+                tags
+            ]
+        ];
+    };
+
+    let mut fields = checkpoint_schema.fields().to_vec();
+    fields.extend(V2_FIELDS.iter().cloned().map(Arc::new));
+    Arc::new(ArrowSchema::new(fields))
+}
+
 /// Returns an arrow schema representing the delta log for use in checkpoints
 ///
 /// # Arguments
@@ -437,10 +465,14 @@ macro_rules! arrow_defs {
 /// * `partition_columns` - The list of partition columns of the table.
 /// * `use_extended_remove_schema` - Whether to include extended file metadata in remove action schema.
 ///    Required for compatibility with different versions of Databricks runtime.
+/// * `write_stats_as_json` - Whether to include the json encoded `stats` column in add actions.
+/// * `write_stats_as_struct` - Whether to include `stats_parsed` and `partitionValues_parsed` in add actions.
 pub(crate) fn delta_log_schema_for_table(
     table_schema: ArrowSchema,
     partition_columns: &[String],
     use_extended_remove_schema: bool,
+    write_stats_as_json: bool,
+    write_stats_as_struct: bool,
 ) -> ArrowSchemaRef {
     lazy_static! {
         static ref SCHEMA_FIELDS: Vec<ArrowField> = arrow_defs![
@@ -463,6 +495,11 @@ pub(crate) fn delta_log_schema_for_table(
                 txn[
                     appId:Utf8,
                     version:Int64
+                ],
+                domainMetadata[
+                    domain:Utf8,
+                    configuration:Utf8,
+                    removed:Boolean
                 ]
         ];
         static ref ADD_FIELDS: Vec<ArrowField> = arrow_defs![
@@ -479,7 +516,9 @@ pub(crate) fn delta_log_schema_for_table(
                 offset:Int32 null,
                 sizeInBytes:Int32 not_null,
                 cardinality:Int64 not_null
-            ]
+            ],
+            baseRowId:Int64,
+            defaultRowCommitVersion:Int64
         ];
         static ref REMOVE_FIELDS: Vec<ArrowField> = arrow_defs![
             path: Utf8,
@@ -528,17 +567,22 @@ pub(crate) fn delta_log_schema_for_table(
         stats_parsed_fields.push(null_count_struct);
     }
     let mut add_fields = ADD_FIELDS.clone();
-    add_fields.push(ArrowField::new(
-        "stats_parsed",
-        ArrowDataType::Struct(stats_parsed_fields.into()),
-        true,
-    ));
-    if !partition_fields.is_empty() {
+    if !write_stats_as_json {
+        add_fields.retain(|f| f.name() != "stats");
+    }
+    if write_stats_as_struct {
         add_fields.push(ArrowField::new(
-            "partitionValues_parsed",
-            ArrowDataType::Struct(partition_fields.into()),
+            "stats_parsed",
+            ArrowDataType::Struct(stats_parsed_fields.into()),
             true,
         ));
+        if !partition_fields.is_empty() {
+            add_fields.push(ArrowField::new(
+                "partitionValues_parsed",
+                ArrowDataType::Struct(partition_fields.into()),
+                true,
+            ));
+        }
     }
 
     // create remove fields with or without extendedFileMetadata
@@ -635,15 +679,27 @@ mod tests {
             ArrowField::new("col1", ArrowDataType::Int32, true),
         ]);
         let partition_columns = vec!["pcol".to_string()];
-        let log_schema =
-            delta_log_schema_for_table(table_schema.clone(), partition_columns.as_slice(), false);
+        let log_schema = delta_log_schema_for_table(
+            table_schema.clone(),
+            partition_columns.as_slice(),
+            false,
+            true,
+            true,
+        );
 
         // verify top-level schema contains all expected fields and they are named correctly.
-        let expected_fields = ["metaData", "protocol", "txn", "remove", "add"];
+        let expected_fields = [
+            "metaData",
+            "protocol",
+            "txn",
+            "domainMetadata",
+            "remove",
+            "add",
+        ];
         for f in log_schema.fields().iter() {
             assert!(expected_fields.contains(&f.name().as_str()));
         }
-        assert_eq!(5, log_schema.fields().len());
+        assert_eq!(6, log_schema.fields().len());
 
         // verify add fields match as expected. a lot of transformation goes into these.
         let add_fields: Vec<_> = log_schema
@@ -669,6 +725,8 @@ mod tests {
                 "partitionValues",
                 "tags",
                 "deletionVector",
+                "baseRowId",
+                "defaultRowCommitVersion",
                 "stats_parsed",
                 "partitionValues_parsed"
             ],
@@ -737,8 +795,13 @@ mod tests {
         assert_eq!(4, num_remove_fields);
 
         // verify extended remove schema fields **ARE** included when `use_extended_remove_schema` is true.
-        let log_schema =
-            delta_log_schema_for_table(table_schema, partition_columns.as_slice(), true);
+        let log_schema = delta_log_schema_for_table(
+            table_schema.clone(),
+            partition_columns.as_slice(),
+            true,
+            true,
+            true,
+        );
         let remove_fields: Vec<_> = log_schema
             .fields()
             .iter()
@@ -764,6 +827,34 @@ mod tests {
         for f in remove_fields.iter() {
             assert!(expected_fields.contains(&f.name().as_str()));
         }
+
+        // verify the stats columns follow the checkpoint stats configuration
+        let add_field_names = |schema: ArrowSchemaRef| -> Vec<String> {
+            match schema.field_with_name("add").unwrap().data_type() {
+                ArrowDataType::Struct(fields) => fields.iter().map(|f| f.name().clone()).collect(),
+                _ => unreachable!(),
+            }
+        };
+        let names = add_field_names(delta_log_schema_for_table(
+            table_schema.clone(),
+            partition_columns.as_slice(),
+            false,
+            true,
+            false,
+        ));
+        assert!(names.contains(&"stats".to_string()));
+        assert!(!names.contains(&"stats_parsed".to_string()));
+        assert!(!names.contains(&"partitionValues_parsed".to_string()));
+        let names = add_field_names(delta_log_schema_for_table(
+            table_schema,
+            partition_columns.as_slice(),
+            false,
+            false,
+            true,
+        ));
+        assert!(!names.contains(&"stats".to_string()));
+        assert!(names.contains(&"stats_parsed".to_string()));
+        assert!(names.contains(&"partitionValues_parsed".to_string()));
     }
 
     #[test]
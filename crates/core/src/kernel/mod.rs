@@ -12,6 +12,7 @@ pub use error::*;
 pub use expressions::*;
 pub use models::*;
 pub use snapshot::*;
+pub(crate) use snapshot::{PathExt, SIDECAR_FOLDER};
 
 /// A trait for all kernel types that are used as part of data checking
 pub trait DataCheck {
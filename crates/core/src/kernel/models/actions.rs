@@ -1,10 +1,9 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::str::FromStr;
-// use std::io::{Cursor, Read};
-// use std::sync::Arc;
 
-// use roaring::RoaringTreemap;
+use object_store::{path::Path, ObjectStore};
+use roaring::RoaringTreemap;
 use serde::{Deserialize, Serialize};
 use tracing::warn;
 use url::Url;
@@ -179,6 +178,8 @@ pub enum ReaderFeatures {
     TimestampWithoutTimezone,
     /// version 2 of checkpointing
     V2Checkpoint,
+    /// Widening the data types of columns
+    TypeWidening,
     /// If we do not match any other reader features
     #[serde(untagged)]
     Other(String),
@@ -194,6 +195,7 @@ impl From<&parquet::record::Field> for ReaderFeatures {
                 }
                 "timestampNtz" => ReaderFeatures::TimestampWithoutTimezone,
                 "v2Checkpoint" => ReaderFeatures::V2Checkpoint,
+                "typeWidening" => ReaderFeatures::TypeWidening,
                 f => ReaderFeatures::Other(f.to_string()),
             },
             f => ReaderFeatures::Other(f.to_string()),
@@ -214,6 +216,7 @@ impl From<&str> for ReaderFeatures {
             "deletionVectors" => ReaderFeatures::DeletionVectors,
             "timestampNtz" => ReaderFeatures::TimestampWithoutTimezone,
             "v2Checkpoint" => ReaderFeatures::V2Checkpoint,
+            "typeWidening" => ReaderFeatures::TypeWidening,
             f => ReaderFeatures::Other(f.to_string()),
         }
     }
@@ -226,6 +229,7 @@ impl AsRef<str> for ReaderFeatures {
             ReaderFeatures::DeletionVectors => "deletionVectors",
             ReaderFeatures::TimestampWithoutTimezone => "timestampNtz",
             ReaderFeatures::V2Checkpoint => "v2Checkpoint",
+            ReaderFeatures::TypeWidening => "typeWidening",
             ReaderFeatures::Other(f) => f,
         }
     }
@@ -269,6 +273,12 @@ pub enum WriterFeatures {
     V2Checkpoint,
     /// Iceberg compatibility support
     IcebergCompatV1,
+    /// Widening the data types of columns
+    TypeWidening,
+    /// Monotonic timestamps stored in the commit info of each commit
+    InCommitTimestamp,
+    /// Protection of the checkpoints around the removal of a feature
+    CheckpointProtection,
     /// If we do not match any other reader features
     #[serde(untagged)]
     Other(String),
@@ -296,6 +306,11 @@ impl From<&str> for WriterFeatures {
             "domainMetadata" => WriterFeatures::DomainMetadata,
             "v2Checkpoint" => WriterFeatures::V2Checkpoint,
             "icebergCompatV1" => WriterFeatures::IcebergCompatV1,
+            "typeWidening" | "delta.enableTypeWidening" => WriterFeatures::TypeWidening,
+            "inCommitTimestamp" | "delta.enableInCommitTimestamps" => {
+                WriterFeatures::InCommitTimestamp
+            }
+            "checkpointProtection" => WriterFeatures::CheckpointProtection,
             f => WriterFeatures::Other(f.to_string()),
         }
     }
@@ -317,6 +332,9 @@ impl AsRef<str> for WriterFeatures {
             WriterFeatures::DomainMetadata => "domainMetadata",
             WriterFeatures::V2Checkpoint => "v2Checkpoint",
             WriterFeatures::IcebergCompatV1 => "icebergCompatV1",
+            WriterFeatures::TypeWidening => "typeWidening",
+            WriterFeatures::InCommitTimestamp => "inCommitTimestamp",
+            WriterFeatures::CheckpointProtection => "checkpointProtection",
             WriterFeatures::Other(f) => f,
         }
     }
@@ -345,6 +363,9 @@ impl From<&parquet::record::Field> for WriterFeatures {
                 "domainMetadata" => WriterFeatures::DomainMetadata,
                 "v2Checkpoint" => WriterFeatures::V2Checkpoint,
                 "icebergCompatV1" => WriterFeatures::IcebergCompatV1,
+                "typeWidening" => WriterFeatures::TypeWidening,
+                "inCommitTimestamp" => WriterFeatures::InCommitTimestamp,
+                "checkpointProtection" => WriterFeatures::CheckpointProtection,
                 f => WriterFeatures::Other(f.to_string()),
             },
             f => WriterFeatures::Other(f.to_string()),
@@ -473,56 +494,120 @@ impl DeletionVectorDescriptor {
         }
     }
 
-    // TODO read only required byte ranges
-    // pub fn read(
-    //     &self,
-    //     fs_client: Arc<dyn FileSystemClient>,
-    //     parent: Url,
-    // ) -> DeltaResult<RoaringTreemap> {
-    //     match self.absolute_path(&parent)? {
-    //         None => {
-    //             let bytes = z85::decode(&self.path_or_inline_dv)
-    //                 .map_err(|_| Error::DeletionVector("Failed to decode DV".to_string()))?;
-    //             RoaringTreemap::deserialize_from(&bytes[12..])
-    //                 .map_err(|err| Error::DeletionVector(err.to_string()))
-    //         }
-    //         Some(path) => {
-    //             let offset = self.offset;
-    //             let size_in_bytes = self.size_in_bytes;
-    //
-    //             let dv_data = fs_client
-    //                 .read_files(vec![(path, None)])?
-    //                 .next()
-    //                 .ok_or(Error::MissingData("No deletion Vector data".to_string()))??;
-    //
-    //             let mut cursor = Cursor::new(dv_data);
-    //             if let Some(offset) = offset {
-    //                 // TODO should we read the datasize from the DV file?
-    //                 // offset plus datasize bytes
-    //                 cursor.set_position((offset + 4) as u64);
-    //             }
-    //
-    //             let mut buf = vec![0; 4];
-    //             cursor
-    //                 .read(&mut buf)
-    //                 .map_err(|err| Error::DeletionVector(err.to_string()))?;
-    //             let magic =
-    //                 i32::from_le_bytes(buf.try_into().map_err(|_| {
-    //                     Error::DeletionVector("filed to read magic bytes".to_string())
-    //                 })?);
-    //             println!("magic  --> : {}", magic);
-    //             // assert!(magic == 1681511377);
-    //
-    //             let mut buf = vec![0; size_in_bytes as usize];
-    //             cursor
-    //                 .read(&mut buf)
-    //                 .map_err(|err| Error::DeletionVector(err.to_string()))?;
-    //
-    //             RoaringTreemap::deserialize_from(Cursor::new(buf))
-    //                 .map_err(|err| Error::DeletionVector(err.to_string()))
-    //         }
-    //     }
-    // }
+    /// Read the deletion vector and return the bitmap of deleted row indexes.
+    ///
+    /// `store` is expected to be rooted at `table_root`, i.e. the object store
+    /// used for reading the data files of the table.
+    pub async fn read(
+        &self,
+        store: &dyn ObjectStore,
+        table_root: &Url,
+    ) -> DeltaResult<RoaringTreemap> {
+        // relative paths are resolved against the root directory, so it must end with a slash
+        let mut table_root = table_root.clone();
+        if !table_root.path().ends_with('/') {
+            table_root.set_path(&format!("{}/", table_root.path()));
+        }
+        match self.absolute_path(&table_root)? {
+            None => {
+                let bytes = z85::decode(&self.path_or_inline_dv)
+                    .map_err(|_| Error::DeletionVector("Failed to decode DV".to_string()))?;
+                deserialize_bitmap_array(&bytes)
+            }
+            Some(dv_url) => {
+                let location = dv_url
+                    .as_str()
+                    .strip_prefix(table_root.as_str())
+                    .ok_or_else(|| {
+                        Error::DeletionVector(format!(
+                            "deletion vector {dv_url} is not located within the table root {table_root}"
+                        ))
+                    })?;
+                let location = Path::from_url_path(location)
+                    .map_err(|err| Error::DeletionVector(err.to_string()))?;
+
+                // Every deletion vector within a file is prefixed with its size (4 bytes, big endian)
+                // and followed by a CRC32 checksum. The offset points to the size prefix.
+                let offset = self.offset.unwrap_or(1) as usize;
+                let size_in_bytes = self.size_in_bytes as usize;
+                let bytes = store
+                    .get_range(&location, offset..offset + 4 + size_in_bytes)
+                    .await?;
+
+                let stored_size =
+                    u32::from_be_bytes(bytes[..4].try_into().map_err(|_| {
+                        Error::DeletionVector("failed to read DV size".to_string())
+                    })?);
+                if stored_size as usize != size_in_bytes {
+                    return Err(Error::DeletionVector(format!(
+                        "DV size mismatch: expected {size_in_bytes} bytes, found {stored_size}"
+                    )));
+                }
+                deserialize_bitmap_array(&bytes[4..])
+            }
+        }
+    }
+
+    /// Persist a bitmap of deleted row indexes as a new deletion vector file and
+    /// return the descriptor referencing it.
+    ///
+    /// `store` is expected to be rooted at the table root, see [`Self::read`].
+    pub async fn write(store: &dyn ObjectStore, bitmap: &RoaringTreemap) -> DeltaResult<Self> {
+        let data = serialize_bitmap_array(bitmap)?;
+
+        // A deletion vector file starts with a format version byte, followed by the
+        // size prefixed bitmap and a CRC32 checksum of the bitmap data.
+        let mut bytes = Vec::with_capacity(data.len() + 9);
+        bytes.push(DV_FILE_FORMAT_VERSION);
+        bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&data);
+        bytes.extend_from_slice(&crc32fast::hash(&data).to_be_bytes());
+
+        let uuid = uuid::Uuid::new_v4();
+        let location = Path::from(format!("deletion_vector_{uuid}.bin"));
+        store.put(&location, bytes.into()).await?;
+
+        Ok(Self {
+            storage_type: StorageType::UuidRelativePath,
+            path_or_inline_dv: z85::encode(uuid.as_bytes()),
+            offset: Some(1),
+            size_in_bytes: data.len() as i32,
+            cardinality: bitmap.len() as i64,
+        })
+    }
+}
+
+/// Magic number identifying the `RoaringBitmapArray` serialization format.
+const DV_MAGIC_NUMBER: u32 = 1681511377;
+
+/// Version of the deletion vector file format written by [`DeletionVectorDescriptor::write`].
+const DV_FILE_FORMAT_VERSION: u8 = 1;
+
+/// Deserialize a `RoaringBitmapArray` as described in the [Deletion Vector Format].
+///
+/// [Deletion Vector Format]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#Deletion-Vector-Format
+fn deserialize_bitmap_array(bytes: &[u8]) -> DeltaResult<RoaringTreemap> {
+    if bytes.len() < 4 {
+        return Err(Error::DeletionVector("DV data is too short".to_string()));
+    }
+    let magic = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+    if magic != DV_MAGIC_NUMBER {
+        return Err(Error::DeletionVector(format!(
+            "invalid magic number for DV: {magic}"
+        )));
+    }
+    RoaringTreemap::deserialize_from(&bytes[4..])
+        .map_err(|err| Error::DeletionVector(err.to_string()))
+}
+
+/// Serialize a bitmap into the `RoaringBitmapArray` format, see [`deserialize_bitmap_array`].
+fn serialize_bitmap_array(bitmap: &RoaringTreemap) -> DeltaResult<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(4 + bitmap.serialized_size());
+    bytes.extend_from_slice(&DV_MAGIC_NUMBER.to_le_bytes());
+    bitmap
+        .serialize_into(&mut bytes)
+        .map_err(|err| Error::DeletionVector(err.to_string()))?;
+    Ok(bytes)
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -681,6 +766,11 @@ pub struct CommitInfo {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp: Option<i64>,
 
+    /// Monotonically increasing commit timestamp in millis, written when the
+    /// `inCommitTimestamp` table feature is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub in_commit_timestamp: Option<i64>,
+
     /// Id of the user invoking the commit
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user_id: Option<String>,
@@ -735,8 +825,8 @@ pub struct DomainMetadata {
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
 /// This action is only allowed in checkpoints following V2 spec. It describes the details about the checkpoint.
 pub struct CheckpointMetadata {
-    /// The flavor of the V2 checkpoint. Allowed values: "flat".
-    pub flavor: String,
+    /// The checkpoint version.
+    pub version: i64,
 
     /// Map containing any additional metadata about the v2 spec checkpoint.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -748,9 +838,9 @@ pub struct CheckpointMetadata {
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct Sidecar {
-    /// The name of the sidecar file (not a path).
-    /// The file must reside in the _delta_log/_sidecars directory.
-    pub file_name: String,
+    /// URI-encoded path to the sidecar file.
+    /// The file must reside in the _delta_log/_sidecars directory, usually only its name is stored.
+    pub path: String,
 
     /// The size of the sidecar file in bytes
     pub size_in_bytes: i64,
@@ -758,11 +848,6 @@ pub struct Sidecar {
     /// The time this sidecar file was created, as milliseconds since the epoch.
     pub modification_time: i64,
 
-    /// Type of sidecar. Valid values are: "fileaction".
-    /// This could be extended in future to allow different kinds of sidecars.
-    #[serde(rename = "type")]
-    pub sidecar_type: String,
-
     /// Map containing any additional metadata about the checkpoint sidecar file.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<HashMap<String, Option<String>>>,
@@ -887,15 +972,10 @@ pub(crate) mod serde_path {
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
-    // use std::sync::Arc;
-
-    // use object_store::local::LocalFileSystem;
 
     use crate::kernel::PrimitiveType;
 
     use super::*;
-    // use crate::client::filesystem::ObjectStoreFileSystemClient;
-    // use crate::executor::tokio::TokioBackgroundExecutor;
 
     fn dv_relateive() -> DeletionVectorDescriptor {
         DeletionVectorDescriptor {
@@ -973,24 +1053,63 @@ mod tests {
         println!("{:?}", types);
     }
 
-    // #[test]
-    // fn test_deletion_vector_read() {
-    //     let store = Arc::new(LocalFileSystem::new());
-    //     let path =
-    //         std::fs::canonicalize(PathBuf::from("../deltalake-test/tests/data/table-with-dv-small/")).unwrap();
-    //     let parent = url::Url::from_directory_path(path).unwrap();
-    //     let root = object_store::path::Path::from(parent.path());
-    //     let fs_client = Arc::new(ObjectStoreFileSystemClient::new(
-    //         store,
-    //         root,
-    //         Arc::new(TokioBackgroundExecutor::new()),
-    //     ));
-    //
-    //     let example = dv_example();
-    //     let tree_map = example.read(fs_client, parent).unwrap();
-    //
-    //     let expected: Vec<u64> = vec![0, 9];
-    //     let found = tree_map.iter().collect::<Vec<_>>();
-    //     assert_eq!(found, expected)
-    // }
+    #[tokio::test]
+    async fn test_deletion_vector_read() {
+        let path = std::fs::canonicalize(PathBuf::from("../test/tests/data/table-with-dv-small/"))
+            .unwrap();
+        let parent = url::Url::from_directory_path(path).unwrap();
+        let store =
+            object_store::local::LocalFileSystem::new_with_prefix(parent.to_file_path().unwrap())
+                .unwrap();
+
+        let example = dv_example();
+        let tree_map = example.read(&store, &parent).await.unwrap();
+
+        let expected: Vec<u64> = vec![0, 9];
+        let found = tree_map.iter().collect::<Vec<_>>();
+        assert_eq!(found, expected)
+    }
+
+    #[tokio::test]
+    async fn test_deletion_vector_read_inline() {
+        let parent = Url::parse("s3://mytable/").unwrap();
+        let store = object_store::memory::InMemory::new();
+
+        let expected: Vec<u64> = vec![3, 4, 7, 11, 18, 29, 1 << 33];
+        let bitmap = expected.iter().copied().collect::<RoaringTreemap>();
+        let mut bytes = serialize_bitmap_array(&bitmap).unwrap();
+        let size_in_bytes = bytes.len() as i32;
+        // z85 requires the input to be padded to a multiple of 4 bytes
+        bytes.resize((bytes.len() + 3) / 4 * 4, 0);
+
+        let inline = DeletionVectorDescriptor {
+            storage_type: StorageType::Inline,
+            path_or_inline_dv: z85::encode(&bytes),
+            offset: None,
+            size_in_bytes,
+            cardinality: expected.len() as i64,
+        };
+        let tree_map = inline.read(&store, &parent).await.unwrap();
+
+        let found = tree_map.iter().collect::<Vec<_>>();
+        assert_eq!(found, expected)
+    }
+
+    #[tokio::test]
+    async fn test_deletion_vector_write() {
+        let parent = Url::parse("memory:///").unwrap();
+        let store = object_store::memory::InMemory::new();
+
+        let expected: Vec<u64> = vec![0, 5, 17, 1 << 40];
+        let bitmap = expected.iter().copied().collect::<RoaringTreemap>();
+        let dv = DeletionVectorDescriptor::write(&store, &bitmap)
+            .await
+            .unwrap();
+        assert_eq!(dv.storage_type, StorageType::UuidRelativePath);
+        assert_eq!(dv.cardinality, expected.len() as i64);
+
+        let tree_map = dv.read(&store, &parent).await.unwrap();
+        let found = tree_map.iter().collect::<Vec<_>>();
+        assert_eq!(found, expected)
+    }
 }
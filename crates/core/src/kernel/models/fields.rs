@@ -90,6 +90,7 @@ lazy_static! {
         "commitInfo",
         StructType::new(vec![
             StructField::new("timestamp", DataType::LONG, false),
+            StructField::new("inCommitTimestamp", DataType::LONG, true),
             StructField::new("operation", DataType::STRING, false),
             StructField::new("isolationLevel", DataType::STRING, true),
             StructField::new("isBlindAppend", DataType::BOOLEAN, true),
@@ -188,15 +189,7 @@ lazy_static! {
         "domainMetadata",
         StructType::new(vec![
             StructField::new("domain", DataType::STRING, false),
-            StructField::new(
-                "configuration",
-                MapType::new(
-                    DataType::STRING,
-                    DataType::STRING,
-                    true,
-                ),
-                true,
-            ),
+            StructField::new("configuration", DataType::STRING, false),
             StructField::new("removed", DataType::BOOLEAN, false),
         ]),
         true,
@@ -205,7 +198,7 @@ lazy_static! {
     static ref CHECKPOINT_METADATA_FIELD: StructField = StructField::new(
         "checkpointMetadata",
         StructType::new(vec![
-            StructField::new("flavor", DataType::STRING, false),
+            StructField::new("version", DataType::LONG, false),
             tags_field(),
         ]),
         true,
@@ -215,9 +208,8 @@ lazy_static! {
         "sidecar",
         StructType::new(vec![
             StructField::new("path", DataType::STRING, false),
-            StructField::new("sizeInBytes", DataType::LONG, true),
+            StructField::new("sizeInBytes", DataType::LONG, false),
             StructField::new("modificationTime", DataType::LONG, false),
-            StructField::new("type", DataType::STRING, false),
             tags_field(),
         ]),
         true,
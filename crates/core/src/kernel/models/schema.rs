@@ -12,6 +12,7 @@ use serde_json::Value;
 use crate::kernel::error::Error;
 use crate::kernel::DataCheck;
 use crate::protocol::ProtocolError;
+use crate::table::config::ColumnMappingMode;
 
 /// Type alias for a top level schema
 pub type Schema = StructType;
@@ -23,11 +24,13 @@ pub type SchemaRef = Arc<StructType>;
 #[serde(untagged)]
 pub enum MetadataValue {
     /// A number value
-    Number(i32),
+    Number(i64),
     /// A string value
     String(String),
     /// A Boolean value
     Boolean(bool),
+    /// Any other json value, e.g. the type changes of a widened column
+    Other(Value),
 }
 
 impl From<String> for MetadataValue {
@@ -44,6 +47,12 @@ impl From<&String> for MetadataValue {
 
 impl From<i32> for MetadataValue {
     fn from(value: i32) -> Self {
+        Self::Number(value.into())
+    }
+}
+
+impl From<i64> for MetadataValue {
+    fn from(value: i64) -> Self {
         Self::Number(value)
     }
 }
@@ -71,6 +80,8 @@ pub enum ColumnMetadataKey {
     IdentityHighWaterMark,
     IdentityAllowExplicitInsert,
     Invariants,
+    TypeChanges,
+    Comment,
 }
 
 impl AsRef<str> for ColumnMetadataKey {
@@ -84,6 +95,8 @@ impl AsRef<str> for ColumnMetadataKey {
             Self::IdentityStart => "delta.identity.start",
             Self::IdentityStep => "delta.identity.step",
             Self::Invariants => "delta.invariants",
+            Self::TypeChanges => "delta.typeChanges",
+            Self::Comment => "comment",
         }
     }
 }
@@ -117,6 +130,66 @@ impl DataCheck for Invariant {
     }
 }
 
+/// A column whose values are computed from other columns of a Delta table.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct GeneratedColumn {
+    /// The name of the column.
+    pub name: String,
+    /// The data type of the column.
+    pub data_type: DataType,
+    /// The SQL expression the column values are generated with.
+    pub generation_expr: String,
+    /// The SQL expression that must always evaluate to true for the column values.
+    pub validation_expr: String,
+}
+
+impl GeneratedColumn {
+    /// Create a new generated column
+    pub fn new(name: &str, data_type: &DataType, generation_expr: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            data_type: data_type.clone(),
+            generation_expr: generation_expr.to_string(),
+            validation_expr: format!("{name} IS NOT DISTINCT FROM ({generation_expr})"),
+        }
+    }
+}
+
+impl DataCheck for GeneratedColumn {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_expression(&self) -> &str {
+        &self.validation_expr
+    }
+}
+
+/// A column whose values are generated by writers when they are not provided.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct IdentityColumn {
+    /// The name of the column.
+    pub name: String,
+    /// The first value generated for the column.
+    pub start: i64,
+    /// The increment between consecutive generated values. Must not be zero.
+    pub step: i64,
+    /// The last value generated for the column, if any values were generated yet.
+    pub high_water_mark: Option<i64>,
+    /// Whether values may be provided explicitly when inserting data.
+    pub allow_explicit_insert: bool,
+}
+
+impl IdentityColumn {
+    /// The next value to be generated for the column
+    pub fn next_value(&self) -> i64 {
+        match self.high_water_mark {
+            Some(high_water_mark) => high_water_mark + self.step,
+            None => self.start,
+        }
+    }
+}
+
 /// Represents a struct field defined in the Delta table schema.
 // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#Schema-Serialization-Format
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -197,12 +270,113 @@ impl StructField {
             None => Ok(&self.name),
             Some(MetadataValue::Boolean(_)) => Ok(&self.name),
             Some(MetadataValue::String(s)) => Ok(s),
-            Some(MetadataValue::Number(_)) => Err(Error::MetadataError(
+            Some(MetadataValue::Number(_) | MetadataValue::Other(_)) => Err(Error::MetadataError(
                 "Unexpected type for physical name".to_string(),
             )),
         }
     }
 
+    /// Returns the name used for the column in data files and the log for the given
+    /// column mapping mode.
+    pub fn physical_name_for(&self, mode: ColumnMappingMode) -> Result<&str, Error> {
+        match mode {
+            ColumnMappingMode::None => Ok(&self.name),
+            ColumnMappingMode::Id | ColumnMappingMode::Name => self.physical_name(),
+        }
+    }
+
+    /// Returns the field id assigned to the column by column mapping, if any
+    pub fn column_mapping_id(&self) -> Option<i32> {
+        match self.get_config_value(&ColumnMetadataKey::ColumnMappingId) {
+            Some(MetadataValue::Number(id)) => i32::try_from(*id).ok(),
+            Some(MetadataValue::String(id)) => id.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns a copy of the field where the field itself and all nested fields
+    /// are named by their physical name for the given column mapping mode.
+    pub fn make_physical(&self, mode: ColumnMappingMode) -> Result<Self, Error> {
+        Ok(Self {
+            name: self.physical_name_for(mode)?.to_string(),
+            data_type: self.data_type.make_physical(mode)?,
+            nullable: self.nullable,
+            metadata: self.metadata.clone(),
+        })
+    }
+
+    /// Returns a copy of the field where the field itself and all nested fields are assigned
+    /// a column mapping id and physical name, unless they already have one.
+    ///
+    /// New ids are assigned starting after `max_column_id`, which is updated to the
+    /// highest id used by any of the fields.
+    pub fn with_column_mapping(&self, max_column_id: &mut i64) -> Self {
+        let mut metadata = self.metadata.clone();
+        match self.column_mapping_id() {
+            Some(id) => *max_column_id = (*max_column_id).max(id as i64),
+            None => {
+                *max_column_id += 1;
+                metadata.insert(
+                    ColumnMetadataKey::ColumnMappingId.as_ref().to_string(),
+                    MetadataValue::Number(*max_column_id),
+                );
+            }
+        }
+        if self
+            .get_config_value(&ColumnMetadataKey::ColumnMappingPhysicalName)
+            .is_none()
+        {
+            metadata.insert(
+                ColumnMetadataKey::ColumnMappingPhysicalName
+                    .as_ref()
+                    .to_string(),
+                MetadataValue::String(format!("col-{}", uuid::Uuid::new_v4())),
+            );
+        }
+        Self {
+            name: self.name.clone(),
+            data_type: self.data_type.with_column_mapping(max_column_id),
+            nullable: self.nullable,
+            metadata,
+        }
+    }
+
+    /// Returns a copy of the field with relaxed nullability and updated metadata
+    ///
+    /// Metadata keys mapped to `None` are removed. Only changes that don't affect existing
+    /// data are allowed: a field can be made nullable, but not non-nullable, and metadata
+    /// maintained by the table in the `delta.` namespace, e.g. column mapping ids, cannot
+    /// be changed.
+    pub fn with_changes(
+        &self,
+        nullable: Option<bool>,
+        metadata: &HashMap<String, Option<MetadataValue>>,
+    ) -> Result<Self, Error> {
+        let mut field = self.clone();
+        match nullable {
+            Some(false) if self.nullable => {
+                return Err(Error::Schema(format!(
+                    "Column {} cannot be made non-nullable",
+                    self.name
+                )))
+            }
+            Some(nullable) => field.nullable = nullable,
+            None => {}
+        }
+        for (key, value) in metadata {
+            if key.starts_with("delta.") {
+                return Err(Error::Schema(format!(
+                    "Column metadata {key} is maintained by the table and cannot be changed"
+                )));
+            }
+            match value {
+                Some(value) => field.metadata.insert(key.clone(), value.clone()),
+                None => field.metadata.remove(key),
+            };
+        }
+        Ok(field)
+    }
+
     #[inline]
     /// Returns the data type of the column
     pub const fn data_type(&self) -> &DataType {
@@ -216,6 +390,18 @@ impl StructField {
     }
 }
 
+/// Position of a column added to a struct
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+pub enum ColumnPosition {
+    /// After all other columns
+    #[default]
+    Last,
+    /// Before all other columns
+    First,
+    /// After the column with the given name
+    After(String),
+}
+
 /// A struct is used to represent both the top-level schema of the table
 /// as well as struct columns that contain nested columns.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Eq, Hash)]
@@ -262,6 +448,105 @@ impl StructType {
         Ok(&self.fields[self.index_of(name)?])
     }
 
+    /// Returns a copy of the schema where all (nested) fields are named by their
+    /// physical name for the given column mapping mode.
+    pub fn make_physical(&self, mode: ColumnMappingMode) -> Result<Self, Error> {
+        Ok(Self::new(
+            self.fields
+                .iter()
+                .map(|field| field.make_physical(mode))
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    /// Returns a copy of the schema where all (nested) fields are assigned a column
+    /// mapping id and physical name, unless they already have one.
+    ///
+    /// See [`StructField::with_column_mapping`].
+    pub fn with_column_mapping(&self, max_column_id: &mut i64) -> Self {
+        Self::new(
+            self.fields
+                .iter()
+                .map(|field| field.with_column_mapping(max_column_id))
+                .collect(),
+        )
+    }
+
+    /// Returns a copy of the schema with `field` added to the struct at `parent`
+    ///
+    /// An empty `parent` path adds a top-level column. Added columns must be nullable,
+    /// since existing rows have no value for them, and must not carry metadata maintained
+    /// by the table in the `delta.` namespace.
+    pub fn with_field_added(
+        &self,
+        parent: &[&str],
+        field: StructField,
+        position: &ColumnPosition,
+    ) -> Result<Self, Error> {
+        let mut fields = self.fields.clone();
+        let Some((name, parent)) = parent.split_first() else {
+            if fields
+                .iter()
+                .any(|f| f.name().eq_ignore_ascii_case(field.name()))
+            {
+                return Err(Error::Schema(format!(
+                    "Column {} already exists",
+                    field.name()
+                )));
+            }
+            if !field.is_nullable() {
+                return Err(Error::Schema(format!(
+                    "Column {} must be nullable, since existing rows have no value for it",
+                    field.name()
+                )));
+            }
+            if let Some(key) = field.metadata.keys().find(|key| key.starts_with("delta.")) {
+                return Err(Error::Schema(format!(
+                    "Column metadata {key} is maintained by the table and cannot be set"
+                )));
+            }
+            let idx = match position {
+                ColumnPosition::Last => fields.len(),
+                ColumnPosition::First => 0,
+                ColumnPosition::After(name) => self.index_of(name)? + 1,
+            };
+            fields.insert(idx, field);
+            return Ok(Self::new(fields));
+        };
+
+        let idx = self.index_of(name)?;
+        let DataType::Struct(nested) = &fields[idx].data_type else {
+            return Err(Error::Schema(format!("Column {name} is not a struct")));
+        };
+        fields[idx].data_type =
+            DataType::Struct(Box::new(nested.with_field_added(parent, field, position)?));
+        Ok(Self::new(fields))
+    }
+
+    /// Returns a copy of the schema with the (nested) field at `path` replaced by the
+    /// result of `update`
+    pub fn with_field_updated(
+        &self,
+        path: &[&str],
+        update: impl FnOnce(&StructField) -> Result<StructField, Error>,
+    ) -> Result<Self, Error> {
+        let Some((name, path)) = path.split_first() else {
+            return Err(Error::Schema("No column provided".to_string()));
+        };
+        let mut fields = self.fields.clone();
+        let idx = self.index_of(name)?;
+        if path.is_empty() {
+            fields[idx] = update(&fields[idx])?;
+            return Ok(Self::new(fields));
+        }
+        let DataType::Struct(nested) = &fields[idx].data_type else {
+            return Err(Error::Schema(format!("Column {name} is not a struct")));
+        };
+        fields[idx].data_type =
+            DataType::Struct(Box::new(nested.with_field_updated(path, update)?));
+        Ok(Self::new(fields))
+    }
+
     /// Get all invariants in the schemas
     pub fn get_invariants(&self) -> Result<Vec<Invariant>, Error> {
         let mut remaining_fields: Vec<(String, StructField)> = self
@@ -335,6 +620,81 @@ impl StructType {
         }
         Ok(invariants)
     }
+
+    /// Get all identity columns in the schema
+    pub fn get_identity_columns(&self) -> Result<Vec<IdentityColumn>, Error> {
+        let number = |field: &StructField, key: ColumnMetadataKey| -> Result<Option<i64>, Error> {
+            match field.get_config_value(&key) {
+                None => Ok(None),
+                Some(MetadataValue::Number(value)) => Ok(Some(*value)),
+                Some(MetadataValue::String(value)) => value.parse().map(Some).map_err(|_| {
+                    Error::MetadataError(format!(
+                        "Invalid value for {} of column {}: {value}",
+                        key.as_ref(),
+                        field.name
+                    ))
+                }),
+                Some(value) => Err(Error::MetadataError(format!(
+                    "Invalid value for {} of column {}: {value:?}",
+                    key.as_ref(),
+                    field.name
+                ))),
+            }
+        };
+
+        self.fields
+            .iter()
+            .filter_map(|field| {
+                let start = match number(field, ColumnMetadataKey::IdentityStart) {
+                    Ok(Some(start)) => start,
+                    Ok(None) => return None,
+                    Err(err) => return Some(Err(err)),
+                };
+                let identity = || {
+                    let step = number(field, ColumnMetadataKey::IdentityStep)?.unwrap_or(1);
+                    if step == 0 {
+                        return Err(Error::MetadataError(format!(
+                            "Identity column {} must have a non-zero step",
+                            field.name
+                        )));
+                    }
+                    let allow_explicit_insert = match field
+                        .get_config_value(&ColumnMetadataKey::IdentityAllowExplicitInsert)
+                    {
+                        Some(MetadataValue::Boolean(allow)) => *allow,
+                        Some(MetadataValue::String(allow)) => allow.eq_ignore_ascii_case("true"),
+                        _ => false,
+                    };
+                    Ok(IdentityColumn {
+                        name: field.name.clone(),
+                        start,
+                        step,
+                        high_water_mark: number(field, ColumnMetadataKey::IdentityHighWaterMark)?,
+                        allow_explicit_insert,
+                    })
+                };
+                Some(identity())
+            })
+            .collect()
+    }
+
+    /// Get all generated columns in the schema
+    pub fn get_generated_columns(&self) -> Vec<GeneratedColumn> {
+        self.fields
+            .iter()
+            .filter_map(|field| {
+                match field
+                    .metadata
+                    .get(ColumnMetadataKey::GenerationExpression.as_ref())
+                {
+                    Some(MetadataValue::String(expr)) => {
+                        Some(GeneratedColumn::new(&field.name, &field.data_type, expr))
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
 }
 
 impl FromIterator<StructField> for StructType {
@@ -640,6 +1000,38 @@ impl DataType {
     pub fn struct_type(fields: Vec<StructField>) -> Self {
         DataType::Struct(Box::new(StructType::new(fields)))
     }
+
+    fn make_physical(&self, mode: ColumnMappingMode) -> Result<Self, Error> {
+        Ok(match self {
+            DataType::Primitive(_) => self.clone(),
+            DataType::Struct(s) => DataType::Struct(Box::new(s.make_physical(mode)?)),
+            DataType::Array(a) => DataType::Array(Box::new(ArrayType::new(
+                a.element_type().make_physical(mode)?,
+                a.contains_null(),
+            ))),
+            DataType::Map(m) => DataType::Map(Box::new(MapType::new(
+                m.key_type().make_physical(mode)?,
+                m.value_type().make_physical(mode)?,
+                m.value_contains_null(),
+            ))),
+        })
+    }
+
+    fn with_column_mapping(&self, max_column_id: &mut i64) -> Self {
+        match self {
+            DataType::Primitive(_) => self.clone(),
+            DataType::Struct(s) => DataType::Struct(Box::new(s.with_column_mapping(max_column_id))),
+            DataType::Array(a) => DataType::Array(Box::new(ArrayType::new(
+                a.element_type().with_column_mapping(max_column_id),
+                a.contains_null(),
+            ))),
+            DataType::Map(m) => DataType::Map(Box::new(MapType::new(
+                m.key_type().with_column_mapping(max_column_id),
+                m.value_type().with_column_mapping(max_column_id),
+                m.value_contains_null(),
+            ))),
+        }
+    }
 }
 
 impl Display for DataType {
@@ -842,6 +1234,102 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_make_physical() {
+        let schema: StructType = serde_json::from_value(json!({
+            "type": "struct",
+            "fields": [{
+                "name": "a",
+                "type": {
+                    "type": "struct",
+                    "fields": [{
+                        "name": "b",
+                        "type": "integer",
+                        "nullable": true,
+                        "metadata": {
+                            "delta.columnMapping.id": 2,
+                            "delta.columnMapping.physicalName": "col-b"
+                        }
+                    }]
+                },
+                "nullable": true,
+                "metadata": {
+                    "delta.columnMapping.id": 1,
+                    "delta.columnMapping.physicalName": "col-a"
+                }
+            }]
+        }))
+        .unwrap();
+
+        let field = schema.field_with_name("a").unwrap();
+        assert_eq!(field.column_mapping_id(), Some(1));
+        assert_eq!(
+            field.physical_name_for(ColumnMappingMode::None).unwrap(),
+            "a"
+        );
+        assert_eq!(
+            field.physical_name_for(ColumnMappingMode::Name).unwrap(),
+            "col-a"
+        );
+
+        assert_eq!(
+            schema.make_physical(ColumnMappingMode::None).unwrap(),
+            schema
+        );
+        let physical = schema.make_physical(ColumnMappingMode::Name).unwrap();
+        let field = physical.field_with_name("col-a").unwrap();
+        assert_eq!(field.column_mapping_id(), Some(1));
+        match field.data_type() {
+            DataType::Struct(inner) => assert!(inner.field_with_name("col-b").is_ok()),
+            _ => panic!("expected struct type"),
+        }
+    }
+
+    #[test]
+    fn test_with_column_mapping() {
+        let schema = StructType::new(vec![
+            StructField::new("a", DataType::INTEGER, true).with_metadata([
+                (
+                    ColumnMetadataKey::ColumnMappingId.as_ref(),
+                    MetadataValue::Number(3),
+                ),
+                (
+                    ColumnMetadataKey::ColumnMappingPhysicalName.as_ref(),
+                    MetadataValue::String("col-a".to_string()),
+                ),
+            ]),
+            StructField::new(
+                "b",
+                DataType::struct_type(vec![StructField::new("c", DataType::STRING, true)]),
+                true,
+            ),
+        ]);
+
+        let mut max_column_id = 0;
+        let mapped = schema.with_column_mapping(&mut max_column_id);
+        assert_eq!(max_column_id, 5);
+
+        let a = mapped.field_with_name("a").unwrap();
+        assert_eq!(a.column_mapping_id(), Some(3));
+        assert_eq!(a.physical_name().unwrap(), "col-a");
+
+        let b = mapped.field_with_name("b").unwrap();
+        assert_eq!(b.column_mapping_id(), Some(4));
+        assert!(b.physical_name().unwrap().starts_with("col-"));
+        match b.data_type() {
+            DataType::Struct(inner) => {
+                let c = inner.field_with_name("c").unwrap();
+                assert_eq!(c.column_mapping_id(), Some(5));
+                assert_ne!(c.physical_name().unwrap(), "c");
+            }
+            _ => panic!("expected struct type"),
+        }
+
+        // fields that already have column mapping metadata are left unchanged
+        assert_eq!(mapped.with_column_mapping(&mut max_column_id), mapped);
+        assert_eq!(max_column_id, 5);
+    }
+
     #[test]
     fn test_read_schemas() {
         let file = std::fs::File::open("./tests/serde/schema.json").unwrap();
@@ -916,11 +1404,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_generated_columns() {
+        let schema: StructType = serde_json::from_value(json!({
+            "type": "struct",
+            "fields": [
+                {"name": "x", "type": "integer", "nullable": true, "metadata": {}},
+                {"name": "y", "type": "integer", "nullable": true, "metadata": {
+                    "delta.generationExpression": "x * 2"
+                }}
+            ]
+        }))
+        .unwrap();
+        let generated = schema.get_generated_columns();
+        assert_eq!(generated.len(), 1);
+        assert_eq!(
+            generated[0],
+            GeneratedColumn::new("y", &DataType::INTEGER, "x * 2")
+        );
+        assert_eq!(
+            generated[0].get_expression(),
+            "y IS NOT DISTINCT FROM (x * 2)"
+        );
+    }
+
     /// <https://github.com/delta-io/delta-rs/issues/2152>
     #[test]
     fn test_identity_columns() {
         let buf = r#"{"type":"struct","fields":[{"name":"ID_D_DATE","type":"long","nullable":true,"metadata":{"delta.identity.start":1,"delta.identity.step":1,"delta.identity.allowExplicitInsert":false}},{"name":"TXT_DateKey","type":"string","nullable":true,"metadata":{}}]}"#;
-        let _schema: StructType = serde_json::from_str(buf).expect("Failed to load");
+        let schema: StructType = serde_json::from_str(buf).expect("Failed to load");
+        let identity_columns = schema.get_identity_columns().unwrap();
+        assert_eq!(
+            identity_columns,
+            vec![IdentityColumn {
+                name: "ID_D_DATE".to_string(),
+                start: 1,
+                step: 1,
+                high_water_mark: None,
+                allow_explicit_insert: false,
+            }]
+        );
+        assert_eq!(identity_columns[0].next_value(), 1);
+
+        let buf = r#"{"type":"struct","fields":[{"name":"id","type":"long","nullable":false,"metadata":{"delta.identity.start":-1,"delta.identity.step":-2,"delta.identity.highWaterMark":-5000000001,"delta.identity.allowExplicitInsert":true}}]}"#;
+        let schema: StructType = serde_json::from_str(buf).expect("Failed to load");
+        let identity_columns = schema.get_identity_columns().unwrap();
+        assert_eq!(identity_columns[0].high_water_mark, Some(-5000000001));
+        assert_eq!(identity_columns[0].next_value(), -5000000003);
+        assert!(identity_columns[0].allow_explicit_insert);
+
+        let buf = r#"{"type":"struct","fields":[{"name":"id","type":"long","nullable":false,"metadata":{"delta.identity.start":1,"delta.identity.step":0}}]}"#;
+        let schema: StructType = serde_json::from_str(buf).expect("Failed to load");
+        assert!(schema.get_identity_columns().is_err());
     }
 
     fn get_hash(field: &StructField) -> u64 {
@@ -983,4 +1518,75 @@ mod tests {
         );
         assert_eq!(get_hash(&field_1), get_hash(&field_2));
     }
+
+    #[test]
+    fn test_with_field_added() {
+        let schema = StructType::new(vec![
+            StructField::new("a", DataType::INTEGER, false),
+            StructField::new(
+                "b",
+                DataType::struct_type(vec![StructField::new("c", DataType::STRING, true)]),
+                true,
+            ),
+        ]);
+
+        let added = schema
+            .with_field_added(
+                &[],
+                StructField::new("d", DataType::LONG, true),
+                &ColumnPosition::After("a".to_string()),
+            )
+            .unwrap();
+        let names = added.fields().iter().map(|f| f.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "d", "b"]);
+
+        let added = schema
+            .with_field_added(
+                &["b"],
+                StructField::new("e", DataType::LONG, true),
+                &ColumnPosition::First,
+            )
+            .unwrap();
+        let DataType::Struct(nested) = added.field_with_name("b").unwrap().data_type() else {
+            panic!("b is not a struct");
+        };
+        let names = nested.fields().iter().map(|f| f.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["e", "c"]);
+
+        let field = StructField::new("A", DataType::LONG, true);
+        assert!(schema
+            .with_field_added(&[], field, &ColumnPosition::Last)
+            .is_err());
+        let field = StructField::new("d", DataType::LONG, false);
+        assert!(schema
+            .with_field_added(&[], field, &ColumnPosition::Last)
+            .is_err());
+        let field = StructField::new("d", DataType::LONG, true);
+        assert!(schema
+            .with_field_added(&["a"], field, &ColumnPosition::Last)
+            .is_err());
+    }
+
+    #[test]
+    fn test_field_with_changes() {
+        let field = StructField::new("a", DataType::INTEGER, false).with_metadata([(
+            ColumnMetadataKey::ColumnMappingId.as_ref(),
+            MetadataValue::Number(1),
+        )]);
+        let comment = HashMap::from([(
+            ColumnMetadataKey::Comment.as_ref().to_string(),
+            Some(MetadataValue::String("comment".to_string())),
+        )]);
+
+        let changed = field.with_changes(Some(true), &comment).unwrap();
+        assert!(changed.is_nullable());
+        assert_eq!(changed.metadata().len(), 2);
+
+        assert!(changed.with_changes(Some(false), &HashMap::new()).is_err());
+        let column_mapping = HashMap::from([(
+            ColumnMetadataKey::ColumnMappingId.as_ref().to_string(),
+            None,
+        )]);
+        assert!(changed.with_changes(None, &column_mapping).is_err());
+    }
 }
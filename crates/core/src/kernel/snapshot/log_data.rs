@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use arrow_array::{Array, Int32Array, Int64Array, MapArray, RecordBatch, StringArray, StructArray};
+use arrow_schema::DataType as ArrowDataType;
 use chrono::{DateTime, Utc};
 use indexmap::IndexMap;
 use object_store::path::Path;
@@ -13,9 +14,10 @@ use crate::kernel::arrow::extract::{extract_and_cast, extract_and_cast_opt};
 use crate::kernel::{
     DataType, DeletionVectorDescriptor, Metadata, Remove, Scalar, StructField, StructType,
 };
+use crate::table::config::{ColumnMappingMode, TableConfig};
 use crate::{DeltaResult, DeltaTableError};
 
-const COL_NUM_RECORDS: &str = "numRecords";
+pub(super) const COL_NUM_RECORDS: &str = "numRecords";
 const COL_MIN_VALUES: &str = "minValues";
 const COL_MAX_VALUES: &str = "maxValues";
 const COL_NULL_COUNT: &str = "nullCount";
@@ -91,7 +93,8 @@ impl<'a> DeletionVectorView<'a> {
         }
     }
 
-    fn descriptor(&self) -> DeletionVectorDescriptor {
+    /// The descriptor used to locate and read the deletion vector.
+    pub fn descriptor(&self) -> DeletionVectorDescriptor {
         DeletionVectorDescriptor {
             storage_type: self.storage_type().parse().unwrap(),
             path_or_inline_dv: self.path_or_inline_dv().to_string(),
@@ -116,7 +119,7 @@ impl<'a> DeletionVectorView<'a> {
     fn offset(&self) -> Option<i32> {
         self.data
             .offset
-            .and_then(|a| a.is_null(self.index).then(|| a.value(self.index)))
+            .and_then(|a| a.is_valid(self.index).then(|| a.value(self.index)))
     }
 }
 
@@ -136,15 +139,23 @@ pub struct LogicalFile<'a> {
     modification_time: &'a Int64Array,
     /// The partition values for this logical file.
     partition_values: &'a MapArray,
+    /// The typed partition values, if written to the checkpoint this file was read from.
+    partition_values_parsed: Option<&'a StructArray>,
     /// Struct containing all available statistics for the columns in this file.
     stats: &'a StructArray,
     /// Array containing the deletion vector data.
     deletion_vector: Option<DeletionVector<'a>>,
+    /// The first row id assigned to this file when row tracking is enabled.
+    base_row_id: Option<&'a Int64Array>,
+    /// The commit version the rows in this file were last committed in.
+    default_row_commit_version: Option<&'a Int64Array>,
 
     /// Pointer to a specific row in the log data.
     index: usize,
     /// Schema fields the table is partitioned by.
     partition_fields: PartitionFields<'a>,
+    /// The column mapping mode of the table.
+    column_mapping_mode: ColumnMappingMode,
 }
 
 impl LogicalFile<'_> {
@@ -206,37 +217,50 @@ impl LogicalFile<'_> {
         let values = keys
             .iter()
             .zip(values.iter())
-            .map(|(k, v)| {
-                let (key, field) = self.partition_fields.get_key_value(k.unwrap()).unwrap();
-                let field_type = match field.data_type() {
+            .filter_map(|(k, v)| k.map(|k| (k, v)))
+            .collect::<HashMap<_, _>>();
+
+        // NOTE: we recreate the map as a IndexMap to ensure the order of the keys is consistently
+        // the same as the order of partition fields. Partition values are keyed by the physical
+        // column names in the log.
+        self.partition_fields
+            .iter()
+            .map(|(k, f)| {
+                let field_type = match f.data_type() {
                     DataType::Primitive(p) => Ok(p),
                     _ => Err(DeltaTableError::Generic(
                         "nested partitioning values are not supported".to_string(),
                     )),
                 }?;
-                Ok((
-                    *key,
-                    v.map(|vv| field_type.parse_scalar(vv))
-                        .transpose()?
-                        .unwrap_or(Scalar::Null(field.data_type().clone())),
-                ))
-            })
-            .collect::<DeltaResult<HashMap<_, _>>>()?;
-
-        // NOTE: we recreate the map as a BTreeMap to ensure the order of the keys is consistently
-        // the same as the order of partition fields.
-        self.partition_fields
-            .iter()
-            .map(|(k, f)| {
+                let physical_name = f.physical_name_for(self.column_mapping_mode)?;
+                if let Some(val) = self.typed_partition_value(physical_name, f) {
+                    return Ok((*k, val));
+                }
                 let val = values
-                    .get(*k)
-                    .cloned()
+                    .get(physical_name)
+                    .copied()
+                    .flatten()
+                    .map(|v| field_type.parse_scalar(v))
+                    .transpose()?
                     .unwrap_or(Scalar::Null(f.data_type.clone()));
                 Ok((*k, val))
             })
             .collect::<DeltaResult<IndexMap<_, _>>>()
     }
 
+    /// Read a partition value from the typed partition values, if available with the
+    /// expected type of the partition field.
+    fn typed_partition_value(&self, physical_name: &str, field: &StructField) -> Option<Scalar> {
+        let column = self
+            .partition_values_parsed?
+            .column_by_name(physical_name)?;
+        let data_type: ArrowDataType = field.data_type().try_into().ok()?;
+        if column.data_type() != &data_type {
+            return None;
+        }
+        Scalar::from_array(column.as_ref(), self.index)
+    }
+
     /// Defines a deletion vector
     pub fn deletion_vector(&self) -> Option<DeletionVectorView<'_>> {
         self.deletion_vector.as_ref().and_then(|arr| {
@@ -249,6 +273,18 @@ impl LogicalFile<'_> {
         })
     }
 
+    /// The row id assigned to the first row of this file, if row tracking is enabled.
+    pub fn base_row_id(&self) -> Option<i64> {
+        self.base_row_id
+            .and_then(|arr| arr.is_valid(self.index).then(|| arr.value(self.index)))
+    }
+
+    /// The commit version rows of this file default to, if row tracking is enabled.
+    pub fn default_row_commit_version(&self) -> Option<i64> {
+        self.default_row_commit_version
+            .and_then(|arr| arr.is_valid(self.index).then(|| arr.value(self.index)))
+    }
+
     /// The number of records stored in the data file.
     pub fn num_records(&self) -> Option<usize> {
         self.stats
@@ -303,8 +339,8 @@ impl LogicalFile<'_> {
             }),
             deletion_vector: self.deletion_vector().map(|dv| dv.descriptor()),
             tags: None,
-            base_row_id: None,
-            default_row_commit_version: None,
+            base_row_id: self.base_row_id(),
+            default_row_commit_version: self.default_row_commit_version(),
         }
     }
 }
@@ -326,12 +362,16 @@ impl<'a> TryFrom<&LogicalFile<'a>> for ObjectMeta {
 /// Helper for processing data from the materialized Delta log.
 pub struct FileStatsAccessor<'a> {
     partition_fields: PartitionFields<'a>,
+    column_mapping_mode: ColumnMappingMode,
     paths: &'a StringArray,
     sizes: &'a Int64Array,
     modification_times: &'a Int64Array,
     stats: &'a StructArray,
     deletion_vector: Option<DeletionVector<'a>>,
+    base_row_ids: Option<&'a Int64Array>,
+    default_row_commit_versions: Option<&'a Int64Array>,
     partition_values: &'a MapArray,
+    partition_values_parsed: Option<&'a StructArray>,
     length: usize,
     pointer: usize,
 }
@@ -347,6 +387,8 @@ impl<'a> FileStatsAccessor<'a> {
         let modification_times = extract_and_cast::<Int64Array>(data, "add.modificationTime")?;
         let stats = extract_and_cast::<StructArray>(data, "add.stats_parsed")?;
         let partition_values = extract_and_cast::<MapArray>(data, "add.partitionValues")?;
+        let partition_values_parsed =
+            extract_and_cast_opt::<StructArray>(data, "add.partitionValues_parsed");
         let partition_fields = Arc::new(
             metadata
                 .partition_columns
@@ -370,14 +412,24 @@ impl<'a> FileStatsAccessor<'a> {
             })
         });
 
+        let base_row_ids = extract_and_cast_opt::<Int64Array>(data, "add.baseRowId");
+        let default_row_commit_versions =
+            extract_and_cast_opt::<Int64Array>(data, "add.defaultRowCommitVersion");
+
+        let column_mapping_mode = TableConfig(&metadata.configuration).column_mapping_mode();
+
         Ok(Self {
             partition_fields,
+            column_mapping_mode,
             paths,
             sizes,
             modification_times,
             stats,
             deletion_vector,
+            base_row_ids,
+            default_row_commit_versions,
             partition_values,
+            partition_values_parsed,
             length: data.num_rows(),
             pointer: 0,
         })
@@ -395,9 +447,13 @@ impl<'a> FileStatsAccessor<'a> {
             size: self.sizes,
             modification_time: self.modification_times,
             partition_values: self.partition_values,
+            partition_values_parsed: self.partition_values_parsed,
             partition_fields: self.partition_fields.clone(),
+            column_mapping_mode: self.column_mapping_mode,
             stats: self.stats,
             deletion_vector: self.deletion_vector.clone(),
+            base_row_id: self.base_row_ids,
+            default_row_commit_version: self.default_row_commit_versions,
             index,
         })
     }
@@ -563,7 +619,21 @@ mod datafusion {
         }
 
         fn num_records(&self) -> Precision<usize> {
-            self.collect_count(COL_NUM_RECORDS)
+            let num_records = self.collect_count(COL_NUM_RECORDS);
+            match self.deleted_records() {
+                Some(deleted) => num_records.sub(&Precision::Exact(deleted)),
+                None => num_records,
+            }
+        }
+
+        /// Number of records logically removed by deletion vectors, if any file has one.
+        fn deleted_records(&self) -> Option<usize> {
+            let dv = self.deletion_vector.as_ref()?;
+            let deleted = (0..self.length)
+                .filter(|idx| dv.storage_type.is_valid(*idx))
+                .map(|idx| dv.cardinality.value(idx) as usize)
+                .sum::<usize>();
+            (deleted > 0).then_some(deleted)
         }
 
         fn total_size_files(&self) -> Precision<usize> {
@@ -601,6 +671,17 @@ mod datafusion {
                 _ => max_value,
             };
 
+            // Rows removed by deletion vectors are still reflected in the file statistics,
+            // so these can only serve as bounds.
+            if self.deleted_records().is_some() {
+                return Ok(ColumnStatistics {
+                    null_count: null_count.to_inexact(),
+                    max_value: max_value.to_inexact(),
+                    min_value: min_value.to_inexact(),
+                    distinct_count: Precision::Absent,
+                });
+            }
+
             Ok(ColumnStatistics {
                 null_count,
                 max_value,
@@ -1,8 +1,8 @@
-use std::cmp::Ordering;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::sync::Arc;
 
-use arrow_array::RecordBatch;
+use arrow_array::{new_null_array, RecordBatch};
+use arrow_schema::Schema as ArrowSchema;
 use chrono::Utc;
 use futures::{stream::BoxStream, StreamExt, TryStreamExt};
 use itertools::Itertools;
@@ -16,17 +16,25 @@ use serde::{Deserialize, Serialize};
 use tracing::debug;
 
 use super::parse;
-use crate::kernel::{arrow::json, ActionType, Metadata, Protocol, Schema, StructType};
+use crate::kernel::{
+    arrow::json, ActionType, DomainMetadata, Metadata, Protocol, Schema, StructType,
+};
 use crate::logstore::LogStore;
 use crate::operations::transaction::CommitData;
 use crate::{DeltaResult, DeltaTableConfig, DeltaTableError};
 
 const LAST_CHECKPOINT_FILE_NAME: &str = "_last_checkpoint";
+/// Folder within the log directory containing the sidecar files of V2 checkpoints
+pub(crate) const SIDECAR_FOLDER: &str = "_sidecars";
 
 lazy_static! {
     static ref CHECKPOINT_FILE_PATTERN: Regex =
-        Regex::new(r"\d+\.checkpoint(\.\d+\.\d+)?\.parquet").unwrap();
+        Regex::new(r"\d+\.checkpoint(\.\d+\.(\d+))?\.parquet").unwrap();
+    static ref V2_CHECKPOINT_FILE_PATTERN: Regex =
+        Regex::new(r"^\d+\.checkpoint\.[0-9a-fA-F-]+\.(parquet|json)$").unwrap();
     static ref DELTA_FILE_PATTERN: Regex = Regex::new(r"^\d+\.json$").unwrap();
+    static ref COMPACTION_FILE_PATTERN: Regex =
+        Regex::new(r"^(\d+)\.(\d+)\.compacted\.json$").unwrap();
     pub(super) static ref COMMIT_SCHEMA: StructType = StructType::new(vec![
         ActionType::Add.schema_field().clone(),
         ActionType::Remove.schema_field().clone(),
@@ -41,8 +49,7 @@ lazy_static! {
 ///
 /// specifically, this trait adds the ability to recognize valid log files and
 /// parse the version number from a log file path
-// TODO handle compaction files
-pub(super) trait PathExt {
+pub(crate) trait PathExt {
     fn child(&self, path: impl AsRef<str>) -> DeltaResult<Path>;
     /// Returns the last path segment if not terminated with a "/"
     fn filename(&self) -> Option<&str>;
@@ -54,11 +61,27 @@ pub(super) trait PathExt {
             .and_then(|(name, _)| name.parse().ok())
     }
 
-    /// Returns true if the file is a checkpoint parquet file
+    /// Returns true if the file is a classic or V2 checkpoint file
     fn is_checkpoint_file(&self) -> bool {
         self.filename()
             .map(|name| CHECKPOINT_FILE_PATTERN.captures(name).is_some())
             .unwrap_or(false)
+            || self.is_v2_checkpoint_file()
+    }
+
+    /// Returns the total number of parts if the file is part of a multi-part checkpoint
+    fn checkpoint_parts(&self) -> Option<usize> {
+        self.filename()
+            .and_then(|name| CHECKPOINT_FILE_PATTERN.captures(name))
+            .and_then(|captures| captures.get(2))
+            .and_then(|parts| parts.as_str().parse().ok())
+    }
+
+    /// Returns true if the file is a top level V2 checkpoint file
+    fn is_v2_checkpoint_file(&self) -> bool {
+        self.filename()
+            .map(|name| V2_CHECKPOINT_FILE_PATTERN.captures(name).is_some())
+            .unwrap_or(false)
     }
 
     /// Returns true if the file is a commit json file
@@ -67,6 +90,19 @@ pub(super) trait PathExt {
             .map(|name| DELTA_FILE_PATTERN.captures(name).is_some())
             .unwrap_or(false)
     }
+
+    /// Returns the first and last commit version covered by a log compaction file
+    fn compaction_versions(&self) -> Option<(i64, i64)> {
+        let captures = COMPACTION_FILE_PATTERN.captures(self.filename()?)?;
+        let start = captures.get(1)?.as_str().parse().ok()?;
+        let end = captures.get(2)?.as_str().parse().ok()?;
+        Some((start, end))
+    }
+
+    /// Returns true if the file is a log compaction file
+    fn is_compaction_file(&self) -> bool {
+        self.compaction_versions().is_some()
+    }
 }
 
 impl PathExt for Path {
@@ -99,7 +135,7 @@ impl LogSegment {
         let maybe_cp = read_last_checkpoint(store, &log_url).await?;
 
         // List relevant files from log
-        let (mut commit_files, checkpoint_files) = match (maybe_cp, version) {
+        let (mut commit_files, checkpoint_files, mut compaction_files) = match (maybe_cp, version) {
             (Some(cp), None) => list_log_files_with_checkpoint(&cp, store, &log_url).await?,
             (Some(cp), Some(v)) if cp.version <= v => {
                 list_log_files_with_checkpoint(&cp, store, &log_url).await?
@@ -110,7 +146,13 @@ impl LogSegment {
         // remove all files above requested version
         if let Some(version) = version {
             commit_files.retain(|meta| meta.location.commit_version() <= Some(version));
+            compaction_files.retain(|meta| {
+                meta.location
+                    .compaction_versions()
+                    .is_some_and(|(_, end)| end <= version)
+            });
         }
+        let commit_files = apply_log_compactions(commit_files, compaction_files);
 
         let mut segment = Self {
             version: 0,
@@ -153,7 +195,7 @@ impl LogSegment {
         );
         log_store.refresh().await?;
         let log_url = table_root.child("_delta_log");
-        let (mut commit_files, checkpoint_files) = list_log_files(
+        let (mut commit_files, checkpoint_files, _) = list_log_files(
             log_store.object_store().as_ref(),
             &log_url,
             end_version,
@@ -197,7 +239,10 @@ impl LogSegment {
     pub fn file_version(&self) -> Option<i64> {
         self.commit_files
             .iter()
-            .filter_map(|f| f.location.commit_version())
+            .filter_map(|f| match f.location.compaction_versions() {
+                Some((_, end)) => Some(end),
+                None => f.location.commit_version(),
+            })
             .max()
             .or(self
                 .checkpoint_files
@@ -233,7 +278,7 @@ impl LogSegment {
     pub fn version_timestamp(&self, version: i64) -> Option<chrono::DateTime<Utc>> {
         self.commit_files
             .iter()
-            .find(|f| f.location.commit_version() == Some(version))
+            .find(|f| f.location.is_commit_file() && f.location.commit_version() == Some(version))
             .map(|f| f.last_modified)
     }
 
@@ -253,27 +298,35 @@ impl LogSegment {
         Ok(json::decode_stream(decoder, stream).boxed())
     }
 
+    /// Read the batches of the checkpoint files
+    ///
+    /// The parts of multi-part checkpoints are read concurrently, so the batches of
+    /// different parts may be interleaved. For V2 checkpoints, the batches of the top level
+    /// checkpoint file are followed by the batches of all sidecar files it references.
     pub(super) fn checkpoint_stream(
         &self,
         store: Arc<dyn ObjectStore>,
-        _read_schema: &Schema,
+        read_schema: &Schema,
         config: &DeltaTableConfig,
     ) -> BoxStream<'_, DeltaResult<RecordBatch>> {
-        let batch_size = config.log_batch_size;
+        let buffer_size = config.log_buffer_size;
+        let read_schema = read_schema.clone();
+        let config = config.clone();
         futures::stream::iter(self.checkpoint_files.clone())
             .map(move |meta| {
                 let store = store.clone();
+                let read_schema = read_schema.clone();
+                let config = config.clone();
                 async move {
-                    let reader = ParquetObjectReader::new(store, meta);
-                    let options = ArrowReaderOptions::new(); //.with_page_index(enable_page_index);
-                    let builder =
-                        ParquetRecordBatchStreamBuilder::new_with_options(reader, options).await?;
-                    builder.with_batch_size(batch_size).build()
+                    if meta.location.is_v2_checkpoint_file() {
+                        read_v2_checkpoint(store, meta, &read_schema, &config).await
+                    } else {
+                        read_parquet(store, meta, config.log_batch_size).await
+                    }
                 }
             })
-            .buffered(config.log_buffer_size)
-            .try_flatten()
-            .map_err(Into::into)
+            .buffer_unordered(buffer_size)
+            .try_flatten_unordered(buffer_size)
             .boxed()
     }
 
@@ -332,6 +385,40 @@ impl LogSegment {
         Ok((maybe_protocol, maybe_metadata))
     }
 
+    /// Read the latest [`DomainMetadata`] action of every domain in the log segment
+    ///
+    /// Domains that have been removed are included with their tombstone, so the result
+    /// can be applied on top of the domains of an earlier segment.
+    pub(super) async fn read_domain_metadata(
+        &self,
+        store: Arc<dyn ObjectStore>,
+        config: &DeltaTableConfig,
+    ) -> DeltaResult<HashMap<String, DomainMetadata>> {
+        lazy_static::lazy_static! {
+            static ref READ_SCHEMA: StructType = StructType::new(vec![
+                ActionType::DomainMetadata.schema_field().clone(),
+            ]);
+        }
+
+        // commits are read newest first, so the first action seen for a domain is the latest
+        let mut domains = HashMap::new();
+        let mut commit_stream = self.commit_stream(store.clone(), &READ_SCHEMA, config)?;
+        while let Some(batch) = commit_stream.next().await {
+            for dm in parse::read_domain_metadata(&batch?)? {
+                domains.entry(dm.domain.clone()).or_insert(dm);
+            }
+        }
+
+        let mut checkpoint_stream = self.checkpoint_stream(store, &READ_SCHEMA, config);
+        while let Some(batch) = checkpoint_stream.next().await {
+            for dm in parse::read_domain_metadata(&batch?)? {
+                domains.entry(dm.domain.clone()).or_insert(dm);
+            }
+        }
+
+        Ok(domains)
+    }
+
     /// Advance the log segment with new commits
     ///
     /// Returns an iterator over record batches, as if the commits were read from the log.
@@ -410,12 +497,111 @@ async fn read_last_checkpoint(
     }
 }
 
+/// Read a parquet file from the log as a stream of record batches
+async fn read_parquet(
+    store: Arc<dyn ObjectStore>,
+    meta: ObjectMeta,
+    batch_size: usize,
+) -> DeltaResult<BoxStream<'static, DeltaResult<RecordBatch>>> {
+    let reader = ParquetObjectReader::new(store, meta);
+    let options = ArrowReaderOptions::new(); //.with_page_index(enable_page_index);
+    let builder = ParquetRecordBatchStreamBuilder::new_with_options(reader, options).await?;
+    Ok(builder
+        .with_batch_size(batch_size)
+        .build()?
+        .map_err(Into::into)
+        .boxed())
+}
+
+/// Read a V2 checkpoint and the sidecar files it references
+///
+/// The top level checkpoint file may be written as json or parquet. It contains all
+/// non-file actions, while the file actions may be stored in sidecar files.
+async fn read_v2_checkpoint(
+    store: Arc<dyn ObjectStore>,
+    meta: ObjectMeta,
+    read_schema: &Schema,
+    config: &DeltaTableConfig,
+) -> DeltaResult<BoxStream<'static, DeltaResult<RecordBatch>>> {
+    let mut fields = read_schema.fields().clone();
+    if read_schema.field_with_name("sidecar").is_err() {
+        fields.push(ActionType::Sidecar.schema_field().clone());
+    }
+    let schema: ArrowSchema = (&StructType::new(fields)).try_into()?;
+
+    let batches = if meta.location.as_ref().ends_with(".json") {
+        let mut decoder = json::get_decoder(Arc::new(schema.clone()), config)?;
+        let bytes = store.get(&meta.location).await?.bytes().await?;
+        json::decode_reader(&mut decoder, json::get_reader(&bytes))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        read_parquet(store.clone(), meta.clone(), config.log_batch_size)
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?
+    };
+    let batches = batches
+        .into_iter()
+        .map(|batch| with_missing_columns(batch, &schema))
+        .collect::<DeltaResult<Vec<_>>>()?;
+
+    let mut sidecars = Vec::new();
+    for batch in &batches {
+        for sidecar in parse::read_sidecars(batch)? {
+            sidecars.push(sidecar_meta(&store, &meta.location, &sidecar.path).await?);
+        }
+    }
+
+    let batch_size = config.log_batch_size;
+    let sidecar_stream = futures::stream::iter(sidecars)
+        .map(move |meta| read_parquet(store.clone(), meta, batch_size))
+        .buffer_unordered(config.log_buffer_size)
+        .try_flatten_unordered(config.log_buffer_size);
+    Ok(futures::stream::iter(batches.into_iter().map(Ok))
+        .chain(sidecar_stream)
+        .boxed())
+}
+
+/// Resolve the location of a sidecar file referenced by the given checkpoint
+async fn sidecar_meta(
+    store: &Arc<dyn ObjectStore>,
+    checkpoint: &Path,
+    sidecar_path: &str,
+) -> DeltaResult<ObjectMeta> {
+    let log_root = checkpoint.parts().collect_vec();
+    let log_root = Path::from_iter(
+        log_root
+            .iter()
+            .take(log_root.len().saturating_sub(1))
+            .cloned(),
+    );
+    let location = Path::from_url_path(format!("{log_root}/{SIDECAR_FOLDER}/{sidecar_path}"))
+        .map_err(|err| DeltaTableError::Generic(format!("Invalid sidecar path: {err}")))?;
+    Ok(store.head(&location).await?)
+}
+
+/// Add null columns for all fields of the schema that are missing in the batch
+fn with_missing_columns(batch: RecordBatch, schema: &ArrowSchema) -> DeltaResult<RecordBatch> {
+    let mut fields = batch.schema().fields().to_vec();
+    let mut columns = batch.columns().to_vec();
+    for field in schema.fields() {
+        if batch.column_by_name(field.name()).is_none() {
+            fields.push(field.clone());
+            columns.push(new_null_array(field.data_type(), batch.num_rows()));
+        }
+    }
+    Ok(RecordBatch::try_new(
+        Arc::new(ArrowSchema::new(fields)),
+        columns,
+    )?)
+}
+
 /// List all log files after a given checkpoint.
 async fn list_log_files_with_checkpoint(
     cp: &CheckpointMetadata,
     fs_client: &dyn ObjectStore,
     log_root: &Path,
-) -> DeltaResult<(Vec<ObjectMeta>, Vec<ObjectMeta>)> {
+) -> DeltaResult<(Vec<ObjectMeta>, Vec<ObjectMeta>, Vec<ObjectMeta>)> {
     let version_prefix = format!("{:020}", cp.version);
     let start_from = log_root.child(version_prefix.as_str());
 
@@ -442,6 +628,14 @@ async fn list_log_files_with_checkpoint(
     // NOTE: this will sort in reverse order
     commit_files.sort_unstable_by(|a, b| b.location.cmp(&a.location));
 
+    let compaction_files = files
+        .iter()
+        .filter(|f| {
+            f.location.commit_version() > Some(cp.version) && f.location.is_compaction_file()
+        })
+        .cloned()
+        .collect_vec();
+
     let checkpoint_files = files
         .iter()
         .filter_map(|f| {
@@ -452,6 +646,8 @@ async fn list_log_files_with_checkpoint(
             }
         })
         .collect_vec();
+    let checkpoint_files =
+        select_checkpoint_files(checkpoint_files, cp.parts.map(|parts| parts as usize));
 
     if checkpoint_files.len() != cp.parts.unwrap_or(1) as usize {
         let msg = format!(
@@ -461,7 +657,7 @@ async fn list_log_files_with_checkpoint(
         );
         Err(DeltaTableError::MetadataError(msg))
     } else {
-        Ok((commit_files, checkpoint_files))
+        Ok((commit_files, checkpoint_files, compaction_files))
     }
 }
 
@@ -473,13 +669,13 @@ pub(super) async fn list_log_files(
     log_root: &Path,
     max_version: Option<i64>,
     start_version: Option<i64>,
-) -> DeltaResult<(Vec<ObjectMeta>, Vec<ObjectMeta>)> {
+) -> DeltaResult<(Vec<ObjectMeta>, Vec<ObjectMeta>, Vec<ObjectMeta>)> {
     let max_version = max_version.unwrap_or(i64::MAX - 1);
     let start_from = log_root.child(format!("{:020}", start_version.unwrap_or(0)).as_str());
 
-    let mut max_checkpoint_version = -1_i64;
     let mut commit_files = Vec::with_capacity(25);
-    let mut checkpoint_files = Vec::with_capacity(10);
+    let mut compaction_files = Vec::new();
+    let mut checkpoints: HashMap<i64, Vec<ObjectMeta>> = HashMap::new();
 
     for meta in fs_client
         .list_with_offset(Some(log_root), &start_from)
@@ -491,28 +687,109 @@ pub(super) async fn list_log_files(
         {
             if meta.location.is_checkpoint_file() {
                 let version = meta.location.commit_version().unwrap_or(0);
-                match version.cmp(&max_checkpoint_version) {
-                    Ordering::Greater => {
-                        max_checkpoint_version = version;
-                        checkpoint_files.clear();
-                        checkpoint_files.push(meta);
-                    }
-                    Ordering::Equal => {
-                        checkpoint_files.push(meta);
-                    }
-                    _ => {}
-                }
+                checkpoints.entry(version).or_default().push(meta);
             } else if meta.location.is_commit_file() {
                 commit_files.push(meta);
+            } else if meta.location.is_compaction_file() {
+                compaction_files.push(meta);
             }
         }
     }
 
+    // use the latest checkpoint which was written completely
+    let (max_checkpoint_version, checkpoint_files) = checkpoints
+        .into_iter()
+        .map(|(version, files)| (version, select_checkpoint_files(files, None)))
+        .filter(|(_, files)| !files.is_empty())
+        .max_by_key(|(version, _)| *version)
+        .unwrap_or((-1, Vec::new()));
+
     commit_files.retain(|f| f.location.commit_version().unwrap_or(0) > max_checkpoint_version);
     // NOTE this will sort in reverse order
     commit_files.sort_unstable_by(|a, b| b.location.cmp(&a.location));
+    compaction_files.retain(|f| f.location.commit_version().unwrap_or(0) > max_checkpoint_version);
+
+    Ok((commit_files, checkpoint_files, compaction_files))
+}
+
+/// Replace ranges of commit files with the log compaction files covering them
+///
+/// A log compaction file `<x>.<y>.compacted.json` contains the reconciled actions of the
+/// commits `x` to `y`. It is only used if all of these commits are part of the log segment,
+/// compaction files covering more commits are preferred. The returned files are sorted in
+/// reverse order, like the commit files.
+fn apply_log_compactions(
+    commit_files: Vec<ObjectMeta>,
+    mut compaction_files: Vec<ObjectMeta>,
+) -> Vec<ObjectMeta> {
+    if compaction_files.is_empty() {
+        return commit_files;
+    }
+
+    let mut files: BTreeMap<i64, ObjectMeta> = commit_files
+        .into_iter()
+        .filter_map(|f| Some((f.location.commit_version()?, f)))
+        .collect();
+    compaction_files.sort_unstable_by_key(|f| {
+        f.location
+            .compaction_versions()
+            .map(|(start, end)| start - end)
+            .unwrap_or_default()
+    });
+
+    let mut compactions = Vec::new();
+    for file in compaction_files {
+        let Some((start, end)) = file.location.compaction_versions() else {
+            continue;
+        };
+        let covered = (start..=end).all(|version| {
+            files
+                .get(&version)
+                .is_some_and(|f| f.location.is_commit_file())
+        });
+        if start <= end && covered {
+            for version in start..=end {
+                files.remove(&version);
+            }
+            compactions.push((end, file));
+        }
+    }
+    files.extend(compactions);
 
-    Ok((commit_files, checkpoint_files))
+    // NOTE: this will sort in reverse order
+    files.into_values().rev().collect()
+}
+
+/// Select the files to read from all checkpoint files written for the same version
+///
+/// Several V2 checkpoints, or V2 and classic checkpoints, may exist for the same version.
+/// Each of them describes the complete table state, so a single V2 checkpoint is preferred.
+/// Otherwise the classic checkpoint with `num_parts` parts is selected, or if not known, the
+/// checkpoint with the fewest parts of which all parts are present.
+fn select_checkpoint_files(files: Vec<ObjectMeta>, num_parts: Option<usize>) -> Vec<ObjectMeta> {
+    if let Some(file) = files
+        .iter()
+        .filter(|f| f.location.is_v2_checkpoint_file())
+        .min_by(|a, b| a.location.cmp(&b.location))
+    {
+        return vec![file.clone()];
+    }
+
+    let mut candidates: BTreeMap<usize, Vec<ObjectMeta>> = BTreeMap::new();
+    for file in files {
+        let parts = file.location.checkpoint_parts().unwrap_or(1);
+        candidates.entry(parts).or_default().push(file);
+    }
+    let mut selected = match num_parts {
+        Some(parts) => candidates.remove(&parts).unwrap_or_default(),
+        None => candidates
+            .into_iter()
+            .find(|(parts, files)| files.len() == *parts)
+            .map(|(_, files)| files)
+            .unwrap_or_default(),
+    };
+    selected.sort_unstable_by(|a, b| a.location.cmp(&b.location));
+    selected
 }
 
 #[cfg(test)]
@@ -563,15 +840,16 @@ pub(super) mod tests {
             .unwrap();
         assert_eq!(cp.version, 10);
 
-        let (log, check) = list_log_files_with_checkpoint(&cp, store.as_ref(), &log_path).await?;
+        let (log, check, _) =
+            list_log_files_with_checkpoint(&cp, store.as_ref(), &log_path).await?;
         assert_eq!(log.len(), 0);
         assert_eq!(check.len(), 1);
 
-        let (log, check) = list_log_files(store.as_ref(), &log_path, None, None).await?;
+        let (log, check, _) = list_log_files(store.as_ref(), &log_path, None, None).await?;
         assert_eq!(log.len(), 0);
         assert_eq!(check.len(), 1);
 
-        let (log, check) = list_log_files(store.as_ref(), &log_path, Some(8), None).await?;
+        let (log, check, _) = list_log_files(store.as_ref(), &log_path, Some(8), None).await?;
         assert_eq!(log.len(), 9);
         assert_eq!(check.len(), 0);
 
@@ -590,11 +868,11 @@ pub(super) mod tests {
             .build_storage()?
             .object_store();
 
-        let (log, check) = list_log_files(store.as_ref(), &log_path, None, None).await?;
+        let (log, check, _) = list_log_files(store.as_ref(), &log_path, None, None).await?;
         assert_eq!(log.len(), 5);
         assert_eq!(check.len(), 0);
 
-        let (log, check) = list_log_files(store.as_ref(), &log_path, Some(2), None).await?;
+        let (log, check, _) = list_log_files(store.as_ref(), &log_path, Some(2), None).await?;
         assert_eq!(log.len(), 3);
         assert_eq!(check.len(), 0);
 
@@ -748,4 +1026,124 @@ pub(super) mod tests {
             assert!(!path.is_commit_file());
         }
     }
+
+    #[test]
+    pub fn is_v2_checkpoint_file_matches_uuid_checkpoints() {
+        let v2_checkpoints = [
+            "_delta_log/00000000000000000010.checkpoint.80a083e8-7026-4e79-81be-64bd76c43a11.json",
+            "_delta_log/00000000000000000010.checkpoint.80a083e8-7026-4e79-81be-64bd76c43a11.parquet",
+        ];
+        for v2_checkpoint in v2_checkpoints {
+            let path = Path::from(v2_checkpoint);
+            assert!(path.is_v2_checkpoint_file());
+            assert!(path.is_checkpoint_file());
+        }
+
+        let classic_checkpoints = [
+            "_delta_log/00000000000000000010.checkpoint.parquet",
+            "_delta_log/00000000000000000010.checkpoint.0000000001.0000000002.parquet",
+        ];
+        for classic_checkpoint in classic_checkpoints {
+            let path = Path::from(classic_checkpoint);
+            assert!(!path.is_v2_checkpoint_file());
+            assert!(path.is_checkpoint_file());
+        }
+    }
+
+    #[test]
+    pub fn select_checkpoint_files_skips_incomplete_multi_part_checkpoints() {
+        let meta = |location: &str| ObjectMeta {
+            location: Path::from(location),
+            last_modified: Utc::now(),
+            size: 0,
+            e_tag: None,
+            version: None,
+        };
+        let names = |files: Vec<ObjectMeta>| {
+            files
+                .iter()
+                .map(|f| f.location.filename().unwrap().to_string())
+                .collect_vec()
+        };
+
+        let files = vec![
+            meta("_delta_log/00000000000000000010.checkpoint.0000000002.0000000003.parquet"),
+            meta("_delta_log/00000000000000000010.checkpoint.0000000002.0000000002.parquet"),
+            meta("_delta_log/00000000000000000010.checkpoint.0000000001.0000000003.parquet"),
+            meta("_delta_log/00000000000000000010.checkpoint.0000000001.0000000002.parquet"),
+        ];
+        assert_eq!(
+            names(select_checkpoint_files(files.clone(), None)),
+            vec![
+                "00000000000000000010.checkpoint.0000000001.0000000002.parquet",
+                "00000000000000000010.checkpoint.0000000002.0000000002.parquet",
+            ]
+        );
+        assert_eq!(select_checkpoint_files(files.clone(), Some(3)).len(), 2);
+        assert!(select_checkpoint_files(files[..1].to_vec(), None).is_empty());
+
+        let mut files = files;
+        files.push(meta("_delta_log/00000000000000000010.checkpoint.parquet"));
+        assert_eq!(
+            names(select_checkpoint_files(files, None)),
+            vec!["00000000000000000010.checkpoint.parquet"]
+        );
+    }
+
+    #[test]
+    pub fn apply_log_compactions_replaces_covered_commits() {
+        let meta = |location: &str| ObjectMeta {
+            location: Path::from(location),
+            last_modified: Utc::now(),
+            size: 0,
+            e_tag: None,
+            version: None,
+        };
+        let names = |files: Vec<ObjectMeta>| {
+            files
+                .iter()
+                .map(|f| f.location.filename().unwrap().to_string())
+                .collect_vec()
+        };
+
+        let compaction =
+            Path::from("_delta_log/00000000000000000002.00000000000000000004.compacted.json");
+        assert_eq!(compaction.compaction_versions(), Some((2, 4)));
+        assert!(compaction.is_compaction_file());
+        assert!(!compaction.is_commit_file());
+        assert!(!Path::from("_delta_log/00000000000000000002.json").is_compaction_file());
+
+        let commits = (1..=5)
+            .rev()
+            .map(|v| meta(&format!("_delta_log/{v:020}.json")))
+            .collect_vec();
+        let compactions = vec![
+            meta("_delta_log/00000000000000000002.00000000000000000003.compacted.json"),
+            meta("_delta_log/00000000000000000002.00000000000000000004.compacted.json"),
+            meta("_delta_log/00000000000000000004.00000000000000000006.compacted.json"),
+        ];
+        assert_eq!(
+            names(apply_log_compactions(commits.clone(), compactions)),
+            vec![
+                "00000000000000000005.json",
+                "00000000000000000002.00000000000000000004.compacted.json",
+                "00000000000000000001.json",
+            ]
+        );
+
+        // compactions are ignored if any of the commits they cover is missing
+        let compactions = vec![meta(
+            "_delta_log/00000000000000000000.00000000000000000002.compacted.json",
+        )];
+        assert_eq!(
+            names(apply_log_compactions(commits, compactions)),
+            vec![
+                "00000000000000000005.json",
+                "00000000000000000004.json",
+                "00000000000000000003.json",
+                "00000000000000000002.json",
+                "00000000000000000001.json",
+            ]
+        );
+    }
 }
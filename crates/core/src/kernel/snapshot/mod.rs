@@ -5,7 +5,7 @@
 //! There are two types of snapshots:
 //!
 //! - [`Snapshot`] is a snapshot where most data is loaded on demand and only the
-//!   bare minimum - [`Protocol`] and [`Metadata`] - is cached in memory.
+//!   bare minimum - [`Protocol`], [`Metadata`] and [`DomainMetadata`] - is cached in memory.
 //! - [`EagerSnapshot`] is a snapshot where much more log data is eagerly loaded into memory.
 //!
 //! The sub modules provide structures and methods that aid in generating
@@ -15,26 +15,29 @@
 //!
 //!
 
+use std::collections::BTreeMap;
 use std::io::{BufRead, BufReader, Cursor};
 use std::sync::Arc;
 
 use ::serde::{Deserialize, Serialize};
 use arrow_array::RecordBatch;
+use arrow_schema::Schema as ArrowSchema;
 use futures::stream::BoxStream;
 use futures::{StreamExt, TryStreamExt};
 use object_store::path::Path;
 use object_store::ObjectStore;
 
-use self::log_segment::{LogSegment, PathExt};
+use self::log_segment::LogSegment;
 use self::parse::{read_adds, read_removes};
 use self::replay::{LogMapper, LogReplayScanner, ReplayStream};
 use super::{
-    Action, Add, AddCDCFile, CommitInfo, DataType, Metadata, Protocol, Remove, StructField,
+    Action, Add, AddCDCFile, CommitInfo, DataType, DomainMetadata, Metadata, Protocol, Remove,
+    StructField, WriterFeatures,
 };
 use crate::kernel::StructType;
 use crate::logstore::LogStore;
 use crate::operations::transaction::CommitData;
-use crate::table::config::TableConfig;
+use crate::table::config::{ColumnMappingMode, TableConfig};
 use crate::{DeltaResult, DeltaTableConfig, DeltaTableError};
 
 mod log_data;
@@ -45,6 +48,7 @@ mod serde;
 
 use crate::kernel::parse::read_cdf_adds;
 pub use log_data::*;
+pub(crate) use log_segment::{PathExt, SIDECAR_FOLDER};
 
 /// A snapshot of a Delta table
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -54,6 +58,9 @@ pub struct Snapshot {
     protocol: Protocol,
     metadata: Metadata,
     schema: StructType,
+    /// latest configuration of the metadata domains set on the table, keyed by domain
+    #[serde(default)]
+    domain_metadata: BTreeMap<String, DomainMetadata>,
     // TODO make this an URL
     /// path of the table root within the object store
     table_url: String,
@@ -76,12 +83,23 @@ impl Snapshot {
         };
         let (metadata, protocol) = (metadata.unwrap(), protocol.unwrap());
         let schema = serde_json::from_str(&metadata.schema_string)?;
+        let mut domain_metadata = BTreeMap::new();
+        if supports_domain_metadata(&protocol) {
+            apply_domain_metadata(
+                &mut domain_metadata,
+                log_segment
+                    .read_domain_metadata(store, &config)
+                    .await?
+                    .into_values(),
+            );
+        }
         Ok(Self {
             log_segment,
             config,
             protocol,
             metadata,
             schema,
+            domain_metadata,
             table_url: table_root.to_string(),
         })
     }
@@ -97,6 +115,8 @@ impl Snapshot {
         let protocol = parse::read_protocol(&batch)?.unwrap();
         let metadata = parse::read_metadata(&batch)?.unwrap();
         let schema = serde_json::from_str(&metadata.schema_string)?;
+        let mut domain_metadata = BTreeMap::new();
+        apply_domain_metadata(&mut domain_metadata, parse::read_domain_metadata(&batch)?);
         Ok((
             Self {
                 log_segment,
@@ -104,6 +124,7 @@ impl Snapshot {
                 protocol,
                 metadata,
                 schema,
+                domain_metadata,
                 table_url: Path::default().to_string(),
             },
             batch,
@@ -154,6 +175,16 @@ impl Snapshot {
             self.metadata = metadata;
             self.schema = serde_json::from_str(&self.metadata.schema_string)?;
         }
+        if supports_domain_metadata(&self.protocol) {
+            let domains = log_segment
+                .read_domain_metadata(log_store.object_store().clone(), &self.config)
+                .await?;
+            // a checkpoint contains all domains still set on the table
+            if !log_segment.checkpoint_files.is_empty() {
+                self.domain_metadata.clear();
+            }
+            apply_domain_metadata(&mut self.domain_metadata, domains.into_values());
+        }
 
         if !log_segment.checkpoint_files.is_empty() {
             self.log_segment.checkpoint_files = log_segment.checkpoint_files.clone();
@@ -217,12 +248,22 @@ impl Snapshot {
         ReplayStream::try_new(log_stream, checkpoint_stream, self)
     }
 
+    /// Get the latest [`DomainMetadata`] of all domains set on the table
+    pub fn all_domain_metadata(&self) -> Vec<DomainMetadata> {
+        self.domain_metadata.values().cloned().collect()
+    }
+
+    /// Get the latest [`DomainMetadata`] of a domain, if it is set on the table
+    pub fn domain_metadata(&self, domain: &str) -> Option<&DomainMetadata> {
+        self.domain_metadata.get(domain)
+    }
+
     /// Get the commit infos in the snapshot
     pub(crate) async fn commit_infos(
         &self,
         store: Arc<dyn ObjectStore>,
         limit: Option<usize>,
-    ) -> DeltaResult<BoxStream<'_, DeltaResult<Option<CommitInfo>>>> {
+    ) -> DeltaResult<BoxStream<'_, DeltaResult<(i64, Option<CommitInfo>)>>> {
         let log_root = self.table_root().child("_delta_log");
         let start_from = log_root.child(
             format!(
@@ -241,12 +282,14 @@ impl Snapshot {
             .await?
         {
             if meta.location.is_commit_file() {
-                commit_files.push(meta);
+                if let Some(version) = meta.location.commit_version() {
+                    commit_files.push((version, meta));
+                }
             }
         }
-        commit_files.sort_unstable_by(|a, b| b.location.cmp(&a.location));
+        commit_files.sort_unstable_by_key(|(version, _)| std::cmp::Reverse(*version));
         Ok(futures::stream::iter(commit_files)
-            .map(move |meta| {
+            .map(move |(version, meta)| {
                 let store = store.clone();
                 async move {
                     let commit_log_bytes = store.get(&meta.location).await?.bytes().await?;
@@ -254,10 +297,10 @@ impl Snapshot {
                     for line in reader.lines() {
                         let action: Action = serde_json::from_str(line?.as_str())?;
                         if let Action::CommitInfo(commit_info) = action {
-                            return Ok::<_, DeltaTableError>(Some(commit_info));
+                            return Ok::<_, DeltaTableError>((version, Some(commit_info)));
                         }
                     }
-                    Ok(None)
+                    Ok((version, None))
                 }
             })
             .buffered(self.config.log_buffer_size)
@@ -288,6 +331,24 @@ impl Snapshot {
 
     /// Get the statistics schema of the snapshot
     pub fn stats_schema(&self, table_schema: Option<&StructType>) -> DeltaResult<StructType> {
+        self.stats_schema_for_mode(table_schema, ColumnMappingMode::None)
+    }
+
+    /// Get the statistics schema of the snapshot as it is written to the log.
+    ///
+    /// If column mapping is enabled, statistics are keyed by the physical column names.
+    pub(crate) fn physical_stats_schema(
+        &self,
+        table_schema: Option<&StructType>,
+    ) -> DeltaResult<StructType> {
+        self.stats_schema_for_mode(table_schema, self.table_config().column_mapping_mode())
+    }
+
+    fn stats_schema_for_mode(
+        &self,
+        table_schema: Option<&StructType>,
+        mode: ColumnMappingMode,
+    ) -> DeltaResult<StructType> {
         let schema = table_schema.unwrap_or_else(|| self.schema());
 
         let stats_fields = if let Some(stats_cols) = self.table_config().stats_columns() {
@@ -302,11 +363,10 @@ impl Snapshot {
                                 field.data_type()
                             )))
                         }
-                        _ => Ok(StructField::new(
-                            field.name(),
-                            field.data_type().clone(),
-                            true,
-                        )),
+                        _ => {
+                            let field = field.make_physical(mode)?;
+                            Ok(StructField::new(field.name, field.data_type, true))
+                        }
                     },
                     _ => Err(DeltaTableError::Generic(format!(
                         "Stats column {} not found in schema",
@@ -320,8 +380,8 @@ impl Snapshot {
                 .fields
                 .iter()
                 .enumerate()
-                .filter_map(|(idx, f)| stats_field(idx, num_indexed_cols, f))
-                .collect()
+                .filter_map(|(idx, f)| stats_field(idx, num_indexed_cols, f, mode).transpose())
+                .collect::<Result<_, _>>()?
         };
         Ok(StructType::new(vec![
             StructField::new("numRecords", DataType::LONG, true),
@@ -462,6 +522,18 @@ impl EagerSnapshot {
         LogDataHandler::new(&self.files, self.metadata(), self.schema())
     }
 
+    /// Get the latest [`DomainMetadata`] of all domains set on the table
+    pub fn all_domain_metadata(&self) -> Vec<DomainMetadata> {
+        self.snapshot.all_domain_metadata()
+    }
+
+    /// Get the configuration of a metadata domain, if it is set on the table
+    pub fn domain_metadata(&self, domain: &str) -> Option<&str> {
+        self.snapshot
+            .domain_metadata(domain)
+            .map(|dm| dm.configuration.as_str())
+    }
+
     /// Get the number of files in the snapshot
     pub fn files_count(&self) -> usize {
         self.files.iter().map(|f| f.num_rows()).sum()
@@ -469,7 +541,12 @@ impl EagerSnapshot {
 
     /// Get the files in the snapshot
     pub fn file_actions(&self) -> DeltaResult<impl Iterator<Item = Add> + '_> {
-        Ok(self.files.iter().flat_map(|b| read_adds(b)).flatten())
+        let stats_schema: ArrowSchema = (&self.snapshot.physical_stats_schema(None)?).try_into()?;
+        Ok(self
+            .files
+            .iter()
+            .flat_map(move |b| read_adds(b, Some(stats_schema.fields())))
+            .flatten())
     }
 
     /// Get a file action iterator for the given version
@@ -489,8 +566,13 @@ impl EagerSnapshot {
     ) -> DeltaResult<i64> {
         let mut metadata = None;
         let mut protocol = None;
+        let mut domains = Vec::new();
         let mut send = Vec::new();
         for commit in commits {
+            domains.extend(commit.actions.iter().filter_map(|a| match a {
+                Action::DomainMetadata(domain) => Some(domain.clone()),
+                _ => None,
+            }));
             if metadata.is_none() {
                 metadata = commit.actions.iter().find_map(|a| match a {
                     Action::Metadata(metadata) => Some(metadata.clone()),
@@ -543,34 +625,59 @@ impl EagerSnapshot {
         if let Some(protocol) = protocol {
             self.snapshot.protocol = protocol;
         }
+        apply_domain_metadata(&mut self.snapshot.domain_metadata, domains);
 
         Ok(self.snapshot.version())
     }
 }
 
-fn stats_field(idx: usize, num_indexed_cols: i32, field: &StructField) -> Option<StructField> {
+/// Whether the protocol allows the table to contain [`DomainMetadata`] actions
+fn supports_domain_metadata(protocol: &Protocol) -> bool {
+    protocol
+        .writer_features
+        .as_ref()
+        .is_some_and(|features| features.contains(&WriterFeatures::DomainMetadata))
+}
+
+/// Apply domain metadata actions, given in commit order, to the domains set on a table
+fn apply_domain_metadata(
+    domains: &mut BTreeMap<String, DomainMetadata>,
+    actions: impl IntoIterator<Item = DomainMetadata>,
+) {
+    for action in actions {
+        if action.removed {
+            domains.remove(&action.domain);
+        } else {
+            domains.insert(action.domain.clone(), action);
+        }
+    }
+}
+
+fn stats_field(
+    idx: usize,
+    num_indexed_cols: i32,
+    field: &StructField,
+    mode: ColumnMappingMode,
+) -> DeltaResult<Option<StructField>> {
     if !(num_indexed_cols < 0 || (idx as i32) < num_indexed_cols) {
-        return None;
+        return Ok(None);
     }
-    match field.data_type() {
+    let name = field.physical_name_for(mode)?;
+    Ok(match field.data_type() {
         DataType::Map(_) | DataType::Array(_) | &DataType::BINARY => None,
         DataType::Struct(dt_struct) => Some(StructField::new(
-            field.name(),
+            name,
             StructType::new(
                 dt_struct
                     .fields()
                     .iter()
-                    .flat_map(|f| stats_field(idx, num_indexed_cols, f))
-                    .collect(),
+                    .filter_map(|f| stats_field(idx, num_indexed_cols, f, mode).transpose())
+                    .collect::<Result<_, _>>()?,
             ),
             true,
         )),
-        DataType::Primitive(_) => Some(StructField::new(
-            field.name(),
-            field.data_type.clone(),
-            true,
-        )),
-    }
+        DataType::Primitive(_) => Some(StructField::new(name, field.data_type.clone(), true)),
+    })
 }
 
 fn to_count_field(field: &StructField) -> Option<StructField> {
@@ -664,7 +771,7 @@ mod tests {
             .await?
             .try_collect::<Vec<_>>()
             .await?;
-        let infos = infos.into_iter().flatten().collect_vec();
+        let infos = infos.into_iter().filter_map(|(_, info)| info).collect_vec();
         assert_eq!(infos.len(), 5);
 
         let tombstones = snapshot
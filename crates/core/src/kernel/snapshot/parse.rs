@@ -1,12 +1,22 @@
 //! Utilities for converting Arrow arrays into Delta data structures.
 
+use std::sync::Arc;
+
+use arrow_arith::boolean::{and, is_not_null, is_null};
 use arrow_array::{
-    Array, BooleanArray, Int32Array, Int64Array, ListArray, MapArray, StringArray, StructArray,
+    Array, BooleanArray, Int32Array, Int64Array, ListArray, MapArray, RecordBatch, StringArray,
+    StructArray,
 };
+use arrow_json::LineDelimitedWriter;
+use arrow_schema::{Fields, Schema as ArrowSchema};
 use percent_encoding::percent_decode_str;
+use tracing::debug;
 
+use super::log_data::COL_NUM_RECORDS;
 use crate::kernel::arrow::extract::{self as ex, ProvidesColumnByName};
-use crate::kernel::{Add, AddCDCFile, DeletionVectorDescriptor, Metadata, Protocol, Remove};
+use crate::kernel::{
+    Add, AddCDCFile, DeletionVectorDescriptor, DomainMetadata, Metadata, Protocol, Remove, Sidecar,
+};
 use crate::{DeltaResult, DeltaTableError};
 
 pub(super) fn read_metadata(batch: &dyn ProvidesColumnByName) -> DeltaResult<Option<Metadata>> {
@@ -64,7 +74,14 @@ pub(super) fn read_protocol(batch: &dyn ProvidesColumnByName) -> DeltaResult<Opt
     Ok(None)
 }
 
-pub(super) fn read_adds(array: &dyn ProvidesColumnByName) -> DeltaResult<Vec<Add>> {
+/// Read the add actions contained in the given array.
+///
+/// Files without json statistics get their typed statistics serialized to json, keyed by
+/// `stats_fields` if given, as they are written to the log.
+pub(super) fn read_adds(
+    array: &dyn ProvidesColumnByName,
+    stats_fields: Option<&Fields>,
+) -> DeltaResult<Vec<Add>> {
     let mut result = Vec::new();
 
     if let Some(arr) = ex::extract_and_cast_opt::<StructArray>(array, "add") {
@@ -73,9 +90,23 @@ pub(super) fn read_adds(array: &dyn ProvidesColumnByName) -> DeltaResult<Vec<Add
         let size = ex::extract_and_cast::<Int64Array>(arr, "size")?;
         let modification_time = ex::extract_and_cast::<Int64Array>(arr, "modificationTime")?;
         let data_change = ex::extract_and_cast::<BooleanArray>(arr, "dataChange")?;
-        let stats = ex::extract_and_cast::<StringArray>(arr, "stats")?;
+        let stats = ex::extract_and_cast_opt::<StringArray>(arr, "stats");
+        let stats_parsed = ex::extract_and_cast_opt::<StructArray>(arr, "stats_parsed");
+        // statistics are restored on a best effort basis, as not all types can be
+        // serialized to json yet.
+        let typed_json_stats = stats_parsed
+            .map(|s| json_stats_for_typed(s, stats, stats_fields))
+            .transpose()
+            .unwrap_or_else(|err| {
+                debug!("failed to serialize typed statistics to json: {err}");
+                None
+            })
+            .flatten();
         let tags = ex::extract_and_cast_opt::<MapArray>(arr, "tags");
         let dv = ex::extract_and_cast_opt::<StructArray>(arr, "deletionVector");
+        let base_row_id = ex::extract_and_cast_opt::<Int64Array>(arr, "baseRowId");
+        let default_row_commit_version =
+            ex::extract_and_cast_opt::<Int64Array>(arr, "defaultRowCommitVersion");
 
         let get_dv: Box<dyn Fn(usize) -> Option<DeletionVectorDescriptor>> = if let Some(d) = dv {
             let storage_type = ex::extract_and_cast::<StringArray>(d, "storageType")?;
@@ -116,14 +147,17 @@ pub(super) fn read_adds(array: &dyn ProvidesColumnByName) -> DeltaResult<Vec<Add
                     size: ex::read_primitive(size, i)?,
                     modification_time: ex::read_primitive(modification_time, i)?,
                     data_change: ex::read_bool(data_change, i)?,
-                    stats: ex::read_str_opt(stats, i).map(|s| s.to_string()),
+                    stats: stats
+                        .and_then(|s| ex::read_str_opt(s, i).map(|s| s.to_string()))
+                        .or_else(|| typed_json_stats.as_ref().and_then(|s| s[i].clone())),
                     partition_values: pvs
                         .and_then(|pv| collect_map(&pv.value(i)).map(|m| m.collect()))
                         .unwrap_or_default(),
                     tags: tags.and_then(|t| collect_map(&t.value(i)).map(|m| m.collect())),
                     deletion_vector: get_dv(i),
-                    base_row_id: None,
-                    default_row_commit_version: None,
+                    base_row_id: base_row_id.and_then(|b| ex::read_primitive_opt(b, i)),
+                    default_row_commit_version: default_row_commit_version
+                        .and_then(|v| ex::read_primitive_opt(v, i)),
                     clustering_provider: None,
                     stats_parsed: None,
                 });
@@ -134,6 +168,54 @@ pub(super) fn read_adds(array: &dyn ProvidesColumnByName) -> DeltaResult<Vec<Add
     Ok(result)
 }
 
+/// Serialize typed statistics to json for all rows without json statistics
+///
+/// Returns `None` if all rows already contain json statistics.
+fn json_stats_for_typed(
+    stats: &StructArray,
+    json_stats: Option<&StringArray>,
+    fields: Option<&Fields>,
+) -> DeltaResult<Option<Vec<Option<String>>>> {
+    // files without statistics are parsed into rows without a record count.
+    let Some(num_records) = stats.column_by_name(COL_NUM_RECORDS) else {
+        return Ok(None);
+    };
+    let has_stats = and(&is_not_null(stats)?, &is_not_null(num_records)?)?;
+    let missing = match json_stats {
+        Some(json_stats) => and(&is_null(json_stats)?, &has_stats)?,
+        None => has_stats,
+    };
+    if missing.true_count() == 0 {
+        return Ok(None);
+    }
+
+    // typed statistics are exposed using the logical column names, which may differ from
+    // the physical names used in the log. Both share the same layout.
+    let fields = match fields {
+        Some(fields) if fields.len() == stats.num_columns() => stats
+            .fields()
+            .iter()
+            .zip(fields.iter())
+            .map(|(typed, physical)| typed.as_ref().clone().with_name(physical.name()))
+            .collect(),
+        _ => stats.fields().clone(),
+    };
+    let batch = RecordBatch::try_new(Arc::new(ArrowSchema::new(fields)), stats.columns().to_vec())?;
+    let mut writer = LineDelimitedWriter::new(Vec::new());
+    writer.write(&batch)?;
+    writer.finish()?;
+    let lines = String::from_utf8(writer.into_inner())
+        .map_err(|err| DeltaTableError::Generic(err.to_string()))?;
+
+    Ok(Some(
+        lines
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| missing.value(idx).then(|| line.to_string()))
+            .collect(),
+    ))
+}
+
 pub(super) fn read_cdf_adds(array: &dyn ProvidesColumnByName) -> DeltaResult<Vec<AddCDCFile>> {
     let mut result = Vec::new();
 
@@ -181,6 +263,9 @@ pub(super) fn read_removes(array: &dyn ProvidesColumnByName) -> DeltaResult<Vec<
         let size = ex::extract_and_cast_opt::<Int64Array>(arr, "size");
         let tags = ex::extract_and_cast_opt::<MapArray>(arr, "tags");
         let dv = ex::extract_and_cast_opt::<StructArray>(arr, "deletionVector");
+        let base_row_id = ex::extract_and_cast_opt::<Int64Array>(arr, "baseRowId");
+        let default_row_commit_version =
+            ex::extract_and_cast_opt::<Int64Array>(arr, "defaultRowCommitVersion");
 
         let get_dv: Box<dyn Fn(usize) -> Option<DeletionVectorDescriptor>> = if let Some(d) = dv {
             let storage_type = ex::extract_and_cast::<StringArray>(d, "storageType")?;
@@ -227,8 +312,57 @@ pub(super) fn read_removes(array: &dyn ProvidesColumnByName) -> DeltaResult<Vec<
                         .and_then(|pv| collect_map(&pv.value(i)).map(|m| m.collect())),
                     tags: tags.and_then(|t| collect_map(&t.value(i)).map(|m| m.collect())),
                     deletion_vector: get_dv(i),
-                    base_row_id: None,
-                    default_row_commit_version: None,
+                    base_row_id: base_row_id.and_then(|b| ex::read_primitive_opt(b, i)),
+                    default_row_commit_version: default_row_commit_version
+                        .and_then(|v| ex::read_primitive_opt(v, i)),
+                });
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+pub(super) fn read_domain_metadata(
+    batch: &dyn ProvidesColumnByName,
+) -> DeltaResult<Vec<DomainMetadata>> {
+    let mut result = Vec::new();
+
+    if let Some(arr) = ex::extract_and_cast_opt::<StructArray>(batch, "domainMetadata") {
+        let domain = ex::extract_and_cast::<StringArray>(arr, "domain")?;
+        let configuration = ex::extract_and_cast::<StringArray>(arr, "configuration")?;
+        let removed = ex::extract_and_cast::<BooleanArray>(arr, "removed")?;
+
+        for idx in 0..arr.len() {
+            if arr.is_valid(idx) {
+                result.push(DomainMetadata {
+                    domain: ex::read_str(domain, idx)?.to_string(),
+                    configuration: ex::read_str(configuration, idx)?.to_string(),
+                    removed: ex::read_bool(removed, idx)?,
+                });
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+pub(super) fn read_sidecars(batch: &dyn ProvidesColumnByName) -> DeltaResult<Vec<Sidecar>> {
+    let mut result = Vec::new();
+
+    if let Some(arr) = ex::extract_and_cast_opt::<StructArray>(batch, "sidecar") {
+        let path = ex::extract_and_cast::<StringArray>(arr, "path")?;
+        let size_in_bytes = ex::extract_and_cast::<Int64Array>(arr, "sizeInBytes")?;
+        let modification_time = ex::extract_and_cast::<Int64Array>(arr, "modificationTime")?;
+        let tags = ex::extract_and_cast_opt::<MapArray>(arr, "tags");
+
+        for idx in 0..arr.len() {
+            if arr.is_valid(idx) {
+                result.push(Sidecar {
+                    path: ex::read_str(path, idx)?.to_string(),
+                    size_in_bytes: ex::read_primitive(size_in_bytes, idx)?,
+                    modification_time: ex::read_primitive(modification_time, idx)?,
+                    tags: tags.and_then(|t| collect_map(&t.value(idx)).map(|m| m.collect())),
                 });
             }
         }
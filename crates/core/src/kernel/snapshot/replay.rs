@@ -3,18 +3,21 @@ use std::sync::Arc;
 use std::task::Context;
 use std::task::Poll;
 
-use arrow_arith::boolean::{is_not_null, or};
+use arrow_arith::boolean::{and, is_not_null, is_null, or};
+use arrow_array::cast::AsArray;
 use arrow_array::{
-    Array, ArrayRef, BooleanArray, Int32Array, RecordBatch, StringArray, StructArray,
+    new_null_array, Array, ArrayRef, BooleanArray, Int32Array, RecordBatch, StringArray,
+    StructArray,
 };
+use arrow_cast::cast;
 use arrow_schema::{
-    DataType as ArrowDataType, Field as ArrowField, Schema as ArrowSchema,
+    DataType as ArrowDataType, Field as ArrowField, Fields, Schema as ArrowSchema,
     SchemaRef as ArrowSchemaRef,
 };
 use arrow_select::filter::filter_record_batch;
+use arrow_select::zip::zip;
 use futures::Stream;
 use hashbrown::HashSet;
-use itertools::Itertools;
 use percent_encoding::percent_decode_str;
 use pin_project_lite::pin_project;
 use tracing::debug;
@@ -42,11 +45,7 @@ pin_project! {
 
 impl<S> ReplayStream<S> {
     pub(super) fn try_new(commits: S, checkpoint: S, snapshot: &Snapshot) -> DeltaResult<Self> {
-        let stats_schema = Arc::new((&snapshot.stats_schema(None)?).try_into()?);
-        let mapper = Arc::new(LogMapper {
-            stats_schema,
-            config: snapshot.config.clone(),
-        });
+        let mapper = Arc::new(LogMapper::try_new(snapshot, None)?);
         Ok(Self {
             commits,
             checkpoint,
@@ -58,6 +57,9 @@ impl<S> ReplayStream<S> {
 
 pub(super) struct LogMapper {
     stats_schema: ArrowSchemaRef,
+    /// Schema of the statistics as written to the log, if it differs from the stats schema
+    /// due to column mapping.
+    physical_stats_schema: Option<ArrowSchemaRef>,
     config: DeltaTableConfig,
 }
 
@@ -66,69 +68,162 @@ impl LogMapper {
         snapshot: &Snapshot,
         table_schema: Option<&StructType>,
     ) -> DeltaResult<Self> {
+        let stats_schema = snapshot.stats_schema(table_schema)?;
+        let physical_stats_schema = snapshot.physical_stats_schema(table_schema)?;
+        let physical_stats_schema = if physical_stats_schema != stats_schema {
+            Some(Arc::new((&physical_stats_schema).try_into()?))
+        } else {
+            None
+        };
         Ok(Self {
-            stats_schema: Arc::new((&snapshot.stats_schema(table_schema)?).try_into()?),
+            stats_schema: Arc::new((&stats_schema).try_into()?),
+            physical_stats_schema,
             config: snapshot.config.clone(),
         })
     }
 
     pub fn map_batch(&self, batch: RecordBatch) -> DeltaResult<RecordBatch> {
-        map_batch(batch, self.stats_schema.clone(), &self.config)
+        map_batch(
+            batch,
+            self.stats_schema.clone(),
+            self.physical_stats_schema.clone(),
+            &self.config,
+        )
     }
 }
 
 fn map_batch(
     batch: RecordBatch,
     stats_schema: ArrowSchemaRef,
+    physical_stats_schema: Option<ArrowSchemaRef>,
     config: &DeltaTableConfig,
 ) -> DeltaResult<RecordBatch> {
     let stats_col = ex::extract_and_cast_opt::<StringArray>(&batch, "add.stats");
     let stats_parsed_col = ex::extract_and_cast_opt::<StructArray>(&batch, "add.stats_parsed");
-    if stats_parsed_col.is_some() {
-        return Ok(batch);
+    // statistics are keyed by physical column names when column mapping is enabled,
+    // the parsed stats are exposed using the logical names of the table schema.
+    let read_schema = physical_stats_schema
+        .clone()
+        .unwrap_or(stats_schema.clone());
+
+    let stats = match (stats_parsed_col, stats_col) {
+        (None, None) => return Ok(batch),
+        (None, Some(json_stats)) => json::parse_json(json_stats, read_schema, config)?.into(),
+        // checkpoints may contain typed statistics, which are preferred over parsing json.
+        (Some(stats_parsed), json_stats) => {
+            match typed_stats(stats_parsed, json_stats, read_schema.clone(), config) {
+                Ok(stats) => stats,
+                Err(err) => match json_stats {
+                    Some(json_stats) => {
+                        debug!("failed to read typed statistics, parsing json instead: {err}");
+                        json::parse_json(json_stats, read_schema, config)?.into()
+                    }
+                    None => return Err(err),
+                },
+            }
+        }
+    };
+
+    let stats: ArrayRef = match physical_stats_schema {
+        Some(_) => cast(
+            &stats,
+            &ArrowDataType::Struct(stats_schema.fields().clone()),
+        )?,
+        None => Arc::new(stats),
+    };
+
+    let schema = batch.schema();
+    let add_col = ex::extract_and_cast::<StructArray>(&batch, "add")?;
+    let (add_idx, _) = schema.column_with_name("add").unwrap();
+    let new_add = with_struct_columns(add_col, vec![("stats_parsed", stats)])?;
+    let new_add_field = Arc::new(ArrowField::new("add", new_add.data_type().clone(), true));
+    let mut fields = schema.fields().to_vec();
+    let _ = std::mem::replace(&mut fields[add_idx], new_add_field);
+    let mut columns = batch.columns().to_vec();
+    let _ = std::mem::replace(&mut columns[add_idx], Arc::new(new_add));
+    Ok(RecordBatch::try_new(
+        Arc::new(ArrowSchema::new(fields)),
+        columns,
+    )?)
+}
+
+/// Read the typed statistics of a checkpoint according to the statistics schema
+///
+/// Rows without typed statistics fall back to the json statistics, if present.
+fn typed_stats(
+    stats_parsed: &StructArray,
+    json_stats: Option<&StringArray>,
+    read_schema: ArrowSchemaRef,
+    config: &DeltaTableConfig,
+) -> DeltaResult<StructArray> {
+    let stats = conform_struct(stats_parsed, read_schema.fields())?;
+    let Some(json_stats) = json_stats else {
+        return Ok(stats);
+    };
+    let missing = and(&is_null(&stats)?, &is_not_null(json_stats)?)?;
+    if missing.true_count() == 0 {
+        return Ok(stats);
     }
-    if let Some(stats) = stats_col {
-        let stats: Arc<StructArray> =
-            Arc::new(json::parse_json(stats, stats_schema.clone(), config)?.into());
-        let schema = batch.schema();
-        let add_col = ex::extract_and_cast::<StructArray>(&batch, "add")?;
-        let (add_idx, _) = schema.column_with_name("add").unwrap();
-        let add_type = add_col
-            .fields()
-            .iter()
-            .cloned()
-            .chain(std::iter::once(Arc::new(ArrowField::new(
-                "stats_parsed",
-                ArrowDataType::Struct(stats_schema.fields().clone()),
-                true,
-            ))))
-            .collect_vec();
-        let new_add = Arc::new(StructArray::try_new(
-            add_type.clone().into(),
-            add_col
-                .columns()
-                .iter()
-                .cloned()
-                .chain(std::iter::once(stats as ArrayRef))
-                .collect(),
-            add_col.nulls().cloned(),
-        )?);
-        let new_add_field = Arc::new(ArrowField::new(
-            "add",
-            ArrowDataType::Struct(add_type.into()),
-            true,
+    let parsed: StructArray = json::parse_json(json_stats, read_schema, config)?.into();
+    Ok(zip(&missing, &parsed, &stats)?.as_struct().clone())
+}
+
+/// Project a struct array onto the given fields by name
+///
+/// Columns are cast to the type of the corresponding field, fields missing in the
+/// array are filled with nulls and columns not contained in the fields are dropped.
+fn conform_struct(array: &StructArray, fields: &Fields) -> DeltaResult<StructArray> {
+    let columns = fields
+        .iter()
+        .map(|field| match array.column_by_name(field.name()) {
+            Some(column) => match (column.data_type(), field.data_type()) {
+                (ArrowDataType::Struct(_), ArrowDataType::Struct(children)) => {
+                    Ok(Arc::new(conform_struct(column.as_struct(), children)?) as ArrayRef)
+                }
+                (from, to) if from == to => Ok(column.clone()),
+                (_, to) => Ok(cast(column, to)?),
+            },
+            None => Ok(new_null_array(field.data_type(), array.len())),
+        })
+        .collect::<DeltaResult<Vec<_>>>()?;
+    if fields.is_empty() {
+        return Ok(StructArray::new_empty_fields(
+            array.len(),
+            array.nulls().cloned(),
         ));
-        let mut fields = schema.fields().to_vec();
-        let _ = std::mem::replace(&mut fields[add_idx], new_add_field);
-        let mut columns = batch.columns().to_vec();
-        let _ = std::mem::replace(&mut columns[add_idx], new_add);
-        return Ok(RecordBatch::try_new(
-            Arc::new(ArrowSchema::new(fields)),
-            columns,
-        )?);
     }
+    Ok(StructArray::try_new(
+        fields.clone(),
+        columns,
+        array.nulls().cloned(),
+    )?)
+}
 
-    Ok(batch)
+/// Replace the given columns of a struct array, appending columns not yet present
+fn with_struct_columns(
+    array: &StructArray,
+    columns: Vec<(&str, ArrayRef)>,
+) -> DeltaResult<StructArray> {
+    let mut fields = array.fields().to_vec();
+    let mut arrays = array.columns().to_vec();
+    for (name, column) in columns {
+        let field = Arc::new(ArrowField::new(name, column.data_type().clone(), true));
+        match fields.iter().position(|f| f.name() == name) {
+            Some(idx) => {
+                fields[idx] = field;
+                arrays[idx] = column;
+            }
+            None => {
+                fields.push(field);
+                arrays.push(column);
+            }
+        }
+    }
+    Ok(StructArray::try_new(
+        fields.into(),
+        arrays,
+        array.nulls().cloned(),
+    )?)
 }
 
 impl<S> Stream for ReplayStream<S>
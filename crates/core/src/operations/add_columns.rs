@@ -0,0 +1,337 @@
+//! Add columns to a table
+//!
+//! Columns are only added to the table schema, the data files are left untouched. Readers
+//! return null for the added columns in files written before they were added, so added
+//! columns must be nullable.
+
+use futures::future::BoxFuture;
+
+use super::transaction::protocol::upgrade_protocol;
+use super::transaction::{CommitBuilder, CommitProperties, PROTOCOL};
+use crate::kernel::{Action, ColumnPosition, StructField, WriterFeatures};
+use crate::logstore::LogStoreRef;
+use crate::protocol::DeltaOperation;
+use crate::table::config::{ColumnMappingMode, DeltaConfigKey, TableConfig};
+use crate::table::state::DeltaTableState;
+use crate::DeltaTable;
+use crate::{DeltaResult, DeltaTableError};
+
+/// A column to add to a table
+struct NewColumn {
+    /// Dot separated path of the struct to add the column to, empty for top-level columns
+    parent: String,
+    field: StructField,
+    position: ColumnPosition,
+}
+
+/// Add columns to a table
+pub struct AddColumnsBuilder {
+    /// A snapshot of the table's state
+    snapshot: DeltaTableState,
+    /// Columns to add, in order
+    columns: Vec<NewColumn>,
+    /// Delta object store for handling data files
+    log_store: LogStoreRef,
+    /// Additional information to add to the commit
+    commit_properties: CommitProperties,
+}
+
+impl super::Operation<()> for AddColumnsBuilder {}
+
+impl AddColumnsBuilder {
+    /// Create a new builder
+    pub fn new(log_store: LogStoreRef, snapshot: DeltaTableState) -> Self {
+        Self {
+            columns: Vec::new(),
+            snapshot,
+            log_store,
+            commit_properties: CommitProperties::default(),
+        }
+    }
+
+    /// Add a column after all other top-level columns
+    pub fn with_column(self, field: StructField) -> Self {
+        self.with_column_at(field, ColumnPosition::Last)
+    }
+
+    /// Add columns after all other top-level columns
+    pub fn with_columns(mut self, fields: impl IntoIterator<Item = StructField>) -> Self {
+        for field in fields {
+            self = self.with_column(field);
+        }
+        self
+    }
+
+    /// Add a top-level column at the given position
+    pub fn with_column_at(self, field: StructField, position: ColumnPosition) -> Self {
+        self.with_nested_column("", field, position)
+    }
+
+    /// Add a field to a struct column at the given position.
+    ///
+    /// Nested struct columns are referenced by their dot separated path.
+    pub fn with_nested_column(
+        mut self,
+        parent: impl Into<String>,
+        field: StructField,
+        position: ColumnPosition,
+    ) -> Self {
+        self.columns.push(NewColumn {
+            parent: parent.into(),
+            field,
+            position,
+        });
+        self
+    }
+
+    /// Additional metadata to be added to commit info
+    pub fn with_commit_properties(mut self, commit_properties: CommitProperties) -> Self {
+        self.commit_properties = commit_properties;
+        self
+    }
+}
+
+impl std::future::IntoFuture for AddColumnsBuilder {
+    type Output = DeltaResult<DeltaTable>;
+
+    type IntoFuture = BoxFuture<'static, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let this = self;
+
+        Box::pin(async move {
+            if this.columns.is_empty() {
+                return Err(DeltaTableError::Generic("No columns provided".to_string()));
+            }
+
+            let mut schema = this.snapshot.schema().clone();
+            let mut added = Vec::with_capacity(this.columns.len());
+            let mut fields = Vec::with_capacity(this.columns.len());
+            for column in this.columns {
+                let parent = match column.parent.as_str() {
+                    "" => vec![],
+                    parent => parent.split('.').collect(),
+                };
+                added.push(
+                    parent
+                        .iter()
+                        .chain([&column.field.name().as_str()])
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .join("."),
+                );
+                fields.push(column.field.clone());
+                schema = schema.with_field_added(&parent, column.field, &column.position)?;
+            }
+
+            let mut metadata = this.snapshot.metadata().clone();
+            let config = TableConfig(&metadata.configuration);
+            if config.column_mapping_mode() != ColumnMappingMode::None {
+                let mut max_column_id = config.column_mapping_max_column_id();
+                schema = schema.with_column_mapping(&mut max_column_id);
+                metadata.configuration.insert(
+                    DeltaConfigKey::ColumnMappingMaxColumnId
+                        .as_ref()
+                        .to_string(),
+                    Some(max_column_id.to_string()),
+                );
+            }
+            metadata.schema_string = serde_json::to_string(&schema)?;
+
+            let mut actions = vec![Action::Metadata(metadata)];
+            if PROTOCOL.contains_timestampntz(&fields) {
+                if let Some(protocol) = upgrade_protocol(
+                    this.snapshot.protocol(),
+                    0,
+                    0,
+                    [WriterFeatures::TimestampWithoutTimezone],
+                ) {
+                    PROTOCOL.can_write_protocol(&protocol)?;
+                    actions.push(Action::Protocol(protocol));
+                }
+            }
+
+            let operation = DeltaOperation::AddColumns { columns: added };
+            let commit = CommitBuilder::from(this.commit_properties)
+                .with_actions(actions)
+                .build(Some(&this.snapshot), this.log_store.clone(), operation)?
+                .await?;
+
+            Ok(DeltaTable::new_with_state(
+                this.log_store,
+                commit.snapshot(),
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "datafusion")]
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{DataType as ArrowDataType, Field, Schema as ArrowSchema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_sorted_eq;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::kernel::{DataType, PrimitiveType, StructType};
+    use crate::writer::test_utils::datafusion::get_data_sorted;
+    use crate::DeltaOps;
+
+    fn nested_type() -> DataType {
+        DataType::Struct(Box::new(StructType::new(vec![StructField::new(
+            "a",
+            DataType::INTEGER,
+            true,
+        )])))
+    }
+
+    async fn create_table(configuration: Option<(DeltaConfigKey, &str)>) -> DeltaTable {
+        let mut builder = DeltaOps::new_in_memory().create().with_columns(vec![
+            StructField::new("id", DataType::INTEGER, false),
+            StructField::new("nested", nested_type(), true),
+        ]);
+        if let Some((key, value)) = configuration {
+            builder = builder.with_configuration_property(key, Some(value));
+        }
+        builder.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_add_columns() {
+        let table = create_table(None).await;
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "id",
+            ArrowDataType::Int32,
+            false,
+        )]));
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1]))]).unwrap();
+        let table = DeltaOps(table)
+            .write(vec![batch])
+            .with_schema_mode(crate::operations::write::SchemaMode::Merge)
+            .await
+            .unwrap();
+
+        let table = DeltaOps(table)
+            .add_columns()
+            .with_column(StructField::new("value", DataType::STRING, true))
+            .with_column_at(
+                StructField::new("first", DataType::LONG, true),
+                ColumnPosition::First,
+            )
+            .with_nested_column(
+                "nested",
+                StructField::new("b", DataType::DATE, true),
+                ColumnPosition::After("a".to_string()),
+            )
+            .await
+            .unwrap();
+        let schema = table.get_schema().unwrap();
+        let names = schema.fields().iter().map(|f| f.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["first", "id", "nested", "value"]);
+        let DataType::Struct(nested) = schema.field_with_name("nested").unwrap().data_type() else {
+            panic!("nested column is not a struct");
+        };
+        assert_eq!(
+            nested.field_with_name("b").unwrap().data_type(),
+            &DataType::Primitive(PrimitiveType::Date)
+        );
+
+        let history = table.history(Some(1)).await.unwrap();
+        assert_eq!(history[0].operation.as_deref(), Some("ADD COLUMNS"));
+
+        // files written before the columns were added are read with null values
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", ArrowDataType::Int32, false),
+            Field::new("value", ArrowDataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![2])),
+                Arc::new(StringArray::from(vec!["b"])),
+            ],
+        )
+        .unwrap();
+        let table = DeltaOps(table)
+            .write(vec![batch])
+            .with_schema_mode(crate::operations::write::SchemaMode::Merge)
+            .await
+            .unwrap();
+        let expected = [
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 1  |       |",
+            "| 2  | b     |",
+            "+----+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &get_data_sorted(&table, "id,value").await);
+    }
+
+    #[tokio::test]
+    async fn test_add_columns_with_column_mapping() {
+        let table = create_table(Some((DeltaConfigKey::ColumnMappingMode, "name"))).await;
+        let max_column_id = table
+            .snapshot()
+            .unwrap()
+            .table_config()
+            .column_mapping_max_column_id();
+
+        let table = DeltaOps(table)
+            .add_columns()
+            .with_column(StructField::new("value", DataType::STRING, true))
+            .await
+            .unwrap();
+        let snapshot = table.snapshot().unwrap();
+        assert_eq!(
+            snapshot.table_config().column_mapping_max_column_id(),
+            max_column_id + 1
+        );
+        let field = snapshot.schema().field_with_name("value").unwrap();
+        assert_eq!(field.column_mapping_id(), Some(max_column_id as i32 + 1));
+        assert!(field.physical_name().unwrap().starts_with("col-"));
+    }
+
+    #[tokio::test]
+    async fn test_add_timestamp_ntz_column() {
+        let table = create_table(None).await;
+        let table = DeltaOps(table)
+            .add_columns()
+            .with_column(StructField::new("ts", DataType::TIMESTAMPNTZ, true))
+            .await
+            .unwrap();
+        let protocol = table.protocol().unwrap();
+        assert_eq!(protocol.min_reader_version, 3);
+        assert!(protocol
+            .writer_features
+            .as_ref()
+            .unwrap()
+            .contains(&WriterFeatures::TimestampWithoutTimezone));
+    }
+
+    #[tokio::test]
+    async fn test_add_invalid_columns() {
+        let table = create_table(None).await;
+        let invalid = [
+            ("", StructField::new("ID", DataType::STRING, true)),
+            ("", StructField::new("value", DataType::STRING, false)),
+            ("id", StructField::new("value", DataType::STRING, true)),
+            ("missing", StructField::new("value", DataType::STRING, true)),
+            (
+                "",
+                StructField::new("value", DataType::STRING, true)
+                    .with_metadata([("delta.generationExpression", "id".to_string())]),
+            ),
+        ];
+        for (parent, field) in invalid {
+            let result = DeltaOps(table.clone())
+                .add_columns()
+                .with_nested_column(parent, field, ColumnPosition::Last)
+                .await;
+            assert!(result.is_err());
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Helpers for operations that record the change data feed of a table
+//!
+//! When the change data feed is enabled, operations which modify existing rows write the
+//! changed rows to files in the `_change_data` folder and commit them as `cdc` actions.
+//! Every row carries its `_change_type`, so readers don't need to infer the changes from
+//! the added and removed data files.
+
+use std::sync::Arc;
+
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion_common::ScalarValue;
+use datafusion_physical_expr::{expressions, PhysicalExpr};
+
+use crate::delta_datafusion::cdf::CHANGE_TYPE_COL;
+use crate::errors::DeltaResult;
+use crate::kernel::WriterFeatures;
+use crate::table::state::DeltaTableState;
+
+/// Folder relative to the table root that change data files are written to
+pub(crate) const CHANGE_DATA_FOLDER: &str = "_change_data";
+
+/// Change type of rows deleted from the table
+pub(crate) const CHANGE_TYPE_DELETE: &str = "delete";
+/// Change type of rows inserted into the table
+pub(crate) const CHANGE_TYPE_INSERT: &str = "insert";
+/// Change type of the previous values of updated rows
+pub(crate) const CHANGE_TYPE_UPDATE_PREIMAGE: &str = "update_preimage";
+/// Change type of the new values of updated rows
+pub(crate) const CHANGE_TYPE_UPDATE_POSTIMAGE: &str = "update_postimage";
+
+/// Determine if the changes of modified rows should be written to change data files.
+pub(crate) fn should_write_cdc(snapshot: &DeltaTableState) -> bool {
+    if !snapshot.table_config().enable_change_data_feed() {
+        return false;
+    }
+    let protocol = snapshot.protocol();
+    match protocol.min_writer_version {
+        0..=3 => false,
+        4..=6 => true,
+        _ => protocol
+            .writer_features
+            .as_ref()
+            .map(|features| features.contains(&WriterFeatures::ChangeDataFeed))
+            .unwrap_or(false),
+    }
+}
+
+/// Append the `_change_type` column with the given change type to all rows of the plan.
+pub(crate) fn with_change_type(
+    plan: Arc<dyn ExecutionPlan>,
+    change_type: &str,
+) -> DeltaResult<Arc<dyn ExecutionPlan>> {
+    let mut expressions: Vec<(Arc<dyn PhysicalExpr>, String)> = plan
+        .schema()
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            (
+                Arc::new(expressions::Column::new(field.name(), i)) as Arc<dyn PhysicalExpr>,
+                field.name().to_owned(),
+            )
+        })
+        .collect();
+    expressions.push((
+        Arc::new(expressions::Literal::new(ScalarValue::Utf8(Some(
+            change_type.to_string(),
+        )))),
+        CHANGE_TYPE_COL.to_string(),
+    ));
+    Ok(Arc::new(ProjectionExec::try_new(expressions, plan)?))
+}
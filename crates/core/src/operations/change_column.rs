@@ -0,0 +1,268 @@
+//! Change the comment, metadata or nullability of a column of a table
+//!
+//! Only changes that don't affect the data files of the table are supported: a column can
+//! be made nullable, but non-nullable columns cannot be added.
+
+use std::collections::HashMap;
+
+use futures::future::BoxFuture;
+
+use super::transaction::{CommitBuilder, CommitProperties};
+use crate::kernel::{Action, ColumnMetadataKey, MetadataValue};
+use crate::logstore::LogStoreRef;
+use crate::protocol::DeltaOperation;
+use crate::table::state::DeltaTableState;
+use crate::DeltaTable;
+use crate::{DeltaResult, DeltaTableError};
+
+/// Change the comment, metadata or nullability of a column
+pub struct ChangeColumnBuilder {
+    /// A snapshot of the table's state
+    snapshot: DeltaTableState,
+    /// Dot separated path of the column to change
+    column: Option<String>,
+    /// New nullability of the column
+    nullable: Option<bool>,
+    /// Metadata to set, or to remove if `None`
+    metadata: HashMap<String, Option<MetadataValue>>,
+    /// Delta object store for handling data files
+    log_store: LogStoreRef,
+    /// Additional information to add to the commit
+    commit_properties: CommitProperties,
+}
+
+impl super::Operation<()> for ChangeColumnBuilder {}
+
+impl ChangeColumnBuilder {
+    /// Create a new builder
+    pub fn new(log_store: LogStoreRef, snapshot: DeltaTableState) -> Self {
+        Self {
+            column: None,
+            nullable: None,
+            metadata: HashMap::new(),
+            snapshot,
+            log_store,
+            commit_properties: CommitProperties::default(),
+        }
+    }
+
+    /// Specify the column to change.
+    ///
+    /// Nested fields of struct columns are referenced by their dot separated path.
+    pub fn with_column<S: Into<String>>(mut self, column: S) -> Self {
+        self.column = Some(column.into());
+        self
+    }
+
+    /// Set the comment of the column
+    pub fn with_comment<S: Into<String>>(self, comment: S) -> Self {
+        self.with_metadata(ColumnMetadataKey::Comment.as_ref(), comment.into())
+    }
+
+    /// Set a metadata entry of the column
+    pub fn with_metadata(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<MetadataValue>,
+    ) -> Self {
+        self.metadata.insert(key.into(), Some(value.into()));
+        self
+    }
+
+    /// Remove a metadata entry, e.g. the comment, from the column
+    pub fn without_metadata(mut self, key: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), None);
+        self
+    }
+
+    /// Specify if the column is nullable. Non-nullable columns can only be made nullable.
+    pub fn with_nullable(mut self, nullable: bool) -> Self {
+        self.nullable = Some(nullable);
+        self
+    }
+
+    /// Additional metadata to be added to commit info
+    pub fn with_commit_properties(mut self, commit_properties: CommitProperties) -> Self {
+        self.commit_properties = commit_properties;
+        self
+    }
+}
+
+impl std::future::IntoFuture for ChangeColumnBuilder {
+    type Output = DeltaResult<DeltaTable>;
+
+    type IntoFuture = BoxFuture<'static, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let this = self;
+
+        Box::pin(async move {
+            let column = this
+                .column
+                .ok_or(DeltaTableError::Generic("No column provided".to_string()))?;
+
+            let path = column.split('.').collect::<Vec<_>>();
+            let mut changed = None;
+            let schema = this.snapshot.schema().with_field_updated(&path, |field| {
+                let field = field.with_changes(this.nullable, &this.metadata)?;
+                changed = Some(field.clone());
+                Ok(field)
+            })?;
+            let Some(field) = changed else {
+                return Err(DeltaTableError::Generic(format!(
+                    "Column {column} does not exist"
+                )));
+            };
+
+            let mut metadata = this.snapshot.metadata().clone();
+            metadata.schema_string = serde_json::to_string(&schema)?;
+
+            let operation = DeltaOperation::ChangeColumn {
+                column_path: column,
+                field,
+            };
+
+            let actions = vec![Action::Metadata(metadata)];
+
+            let commit = CommitBuilder::from(this.commit_properties)
+                .with_actions(actions)
+                .build(Some(&this.snapshot), this.log_store.clone(), operation)?
+                .await?;
+
+            Ok(DeltaTable::new_with_state(
+                this.log_store,
+                commit.snapshot(),
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "datafusion")]
+#[cfg(test)]
+mod tests {
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType as ArrowDataType, Field, Schema as ArrowSchema};
+    use arrow::record_batch::RecordBatch;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::kernel::{DataType, StructField, StructType};
+    use crate::DeltaOps;
+
+    async fn create_table() -> DeltaTable {
+        DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![
+                StructField::new("id", DataType::INTEGER, false),
+                StructField::new(
+                    "nested",
+                    DataType::Struct(Box::new(StructType::new(vec![StructField::new(
+                        "a",
+                        DataType::INTEGER,
+                        false,
+                    )]))),
+                    true,
+                ),
+            ])
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_change_comment() {
+        let table = DeltaOps(create_table().await)
+            .change_column()
+            .with_column("id")
+            .with_comment("identifier")
+            .await
+            .unwrap();
+        let field = table
+            .get_schema()
+            .unwrap()
+            .field_with_name("id")
+            .unwrap()
+            .clone();
+        assert_eq!(
+            field.get_config_value(&ColumnMetadataKey::Comment),
+            Some(&MetadataValue::String("identifier".to_string()))
+        );
+        assert!(!field.is_nullable());
+
+        let history = table.history(Some(1)).await.unwrap();
+        assert_eq!(history[0].operation.as_deref(), Some("CHANGE COLUMN"));
+
+        let table = DeltaOps(table)
+            .change_column()
+            .with_column("id")
+            .without_metadata(ColumnMetadataKey::Comment.as_ref())
+            .await
+            .unwrap();
+        let schema = table.get_schema().unwrap();
+        assert!(schema.field_with_name("id").unwrap().metadata().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_drop_not_null() {
+        let table = DeltaOps(create_table().await)
+            .change_column()
+            .with_column("nested.a")
+            .with_nullable(true)
+            .await
+            .unwrap();
+        let table = DeltaOps(table)
+            .change_column()
+            .with_column("id")
+            .with_nullable(true)
+            .await
+            .unwrap();
+        assert!(table
+            .get_schema()
+            .unwrap()
+            .field_with_name("id")
+            .unwrap()
+            .is_nullable());
+
+        // null values can be written to the column
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "id",
+            ArrowDataType::Int32,
+            true,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from(vec![Some(1), None]))],
+        )
+        .unwrap();
+        let table = DeltaOps(table)
+            .write(vec![batch])
+            .with_schema_mode(crate::operations::write::SchemaMode::Merge)
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_changes() {
+        let table = create_table().await;
+        let result = DeltaOps(table.clone())
+            .change_column()
+            .with_column("nested")
+            .with_nullable(false)
+            .await;
+        assert!(result.is_err());
+
+        let result = DeltaOps(table.clone())
+            .change_column()
+            .with_column("id")
+            .with_metadata(ColumnMetadataKey::ColumnMappingId.as_ref(), 1)
+            .await;
+        assert!(result.is_err());
+
+        let result = DeltaOps(table)
+            .change_column()
+            .with_column("missing")
+            .with_comment("missing")
+            .await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,444 @@
+//! Widen the data type of a column of a table
+//!
+//! With the type widening table feature enabled, the type of a column can be changed to a
+//! wider type without rewriting any data files. Each change is recorded in the
+//! `delta.typeChanges` metadata of the column, and readers upcast the values of files
+//! written before the change to the new type.
+
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+
+use super::transaction::{CommitBuilder, CommitProperties};
+use crate::kernel::{
+    Action, ColumnMetadataKey, DataType, MetadataValue, PrimitiveType, StructField, StructType,
+    WriterFeatures,
+};
+use crate::logstore::LogStoreRef;
+use crate::protocol::DeltaOperation;
+use crate::table::state::DeltaTableState;
+use crate::DeltaTable;
+use crate::{DeltaResult, DeltaTableError};
+
+/// Widen the data type of a column of a table
+pub struct ChangeColumnTypeBuilder {
+    /// A snapshot of the table's state
+    snapshot: DeltaTableState,
+    /// Dot separated path of the column to change
+    column: Option<String>,
+    /// New data type of the column
+    data_type: Option<DataType>,
+    /// Delta object store for handling data files
+    log_store: LogStoreRef,
+    /// Additional information to add to the commit
+    commit_properties: CommitProperties,
+}
+
+impl super::Operation<()> for ChangeColumnTypeBuilder {}
+
+impl ChangeColumnTypeBuilder {
+    /// Create a new builder
+    pub fn new(log_store: LogStoreRef, snapshot: DeltaTableState) -> Self {
+        Self {
+            column: None,
+            data_type: None,
+            snapshot,
+            log_store,
+            commit_properties: CommitProperties::default(),
+        }
+    }
+
+    /// Specify the column to change.
+    ///
+    /// Nested fields of struct columns are referenced by their dot separated path.
+    pub fn with_column<S: Into<String>>(mut self, column: S) -> Self {
+        self.column = Some(column.into());
+        self
+    }
+
+    /// Specify the new data type of the column
+    pub fn with_data_type(mut self, data_type: DataType) -> Self {
+        self.data_type = Some(data_type);
+        self
+    }
+
+    /// Additional metadata to be added to commit info
+    pub fn with_commit_properties(mut self, commit_properties: CommitProperties) -> Self {
+        self.commit_properties = commit_properties;
+        self
+    }
+}
+
+/// Determine if values of type `from` can be read as type `to` without rewriting data files.
+pub(crate) fn is_widening_supported(from: &PrimitiveType, to: &PrimitiveType) -> bool {
+    use PrimitiveType::*;
+    match (from, to) {
+        (Byte, Short | Integer | Long | Double)
+        | (Short, Integer | Long | Double)
+        | (Integer, Long | Double)
+        | (Float, Double)
+        | (Date, TimestampNtz) => true,
+        (Decimal(from_precision, from_scale), Decimal(to_precision, to_scale)) => {
+            to_scale >= from_scale
+                && (*to_precision as i16 - *from_precision as i16)
+                    >= (*to_scale as i16 - *from_scale as i16)
+        }
+        // integers are widened to decimals which can hold all of their digits
+        (Byte | Short | Integer, Decimal(precision, scale)) => {
+            *precision as i16 - *scale as i16 >= 10
+        }
+        (Long, Decimal(precision, scale)) => *precision as i16 - *scale as i16 >= 20,
+        _ => false,
+    }
+}
+
+/// Change the type of the field at the given path, returning the updated fields and the
+/// previous type of the field.
+fn change_field_type(
+    fields: &[StructField],
+    path: &[&str],
+    data_type: &DataType,
+) -> DeltaResult<(Vec<StructField>, DataType)> {
+    let (name, rest) = path
+        .split_first()
+        .ok_or_else(|| DeltaTableError::Generic("No column provided".to_string()))?;
+    let mut from_type = None;
+    let fields = fields
+        .iter()
+        .map(|field| {
+            if field.name() != name {
+                return Ok(field.clone());
+            }
+            if rest.is_empty() {
+                let (from, to) = match (field.data_type(), data_type) {
+                    (DataType::Primitive(from), DataType::Primitive(to))
+                        if is_widening_supported(from, to) =>
+                    {
+                        (from, to)
+                    }
+                    (from, to) => {
+                        let msg = format!("Changing the type of column {name} from {from} to {to}");
+                        return Err(DeltaTableError::Generic(format!("{msg} is not supported")));
+                    }
+                };
+                from_type = Some(field.data_type().clone());
+
+                let type_changes = field.get_config_value(&ColumnMetadataKey::TypeChanges);
+                let mut type_changes = match type_changes {
+                    Some(MetadataValue::Other(Value::Array(changes))) => changes.clone(),
+                    _ => vec![],
+                };
+                type_changes.push(json!({
+                    "fromType": from.to_string(),
+                    "toType": to.to_string(),
+                }));
+                let mut field = StructField {
+                    data_type: data_type.clone(),
+                    ..field.clone()
+                };
+                field.metadata.insert(
+                    ColumnMetadataKey::TypeChanges.as_ref().to_string(),
+                    MetadataValue::Other(Value::Array(type_changes)),
+                );
+                return Ok(field);
+            }
+            match field.data_type() {
+                DataType::Struct(inner) => {
+                    let (inner_fields, from) = change_field_type(inner.fields(), rest, data_type)?;
+                    from_type = Some(from);
+                    Ok(StructField {
+                        data_type: DataType::Struct(Box::new(StructType::new(inner_fields))),
+                        ..field.clone()
+                    })
+                }
+                _ => Err(DeltaTableError::Generic(format!(
+                    "Column {name} is not a struct"
+                ))),
+            }
+        })
+        .collect::<DeltaResult<Vec<_>>>()?;
+
+    match from_type {
+        Some(from_type) => Ok((fields, from_type)),
+        None => Err(DeltaTableError::Generic(format!(
+            "Column {name} does not exist"
+        ))),
+    }
+}
+
+impl std::future::IntoFuture for ChangeColumnTypeBuilder {
+    type Output = DeltaResult<DeltaTable>;
+
+    type IntoFuture = BoxFuture<'static, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let this = self;
+
+        Box::pin(async move {
+            let column = this
+                .column
+                .ok_or(DeltaTableError::Generic("No column provided".to_string()))?;
+            let data_type = this.data_type.ok_or(DeltaTableError::Generic(
+                "No data type provided".to_string(),
+            ))?;
+
+            let supports_type_widening = this
+                .snapshot
+                .protocol()
+                .writer_features
+                .as_ref()
+                .map(|features| features.contains(&WriterFeatures::TypeWidening))
+                .unwrap_or(false);
+            if !supports_type_widening || !this.snapshot.table_config().enable_type_widening() {
+                return Err(DeltaTableError::Generic(
+                    "Type widening must be enabled to change the type of columns".to_string(),
+                ));
+            }
+
+            let path = column.split('.').collect::<Vec<_>>();
+            let (fields, from_type) =
+                change_field_type(this.snapshot.schema().fields(), &path, &data_type)?;
+
+            let mut metadata = this.snapshot.metadata().clone();
+            metadata.schema_string = serde_json::to_string(&StructType::new(fields))?;
+
+            let operation = DeltaOperation::ChangeColumnType {
+                column_path: column,
+                from_type,
+                to_type: data_type,
+            };
+
+            let actions = vec![Action::Metadata(metadata)];
+
+            let commit = CommitBuilder::from(this.commit_properties)
+                .with_actions(actions)
+                .build(Some(&this.snapshot), this.log_store.clone(), operation)?
+                .await?;
+
+            Ok(DeltaTable::new_with_state(
+                this.log_store,
+                commit.snapshot(),
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "datafusion")]
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Int32Array, Int64Array, StringArray, StructArray};
+    use arrow::datatypes::{DataType as ArrowDataType, Field, Fields, Schema as ArrowSchema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_sorted_eq;
+    use datafusion::prelude::SessionContext;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::kernel::{ReaderFeatures, StructType};
+    use crate::protocol::SaveMode;
+    use crate::{DeltaConfigKey, DeltaOps};
+
+    fn nested_type(a: DataType) -> DataType {
+        DataType::Struct(Box::new(StructType::new(vec![StructField::new(
+            "a", a, true,
+        )])))
+    }
+
+    fn batch(
+        id: Arc<dyn arrow::array::Array>,
+        nested: Arc<dyn arrow::array::Array>,
+    ) -> RecordBatch {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", id.data_type().clone(), true),
+            Field::new("value", ArrowDataType::Utf8, true),
+            Field::new("nested", nested.data_type().clone(), true),
+        ]));
+        let len = id.len();
+        RecordBatch::try_new(
+            schema,
+            vec![id, Arc::new(StringArray::from(vec!["a"; len])), nested],
+        )
+        .unwrap()
+    }
+
+    fn nested_array(values: Arc<dyn arrow::array::Array>) -> Arc<StructArray> {
+        Arc::new(StructArray::new(
+            Fields::from(vec![Field::new("a", values.data_type().clone(), true)]),
+            vec![values],
+            None,
+        ))
+    }
+
+    async fn create_type_widening_table() -> DeltaResult<DeltaTable> {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![
+                StructField::new("id", DataType::INTEGER, true),
+                StructField::new("value", DataType::STRING, true),
+                StructField::new("nested", nested_type(DataType::INTEGER), true),
+            ])
+            .with_partition_columns(["value"])
+            .with_configuration_property(DeltaConfigKey::EnableTypeWidening, Some("true"))
+            .await?;
+
+        let ids = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        DeltaOps(table)
+            .write(vec![batch(ids.clone(), nested_array(ids))])
+            .with_save_mode(SaveMode::Append)
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_create_type_widening_table() -> DeltaResult<()> {
+        let table = create_type_widening_table().await?;
+        let protocol = table.protocol()?;
+        assert_eq!(protocol.min_reader_version, 3);
+        assert_eq!(protocol.min_writer_version, 7);
+        assert!(protocol
+            .reader_features
+            .as_ref()
+            .unwrap()
+            .contains(&ReaderFeatures::TypeWidening));
+        assert!(protocol
+            .writer_features
+            .as_ref()
+            .unwrap()
+            .contains(&WriterFeatures::TypeWidening));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_change_column_type() -> DeltaResult<()> {
+        let table = create_type_widening_table().await?;
+        let version = table.version();
+
+        let table = DeltaOps(table)
+            .change_column_type()
+            .with_column("id")
+            .with_data_type(DataType::LONG)
+            .await?;
+        assert_eq!(table.version(), version + 1);
+        let table = DeltaOps(table)
+            .change_column_type()
+            .with_column("nested.a")
+            .with_data_type(DataType::LONG)
+            .await?;
+
+        let schema = table.metadata()?.schema()?;
+        let field = schema.field_with_name("id")?;
+        assert_eq!(field.data_type(), &DataType::LONG);
+        assert_eq!(
+            field.get_config_value(&ColumnMetadataKey::TypeChanges),
+            Some(&MetadataValue::Other(json!([
+                {"fromType": "integer", "toType": "long"}
+            ])))
+        );
+        let DataType::Struct(nested) = schema.field_with_name("nested")?.data_type() else {
+            panic!("expected a struct")
+        };
+        assert_eq!(nested.field_with_name("a")?.data_type(), &DataType::LONG);
+
+        // files written before and after the type change are read with the widened type
+        let ids = Arc::new(Int64Array::from(vec![i64::MAX]));
+        let table = DeltaOps(table)
+            .write(vec![batch(ids.clone(), nested_array(ids))])
+            .with_save_mode(SaveMode::Append)
+            .await?;
+
+        let ctx = SessionContext::new();
+        ctx.register_table("test", Arc::new(table))?;
+        let batches = ctx
+            .sql("SELECT id, nested['a'] AS a FROM test WHERE id > 1")
+            .await?
+            .collect()
+            .await?;
+        assert_eq!(
+            batches[0].schema().field(0).data_type(),
+            &ArrowDataType::Int64
+        );
+        let expected = vec![
+            "+---------------------+---------------------+",
+            "| id                  | a                   |",
+            "+---------------------+---------------------+",
+            "| 2                   | 2                   |",
+            "| 3                   | 3                   |",
+            "| 9223372036854775807 | 9223372036854775807 |",
+            "+---------------------+---------------------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_change_column_type_read_before_write() -> DeltaResult<()> {
+        let table = create_type_widening_table().await?;
+        let table = DeltaOps(table)
+            .change_column_type()
+            .with_column("id")
+            .with_data_type(DataType::Primitive(PrimitiveType::Decimal(12, 2)))
+            .await?;
+
+        let ctx = SessionContext::new();
+        ctx.register_table("test", Arc::new(table))?;
+        let batches = ctx.sql("SELECT id FROM test").await?.collect().await?;
+        let expected = vec![
+            "+------+", //
+            "| id   |", "+------+", "| 1.00 |", "| 2.00 |", "| 3.00 |", "+------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_change_column_type_unsupported() -> DeltaResult<()> {
+        let table = create_type_widening_table().await?;
+        for (column, data_type) in [
+            ("id", DataType::SHORT),
+            ("id", DataType::STRING),
+            ("id", DataType::Primitive(PrimitiveType::Decimal(10, 2))),
+            ("value", DataType::LONG),
+            ("nested", DataType::LONG),
+            ("missing", DataType::LONG),
+        ] {
+            let result = DeltaOps(table.clone())
+                .change_column_type()
+                .with_column(column)
+                .with_data_type(data_type)
+                .await;
+            assert!(result.is_err(), "{column}");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_change_column_type_requires_type_widening() -> DeltaResult<()> {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![StructField::new("id", DataType::INTEGER, true)])
+            .await?;
+
+        let result = DeltaOps(table)
+            .change_column_type()
+            .with_column("id")
+            .with_data_type(DataType::LONG)
+            .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_widening_supported() {
+        use PrimitiveType::*;
+        assert!(is_widening_supported(&Byte, &Integer));
+        assert!(is_widening_supported(&Integer, &Double));
+        assert!(is_widening_supported(&Float, &Double));
+        assert!(is_widening_supported(&Date, &TimestampNtz));
+        assert!(is_widening_supported(&Decimal(10, 2), &Decimal(12, 4)));
+        assert!(is_widening_supported(&Long, &Decimal(20, 0)));
+        assert!(!is_widening_supported(&Long, &Integer));
+        assert!(!is_widening_supported(&Long, &Double));
+        assert!(!is_widening_supported(&Decimal(10, 2), &Decimal(11, 4)));
+        assert!(!is_widening_supported(&Integer, &Decimal(10, 1)));
+        assert!(!is_widening_supported(&Date, &Timestamp));
+    }
+}
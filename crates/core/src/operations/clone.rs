@@ -0,0 +1,1225 @@
+//! Clone a table to another location
+//!
+//! A clone is a new table at another location, which starts out with the data of a version of
+//! the source table. The clone carries over the schema, partitioning and properties of the
+//! source table, but has its own history: changes to the clone do not affect the source table
+//! and vice versa.
+//!
+//! A shallow clone does not copy any data files, instead it references the files of the source
+//! table by their absolute URIs. Files of the clone located outside of its root are never
+//! deleted by vacuuming the clone. Vacuuming the source table however may delete files the
+//! clone still references. Optimize only rewrites files written to the clone and skips the
+//! files of the source table.
+//!
+//! A deep clone copies the files of the source table to the clone, so the clone is independent
+//! of the source table, e.g. to migrate a table to another object store. Files the source table
+//! references outside of its root, e.g. the files of a shallow clone, are copied to the root of
+//! the clone. Files are copied concurrently and files which already exist at the target are not
+//! copied again, so a deep clone that failed can be resumed by running it again.
+//!
+//! An existing clone can be synced with a later version of the source table, if the source
+//! table is recorded in the clone's history. Files the clone already contains are kept, files
+//! added to the source table since are added to the clone and, for deep clones, copied, and
+//! files no longer part of the source table are removed.
+//!
+//! # Example
+//! ```rust ignore
+//! let table = open_table("../path/to/table")?;
+//! let (clone, metrics) = DeltaOps(table)
+//!     .clone()
+//!     .with_location("../path/to/clone")
+//!     .with_version(1)
+//!     .await?;
+//! ````
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::ObjectStore;
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use url::Url;
+
+use super::transaction::protocol::upgrade_protocol;
+use super::transaction::{CommitBuilder, CommitProperties, TableReference, PROTOCOL};
+use crate::kernel::{Action, Add, Metadata, Remove, StorageType};
+use crate::logstore::LogStoreRef;
+use crate::protocol::DeltaOperation;
+use crate::storage::utils::is_absolute_path;
+use crate::storage::{root_store_for, store_root, ObjectStoreRef, StorageOptions};
+use crate::table::builder::ensure_table_uri;
+use crate::table::config::DeltaConfigKey;
+use crate::table::state::DeltaTableState;
+use crate::{DeltaResult, DeltaTable, DeltaTableBuilder, DeltaTableError, ObjectStoreError};
+
+/// Errors that can occur during clone
+#[derive(thiserror::Error, Debug)]
+enum CloneError {
+    #[error("Location must be provided to clone a table.")]
+    MissingLocation,
+
+    #[error("A Delta Lake table already exists at that location.")]
+    TableAlreadyExists,
+
+    #[error("A table cannot be cloned to its own location.")]
+    SameLocation,
+
+    #[error("The table at the location of the clone is not a clone of {location}.")]
+    NotACloneOfSource { location: String },
+
+    #[error("Either the version or datetime should be provided for clone, not both.")]
+    InvalidCloneParameter,
+
+    #[error("Files {first} and {second} of the source table would both be copied to {path} in the clone.")]
+    ConflictingFiles {
+        first: String,
+        second: String,
+        path: String,
+    },
+}
+
+impl From<CloneError> for DeltaTableError {
+    fn from(err: CloneError) -> Self {
+        DeltaTableError::GenericError {
+            source: Box::new(err),
+        }
+    }
+}
+
+/// Table properties which refer to the history of a table, and thus don't apply to the clone
+const HISTORY_PROPERTIES: [DeltaConfigKey; 3] = [
+    DeltaConfigKey::InCommitTimestampEnablementVersion,
+    DeltaConfigKey::InCommitTimestampEnablementTimestamp,
+    DeltaConfigKey::RequireCheckpointProtectionBeforeVersion,
+];
+
+/// Metrics from Clone
+#[derive(Default, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloneMetrics {
+    /// Size in bytes of the cloned version of the source table
+    pub source_table_size: i64,
+    /// Number of files in the cloned version of the source table
+    pub source_num_of_files: usize,
+    /// Number of files removed from the clone when syncing it
+    pub num_removed_files: usize,
+    /// Size in bytes of the files removed from the clone when syncing it
+    pub removed_files_size: i64,
+    /// Number of files copied to the clone
+    pub num_copied_files: usize,
+    /// Size in bytes of the files copied to the clone
+    pub copied_files_size: i64,
+}
+
+/// Clone a table to another location
+/// See this module's documentation for more information
+pub struct CloneBuilder {
+    /// A snapshot of the source table's state
+    snapshot: DeltaTableState,
+    /// Delta object store of the source table
+    log_store: LogStoreRef,
+    /// Version of the source table to clone
+    version: Option<i64>,
+    /// Datetime of the source table to clone
+    datetime: Option<DateTime<Utc>>,
+    /// Location of the clone
+    location: Option<String>,
+    /// Options used to initialize the storage of the clone
+    storage_options: Option<HashMap<String, String>>,
+    /// Delta object store of the clone
+    target_log_store: Option<LogStoreRef>,
+    /// Copy the data files to the clone
+    deep: bool,
+    /// Sync an existing clone
+    sync: bool,
+    /// Max number of files copied concurrently
+    max_concurrent_tasks: usize,
+    /// Additional information to add to the commit
+    commit_properties: CommitProperties,
+}
+
+impl super::Operation<()> for CloneBuilder {}
+
+impl CloneBuilder {
+    /// Create a new [`CloneBuilder`]
+    pub fn new(log_store: LogStoreRef, snapshot: DeltaTableState) -> Self {
+        Self {
+            snapshot,
+            log_store,
+            version: None,
+            datetime: None,
+            location: None,
+            storage_options: None,
+            target_log_store: None,
+            deep: false,
+            sync: false,
+            max_concurrent_tasks: num_cpus::get(),
+            commit_properties: CommitProperties::default(),
+        }
+    }
+
+    /// Clone the given version of the source table, defaults to the loaded version
+    pub fn with_version(mut self, version: i64) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Clone the latest version of the source table created at or before the given datetime
+    pub fn with_datetime(mut self, datetime: DateTime<Utc>) -> Self {
+        self.datetime = Some(datetime);
+        self
+    }
+
+    /// Specify the location of the clone
+    pub fn with_location(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// Set options used to initialize the storage of the clone
+    ///
+    /// The same options are used to read the files of a shallow clone's source table.
+    pub fn with_storage_options(mut self, storage_options: HashMap<String, String>) -> Self {
+        self.storage_options = Some(storage_options);
+        self
+    }
+
+    /// Provide a [`LogStore`](crate::logstore::LogStore) instance, that points at the location
+    /// of the clone
+    pub fn with_log_store(mut self, log_store: LogStoreRef) -> Self {
+        self.target_log_store = Some(log_store);
+        self
+    }
+
+    /// Whether to copy the data files of the source table to the clone, defaults to `false`
+    pub fn with_deep(mut self, deep: bool) -> Self {
+        self.deep = deep;
+        self
+    }
+
+    /// Whether to sync an existing clone with the source table rather than fail if a table
+    /// exists at the location of the clone, defaults to `false`
+    ///
+    /// Syncing fails if the latest clone commit of the existing table did not clone the
+    /// source table.
+    pub fn with_sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// Max number of files copied concurrently by deep clones
+    pub fn with_max_concurrent_tasks(mut self, max_concurrent_tasks: usize) -> Self {
+        self.max_concurrent_tasks = max_concurrent_tasks;
+        self
+    }
+
+    /// Additional metadata to be added to commit info
+    pub fn with_commit_properties(mut self, commit_properties: CommitProperties) -> Self {
+        self.commit_properties = commit_properties;
+        self
+    }
+}
+
+/// Reference a file of the source table by its absolute URI
+fn with_absolute_paths(mut add: Add, source_root: &Url) -> DeltaResult<Add> {
+    if !is_absolute_path(&add.path)? {
+        add.path = source_root
+            .join(&add.path)
+            .map_err(|_| DeltaTableError::InvalidTableLocation(add.path.clone()))?
+            .to_string();
+    }
+    if let Some(dv) = add.deletion_vector.as_mut() {
+        if dv.storage_type == StorageType::UuidRelativePath {
+            if let Some(path) = dv.absolute_path(source_root)? {
+                dv.storage_type = StorageType::AbsolutePath;
+                dv.path_or_inline_dv = path.to_string();
+            }
+        }
+    }
+    add.data_change = true;
+    Ok(add)
+}
+
+/// A file of the source table which is copied to the clone
+#[derive(Debug, Clone, PartialEq)]
+enum SourceFile {
+    /// A file referenced relative to the root of the source table
+    Table(Path),
+    /// A file referenced by its absolute URI, within or outside of the root of the source table
+    External(Url),
+}
+
+impl std::fmt::Display for SourceFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Table(path) => write!(f, "{path}"),
+            Self::External(url) => write!(f, "{url}"),
+        }
+    }
+}
+
+/// Parse the relative path of a data file, preserving its percent encoding if possible
+fn parse_path(path: &str) -> Path {
+    Path::parse(path).unwrap_or_else(|_| Path::from(path))
+}
+
+/// Location of a file relative to the root of the clone it is copied to, as a URL path
+///
+/// Files within the source root keep their relative location. Files outside of it, e.g. the
+/// files a shallow clone references in its own source table, are copied to the root of the clone.
+fn relative_location(location: &Url, source_root: &Url) -> DeltaResult<String> {
+    if let Some(path) = location.as_str().strip_prefix(source_root.as_str()) {
+        return Ok(path.to_string());
+    }
+    location
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .ok_or_else(|| DeltaTableError::InvalidTableLocation(location.to_string()))
+}
+
+/// Reference a file of the source table relative to the root of the clone, where it is copied to
+///
+/// Returns the rewritten action, along with the data file and deletion vector to copy, keyed by
+/// their path within the root of the clone.
+fn with_relative_paths(
+    mut add: Add,
+    source_root: &Url,
+    target_root: &Url,
+) -> DeltaResult<(Add, Vec<(Path, SourceFile)>)> {
+    let mut files = Vec::new();
+    if is_absolute_path(&add.path)? {
+        let location = Url::parse(&add.path)
+            .map_err(|_| DeltaTableError::InvalidTableLocation(add.path.clone()))?;
+        add.path = relative_location(&location, source_root)?;
+        files.push((parse_path(&add.path), SourceFile::External(location)));
+    } else {
+        let path = parse_path(&add.path);
+        files.push((path.clone(), SourceFile::Table(path)));
+    }
+    if let Some(dv) = add.deletion_vector.as_mut() {
+        if let Some(location) = dv.absolute_path(source_root)? {
+            let relative = relative_location(&location, source_root)?;
+            // relative deletion vectors resolve against the root of the clone as they are,
+            // absolute ones must point at the copy
+            if dv.storage_type == StorageType::AbsolutePath {
+                dv.path_or_inline_dv = target_root
+                    .join(&relative)
+                    .map_err(|_| DeltaTableError::InvalidTableLocation(relative.clone()))?
+                    .to_string();
+            }
+            files.push((
+                Path::from_url_path(&relative)?,
+                SourceFile::External(location),
+            ));
+        }
+    }
+    add.data_change = true;
+    Ok((add, files))
+}
+
+/// Whether two table roots are located in the same store, so files can be copied within it
+fn same_store(source_root: &Url, target_root: &Url) -> bool {
+    // in-memory stores are separate instances per table
+    source_root.scheme() != "memory" && store_root(source_root) == store_root(target_root)
+}
+
+/// Stream a file from one store to another, without holding it in memory
+async fn stream_file(
+    source: ObjectStoreRef,
+    from: Path,
+    target: ObjectStoreRef,
+    to: Path,
+) -> DeltaResult<()> {
+    let mut stream = source.get(&from).await?.into_stream();
+    let (id, mut writer) = target.put_multipart(&to).await?;
+    let mut result = Ok(());
+    while let Some(bytes) = stream.next().await {
+        result = match bytes {
+            Ok(bytes) => writer
+                .write_all(&bytes)
+                .await
+                .map_err(DeltaTableError::from),
+            Err(err) => Err(err.into()),
+        };
+        if result.is_err() {
+            break;
+        }
+    }
+    if result.is_ok() {
+        result = writer.shutdown().await.map_err(DeltaTableError::from);
+    }
+    if result.is_err() {
+        // the error of the copy is more relevant than one from cleaning up after it
+        let _ = target.abort_multipart(&to, &id).await;
+    }
+    result
+}
+
+/// A file of the source table to copy to the clone
+struct FileCopy {
+    /// Store the file is read from
+    store: ObjectStoreRef,
+    /// Location of the file within its store
+    location: Path,
+    /// Path of the copy within the root of the clone
+    path: Path,
+    /// Store containing both the file and the clone, along with the full paths of the file and
+    /// its copy, if the file can be copied within it
+    shared_store: Option<(ObjectStoreRef, Path, Path)>,
+}
+
+/// Resolve the stores and locations to copy the files of the source table from and to
+fn plan_copies(
+    source: &LogStoreRef,
+    source_root: &Url,
+    target: &LogStoreRef,
+    target_root: &Url,
+    files: HashMap<Path, SourceFile>,
+) -> DeltaResult<Vec<FileCopy>> {
+    let source_prefix = Path::from_url_path(source_root.path())?;
+    let target_prefix = Path::from_url_path(target_root.path())?;
+    let mut stores: HashMap<Url, ObjectStoreRef> = HashMap::new();
+    let mut store_for = |root: Url, options: &StorageOptions| -> DeltaResult<ObjectStoreRef> {
+        if let Some(store) = stores.get(&root) {
+            return Ok(store.clone());
+        }
+        let store = root_store_for(&root, options)?;
+        stores.insert(root, store.clone());
+        Ok(store)
+    };
+
+    let mut copies = Vec::with_capacity(files.len());
+    for (path, file) in files {
+        let file_root = match &file {
+            SourceFile::Table(_) => source_root.clone(),
+            SourceFile::External(url) => url.clone(),
+        };
+        let (store, location, full_location) = match file {
+            SourceFile::Table(location) => {
+                let full_location = source_prefix.parts().chain(location.parts()).collect();
+                (source.object_store(), location, full_location)
+            }
+            SourceFile::External(url) => {
+                let full_location = Path::from_url_path(url.path())?;
+                match url.as_str().strip_prefix(source_root.as_str()) {
+                    Some(relative) => (
+                        source.object_store(),
+                        Path::from_url_path(relative)?,
+                        full_location,
+                    ),
+                    None => (
+                        store_for(store_root(&url), &source.config().options)?,
+                        full_location.clone(),
+                        full_location,
+                    ),
+                }
+            }
+        };
+        let shared_store = if same_store(&file_root, target_root) {
+            let store = store_for(store_root(target_root), &target.config().options)?;
+            let full_path = target_prefix.parts().chain(path.parts()).collect::<Path>();
+            Some((store, full_location, full_path))
+        } else {
+            None
+        };
+        copies.push(FileCopy {
+            store,
+            location,
+            path,
+            shared_store,
+        });
+    }
+    Ok(copies)
+}
+
+/// Copy files of the source table to the clone, returning the number and size of copied files
+///
+/// Files are copied within the store if the source file and the clone are located in the same
+/// store, and streamed from the source store to the store of the clone otherwise. Files which
+/// already exist in the target store with the same size, e.g. because they were copied by a
+/// previous attempt, are not copied again.
+async fn copy_files(
+    source: &LogStoreRef,
+    source_root: &Url,
+    target: &LogStoreRef,
+    target_root: &Url,
+    files: HashMap<Path, SourceFile>,
+    max_concurrent_tasks: usize,
+) -> DeltaResult<(usize, i64)> {
+    let copies = plan_copies(source, source_root, target, target_root, files)?;
+    let target_store = target.object_store();
+    let copied = futures::stream::iter(copies)
+        .map(|copy| {
+            let target = target_store.clone();
+            async move {
+                let meta = copy.store.head(&copy.location).await?;
+                match target.head(&copy.path).await {
+                    Ok(existing) if existing.size == meta.size => return Ok(None),
+                    Ok(_) | Err(ObjectStoreError::NotFound { .. }) => {}
+                    Err(err) => return Err(err.into()),
+                }
+                match copy.shared_store {
+                    Some((store, from, to)) => store.copy(&from, &to).await?,
+                    None => stream_file(copy.store, copy.location, target, copy.path).await?,
+                }
+                Ok::<_, DeltaTableError>(Some(meta.size as i64))
+            }
+        })
+        .buffer_unordered(max_concurrent_tasks.max(1))
+        .try_filter_map(|size| futures::future::ready(Ok(size)))
+        .try_collect::<Vec<_>>()
+        .await?;
+    Ok((copied.len(), copied.iter().sum()))
+}
+
+/// Whether the latest clone commit in the history of the table cloned the table at `source`
+async fn is_clone_of(table: &DeltaTable, source: &str) -> DeltaResult<bool> {
+    let history = table.history(None).await?;
+    let Some(clone) = history
+        .iter()
+        .find(|commit| commit.operation.as_deref() == Some("CLONE"))
+    else {
+        return Ok(false);
+    };
+    Ok(clone
+        .operation_parameters
+        .as_ref()
+        .and_then(|parameters| parameters.get("source"))
+        .and_then(|location| location.as_str())
+        == Some(source))
+}
+
+impl std::future::IntoFuture for CloneBuilder {
+    type Output = DeltaResult<(DeltaTable, CloneMetrics)>;
+    type IntoFuture = BoxFuture<'static, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let this = self;
+
+        Box::pin(async move {
+            let mut source = DeltaTable::new_with_state(this.log_store, this.snapshot);
+            match (this.version, this.datetime) {
+                (Some(_), Some(_)) => return Err(CloneError::InvalidCloneParameter.into()),
+                (Some(version), None) => source.load_version(version).await?,
+                (None, Some(datetime)) => source.load_with_datetime(datetime).await?,
+                (None, None) => {}
+            }
+            let snapshot = source.snapshot()?;
+            PROTOCOL.can_write_protocol(snapshot.protocol())?;
+
+            let target_log_store = match this.target_log_store {
+                Some(log_store) => log_store,
+                None => {
+                    let location =
+                        ensure_table_uri(this.location.ok_or(CloneError::MissingLocation)?)?;
+                    DeltaTableBuilder::from_uri(location)
+                        .with_storage_options(this.storage_options.unwrap_or_default())
+                        .build_storage()?
+                }
+            };
+            let mut source_root = ensure_table_uri(source.log_store().root_uri())?;
+            let mut target_root = ensure_table_uri(target_log_store.root_uri())?;
+            if source_root == target_root {
+                return Err(CloneError::SameLocation.into());
+            }
+            let target = if target_log_store.is_delta_table_location().await? {
+                if !this.sync {
+                    return Err(CloneError::TableAlreadyExists.into());
+                }
+                let mut target = DeltaTable::new(target_log_store.clone(), Default::default());
+                target.load().await?;
+                // syncing removes the files not in the source table, so the table must be a
+                // clone of the source table
+                let location = source_root.as_str().trim_end_matches('/').to_string();
+                if !is_clone_of(&target, &location).await? {
+                    return Err(CloneError::NotACloneOfSource { location }.into());
+                }
+                Some(target)
+            } else {
+                None
+            };
+            let target_snapshot = target.as_ref().map(|table| table.snapshot()).transpose()?;
+            // relative paths are resolved against the root directory, so it must end with a slash
+            for root in [&mut source_root, &mut target_root] {
+                if !root.path().ends_with('/') {
+                    root.set_path(&format!("{}/", root.path()));
+                }
+            }
+
+            let source_metadata = snapshot.metadata();
+            let mut configuration = source_metadata.configuration.clone();
+            for key in HISTORY_PROPERTIES {
+                configuration.remove(key.as_ref());
+                if let Some(value) = target_snapshot
+                    .and_then(|target| target.metadata().configuration.get(key.as_ref()))
+                {
+                    configuration.insert(key.as_ref().to_string(), value.clone());
+                }
+            }
+            let mut metadata = Metadata::try_new(
+                snapshot.schema().clone(),
+                source_metadata.partition_columns.clone(),
+                configuration,
+            )?
+            .with_created_time(Utc::now().timestamp_millis());
+            metadata.name = source_metadata.name.clone();
+            metadata.description = source_metadata.description.clone();
+
+            let mut actions = Vec::new();
+            match target_snapshot {
+                Some(target) => {
+                    if let Some(protocol) = upgrade_protocol(
+                        target.protocol(),
+                        snapshot.protocol().min_reader_version,
+                        snapshot.protocol().min_writer_version,
+                        snapshot
+                            .protocol()
+                            .writer_features
+                            .iter()
+                            .flatten()
+                            .cloned(),
+                    ) {
+                        PROTOCOL.can_write_protocol(&protocol)?;
+                        actions.push(Action::Protocol(protocol));
+                    }
+                    metadata.id = target.metadata().id.clone();
+                    metadata.created_time = target.metadata().created_time;
+                    if &metadata != target.metadata() {
+                        actions.push(Action::Metadata(metadata));
+                    }
+                }
+                None => {
+                    actions.push(Action::Protocol(snapshot.protocol().clone()));
+                    actions.push(Action::Metadata(metadata));
+                }
+            }
+
+            // Files the clone already contains are kept
+            let mut existing_files = target_snapshot
+                .map(|target| target.file_actions())
+                .transpose()?
+                .unwrap_or_default()
+                .into_iter()
+                .map(|add| (add.path.clone(), add))
+                .collect::<HashMap<_, _>>();
+            let mut metrics = CloneMetrics::default();
+            // files copied to the clone by their path within it, which must be unique
+            let mut clone_files: HashMap<Path, SourceFile> = HashMap::new();
+            let mut files_to_copy = HashMap::new();
+            for add in snapshot.file_actions()? {
+                metrics.source_table_size += add.size;
+                metrics.source_num_of_files += 1;
+                let (add, files) = if this.deep {
+                    with_relative_paths(add, &source_root, &target_root)?
+                } else {
+                    (with_absolute_paths(add, &source_root)?, Vec::new())
+                };
+                for (path, file) in &files {
+                    match clone_files.get(path) {
+                        Some(other) if other != file => {
+                            return Err(CloneError::ConflictingFiles {
+                                first: other.to_string(),
+                                second: file.to_string(),
+                                path: path.to_string(),
+                            }
+                            .into());
+                        }
+                        Some(_) => {}
+                        None => {
+                            clone_files.insert(path.clone(), file.clone());
+                        }
+                    }
+                }
+                match existing_files.remove(&add.path) {
+                    Some(existing) if existing.deletion_vector == add.deletion_vector => {}
+                    _ => {
+                        files_to_copy.extend(files);
+                        actions.push(Action::Add(add));
+                    }
+                }
+            }
+            let deletion_timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as i64;
+            for (_, file) in existing_files {
+                metrics.num_removed_files += 1;
+                metrics.removed_files_size += file.size;
+                actions.push(Action::Remove(Remove {
+                    path: file.path,
+                    deletion_timestamp: Some(deletion_timestamp),
+                    data_change: true,
+                    extended_file_metadata: None,
+                    partition_values: Some(file.partition_values),
+                    size: Some(file.size),
+                    deletion_vector: file.deletion_vector,
+                    tags: file.tags,
+                    base_row_id: file.base_row_id,
+                    default_row_commit_version: file.default_row_commit_version,
+                }));
+            }
+            // e.g. the high water mark of assigned row ids, since the files keep their row ids
+            actions.extend(
+                snapshot
+                    .all_domain_metadata()
+                    .into_iter()
+                    .map(Action::DomainMetadata),
+            );
+
+            if this.deep {
+                let (num_copied_files, copied_files_size) = copy_files(
+                    &source.log_store(),
+                    &source_root,
+                    &target_log_store,
+                    &target_root,
+                    files_to_copy,
+                    this.max_concurrent_tasks,
+                )
+                .await?;
+                metrics.num_copied_files = num_copied_files;
+                metrics.copied_files_size = copied_files_size;
+            }
+
+            let mut commit_properties = this.commit_properties;
+            commit_properties.app_metadata.insert(
+                "operationMetrics".to_owned(),
+                serde_json::to_value(&metrics)?,
+            );
+            let operation = DeltaOperation::Clone {
+                source: source_root.as_str().trim_end_matches('/').to_string(),
+                source_version: snapshot.version(),
+                is_shallow: !this.deep,
+            };
+            let commit = CommitBuilder::from(commit_properties)
+                .with_actions(actions)
+                .build(
+                    target_snapshot.map(|target| target as &dyn TableReference),
+                    target_log_store.clone(),
+                    operation,
+                )?
+                .await?;
+
+            Ok((
+                DeltaTable::new_with_state(target_log_store, commit.snapshot()),
+                metrics,
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "datafusion")]
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{DataType as ArrowDataType, Field, Schema as ArrowSchema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_sorted_eq;
+    use datafusion::prelude::{col, lit};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::kernel::{DataType, Protocol, ReaderFeatures, StructField, WriterFeatures};
+    use crate::writer::test_utils::datafusion::get_data_sorted;
+    use crate::DeltaOps;
+
+    fn batch(ids: Vec<i32>, values: Vec<&str>) -> RecordBatch {
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", ArrowDataType::Int32, true),
+            Field::new("value", ArrowDataType::Utf8, true),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(ids)),
+                Arc::new(StringArray::from(values)),
+            ],
+        )
+        .unwrap()
+    }
+
+    async fn create_source(location: &str) -> DeltaTable {
+        let table = DeltaOps::try_from_uri(location)
+            .await
+            .unwrap()
+            .create()
+            .with_columns(vec![
+                StructField::new("id", DataType::INTEGER, true),
+                StructField::new("value", DataType::STRING, true),
+            ])
+            .with_partition_columns(["value"])
+            .with_configuration_property(DeltaConfigKey::AppendOnly, Some("false"))
+            .await
+            .unwrap();
+        let table = DeltaOps(table)
+            .write(vec![batch(vec![1, 2], vec!["a", "b"])])
+            .await
+            .unwrap();
+        DeltaOps(table)
+            .write(vec![batch(vec![3], vec!["a"])])
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_shallow_clone() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let target_dir = tempfile::tempdir().unwrap();
+        let source = create_source(source_dir.path().to_str().unwrap()).await;
+
+        let (clone, metrics) = DeltaOps(source.clone())
+            .clone()
+            .with_location(target_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(clone.version(), 0);
+        assert_eq!(metrics.source_num_of_files, 3);
+        assert_eq!(metrics.num_copied_files, 0);
+
+        let snapshot = clone.snapshot().unwrap();
+        assert_ne!(snapshot.metadata().id, source.metadata().unwrap().id);
+        assert_eq!(snapshot.metadata().partition_columns, vec!["value"]);
+        assert_eq!(
+            snapshot.metadata().configuration,
+            source.metadata().unwrap().configuration
+        );
+        assert_eq!(snapshot.schema(), source.get_schema().unwrap());
+        for add in snapshot.file_actions().unwrap() {
+            assert!(is_absolute_path(&add.path).unwrap());
+        }
+        // no files are written to the clone
+        assert!(std::fs::read_dir(target_dir.path())
+            .unwrap()
+            .all(|entry| entry.unwrap().file_name() == "_delta_log"));
+
+        let history = clone.history(Some(1)).await.unwrap();
+        assert_eq!(history[0].operation.as_deref(), Some("CLONE"));
+        let parameters = history[0].operation_parameters.clone().unwrap();
+        assert_eq!(parameters["sourceVersion"], "2");
+        assert_eq!(parameters["isShallow"], "true");
+        assert_eq!(
+            parameters["source"],
+            ensure_table_uri(source.table_uri())
+                .unwrap()
+                .as_str()
+                .trim_end_matches('/')
+        );
+
+        let expected = [
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 1  | a     |",
+            "| 2  | b     |",
+            "| 3  | a     |",
+            "+----+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &get_data_sorted(&clone, "id,value").await);
+
+        // the clone can be changed independently of the source table
+        let clone = DeltaOps(clone)
+            .write(vec![batch(vec![4], vec!["c"])])
+            .await
+            .unwrap();
+        let (clone, _) = DeltaOps(clone)
+            .delete()
+            .with_predicate(col("id").eq(lit(1)))
+            .await
+            .unwrap();
+        let expected = [
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 2  | b     |",
+            "| 3  | a     |",
+            "| 4  | c     |",
+            "+----+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &get_data_sorted(&clone, "id,value").await);
+        assert_eq!(source.get_files_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_optimize_shallow_clone() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let target_dir = tempfile::tempdir().unwrap();
+        let source = create_source(source_dir.path().to_str().unwrap()).await;
+        let (clone, _) = DeltaOps(source)
+            .clone()
+            .with_location(target_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        // the files of the source table are skipped
+        let (clone, metrics) = DeltaOps(clone).optimize().await.unwrap();
+        assert_eq!(metrics.num_files_removed, 0);
+        assert_eq!(metrics.total_considered_files, 3);
+        assert_eq!(metrics.total_files_skipped, 3);
+
+        // files written to the clone are
+        let clone = DeltaOps(clone)
+            .write(vec![batch(vec![4], vec!["c"])])
+            .await
+            .unwrap();
+        let clone = DeltaOps(clone)
+            .write(vec![batch(vec![5], vec!["c"])])
+            .await
+            .unwrap();
+        let (clone, metrics) = DeltaOps(clone).optimize().await.unwrap();
+        assert_eq!(metrics.num_files_removed, 2);
+        assert_eq!(metrics.num_files_added, 1);
+        let expected = [
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 1  | a     |",
+            "| 2  | b     |",
+            "| 3  | a     |",
+            "| 4  | c     |",
+            "| 5  | c     |",
+            "+----+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &get_data_sorted(&clone, "id,value").await);
+    }
+
+    #[tokio::test]
+    async fn test_clone_version() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let target_dir = tempfile::tempdir().unwrap();
+        let source = create_source(source_dir.path().to_str().unwrap()).await;
+
+        let (clone, metrics) = DeltaOps(source.clone())
+            .clone()
+            .with_location(target_dir.path().to_str().unwrap())
+            .with_version(1)
+            .await
+            .unwrap();
+        assert_eq!(metrics.source_num_of_files, 2);
+        let history = clone.history(Some(1)).await.unwrap();
+        assert_eq!(
+            history[0].operation_parameters.as_ref().unwrap()["sourceVersion"],
+            "1"
+        );
+        let expected = [
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 1  | a     |",
+            "| 2  | b     |",
+            "+----+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &get_data_sorted(&clone, "id,value").await);
+
+        // the target must not be a table yet
+        let result = DeltaOps(source.clone())
+            .clone()
+            .with_location(target_dir.path().to_str().unwrap())
+            .await;
+        assert!(result.is_err());
+        let result = DeltaOps(source.clone())
+            .clone()
+            .with_location(source_dir.path().to_str().unwrap())
+            .await;
+        assert!(result.is_err());
+    }
+
+    async fn get_data(table: &DeltaTable) -> Vec<RecordBatch> {
+        get_data_sorted(table, "id,value").await
+    }
+
+    #[tokio::test]
+    async fn test_deep_clone() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let target_dir = tempfile::tempdir().unwrap();
+        let source = create_source(source_dir.path().to_str().unwrap()).await;
+        let expected = get_data(&source).await;
+
+        let (clone, metrics) = DeltaOps(source.clone())
+            .clone()
+            .with_location(target_dir.path().to_str().unwrap())
+            .with_deep(true)
+            .await
+            .unwrap();
+        assert_eq!(clone.version(), 0);
+        assert_eq!(metrics.num_copied_files, 3);
+        assert_eq!(metrics.copied_files_size, metrics.source_table_size);
+        let mut paths = clone.get_files_iter().unwrap().collect::<Vec<_>>();
+        let mut source_paths = source.get_files_iter().unwrap().collect::<Vec<_>>();
+        paths.sort();
+        source_paths.sort();
+        assert_eq!(paths, source_paths);
+
+        let history = clone.history(Some(1)).await.unwrap();
+        let parameters = history[0].operation_parameters.clone().unwrap();
+        assert_eq!(parameters["isShallow"], "false");
+
+        // the clone does not depend on the source table
+        drop(source_dir);
+        let clone = crate::open_table(clone.table_uri()).await.unwrap();
+        assert_eq!(get_data(&clone).await, expected);
+    }
+
+    #[tokio::test]
+    async fn test_deep_clone_to_other_store() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = create_source(source_dir.path().to_str().unwrap()).await;
+        let target_log_store = DeltaTableBuilder::from_uri("memory:///clone")
+            .build_storage()
+            .unwrap();
+
+        // files are streamed from the local store of the source table to the in-memory store
+        let (clone, metrics) = DeltaOps(source.clone())
+            .clone()
+            .with_log_store(target_log_store)
+            .with_deep(true)
+            .await
+            .unwrap();
+        assert_eq!(metrics.num_copied_files, 3);
+        assert_eq!(metrics.copied_files_size, metrics.source_table_size);
+        assert_eq!(get_data(&clone).await, get_data(&source).await);
+    }
+
+    #[tokio::test]
+    async fn test_resume_deep_clone() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let target_dir = tempfile::tempdir().unwrap();
+        let source = create_source(source_dir.path().to_str().unwrap()).await;
+
+        // a previous attempt copied a file, but did not commit
+        let path = source.get_files_iter().unwrap().next().unwrap();
+        let bytes = source
+            .object_store()
+            .get(&path)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let target_store = DeltaTableBuilder::from_uri(target_dir.path().to_str().unwrap())
+            .build_storage()
+            .unwrap()
+            .object_store();
+        target_store.put(&path, bytes).await.unwrap();
+
+        let (clone, metrics) = DeltaOps(source.clone())
+            .clone()
+            .with_location(target_dir.path().to_str().unwrap())
+            .with_deep(true)
+            .with_max_concurrent_tasks(1)
+            .await
+            .unwrap();
+        assert_eq!(metrics.num_copied_files, 2);
+        assert_eq!(get_data(&clone).await, get_data(&source).await);
+    }
+
+    #[tokio::test]
+    async fn test_sync_clone() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let target_dir = tempfile::tempdir().unwrap();
+        let source = create_source(source_dir.path().to_str().unwrap()).await;
+
+        let (clone, _) = DeltaOps(source.clone())
+            .clone()
+            .with_location(target_dir.path().to_str().unwrap())
+            .with_version(1)
+            .with_deep(true)
+            .await
+            .unwrap();
+
+        let source = DeltaOps(source)
+            .write(vec![batch(vec![4], vec!["c"])])
+            .await
+            .unwrap();
+        let (source, _) = DeltaOps(source)
+            .delete()
+            .with_predicate(col("value").eq(lit("b")))
+            .await
+            .unwrap();
+
+        let (clone, metrics) = DeltaOps(source.clone())
+            .clone()
+            .with_location(clone.table_uri())
+            .with_deep(true)
+            .with_sync(true)
+            .await
+            .unwrap();
+        assert_eq!(clone.version(), 1);
+        assert_eq!(metrics.num_copied_files, 2);
+        assert_eq!(metrics.num_removed_files, 1);
+        let expected = [
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 1  | a     |",
+            "| 3  | a     |",
+            "| 4  | c     |",
+            "+----+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &get_data(&clone).await);
+
+        // nothing is copied if the clone is up to date
+        let (clone, metrics) = DeltaOps(source)
+            .clone()
+            .with_location(clone.table_uri())
+            .with_deep(true)
+            .with_sync(true)
+            .await
+            .unwrap();
+        assert_eq!(clone.version(), 2);
+        assert_eq!(metrics.num_copied_files, 0);
+        assert_eq!(metrics.num_removed_files, 0);
+        assert_eq!(clone.get_files_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_sync_requires_clone_of_source() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let other_dir = tempfile::tempdir().unwrap();
+        let target_dir = tempfile::tempdir().unwrap();
+        let source = create_source(source_dir.path().to_str().unwrap()).await;
+        let other = create_source(other_dir.path().to_str().unwrap()).await;
+
+        // a table which is not a clone is not synced
+        let result = DeltaOps(source.clone())
+            .clone()
+            .with_location(other.table_uri())
+            .with_sync(true)
+            .await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("is not a clone of"));
+
+        // nor is a clone of another table
+        let (clone, _) = DeltaOps(other)
+            .clone()
+            .with_location(target_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+        let result = DeltaOps(source)
+            .clone()
+            .with_location(clone.table_uri())
+            .with_sync(true)
+            .await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("is not a clone of"));
+
+        let mut clone = DeltaTable::new(clone.log_store(), Default::default());
+        clone.load().await.unwrap();
+        assert_eq!(clone.version(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_deep_clone_of_shallow_clone() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let shallow_dir = tempfile::tempdir().unwrap();
+        let target_dir = tempfile::tempdir().unwrap();
+        let source = DeltaOps::try_from_uri(source_dir.path().to_str().unwrap())
+            .await
+            .unwrap()
+            .create()
+            .with_columns(vec![
+                StructField::new("id", DataType::INTEGER, true),
+                StructField::new("value", DataType::STRING, true),
+            ])
+            .with_partition_columns(["value"])
+            .with_configuration_property(DeltaConfigKey::EnableDeletionVectors, Some("true"))
+            .with_actions(vec![Action::Protocol(Protocol {
+                min_reader_version: 3,
+                min_writer_version: 7,
+                reader_features: Some([ReaderFeatures::DeletionVectors].into()),
+                writer_features: Some([WriterFeatures::DeletionVectors].into()),
+            })])
+            .await
+            .unwrap();
+        let source = DeltaOps(source)
+            .write(vec![batch(vec![1, 2, 3], vec!["a", "a", "b"])])
+            .await
+            .unwrap();
+        let (source, _) = DeltaOps(source)
+            .delete()
+            .with_predicate(col("id").eq(lit(1)))
+            .await
+            .unwrap();
+
+        // the shallow clone references the data files and deletion vectors of the source table
+        let (shallow, _) = DeltaOps(source)
+            .clone()
+            .with_location(shallow_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+        let expected = get_data(&shallow).await;
+        let files = shallow.snapshot().unwrap().file_actions().unwrap();
+        assert!(files.iter().any(|add| add
+            .deletion_vector
+            .as_ref()
+            .is_some_and(|dv| dv.storage_type == StorageType::AbsolutePath)));
+
+        let (clone, metrics) = DeltaOps(shallow)
+            .clone()
+            .with_location(target_dir.path().to_str().unwrap())
+            .with_deep(true)
+            .await
+            .unwrap();
+        // two data files and a deletion vector
+        assert_eq!(metrics.num_copied_files, 3);
+        let target_root = ensure_table_uri(clone.table_uri()).unwrap();
+        for add in clone.snapshot().unwrap().file_actions().unwrap() {
+            assert!(!is_absolute_path(&add.path).unwrap());
+            if let Some(dv) = add.deletion_vector {
+                let path = dv.absolute_path(&target_root).unwrap().unwrap();
+                assert!(path.as_str().starts_with(target_root.as_str()));
+            }
+        }
+
+        // the clone depends on neither the shallow clone nor its source table
+        drop(source_dir);
+        drop(shallow_dir);
+        let clone = crate::open_table(clone.table_uri()).await.unwrap();
+        assert_eq!(get_data(&clone).await, expected);
+    }
+
+    #[tokio::test]
+    async fn test_vacuum_clone_keeps_source_files() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let target_dir = tempfile::tempdir().unwrap();
+        let source = create_source(source_dir.path().to_str().unwrap()).await;
+
+        let (clone, _) = DeltaOps(source.clone())
+            .clone()
+            .with_location(target_dir.path().to_str().unwrap())
+            .await
+            .unwrap();
+        let clone = DeltaOps(clone)
+            .write(vec![batch(vec![4], vec!["c"])])
+            .await
+            .unwrap();
+        // removes the files of the source table as well as the file written to the clone
+        let (clone, _) = DeltaOps(clone).delete().await.unwrap();
+
+        let (_, metrics) = DeltaOps(clone)
+            .vacuum()
+            .with_retention_period(chrono::Duration::zero())
+            .with_enforce_retention_duration(false)
+            .await
+            .unwrap();
+        assert_eq!(metrics.files_deleted.len(), 1);
+
+        let source = crate::open_table(source.table_uri()).await.unwrap();
+        assert_eq!(
+            get_data_sorted(&source, "id,value")
+                .await
+                .iter()
+                .map(|batch| batch.num_rows())
+                .sum::<usize>(),
+            3
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Find the columns referenced by the SQL expressions stored in a table's metadata
+//!
+//! Check constraints and generation expressions refer to columns by name, so columns they
+//! reference cannot be renamed or dropped without breaking later writes to the table.
+
+use crate::kernel::{Metadata, StructType};
+use crate::{DeltaResult, DeltaTableError};
+
+/// Fail if a check constraint or the generation expression of a column references the
+/// column at `path`.
+///
+/// Generation expressions of the columns in `ignored_columns`, e.g. columns dropped together
+/// with the referenced column, are not checked.
+pub(super) fn ensure_unreferenced(
+    metadata: &Metadata,
+    schema: &StructType,
+    path: &[&str],
+    ignored_columns: &[String],
+    action: &str,
+) -> DeltaResult<()> {
+    let column = path.join(".");
+    for (key, expr) in &metadata.configuration {
+        let (Some(name), Some(expr)) = (key.strip_prefix("delta.constraints."), expr) else {
+            continue;
+        };
+        if references(expr, path) {
+            return Err(DeltaTableError::Generic(format!(
+                "Cannot {action} column {column}, it is referenced by constraint {name}: {expr}"
+            )));
+        }
+    }
+    for generated in schema.get_generated_columns() {
+        if ignored_columns.contains(&generated.name) {
+            continue;
+        }
+        if references(&generated.generation_expr, path) {
+            return Err(DeltaTableError::Generic(format!(
+                "Cannot {action} column {column}, it is referenced by the generation expression of column {}: {}",
+                generated.name, generated.generation_expr
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether the expression references the column at `path`, one of its nested fields or
+/// the struct containing it. Column names are compared case-insensitively.
+fn references(expr: &str, path: &[&str]) -> bool {
+    referenced_columns(expr).iter().any(|reference| {
+        reference
+            .iter()
+            .zip(path)
+            .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    })
+}
+
+/// The dot separated column paths referenced in a SQL expression.
+///
+/// Identifiers followed by an opening parenthesis are function names and not returned,
+/// keywords are returned as columns, which at worst makes the check more conservative.
+fn referenced_columns(expr: &str) -> Vec<Vec<String>> {
+    let chars = expr.chars().collect::<Vec<_>>();
+    let starts_identifier = |c: char| c == '`' || c == '_' || c.is_alphabetic();
+    let mut columns = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        if c == '\'' || c == '"' {
+            // string literal, with quotes escaped by doubling or a backslash
+            idx += 1;
+            while idx < chars.len() {
+                if chars[idx] == '\\' {
+                    idx += 1;
+                } else if chars[idx] == c {
+                    if chars.get(idx + 1) != Some(&c) {
+                        break;
+                    }
+                    idx += 1;
+                }
+                idx += 1;
+            }
+            idx += 1;
+        } else if starts_identifier(c) {
+            let mut column = Vec::new();
+            loop {
+                let (segment, next) = identifier(&chars, idx);
+                column.push(segment);
+                idx = next;
+                match (chars.get(idx), chars.get(idx + 1)) {
+                    (Some('.'), Some(&c)) if starts_identifier(c) => idx += 1,
+                    _ => break,
+                }
+            }
+            let is_function = chars[idx..].iter().find(|c| !c.is_whitespace()) == Some(&'(');
+            if !is_function {
+                columns.push(column);
+            }
+        } else if c.is_ascii_digit() {
+            // numbers, including decimals and exponents
+            while idx < chars.len()
+                && (chars[idx].is_alphanumeric() || chars[idx] == '.' || chars[idx] == '_')
+            {
+                idx += 1;
+            }
+        } else {
+            idx += 1;
+        }
+    }
+    columns
+}
+
+/// Read the identifier starting at `start`, returning it and the index following it
+fn identifier(chars: &[char], start: usize) -> (String, usize) {
+    let mut name = String::new();
+    let mut idx = start;
+    if chars[idx] == '`' {
+        // quoted identifier, with backticks escaped by doubling
+        idx += 1;
+        while idx < chars.len() {
+            if chars[idx] == '`' {
+                if chars.get(idx + 1) != Some(&'`') {
+                    break;
+                }
+                idx += 1;
+            }
+            name.push(chars[idx]);
+            idx += 1;
+        }
+        return (name, idx + 1);
+    }
+    while idx < chars.len() && (chars[idx].is_alphanumeric() || chars[idx] == '_') {
+        name.push(chars[idx]);
+        idx += 1;
+    }
+    (name, idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(expr: &str) -> Vec<String> {
+        referenced_columns(expr)
+            .into_iter()
+            .map(|column| column.join("."))
+            .collect()
+    }
+
+    #[test]
+    fn test_referenced_columns() {
+        assert_eq!(columns("id > 0"), vec!["id"]);
+        assert_eq!(
+            columns("length(value) < 10 AND nested.a = 'it''s value.x'"),
+            vec!["value", "AND", "nested.a"]
+        );
+        assert_eq!(columns("`odd col`.`x``y` >= 1.5e3"), vec!["odd col.x`y"]);
+        assert_eq!(columns("CAST(ts AS DATE)"), vec!["ts", "AS", "DATE"]);
+
+        assert!(references("NESTED.a > 1", &["nested", "a"]));
+        assert!(references("nested IS NOT NULL", &["nested", "a"]));
+        assert!(references("nested.a.b > 1", &["nested", "a"]));
+        assert!(!references("nested.b > 1", &["nested", "a"]));
+        assert!(!references("'id' = value", &["id"]));
+    }
+}
@@ -7,15 +7,16 @@ use std::sync::Arc;
 use futures::future::BoxFuture;
 use serde_json::Value;
 
-use super::transaction::{CommitBuilder, TableReference, PROTOCOL};
+use super::transaction::{row_tracking, CommitBuilder, TableReference, PROTOCOL};
 use crate::errors::{DeltaResult, DeltaTableError};
 use crate::kernel::{
-    Action, DataType, Metadata, Protocol, ReaderFeatures, StructField, StructType, WriterFeatures,
+    Action, ColumnMetadataKey, DataType, Metadata, MetadataValue, Protocol, ReaderFeatures,
+    StructField, StructType, WriterFeatures,
 };
 use crate::logstore::{LogStore, LogStoreRef};
 use crate::protocol::{DeltaOperation, SaveMode};
 use crate::table::builder::ensure_table_uri;
-use crate::table::config::DeltaConfigKey;
+use crate::table::config::{CheckpointPolicy, ColumnMappingMode, DeltaConfigKey, TableConfig};
 use crate::{DeltaTable, DeltaTableBuilder};
 
 #[derive(thiserror::Error, Debug)]
@@ -128,6 +129,51 @@ impl CreateBuilder {
         self
     }
 
+    /// Specify a generated column, whose values are computed from other columns
+    /// of the table using the given SQL expression
+    pub fn with_generated_column(
+        mut self,
+        name: impl Into<String>,
+        data_type: DataType,
+        nullable: bool,
+        generation_expr: impl Into<String>,
+    ) -> Self {
+        let field = StructField::new(name.into(), data_type, nullable).with_metadata([(
+            ColumnMetadataKey::GenerationExpression.as_ref(),
+            MetadataValue::String(generation_expr.into()),
+        )]);
+        self.columns.push(field);
+        self
+    }
+
+    /// Specify an identity column, whose values are generated when they are not provided.
+    ///
+    /// The generated values start at `start` and are incremented by `step`.
+    pub fn with_identity_column(
+        mut self,
+        name: impl Into<String>,
+        start: i64,
+        step: i64,
+        allow_explicit_insert: bool,
+    ) -> Self {
+        let field = StructField::new(name.into(), DataType::LONG, false).with_metadata([
+            (
+                ColumnMetadataKey::IdentityStart.as_ref(),
+                MetadataValue::Number(start),
+            ),
+            (
+                ColumnMetadataKey::IdentityStep.as_ref(),
+                MetadataValue::Number(step),
+            ),
+            (
+                ColumnMetadataKey::IdentityAllowExplicitInsert.as_ref(),
+                MetadataValue::Boolean(allow_explicit_insert),
+            ),
+        ]);
+        self.columns.push(field);
+        self
+    }
+
     /// Specify columns to append to schema
     pub fn with_columns(
         mut self,
@@ -241,37 +287,147 @@ impl CreateBuilder {
         // TODO configure more permissive versions based on configuration. Also how should this ideally be handled?
         // We set the lowest protocol we can, and if subsequent writes use newer features we update metadata?
 
-        let (min_reader_version, min_writer_version, writer_features, reader_features) =
-            if contains_timestampntz {
-                let mut converted_writer_features = self
-                    .configuration
-                    .keys()
-                    .map(|key| key.clone().into())
-                    .filter(|v| !matches!(v, WriterFeatures::Other(_)))
-                    .collect::<HashSet<WriterFeatures>>();
-
-                let mut converted_reader_features = self
-                    .configuration
-                    .keys()
-                    .map(|key| key.clone().into())
-                    .filter(|v| !matches!(v, ReaderFeatures::Other(_)))
-                    .collect::<HashSet<ReaderFeatures>>();
-                converted_writer_features.insert(WriterFeatures::TimestampWithoutTimezone);
-                converted_reader_features.insert(ReaderFeatures::TimestampWithoutTimezone);
-                (
-                    3,
-                    7,
-                    Some(converted_writer_features),
-                    Some(converted_reader_features),
-                )
-            } else {
-                (
-                    PROTOCOL.default_reader_version(),
-                    PROTOCOL.default_writer_version(),
-                    None,
-                    None,
-                )
-            };
+        let (
+            mut min_reader_version,
+            mut min_writer_version,
+            mut writer_features,
+            mut reader_features,
+        ) = if contains_timestampntz {
+            let mut converted_writer_features = self
+                .configuration
+                .keys()
+                .map(|key| key.clone().into())
+                .filter(|v| !matches!(v, WriterFeatures::Other(_)))
+                .collect::<HashSet<WriterFeatures>>();
+
+            let mut converted_reader_features = self
+                .configuration
+                .keys()
+                .map(|key| key.clone().into())
+                .filter(|v| !matches!(v, ReaderFeatures::Other(_)))
+                .collect::<HashSet<ReaderFeatures>>();
+            converted_writer_features.insert(WriterFeatures::TimestampWithoutTimezone);
+            converted_reader_features.insert(ReaderFeatures::TimestampWithoutTimezone);
+            (
+                3,
+                7,
+                Some(converted_writer_features),
+                Some(converted_reader_features),
+            )
+        } else {
+            (
+                PROTOCOL.default_reader_version(),
+                PROTOCOL.default_writer_version(),
+                None,
+                None,
+            )
+        };
+
+        let mut configuration = self.configuration;
+        let mut schema = StructType::new(self.columns);
+        if TableConfig(&configuration).column_mapping_mode() != ColumnMappingMode::None {
+            let mut max_column_id = TableConfig(&configuration).column_mapping_max_column_id();
+            schema = schema.with_column_mapping(&mut max_column_id);
+            configuration.insert(
+                DeltaConfigKey::ColumnMappingMaxColumnId
+                    .as_ref()
+                    .to_string(),
+                Some(max_column_id.to_string()),
+            );
+            // column mapping is supported by reader version 2, writers use the table feature
+            min_reader_version = min_reader_version.max(2);
+            min_writer_version = 7;
+            writer_features
+                .get_or_insert_with(HashSet::new)
+                .insert(WriterFeatures::ColumnMapping);
+            if let Some(reader_features) = reader_features.as_mut() {
+                reader_features.insert(ReaderFeatures::ColumnMapping);
+            }
+        }
+
+        // generated columns are computed and validated by writers supporting the table feature
+        if !schema.get_generated_columns().is_empty() {
+            #[cfg(feature = "datafusion")]
+            super::generated_columns::validate_generated_columns(&schema)?;
+            min_writer_version = 7;
+            writer_features
+                .get_or_insert_with(HashSet::new)
+                .insert(WriterFeatures::GeneratedColumns);
+        }
+
+        // identity column values are generated by writers supporting the table feature
+        let identity_columns = schema.get_identity_columns()?;
+        if !identity_columns.is_empty() {
+            if let Some(column) = identity_columns.iter().find(|column| {
+                schema
+                    .field_with_name(&column.name)
+                    .map(|field| field.data_type() != &DataType::LONG)
+                    .unwrap_or(true)
+            }) {
+                return Err(DeltaTableError::Generic(format!(
+                    "Identity column {} must be of type long",
+                    column.name
+                )));
+            }
+            min_writer_version = 7;
+            writer_features
+                .get_or_insert_with(HashSet::new)
+                .insert(WriterFeatures::IdentityColumns);
+        }
+
+        // row ids are assigned by writers supporting the table feature, the high water mark
+        // of assigned ids is tracked in a metadata domain
+        if TableConfig(&configuration).enable_row_tracking() {
+            row_tracking::with_materialized_column_names(&mut configuration);
+            min_writer_version = 7;
+            let features = writer_features.get_or_insert_with(HashSet::new);
+            features.insert(WriterFeatures::RowTracking);
+            features.insert(WriterFeatures::DomainMetadata);
+        }
+
+        // the changes of modified rows are recorded by writers supporting the table feature
+        if TableConfig(&configuration).enable_change_data_feed() {
+            min_writer_version = 7;
+            writer_features
+                .get_or_insert_with(HashSet::new)
+                .insert(WriterFeatures::ChangeDataFeed);
+        }
+
+        // files written before a column was widened are read by upcasting their values, so
+        // both readers and writers need to support the table feature
+        if TableConfig(&configuration).enable_type_widening() {
+            min_reader_version = 3;
+            min_writer_version = 7;
+            reader_features
+                .get_or_insert_with(HashSet::new)
+                .insert(ReaderFeatures::TypeWidening);
+            writer_features
+                .get_or_insert_with(HashSet::new)
+                .insert(WriterFeatures::TypeWidening);
+        }
+
+        // commit timestamps are stored in the commit info by writers supporting the table feature
+        if TableConfig(&configuration).enable_in_commit_timestamps() {
+            min_writer_version = 7;
+            writer_features
+                .get_or_insert_with(HashSet::new)
+                .insert(WriterFeatures::InCommitTimestamp);
+        }
+
+        // v2 checkpoints must only be written and read by clients supporting the table feature
+        if TableConfig(&configuration).checkpoint_policy() == CheckpointPolicy::V2 {
+            min_reader_version = 3;
+            min_writer_version = 7;
+            let features = reader_features.get_or_insert_with(HashSet::new);
+            features.insert(ReaderFeatures::V2Checkpoint);
+            if TableConfig(&configuration).column_mapping_mode() != ColumnMappingMode::None {
+                features.insert(ReaderFeatures::ColumnMapping);
+            }
+            writer_features
+                .get_or_insert_with(HashSet::new)
+                .insert(WriterFeatures::V2Checkpoint);
+        }
+
         let protocol = self
             .actions
             .iter()
@@ -288,9 +444,9 @@ impl CreateBuilder {
             });
 
         let mut metadata = Metadata::try_new(
-            StructType::new(self.columns),
+            schema,
             self.partition_columns.unwrap_or_default(),
-            self.configuration,
+            configuration,
         )?
         .with_created_time(chrono::Utc::now().timestamp_millis());
         if let Some(name) = self.name {
@@ -572,4 +728,77 @@ mod tests {
         // Checks if files got removed after overwrite
         assert_eq!(table.get_files_count(), 0);
     }
+
+    #[cfg(feature = "datafusion")]
+    #[tokio::test]
+    async fn test_create_generated_columns() {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_column("value", DataType::INTEGER, true, None)
+            .with_generated_column("doubled", DataType::LONG, true, "value * 2")
+            .await
+            .unwrap();
+        let protocol = table.protocol().unwrap();
+        assert_eq!(protocol.min_writer_version, 7);
+        assert!(protocol
+            .writer_features
+            .as_ref()
+            .unwrap()
+            .contains(&WriterFeatures::GeneratedColumns));
+        let generated = table.get_schema().unwrap().get_generated_columns();
+        assert_eq!(generated.len(), 1);
+        assert_eq!(generated[0].generation_expr, "value * 2");
+
+        // generation expressions must only reference columns of the table
+        let result = DeltaOps::new_in_memory()
+            .create()
+            .with_column("value", DataType::INTEGER, true, None)
+            .with_generated_column("doubled", DataType::LONG, true, "missing * 2")
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_identity_columns() {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_identity_column("id", 1, 1, false)
+            .with_column("value", DataType::STRING, true, None)
+            .await
+            .unwrap();
+        let protocol = table.protocol().unwrap();
+        assert_eq!(protocol.min_writer_version, 7);
+        assert!(protocol
+            .writer_features
+            .as_ref()
+            .unwrap()
+            .contains(&WriterFeatures::IdentityColumns));
+        let identity = table.get_schema().unwrap().get_identity_columns().unwrap();
+        assert_eq!(identity.len(), 1);
+        assert_eq!(identity[0].next_value(), 1);
+
+        // identity columns must have a non-zero step
+        let result = DeltaOps::new_in_memory()
+            .create()
+            .with_identity_column("id", 1, 0, false)
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_change_data_feed() {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_column("value", DataType::INTEGER, true, None)
+            .with_configuration_property(DeltaConfigKey::EnableChangeDataFeed, Some("true"))
+            .await
+            .unwrap();
+        let protocol = table.protocol().unwrap();
+        assert_eq!(protocol.min_writer_version, 7);
+        assert!(protocol
+            .writer_features
+            .as_ref()
+            .unwrap()
+            .contains(&WriterFeatures::ChangeDataFeed));
+    }
 }
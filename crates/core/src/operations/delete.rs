@@ -24,25 +24,29 @@ use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use crate::logstore::LogStoreRef;
 use datafusion::execution::context::{SessionContext, SessionState};
 use datafusion::physical_plan::filter::FilterExec;
-use datafusion::physical_plan::ExecutionPlan;
+use datafusion::physical_plan::{execute_stream, ExecutionPlan};
 use datafusion::prelude::Expr;
 use datafusion_common::scalar::ScalarValue;
 use datafusion_common::DFSchema;
 use futures::future::BoxFuture;
+use futures::TryStreamExt;
 use parquet::file::properties::WriterProperties;
 use serde::Serialize;
 
+use super::cdc::{should_write_cdc, with_change_type, CHANGE_TYPE_DELETE};
 use super::datafusion_utils::Expression;
+use super::deletion_vector::{use_deletion_vectors, write_deletion_vectors};
 use super::transaction::{CommitBuilder, CommitProperties, PROTOCOL};
 use super::write::WriterStatsConfig;
+use crate::delta_datafusion::deletion_vector::DeletedRowsCollectorExec;
 use crate::delta_datafusion::expr::fmt_expr_to_sql;
 use crate::delta_datafusion::{
     create_physical_expr_fix, find_files, register_store, DataFusionMixins, DeltaScanBuilder,
-    DeltaSessionContext,
+    DeltaScanConfigBuilder, DeltaSessionContext,
 };
 use crate::errors::DeltaResult;
 use crate::kernel::{Action, Add, Remove};
-use crate::operations::write::write_execution_plan;
+use crate::operations::write::{write_execution_plan, write_execution_plan_cdc};
 use crate::protocol::DeltaOperation;
 use crate::table::state::DeltaTableState;
 use crate::DeltaTable;
@@ -75,6 +79,8 @@ pub struct DeleteMetrics {
     pub num_deleted_rows: Option<usize>,
     /// Number of rows copied in the process of deleting files
     pub num_copied_rows: Option<usize>,
+    /// Number of files that were re-added with a deletion vector
+    pub num_deletion_vectors_added: usize,
     /// Time taken to execute the entire operation
     pub execution_time_ms: u128,
     /// Time taken to scan the file for matches
@@ -193,6 +199,102 @@ async fn excute_non_empty_expr(
     Ok(add_actions)
 }
 
+/// Mark the records that satisfy the predicate as deleted using deletion vectors,
+/// instead of rewriting the files that contain them.
+async fn execute_deletion_vectors(
+    snapshot: &DeltaTableState,
+    log_store: LogStoreRef,
+    state: &SessionState,
+    expression: &Expr,
+    metrics: &mut DeleteMetrics,
+    candidates: Vec<Add>,
+) -> DeltaResult<Vec<Action>> {
+    let scan_config = DeltaScanConfigBuilder::new()
+        .with_file_column(true)
+        .with_row_index_column(true)
+        .build(snapshot)?;
+    let file_column = Arc::new(scan_config.file_column_name.clone().unwrap());
+    let row_index_column = Arc::new(scan_config.row_index_column_name.clone().unwrap());
+
+    let scan = DeltaScanBuilder::new(snapshot, log_store.clone(), state)
+        .with_files(&candidates)
+        .with_scan_config(scan_config)
+        .build()
+        .await?;
+    let scan = Arc::new(scan);
+
+    let input_dfschema = DFSchema::try_from(scan.schema().as_ref().clone())?;
+    let predicate_expr = create_physical_expr_fix(
+        Expr::IsTrue(Box::new(expression.clone())),
+        &input_dfschema,
+        state.execution_props(),
+    )?;
+    let filter: Arc<dyn ExecutionPlan> = Arc::new(FilterExec::try_new(predicate_expr, scan)?);
+    let collector = DeletedRowsCollectorExec::new(filter, file_column, row_index_column, None);
+    let deleted_rows = collector.deleted_rows();
+
+    execute_stream(Arc::new(collector), state.task_ctx())?
+        .try_for_each(|_| futures::future::ready(Ok(())))
+        .await?;
+
+    let deleted_rows = std::mem::take(&mut *deleted_rows.lock().unwrap());
+    let result = write_deletion_vectors(log_store, candidates, deleted_rows).await?;
+
+    metrics.num_removed_files = result.num_removed_files;
+    metrics.num_deletion_vectors_added = result.num_deletion_vectors_added;
+    metrics.num_deleted_rows = Some(result.num_deleted_rows);
+    metrics.num_copied_rows = Some(0);
+
+    Ok(result.actions)
+}
+
+/// Write the records that satisfy the predicate to change data files.
+async fn write_change_data(
+    snapshot: &DeltaTableState,
+    log_store: LogStoreRef,
+    state: &SessionState,
+    expression: &Expr,
+    candidates: &[Add],
+    writer_properties: Option<WriterProperties>,
+) -> DeltaResult<Vec<Action>> {
+    let input_dfschema: DFSchema = snapshot.input_schema()?.as_ref().clone().try_into()?;
+
+    let scan = DeltaScanBuilder::new(snapshot, log_store.clone(), state)
+        .with_files(candidates)
+        .build()
+        .await?;
+
+    let predicate_expr = create_physical_expr_fix(
+        Expr::IsTrue(Box::new(expression.clone())),
+        &input_dfschema,
+        state.execution_props(),
+    )?;
+    let filter: Arc<dyn ExecutionPlan> =
+        Arc::new(FilterExec::try_new(predicate_expr, Arc::new(scan))?);
+
+    let writer_stats_config = WriterStatsConfig::new(
+        snapshot.table_config().num_indexed_cols(),
+        snapshot
+            .table_config()
+            .stats_columns()
+            .map(|v| v.iter().map(|v| v.to_string()).collect::<Vec<String>>()),
+    );
+
+    write_execution_plan_cdc(
+        snapshot,
+        state.clone(),
+        with_change_type(filter, CHANGE_TYPE_DELETE)?,
+        snapshot.metadata().partition_columns.clone(),
+        log_store.object_store(),
+        Some(snapshot.table_config().target_file_size() as usize),
+        None,
+        writer_properties,
+        false,
+        writer_stats_config,
+    )
+    .await
+}
+
 async fn execute(
     predicate: Option<Expr>,
     log_store: LogStoreRef,
@@ -210,48 +312,80 @@ async fn execute(
 
     let predicate = predicate.unwrap_or(Expr::Literal(ScalarValue::Boolean(Some(true))));
 
-    let add = if candidates.partition_scan {
-        Vec::new()
+    // The deleted records are written before the files containing them are rewritten
+    let change_data = if should_write_cdc(&snapshot) {
+        write_change_data(
+            &snapshot,
+            log_store.clone(),
+            &state,
+            &predicate,
+            &candidates.candidates,
+            writer_properties.clone(),
+        )
+        .await?
     } else {
+        Vec::new()
+    };
+
+    let mut actions = if !candidates.partition_scan && use_deletion_vectors(&snapshot) {
         let write_start = Instant::now();
-        let add = excute_non_empty_expr(
+        let actions = execute_deletion_vectors(
             &snapshot,
             log_store.clone(),
             &state,
             &predicate,
             &mut metrics,
-            &candidates.candidates,
-            writer_properties,
+            candidates.candidates,
         )
         .await?;
         metrics.rewrite_time_ms = Instant::now().duration_since(write_start).as_millis();
-        add
+        actions
+    } else {
+        let add = if candidates.partition_scan {
+            Vec::new()
+        } else {
+            let write_start = Instant::now();
+            let add = excute_non_empty_expr(
+                &snapshot,
+                log_store.clone(),
+                &state,
+                &predicate,
+                &mut metrics,
+                &candidates.candidates,
+                writer_properties,
+            )
+            .await?;
+            metrics.rewrite_time_ms = Instant::now().duration_since(write_start).as_millis();
+            add
+        };
+        let remove = candidates.candidates;
+
+        let deletion_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+
+        let mut actions: Vec<Action> = add.into_iter().map(Action::Add).collect();
+        metrics.num_removed_files = remove.len();
+        metrics.num_added_files = actions.len();
+
+        for action in remove {
+            actions.push(Action::Remove(Remove {
+                path: action.path,
+                deletion_timestamp: Some(deletion_timestamp),
+                data_change: true,
+                extended_file_metadata: Some(true),
+                partition_values: Some(action.partition_values),
+                size: Some(action.size),
+                deletion_vector: action.deletion_vector,
+                tags: None,
+                base_row_id: action.base_row_id,
+                default_row_commit_version: action.default_row_commit_version,
+            }))
+        }
+        actions
     };
-    let remove = candidates.candidates;
-
-    let deletion_timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as i64;
-
-    let mut actions: Vec<Action> = add.into_iter().map(Action::Add).collect();
-    metrics.num_removed_files = remove.len();
-    metrics.num_added_files = actions.len();
-
-    for action in remove {
-        actions.push(Action::Remove(Remove {
-            path: action.path,
-            deletion_timestamp: Some(deletion_timestamp),
-            data_change: true,
-            extended_file_metadata: Some(true),
-            partition_values: Some(action.partition_values),
-            size: Some(action.size),
-            deletion_vector: action.deletion_vector,
-            tags: None,
-            base_row_id: action.base_row_id,
-            default_row_commit_version: action.default_row_commit_version,
-        }))
-    }
+    actions.extend(change_data);
 
     metrics.execution_time_ms = Instant::now().duration_since(exec_start).as_millis();
 
@@ -330,10 +464,11 @@ impl std::future::IntoFuture for DeleteBuilder {
 mod tests {
     use crate::operations::DeltaOps;
     use crate::protocol::*;
-    use crate::writer::test_utils::datafusion::get_data;
     use crate::writer::test_utils::datafusion::write_batch;
+    use crate::writer::test_utils::datafusion::{get_cdf_data, get_data};
     use crate::writer::test_utils::{
         get_arrow_schema, get_delta_schema, get_record_batch, setup_table_with_configuration,
+        setup_table_with_deletion_vectors,
     };
     use crate::DeltaConfigKey;
     use crate::DeltaTable;
@@ -526,6 +661,152 @@ mod tests {
         assert_batches_sorted_eq!(&expected, &actual);
     }
 
+    #[tokio::test]
+    async fn test_delete_with_deletion_vectors() {
+        let schema = get_arrow_schema(&None);
+        let table = setup_table_with_deletion_vectors(None).await;
+
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(arrow::array::StringArray::from(vec!["A", "B", "A", "A"])),
+                Arc::new(arrow::array::Int32Array::from(vec![1, 10, 10, 100])),
+                Arc::new(arrow::array::StringArray::from(vec![
+                    "2021-02-02",
+                    "2021-02-02",
+                    "2021-02-02",
+                    "2021-02-02",
+                ])),
+            ],
+        )
+        .unwrap();
+        let table = DeltaOps(table)
+            .write(vec![batch])
+            .with_save_mode(SaveMode::Append)
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 1);
+        let path = table.get_files_iter().unwrap().next().unwrap();
+
+        let (table, metrics) = DeltaOps(table)
+            .delete()
+            .with_predicate(col("value").eq(lit(1)))
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 2);
+        assert_eq!(metrics.num_added_files, 0);
+        assert_eq!(metrics.num_removed_files, 0);
+        assert_eq!(metrics.num_deletion_vectors_added, 1);
+        assert_eq!(metrics.num_deleted_rows, Some(1));
+        assert_eq!(metrics.num_copied_rows, Some(0));
+
+        // the data file is kept, only a deletion vector is added
+        let files = table.snapshot().unwrap().file_actions().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, path.as_ref());
+        assert_eq!(files[0].deletion_vector.as_ref().unwrap().cardinality, 1);
+
+        let expected = vec![
+            "+----+-------+------------+",
+            "| id | value | modified   |",
+            "+----+-------+------------+",
+            "| A  | 10    | 2021-02-02 |",
+            "| A  | 100   | 2021-02-02 |",
+            "| B  | 10    | 2021-02-02 |",
+            "+----+-------+------------+",
+        ];
+        let actual = get_data(&table).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+
+        // previously deleted rows are retained in the new deletion vector
+        let (table, metrics) = DeltaOps(table)
+            .delete()
+            .with_predicate(col("value").eq(lit(10)))
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 3);
+        assert_eq!(metrics.num_deleted_rows, Some(2));
+        let files = table.snapshot().unwrap().file_actions().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].deletion_vector.as_ref().unwrap().cardinality, 3);
+
+        let expected = vec![
+            "+----+-------+------------+",
+            "| id | value | modified   |",
+            "+----+-------+------------+",
+            "| A  | 100   | 2021-02-02 |",
+            "+----+-------+------------+",
+        ];
+        let actual = get_data(&table).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+
+        // files without any remaining rows are removed
+        let (table, metrics) = DeltaOps(table)
+            .delete()
+            .with_predicate(col("id").eq(lit("A")))
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 4);
+        assert_eq!(metrics.num_removed_files, 1);
+        assert_eq!(metrics.num_deletion_vectors_added, 0);
+        assert_eq!(metrics.num_deleted_rows, Some(1));
+        assert_eq!(table.get_files_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_delete_with_change_data_feed() {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(get_delta_schema().fields().clone())
+            .with_partition_columns(["modified"])
+            .with_configuration_property(DeltaConfigKey::EnableChangeDataFeed, Some("true"))
+            .await
+            .unwrap();
+        let batch = RecordBatch::try_new(
+            get_arrow_schema(&None),
+            vec![
+                Arc::new(arrow::array::StringArray::from(vec!["A", "B", "A", "A"])),
+                Arc::new(arrow::array::Int32Array::from(vec![1, 10, 10, 100])),
+                Arc::new(arrow::array::StringArray::from(vec![
+                    "2021-02-02",
+                    "2021-02-02",
+                    "2021-02-02",
+                    "2021-02-01",
+                ])),
+            ],
+        )
+        .unwrap();
+        let table = write_batch(table, batch).await;
+        assert_eq!(table.version(), 1);
+
+        let (table, _) = DeltaOps(table)
+            .delete()
+            .with_predicate(col("value").eq(lit(10)))
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 2);
+
+        // deletes of whole partitions record the deleted rows as well
+        let (table, _) = DeltaOps(table)
+            .delete()
+            .with_predicate(col("modified").eq(lit("2021-02-01")))
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 3);
+
+        let expected = vec![
+            "+----+-------+--------------+-----------------+------------+",
+            "| id | value | _change_type | _commit_version | modified   |",
+            "+----+-------+--------------+-----------------+------------+",
+            "| A  | 10    | delete       | 2               | 2021-02-02 |",
+            "| A  | 100   | delete       | 3               | 2021-02-01 |",
+            "| B  | 10    | delete       | 2               | 2021-02-02 |",
+            "+----+-------+--------------+-----------------+------------+",
+        ];
+        let actual = get_cdf_data(&table, 2).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+    }
+
     #[tokio::test]
     async fn test_delete_null() {
         // Demonstrate deletion of null
@@ -0,0 +1,176 @@
+//! Helpers for operations that mark rows as deleted using deletion vectors
+//!
+//! Instead of rewriting every data file that contains a modified row, operations
+//! may record the positions of the removed rows in a deletion vector. The data file
+//! is then removed from the log and immediately re-added with the new deletion vector.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use datafusion::execution::context::SessionContext;
+use roaring::RoaringTreemap;
+
+use super::write::{write_execution_plan, WriterStatsConfig};
+use crate::delta_datafusion::{register_store, DeltaScanBuilder, DeltaSessionContext};
+use crate::errors::DeltaResult;
+use crate::kernel::{Action, Add, DeletionVectorDescriptor, Remove, WriterFeatures};
+use crate::logstore::LogStoreRef;
+use crate::table::builder::ensure_table_uri;
+use crate::table::state::DeltaTableState;
+
+/// Determine if modified rows should be marked as deleted via deletion vectors,
+/// rather than rewriting the data files they are contained in.
+pub(crate) fn use_deletion_vectors(snapshot: &DeltaTableState) -> bool {
+    snapshot.table_config().enable_deletion_vectors()
+        && snapshot
+            .protocol()
+            .writer_features
+            .as_ref()
+            .map(|features| features.contains(&WriterFeatures::DeletionVectors))
+            .unwrap_or(false)
+}
+
+/// Actions created for files that had rows deleted via deletion vectors
+#[derive(Debug, Default)]
+pub(crate) struct DeletionVectorActions {
+    /// The remove and add actions to commit
+    pub actions: Vec<Action>,
+    /// Number of files that were removed from the table, since all their rows were deleted
+    pub num_removed_files: usize,
+    /// Number of files that were re-added with a new deletion vector
+    pub num_deletion_vectors_added: usize,
+    /// Number of rows that were newly marked as deleted
+    pub num_deleted_rows: usize,
+}
+
+/// Write deletion vectors for the given rows and create the actions to replace the affected files.
+///
+/// `deleted_rows` maps the path of a data file to the positions of the rows deleted from it.
+/// Rows deleted by an existing deletion vector of a file are retained. If all rows of a file
+/// are deleted, the file is removed without being added again.
+pub(crate) async fn write_deletion_vectors(
+    log_store: LogStoreRef,
+    files: impl IntoIterator<Item = Add>,
+    mut deleted_rows: HashMap<String, RoaringTreemap>,
+) -> DeltaResult<DeletionVectorActions> {
+    let table_root = ensure_table_uri(log_store.root_uri())?;
+    let object_store = log_store.object_store();
+    let deletion_timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64;
+
+    let mut result = DeletionVectorActions::default();
+    for add in files {
+        let mut bitmap = match deleted_rows.remove(&add.path) {
+            Some(bitmap) if !bitmap.is_empty() => bitmap,
+            _ => continue,
+        };
+        if let Some(existing) = &add.deletion_vector {
+            let existing = existing.read(object_store.as_ref(), &table_root).await?;
+            result.num_deleted_rows += (&bitmap - &existing).len() as usize;
+            bitmap |= existing;
+        } else {
+            result.num_deleted_rows += bitmap.len() as usize;
+        }
+
+        result.actions.push(Action::Remove(Remove {
+            path: add.path.clone(),
+            deletion_timestamp: Some(deletion_timestamp),
+            data_change: true,
+            extended_file_metadata: Some(true),
+            partition_values: Some(add.partition_values.clone()),
+            size: Some(add.size),
+            deletion_vector: add.deletion_vector.clone(),
+            tags: add.tags.clone(),
+            base_row_id: add.base_row_id,
+            default_row_commit_version: add.default_row_commit_version,
+        }));
+
+        let num_records = add.get_stats()?.map(|stats| stats.num_records as u64);
+        if num_records.is_some_and(|num_records| bitmap.len() >= num_records) {
+            result.num_removed_files += 1;
+            continue;
+        }
+
+        let deletion_vector =
+            DeletionVectorDescriptor::write(object_store.as_ref(), &bitmap).await?;
+        result.actions.push(Action::Add(Add {
+            data_change: true,
+            deletion_vector: Some(deletion_vector),
+            ..add
+        }));
+        result.num_deletion_vectors_added += 1;
+    }
+
+    Ok(result)
+}
+
+/// Rewrite the given files without the rows deleted by their deletion vectors.
+///
+/// The files are removed from the table and replaced by files without deletion vectors,
+/// leaving the data of the table unchanged.
+pub(crate) async fn purge_deletion_vectors(
+    snapshot: &DeltaTableState,
+    log_store: LogStoreRef,
+    files: Vec<Add>,
+) -> DeltaResult<Vec<Action>> {
+    if files.is_empty() {
+        return Ok(Vec::new());
+    }
+    let session: SessionContext = DeltaSessionContext::default().into();
+    register_store(log_store.clone(), session.runtime_env());
+    let state = session.state();
+
+    let scan = DeltaScanBuilder::new(snapshot, log_store.clone(), &state)
+        .with_files(&files)
+        .build()
+        .await?;
+    let writer_stats_config = WriterStatsConfig::new(
+        snapshot.table_config().num_indexed_cols(),
+        snapshot
+            .table_config()
+            .stats_columns()
+            .map(|v| v.iter().map(|v| v.to_string()).collect::<Vec<String>>()),
+    );
+    let mut actions = write_execution_plan(
+        Some(snapshot),
+        state,
+        Arc::new(scan),
+        snapshot.metadata().partition_columns.clone(),
+        log_store.object_store(),
+        Some(snapshot.table_config().target_file_size() as usize),
+        None,
+        None,
+        false,
+        None,
+        writer_stats_config,
+    )
+    .await?;
+    for action in actions.iter_mut() {
+        if let Action::Add(add) = action {
+            add.data_change = false;
+        }
+    }
+
+    let deletion_timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64;
+    actions.extend(files.into_iter().map(|add| {
+        Action::Remove(Remove {
+            path: add.path,
+            deletion_timestamp: Some(deletion_timestamp),
+            data_change: false,
+            extended_file_metadata: Some(true),
+            partition_values: Some(add.partition_values),
+            size: Some(add.size),
+            deletion_vector: add.deletion_vector,
+            tags: add.tags,
+            base_row_id: add.base_row_id,
+            default_row_commit_version: add.default_row_commit_version,
+        })
+    }));
+    Ok(actions)
+}
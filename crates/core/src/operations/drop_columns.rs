@@ -0,0 +1,321 @@
+//! Drop columns from a table
+//!
+//! Dropping columns requires column mapping to be enabled on the table. The dropped
+//! columns are only removed from the table schema, the data files are left untouched.
+
+use futures::future::BoxFuture;
+
+use super::column_references::ensure_unreferenced;
+use super::transaction::{CommitBuilder, CommitProperties};
+use crate::kernel::{Action, StructType};
+use crate::logstore::LogStoreRef;
+use crate::protocol::DeltaOperation;
+use crate::table::config::{ColumnMappingMode, DeltaConfigKey, TableConfig};
+use crate::table::state::DeltaTableState;
+use crate::DeltaTable;
+use crate::{DeltaResult, DeltaTableError};
+
+/// Remove columns from the table
+pub struct DropColumnsBuilder {
+    /// A snapshot of the table's state
+    snapshot: DeltaTableState,
+    /// Names of the columns to drop
+    columns: Vec<String>,
+    /// Raise if a column doesn't exist
+    raise_if_not_exists: bool,
+    /// Delta object store for handling data files
+    log_store: LogStoreRef,
+    /// Additional information to add to the commit
+    commit_properties: CommitProperties,
+}
+
+impl super::Operation<()> for DropColumnsBuilder {}
+
+impl DropColumnsBuilder {
+    /// Create a new builder
+    pub fn new(log_store: LogStoreRef, snapshot: DeltaTableState) -> Self {
+        Self {
+            columns: Vec::new(),
+            raise_if_not_exists: true,
+            snapshot,
+            log_store,
+            commit_properties: CommitProperties::default(),
+        }
+    }
+
+    /// Specify the columns to be dropped
+    pub fn with_columns(mut self, columns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.columns = columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Specify if you want to raise if a column does not exist
+    pub fn with_raise_if_not_exists(mut self, raise: bool) -> Self {
+        self.raise_if_not_exists = raise;
+        self
+    }
+
+    /// Additional metadata to be added to commit info
+    pub fn with_commit_properties(mut self, commit_properties: CommitProperties) -> Self {
+        self.commit_properties = commit_properties;
+        self
+    }
+}
+
+impl std::future::IntoFuture for DropColumnsBuilder {
+    type Output = DeltaResult<DeltaTable>;
+
+    type IntoFuture = BoxFuture<'static, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let this = self;
+
+        Box::pin(async move {
+            if this.columns.is_empty() {
+                return Err(DeltaTableError::Generic("No columns provided".to_string()));
+            }
+
+            let mut metadata = this.snapshot.metadata().clone();
+            let config = TableConfig(&metadata.configuration);
+            if config.column_mapping_mode() == ColumnMappingMode::None {
+                return Err(DeltaTableError::Generic(
+                    "Column mapping must be enabled to drop columns".to_string(),
+                ));
+            }
+
+            let mut fields = this.snapshot.schema().fields().clone();
+            let mut dropped = Vec::new();
+            for column in &this.columns {
+                if metadata.partition_columns.contains(column) {
+                    return Err(DeltaTableError::Generic(format!(
+                        "Cannot drop partition column {column}"
+                    )));
+                }
+                ensure_unreferenced(
+                    &metadata,
+                    this.snapshot.schema(),
+                    &[column],
+                    &this.columns,
+                    "drop",
+                )?;
+                match fields.iter().position(|field| field.name() == column) {
+                    Some(idx) => {
+                        fields.remove(idx);
+                        dropped.push(column.clone());
+                    }
+                    None if this.raise_if_not_exists => {
+                        return Err(DeltaTableError::Generic(format!(
+                            "Column {column} does not exist"
+                        )));
+                    }
+                    None => {}
+                }
+            }
+            if dropped.is_empty() {
+                return Ok(DeltaTable::new_with_state(this.log_store, this.snapshot));
+            }
+            if fields.is_empty() {
+                return Err(DeltaTableError::Generic(
+                    "Cannot drop all columns of a table".to_string(),
+                ));
+            }
+
+            // make sure all columns have a physical name, so data files can still be read
+            let mut max_column_id = config.column_mapping_max_column_id();
+            let schema = StructType::new(fields).with_column_mapping(&mut max_column_id);
+            metadata.configuration.insert(
+                DeltaConfigKey::ColumnMappingMaxColumnId
+                    .as_ref()
+                    .to_string(),
+                Some(max_column_id.to_string()),
+            );
+            metadata.schema_string = serde_json::to_string(&schema)?;
+
+            let operation = DeltaOperation::DropColumns { columns: dropped };
+
+            let actions = vec![Action::Metadata(metadata)];
+
+            let commit = CommitBuilder::from(this.commit_properties)
+                .with_actions(actions)
+                .build(Some(&this.snapshot), this.log_store.clone(), operation)?
+                .await?;
+
+            Ok(DeltaTable::new_with_state(
+                this.log_store,
+                commit.snapshot(),
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "datafusion")]
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Int32Array, StringArray, StructArray};
+    use arrow::datatypes::{DataType as ArrowDataType, Field, Schema as ArrowSchema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_sorted_eq;
+    use std::sync::Arc;
+
+    use crate::kernel::{DataType, PrimitiveType, StructField};
+    use crate::operations::collect_sendable_stream;
+    use crate::{DeltaOps, DeltaResult, DeltaTable};
+
+    async fn create_column_mapped_table() -> DeltaResult<DeltaTable> {
+        let nested_fields = vec![
+            Field::new("a", ArrowDataType::Int32, true),
+            Field::new("b", ArrowDataType::Utf8, true),
+        ];
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", ArrowDataType::Int32, true),
+            Field::new("value", ArrowDataType::Utf8, true),
+            Field::new(
+                "nested",
+                ArrowDataType::Struct(nested_fields.clone().into()),
+                true,
+            ),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(StructArray::new(
+                    nested_fields.into(),
+                    vec![
+                        Arc::new(Int32Array::from(vec![10, 20])),
+                        Arc::new(StringArray::from(vec!["x", "y"])),
+                    ],
+                    None,
+                )),
+            ],
+        )?;
+        DeltaOps::new_in_memory()
+            .write(vec![batch])
+            .with_configuration([("delta.columnMapping.mode", Some("name"))])
+            .await
+    }
+
+    #[tokio::test]
+    async fn drop_columns() -> DeltaResult<()> {
+        let table = create_column_mapped_table().await?;
+        let version = table.version();
+
+        let table = DeltaOps(table)
+            .drop_columns()
+            .with_columns(["value"])
+            .await?;
+        assert_eq!(table.version(), version + 1);
+
+        let schema = table.metadata()?.schema()?;
+        assert!(schema.field_with_name("value").is_err());
+        let max_column_id = table
+            .metadata()?
+            .configuration
+            .get("delta.columnMapping.maxColumnId")
+            .cloned()
+            .flatten();
+        assert_eq!(max_column_id, Some("5".to_string()));
+
+        let last_commit = &table.history(None).await?[0];
+        assert_eq!(last_commit.operation, Some("DROP COLUMNS".to_string()));
+
+        let (_, stream) = DeltaOps(table).load().await?;
+        let batches = collect_sendable_stream(stream).await?;
+        let expected = vec![
+            "+----+---------------+",
+            "| id | nested        |",
+            "+----+---------------+",
+            "| 1  | {a: 10, b: x} |",
+            "| 2  | {a: 20, b: y} |",
+            "+----+---------------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn drop_missing_column() -> DeltaResult<()> {
+        let table = create_column_mapped_table().await?;
+        let version = table.version();
+
+        let result = DeltaOps(table.clone())
+            .drop_columns()
+            .with_columns(["missing"])
+            .await;
+        assert!(result.is_err());
+
+        let table = DeltaOps(table)
+            .drop_columns()
+            .with_columns(["missing"])
+            .with_raise_if_not_exists(false)
+            .await?;
+        assert_eq!(table.version(), version);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn drop_column_referenced_by_constraint() -> DeltaResult<()> {
+        let table = DeltaOps(create_column_mapped_table().await?)
+            .add_constraint()
+            .with_constraint("valid_a", "nested.a > 0")
+            .await?;
+
+        let result = DeltaOps(table.clone())
+            .drop_columns()
+            .with_columns(["nested"])
+            .await;
+        assert!(result.is_err());
+
+        let table = DeltaOps(table)
+            .drop_columns()
+            .with_columns(["value"])
+            .await?;
+        assert!(table.get_schema()?.field_with_name("value").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn drop_column_referenced_by_generated_column() -> DeltaResult<()> {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![
+                StructField::new("id", DataType::INTEGER, true),
+                StructField::new("value", DataType::STRING, true),
+            ])
+            .with_generated_column("doubled", DataType::INTEGER, true, "id * 2")
+            .with_configuration_property(crate::DeltaConfigKey::ColumnMappingMode, Some("name"))
+            .await?;
+
+        let result = DeltaOps(table.clone())
+            .drop_columns()
+            .with_columns(["id"])
+            .await;
+        assert!(result.is_err());
+
+        // the generated column can be dropped together with the columns it references
+        let table = DeltaOps(table)
+            .drop_columns()
+            .with_columns(["doubled", "id"])
+            .await?;
+        let schema = table.get_schema()?;
+        let names = schema.fields().iter().map(|f| f.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["value"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn drop_columns_requires_column_mapping() -> DeltaResult<()> {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![
+                StructField::new("id", DataType::Primitive(PrimitiveType::Integer), true),
+                StructField::new("value", DataType::Primitive(PrimitiveType::String), true),
+            ])
+            .await?;
+
+        let result = DeltaOps(table).drop_columns().with_columns(["value"]).await;
+        assert!(result.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,672 @@
+//! Drop a table feature from the protocol of a table
+//!
+//! Writer features are dropped by removing them from the protocol, once the table no longer
+//! relies on them. Reader-writer features additionally require all traces of the feature to be
+//! removed from the table, e.g. by rewriting the files that have deletion vectors, before the
+//! protocol is downgraded.
+//!
+//! Clients that do not support a dropped reader-writer feature must not read the history
+//! written while it was enabled. By default, checkpoints are written before and after the
+//! protocol downgrade, and the `checkpointProtection` writer feature keeps writers from
+//! cleaning up only part of the log before the downgrade. Alternatively, the history can be
+//! truncated once the traces of the feature are older than the log retention duration.
+//!
+//! See <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#table-features>
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use futures::future::BoxFuture;
+use futures::TryStreamExt;
+use object_store::ObjectStore;
+
+use super::transaction::{CommitBuilder, CommitProperties};
+use crate::checkpoints::{cleanup_expired_logs_for, create_checkpoint_for};
+use crate::kernel::{Action, PathExt, Protocol, ReaderFeatures, WriterFeatures};
+use crate::logstore::{get_actions, LogStoreRef};
+use crate::protocol::DeltaOperation;
+use crate::table::config::{CheckpointPolicy, DeltaConfigKey, TableConfig};
+use crate::table::state::DeltaTableState;
+use crate::DeltaTable;
+use crate::{DeltaResult, DeltaTableError};
+
+/// Drop a feature from the protocol of a table
+pub struct DropFeatureBuilder {
+    /// A snapshot of the table's state
+    snapshot: DeltaTableState,
+    /// Name of the feature to drop
+    name: Option<String>,
+    /// Truncate the history instead of protecting the checkpoints around the downgrade
+    truncate_history: bool,
+    /// Delta object store for handling data files
+    log_store: LogStoreRef,
+    /// Additional information to add to the commit
+    commit_properties: CommitProperties,
+}
+
+impl super::Operation<()> for DropFeatureBuilder {}
+
+impl DropFeatureBuilder {
+    /// Create a new builder
+    pub fn new(log_store: LogStoreRef, snapshot: DeltaTableState) -> Self {
+        Self {
+            name: None,
+            truncate_history: false,
+            snapshot,
+            log_store,
+            commit_properties: CommitProperties::default(),
+        }
+    }
+
+    /// Specify the name of the feature to drop, e.g. `deletionVectors`
+    pub fn with_feature<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Truncate the history of the table when dropping a reader-writer feature.
+    ///
+    /// This requires the traces of the feature to be older than the log retention duration.
+    /// If traces are removed by this operation, it fails after removing them and must be
+    /// retried once they expired.
+    pub fn with_truncate_history(mut self, truncate_history: bool) -> Self {
+        self.truncate_history = truncate_history;
+        self
+    }
+
+    /// Additional metadata to be added to commit info
+    pub fn with_commit_properties(mut self, commit_properties: CommitProperties) -> Self {
+        self.commit_properties = commit_properties;
+        self
+    }
+}
+
+/// The table features that can be dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DroppableFeature {
+    DeletionVectors,
+    V2Checkpoint,
+    InCommitTimestamp,
+    CheckConstraints,
+}
+
+impl DroppableFeature {
+    fn try_from_name(name: &str) -> DeltaResult<Self> {
+        match WriterFeatures::from(name) {
+            WriterFeatures::DeletionVectors => Ok(Self::DeletionVectors),
+            WriterFeatures::V2Checkpoint => Ok(Self::V2Checkpoint),
+            WriterFeatures::InCommitTimestamp => Ok(Self::InCommitTimestamp),
+            WriterFeatures::CheckConstraints => Ok(Self::CheckConstraints),
+            _ => Err(DeltaTableError::Generic(format!(
+                "Dropping the feature {name} is not supported"
+            ))),
+        }
+    }
+
+    fn writer_feature(self) -> WriterFeatures {
+        match self {
+            Self::DeletionVectors => WriterFeatures::DeletionVectors,
+            Self::V2Checkpoint => WriterFeatures::V2Checkpoint,
+            Self::InCommitTimestamp => WriterFeatures::InCommitTimestamp,
+            Self::CheckConstraints => WriterFeatures::CheckConstraints,
+        }
+    }
+
+    fn reader_feature(self) -> Option<ReaderFeatures> {
+        match self {
+            Self::DeletionVectors => Some(ReaderFeatures::DeletionVectors),
+            Self::V2Checkpoint => Some(ReaderFeatures::V2Checkpoint),
+            Self::InCommitTimestamp | Self::CheckConstraints => None,
+        }
+    }
+
+    /// Table properties that keep writers from using the feature any further
+    fn disabling_properties(self, config: &TableConfig<'_>) -> HashMap<String, String> {
+        let mut properties = HashMap::new();
+        match self {
+            Self::DeletionVectors if config.enable_deletion_vectors() => {
+                properties.insert(
+                    DeltaConfigKey::EnableDeletionVectors.as_ref().to_string(),
+                    "false".to_string(),
+                );
+            }
+            Self::V2Checkpoint if config.checkpoint_policy() == CheckpointPolicy::V2 => {
+                properties.insert(
+                    DeltaConfigKey::CheckpointPolicy.as_ref().to_string(),
+                    CheckpointPolicy::Classic.as_ref().to_string(),
+                );
+            }
+            _ => {}
+        }
+        properties
+    }
+
+    /// Returns true if the actions of a commit reference the feature
+    fn has_traces(self, actions: &[Action]) -> bool {
+        match self {
+            Self::DeletionVectors => actions.iter().any(|action| match action {
+                Action::Add(add) => add.deletion_vector.is_some(),
+                Action::Remove(remove) => remove.deletion_vector.is_some(),
+                _ => false,
+            }),
+            _ => false,
+        }
+    }
+
+    /// Remove the feature from the protocol, lowering the reader version if no reader
+    /// features remain
+    fn remove_from(self, protocol: &mut Protocol) {
+        if let Some(features) = protocol.writer_features.as_mut() {
+            features.remove(&self.writer_feature());
+        }
+        if let Some(feature) = self.reader_feature() {
+            if let Some(features) = protocol.reader_features.as_mut() {
+                features.remove(&feature);
+                if features.is_empty() {
+                    protocol.reader_features = None;
+                    protocol.min_reader_version = 1;
+                }
+            }
+        }
+    }
+}
+
+/// Disable the feature and remove its traces from the current version of the table
+async fn remove_traces(
+    feature: DroppableFeature,
+    mut snapshot: DeltaTableState,
+    log_store: &LogStoreRef,
+    commit_properties: &CommitProperties,
+) -> DeltaResult<(DeltaTableState, bool)> {
+    let mut removed = false;
+
+    let properties = feature.disabling_properties(&snapshot.table_config());
+    if !properties.is_empty() {
+        let mut metadata = snapshot.metadata().clone();
+        for (key, value) in &properties {
+            metadata
+                .configuration
+                .insert(key.clone(), Some(value.clone()));
+        }
+        let commit = CommitBuilder::from(commit_properties.clone())
+            .with_actions(vec![Action::Metadata(metadata)])
+            .build(
+                Some(&snapshot),
+                log_store.clone(),
+                DeltaOperation::SetTableProperties { properties },
+            )?
+            .await?;
+        snapshot = commit.snapshot();
+        removed = true;
+    }
+
+    if feature == DroppableFeature::DeletionVectors {
+        let files = snapshot
+            .file_actions()?
+            .into_iter()
+            .filter(|add| add.deletion_vector.is_some())
+            .collect::<Vec<_>>();
+        if !files.is_empty() {
+            let actions = purge_deletion_vectors(&snapshot, log_store.clone(), files).await?;
+            let commit = CommitBuilder::from(commit_properties.clone())
+                .with_actions(actions)
+                .build(
+                    Some(&snapshot),
+                    log_store.clone(),
+                    DeltaOperation::Reorg { apply_purge: true },
+                )?
+                .await?;
+            snapshot = commit.snapshot();
+            removed = true;
+        }
+    }
+
+    Ok((snapshot, removed))
+}
+
+#[cfg(feature = "datafusion")]
+use super::deletion_vector::purge_deletion_vectors;
+
+#[cfg(not(feature = "datafusion"))]
+async fn purge_deletion_vectors(
+    _snapshot: &DeltaTableState,
+    _log_store: LogStoreRef,
+    files: Vec<crate::kernel::Add>,
+) -> DeltaResult<Vec<Action>> {
+    Err(DeltaTableError::Generic(format!(
+        "Purging {} files with deletion vectors requires the datafusion feature",
+        files.len()
+    )))
+}
+
+/// Ensure the log entries that are retained when truncating the history at `cutoff_timestamp`
+/// don't reference the feature
+async fn ensure_no_traces_in_history(
+    feature: DroppableFeature,
+    name: &str,
+    snapshot: &DeltaTableState,
+    log_store: &LogStoreRef,
+    cutoff_timestamp: i64,
+) -> DeltaResult<()> {
+    let object_store = log_store.object_store();
+    let mut files = object_store.list(Some(log_store.log_path()));
+    while let Some(meta) = files.try_next().await? {
+        if meta.last_modified.timestamp_millis() <= cutoff_timestamp {
+            continue;
+        }
+        let has_traces = match (meta.location.commit_version(), feature) {
+            (_, DroppableFeature::V2Checkpoint) => meta.location.is_v2_checkpoint_file(),
+            (Some(version), _) if meta.location.is_commit_file() => {
+                let bytes = object_store.get(&meta.location).await?.bytes().await?;
+                feature.has_traces(&get_actions(version, bytes).await?)
+            }
+            _ => false,
+        };
+        if has_traces {
+            let retention = snapshot.table_config().log_retention_duration();
+            return Err(DeltaTableError::Generic(format!(
+                "The history within the log retention duration of {retention:?} still \
+                 references the feature {name}, retry truncating the history once it expired"
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl std::future::IntoFuture for DropFeatureBuilder {
+    type Output = DeltaResult<DeltaTable>;
+
+    type IntoFuture = BoxFuture<'static, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let this = self;
+
+        Box::pin(async move {
+            let name = this
+                .name
+                .ok_or(DeltaTableError::Generic("No feature provided".to_string()))?;
+            let feature = DroppableFeature::try_from_name(&name)?;
+            let enabled = this
+                .snapshot
+                .protocol()
+                .writer_features
+                .as_ref()
+                .is_some_and(|features| features.contains(&feature.writer_feature()));
+            if !enabled {
+                return Err(DeltaTableError::Generic(format!(
+                    "The feature {name} is not supported by the protocol of the table"
+                )));
+            }
+
+            let mut snapshot = this.snapshot;
+            let reader_writer_feature = feature.reader_feature().is_some();
+            let protect_checkpoints = reader_writer_feature && !this.truncate_history;
+            let cutoff_timestamp = Utc::now().timestamp_millis()
+                - snapshot.table_config().log_retention_duration().as_millis() as i64;
+
+            if reader_writer_feature {
+                let (state, removed) =
+                    remove_traces(feature, snapshot, &this.log_store, &this.commit_properties)
+                        .await?;
+                snapshot = state;
+                if this.truncate_history {
+                    if removed {
+                        let retention = snapshot.table_config().log_retention_duration();
+                        return Err(DeltaTableError::Generic(format!(
+                            "Removed the traces of the feature {name}, retry truncating the \
+                             history once they are older than the log retention duration of \
+                             {retention:?}"
+                        )));
+                    }
+                    ensure_no_traces_in_history(
+                        feature,
+                        &name,
+                        &snapshot,
+                        &this.log_store,
+                        cutoff_timestamp,
+                    )
+                    .await?;
+                } else {
+                    // allows reading the table before the downgrade without replaying commits
+                    create_checkpoint_for(snapshot.version(), &snapshot, this.log_store.as_ref())
+                        .await?;
+                }
+            }
+
+            let mut protocol = snapshot.protocol().clone();
+            let mut metadata = snapshot.metadata().clone();
+            feature.remove_from(&mut protocol);
+            match feature {
+                DroppableFeature::InCommitTimestamp => {
+                    for key in [
+                        DeltaConfigKey::EnableInCommitTimestamps,
+                        DeltaConfigKey::InCommitTimestampEnablementVersion,
+                        DeltaConfigKey::InCommitTimestampEnablementTimestamp,
+                    ] {
+                        metadata.configuration.remove(key.as_ref());
+                    }
+                }
+                DroppableFeature::CheckConstraints
+                    if !snapshot.table_config().get_constraints().is_empty() =>
+                {
+                    return Err(DeltaTableError::Generic(format!(
+                        "The feature {name} cannot be dropped while the table has check \
+                         constraints"
+                    )));
+                }
+                _ => {}
+            }
+            if protect_checkpoints {
+                protocol
+                    .writer_features
+                    .get_or_insert_with(Default::default)
+                    .insert(WriterFeatures::CheckpointProtection);
+                metadata.configuration.insert(
+                    DeltaConfigKey::RequireCheckpointProtectionBeforeVersion
+                        .as_ref()
+                        .to_string(),
+                    Some((snapshot.version() + 1).to_string()),
+                );
+            }
+
+            let mut actions = vec![Action::Protocol(protocol)];
+            if &metadata != snapshot.metadata() {
+                actions.push(Action::Metadata(metadata));
+            }
+            let operation = DeltaOperation::DropFeature {
+                feature_name: name,
+                truncate_history: this.truncate_history,
+            };
+            // a concurrent commit may use the feature again, so the downgrade is not retried
+            let commit = CommitBuilder::from(this.commit_properties)
+                .with_actions(actions)
+                .with_max_retries(1)
+                .build(Some(&snapshot), this.log_store.clone(), operation)?
+                .await?;
+            let snapshot = commit.snapshot();
+
+            if reader_writer_feature {
+                create_checkpoint_for(snapshot.version(), &snapshot, this.log_store.as_ref())
+                    .await?;
+                if this.truncate_history {
+                    cleanup_expired_logs_for(
+                        snapshot.version(),
+                        this.log_store.as_ref(),
+                        cutoff_timestamp,
+                    )
+                    .await?;
+                }
+            }
+
+            Ok(DeltaTable::new_with_state(this.log_store, snapshot))
+        })
+    }
+}
+
+#[cfg(feature = "datafusion")]
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::writer::test_utils::datafusion::{get_data_sorted, write_batch};
+    use crate::writer::test_utils::{get_record_batch, setup_table_with_deletion_vectors};
+    use crate::DeltaOps;
+
+    async fn table_with_deletion_vectors() -> DeltaTable {
+        let table = setup_table_with_deletion_vectors(None).await;
+        let table = write_batch(table, get_record_batch(None, false)).await;
+        let (table, _) = DeltaOps(table)
+            .delete()
+            .with_predicate("id = 'A'")
+            .await
+            .unwrap();
+        assert!(table
+            .snapshot()
+            .unwrap()
+            .file_actions()
+            .unwrap()
+            .iter()
+            .any(|add| add.deletion_vector.is_some()));
+        table
+    }
+
+    async fn log_versions(table: &DeltaTable) -> Vec<i64> {
+        let log_store = table.log_store();
+        let mut versions = log_store
+            .object_store()
+            .list(Some(log_store.log_path()))
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|meta| meta.location.is_commit_file())
+            .filter_map(|meta| meta.location.commit_version())
+            .collect::<Vec<_>>();
+        versions.sort();
+        versions
+    }
+
+    #[tokio::test]
+    async fn test_drop_deletion_vectors() {
+        let table = table_with_deletion_vectors().await;
+        let expected = get_data_sorted(&table, "id,value,modified").await;
+
+        let table = DeltaOps(table)
+            .drop_feature()
+            .with_feature("deletionVectors")
+            .await
+            .unwrap();
+        let snapshot = table.snapshot().unwrap();
+        // disabling the feature, purging the deletion vectors and the downgrade are committed
+        assert_eq!(table.version(), 5);
+        let protocol = snapshot.protocol();
+        assert_eq!(protocol.min_reader_version, 1);
+        assert_eq!(protocol.reader_features, None);
+        assert_eq!(
+            protocol.writer_features,
+            Some([WriterFeatures::CheckpointProtection].into())
+        );
+        let config = snapshot.table_config();
+        assert!(!config.enable_deletion_vectors());
+        assert_eq!(config.require_checkpoint_protection_before_version(), 5);
+        assert!(snapshot
+            .file_actions()
+            .unwrap()
+            .iter()
+            .all(|add| add.deletion_vector.is_none()));
+        assert_eq!(get_data_sorted(&table, "id,value,modified").await, expected);
+
+        let history = table.history(Some(1)).await.unwrap();
+        assert_eq!(history[0].operation.as_deref(), Some("DROP FEATURE"));
+
+        // the checkpoints around the downgrade are written
+        let object_store = table.log_store().object_store();
+        for version in [4, 5] {
+            let path = table
+                .log_store()
+                .log_path()
+                .child(format!("{version:020}.checkpoint.parquet"));
+            assert!(object_store.head(&path).await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drop_deletion_vectors_truncating_history() {
+        let table = table_with_deletion_vectors().await;
+        let mut metadata = table.metadata().unwrap().clone();
+        metadata.configuration.insert(
+            DeltaConfigKey::LogRetentionDuration.as_ref().to_string(),
+            Some("interval 0 seconds".to_string()),
+        );
+        let commit = CommitBuilder::default()
+            .with_actions(vec![Action::Metadata(metadata)])
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                DeltaOperation::SetTableProperties {
+                    properties: HashMap::new(),
+                },
+            )
+            .unwrap()
+            .await
+            .unwrap();
+        let mut table = DeltaTable::new_with_state(table.log_store(), commit.snapshot());
+
+        // the traces are removed first, dropping the feature must wait for them to expire
+        let result = DeltaOps(table.clone())
+            .drop_feature()
+            .with_feature("deletionVectors")
+            .with_truncate_history(true)
+            .await;
+        assert!(result.is_err());
+        table.update().await.unwrap();
+        assert_eq!(table.version(), 5);
+        assert!(table
+            .snapshot()
+            .unwrap()
+            .file_actions()
+            .unwrap()
+            .iter()
+            .all(|add| add.deletion_vector.is_none()));
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let table = DeltaOps(table)
+            .drop_feature()
+            .with_feature("deletionVectors")
+            .with_truncate_history(true)
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 6);
+        let protocol = table.snapshot().unwrap().protocol().clone();
+        assert_eq!(protocol.min_reader_version, 1);
+        assert_eq!(protocol.writer_features, Some(Default::default()));
+        assert_eq!(log_versions(&table).await, vec![6]);
+    }
+
+    #[tokio::test]
+    async fn test_drop_in_commit_timestamps() {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(
+                crate::writer::test_utils::get_delta_schema()
+                    .fields()
+                    .clone(),
+            )
+            .with_configuration_property(DeltaConfigKey::EnableInCommitTimestamps, Some("true"))
+            .await
+            .unwrap();
+
+        let table = DeltaOps(table)
+            .drop_feature()
+            .with_feature("inCommitTimestamp")
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 1);
+        let snapshot = table.snapshot().unwrap();
+        assert_eq!(
+            snapshot.protocol().writer_features,
+            Some(Default::default())
+        );
+        assert!(!snapshot
+            .metadata()
+            .configuration
+            .contains_key(DeltaConfigKey::EnableInCommitTimestamps.as_ref()));
+
+        let table = write_batch(table, get_record_batch(None, false)).await;
+        let history = table.history(Some(1)).await.unwrap();
+        assert_eq!(history[0].in_commit_timestamp, None);
+    }
+
+    #[tokio::test]
+    async fn test_drop_v2_checkpoint() {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(
+                crate::writer::test_utils::get_delta_schema()
+                    .fields()
+                    .clone(),
+            )
+            .with_configuration_property(DeltaConfigKey::CheckpointPolicy, Some("v2"))
+            .await
+            .unwrap();
+        let table = write_batch(table, get_record_batch(None, false)).await;
+        create_checkpoint_for(
+            table.version(),
+            table.snapshot().unwrap(),
+            table.log_store().as_ref(),
+        )
+        .await
+        .unwrap();
+        let expected = get_data_sorted(&table, "id,value,modified").await;
+
+        let log_store = table.log_store();
+        let object_store = log_store.object_store();
+        let log_files = |object_store: Arc<dyn ObjectStore>| async move {
+            object_store
+                .list(Some(&object_store::path::Path::from("_delta_log")))
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap()
+        };
+        assert!(log_files(object_store.clone())
+            .await
+            .iter()
+            .any(|meta| meta.location.is_v2_checkpoint_file()));
+
+        let table = DeltaOps(table)
+            .drop_feature()
+            .with_feature("v2Checkpoint")
+            .await
+            .unwrap();
+        // switching to classic checkpoints and the downgrade are committed
+        assert_eq!(table.version(), 3);
+        let protocol = table.snapshot().unwrap().protocol();
+        assert!(!protocol
+            .reader_features
+            .as_ref()
+            .is_some_and(|features| features.contains(&ReaderFeatures::V2Checkpoint)));
+        assert!(!protocol
+            .writer_features
+            .as_ref()
+            .is_some_and(|features| features.contains(&WriterFeatures::V2Checkpoint)));
+
+        // without the commits the table can only be loaded from the classic checkpoint
+        for meta in log_files(object_store.clone()).await {
+            if meta.location.is_commit_file() {
+                object_store.delete(&meta.location).await.unwrap();
+            }
+        }
+        let checkpoint = log_store
+            .log_path()
+            .child(format!("{:020}.checkpoint.parquet", 3));
+        assert!(object_store.head(&checkpoint).await.is_ok());
+
+        let mut table = DeltaTable::new(log_store, Default::default());
+        table.load().await.unwrap();
+        assert_eq!(table.version(), 3);
+        let protocol = table.snapshot().unwrap().protocol();
+        assert!(!protocol
+            .writer_features
+            .as_ref()
+            .is_some_and(|features| features.contains(&WriterFeatures::V2Checkpoint)));
+        assert_eq!(get_data_sorted(&table, "id,value,modified").await, expected);
+    }
+
+    #[tokio::test]
+    async fn test_drop_unsupported_feature() {
+        let table = setup_table_with_deletion_vectors(None).await;
+        let result = DeltaOps(table.clone())
+            .drop_feature()
+            .with_feature("columnMapping")
+            .await;
+        assert!(result.is_err());
+
+        let result = DeltaOps(table)
+            .drop_feature()
+            .with_feature("v2Checkpoint")
+            .await;
+        assert!(result.is_err());
+    }
+}
@@ -22,12 +22,12 @@ use futures::StreamExt;
 pub use object_store::path::Path;
 use object_store::ObjectStore;
 use serde::Serialize;
-use url::{ParseError, Url};
 
 use crate::errors::{DeltaResult, DeltaTableError};
 use crate::kernel::{Action, Add, Remove};
 use crate::logstore::LogStoreRef;
 use crate::protocol::DeltaOperation;
+use crate::storage::utils::is_absolute_path;
 use crate::table::state::DeltaTableState;
 use crate::DeltaTable;
 
@@ -64,17 +64,6 @@ struct FileSystemCheckPlan {
     pub files_to_remove: Vec<Add>,
 }
 
-fn is_absolute_path(path: &str) -> DeltaResult<bool> {
-    match Url::parse(path) {
-        Ok(_) => Ok(true),
-        Err(ParseError::RelativeUrlWithoutBase) => Ok(false),
-        Err(_) => Err(DeltaTableError::Generic(format!(
-            "Unable to parse path: {}",
-            &path
-        ))),
-    }
-}
-
 impl super::Operation<()> for FileSystemCheckBuilder {}
 
 impl FileSystemCheckBuilder {
@@ -0,0 +1,259 @@
+//! Helpers for computing the values of generated columns
+//!
+//! Generated columns store the result of a SQL expression over other columns of
+//! the same row. Writers compute the values of generated columns that are not
+//! provided, while explicitly provided values are validated by the [`DeltaDataChecker`].
+//!
+//! [`DeltaDataChecker`]: crate::delta_datafusion::DeltaDataChecker
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_array::RecordBatch;
+use arrow_schema::{DataType as ArrowDataType, Field, Schema as ArrowSchema};
+use datafusion::execution::context::SessionState;
+use datafusion::optimizer::simplify_expressions::{ExprSimplifier, SimplifyContext};
+use datafusion::physical_plan::{projection::ProjectionExec, ExecutionPlan};
+use datafusion::prelude::SessionContext;
+use datafusion_common::tree_node::{Transformed, TreeNode};
+use datafusion_common::{Column, DFSchema, OwnedTableReference};
+use datafusion_expr::{cast, Expr, ExprSchemable};
+use datafusion_physical_expr::{expressions, PhysicalExpr};
+
+use crate::delta_datafusion::create_physical_expr_fix;
+use crate::delta_datafusion::expr::parse_predicate_expression;
+use crate::errors::{DeltaResult, DeltaTableError};
+use crate::kernel::{GeneratedColumn, StructType};
+
+/// Parse the generation expression of a column, casting the result to the column type.
+///
+/// The types within the expression are coerced, so it can be turned into a physical expression.
+fn generation_expr(
+    generated_column: &GeneratedColumn,
+    schema: &DFSchema,
+    state: &SessionState,
+) -> DeltaResult<Expr> {
+    let data_type: ArrowDataType = (&generated_column.data_type).try_into()?;
+    let expr = parse_predicate_expression(schema, &generated_column.generation_expr, state)?;
+    let simplifier = ExprSimplifier::new(SimplifyContext::new(state.execution_props()));
+    Ok(simplifier.coerce(cast(expr, data_type), Arc::new(schema.clone()))?)
+}
+
+/// Create the physical expressions computing the generated columns missing from the schema.
+fn missing_generated_columns(
+    schema: &ArrowSchema,
+    generated_columns: &[GeneratedColumn],
+    state: &SessionState,
+) -> DeltaResult<Vec<(Arc<dyn PhysicalExpr>, Field)>> {
+    let df_schema = DFSchema::try_from(schema.clone())?;
+    generated_columns
+        .iter()
+        .filter(|generated_column| schema.field_with_name(&generated_column.name).is_err())
+        .map(|generated_column| {
+            let expr = generation_expr(generated_column, &df_schema, state)?;
+            let field = Field::new(&generated_column.name, expr.get_type(&df_schema)?, true);
+            let expr = create_physical_expr_fix(expr, &df_schema, state.execution_props())?;
+            Ok((expr, field))
+        })
+        .collect()
+}
+
+/// Append the generated columns that are missing from the output of the plan.
+pub(crate) fn add_missing_generated_columns(
+    plan: Arc<dyn ExecutionPlan>,
+    generated_columns: &[GeneratedColumn],
+    state: &SessionState,
+) -> DeltaResult<Arc<dyn ExecutionPlan>> {
+    let schema = plan.schema();
+    let missing = missing_generated_columns(&schema, generated_columns, state)?;
+    if missing.is_empty() {
+        return Ok(plan);
+    }
+
+    let mut expressions: Vec<(Arc<dyn PhysicalExpr>, String)> = Vec::new();
+    for (i, field) in schema.fields().into_iter().enumerate() {
+        expressions.push((
+            Arc::new(expressions::Column::new(field.name(), i)),
+            field.name().to_owned(),
+        ));
+    }
+    for (expr, field) in missing {
+        expressions.push((expr, field.name().to_owned()));
+    }
+
+    Ok(Arc::new(ProjectionExec::try_new(expressions, plan)?))
+}
+
+/// Append the generated columns that are missing from the record batch.
+pub(crate) fn add_missing_generated_columns_to_batch(
+    batch: RecordBatch,
+    generated_columns: &[GeneratedColumn],
+    state: &SessionState,
+) -> DeltaResult<RecordBatch> {
+    let schema = batch.schema();
+    let missing = missing_generated_columns(&schema, generated_columns, state)?;
+    if missing.is_empty() {
+        return Ok(batch);
+    }
+
+    let mut fields = schema.fields().to_vec();
+    let mut columns = batch.columns().to_vec();
+    for (expr, field) in missing {
+        columns.push(expr.evaluate(&batch)?.into_array(batch.num_rows())?);
+        fields.push(Arc::new(field));
+    }
+
+    Ok(RecordBatch::try_new(
+        Arc::new(ArrowSchema::new_with_metadata(
+            fields,
+            schema.metadata().clone(),
+        )),
+        columns,
+    )?)
+}
+
+/// Create the assignments for all generated columns that are not explicitly assigned.
+///
+/// The generation expressions are evaluated on the assigned values of the columns
+/// they reference, so the generated columns reflect the updated rows. Columns of
+/// the table are qualified with `qualifier` in the assignments.
+pub(crate) fn generated_column_assignments(
+    generated_columns: &[GeneratedColumn],
+    table_schema: &DFSchema,
+    assignments: &HashMap<Column, Expr>,
+    qualifier: Option<&OwnedTableReference>,
+    state: &SessionState,
+) -> DeltaResult<Vec<(Column, Expr)>> {
+    generated_columns
+        .iter()
+        .filter(|generated_column| {
+            !assignments
+                .keys()
+                .any(|column| column.name == generated_column.name)
+        })
+        .map(|generated_column| {
+            let expr = generation_expr(generated_column, table_schema, state)?
+                .transform_up(&|expr| {
+                    Ok(match expr {
+                        Expr::Column(column) => {
+                            let column = Column::new(qualifier.cloned(), column.name);
+                            Transformed::yes(
+                                assignments
+                                    .get(&column)
+                                    .cloned()
+                                    .unwrap_or(Expr::Column(column)),
+                            )
+                        }
+                        _ => Transformed::no(expr),
+                    })
+                })?
+                .data;
+            Ok((
+                Column::new(qualifier.cloned(), &generated_column.name),
+                expr,
+            ))
+        })
+        .collect()
+}
+
+/// Validate that the generation expressions of a schema only reference columns of the schema.
+pub(crate) fn validate_generated_columns(schema: &StructType) -> DeltaResult<()> {
+    let generated_columns = schema.get_generated_columns();
+    let arrow_schema: ArrowSchema = schema.try_into()?;
+    let df_schema = DFSchema::try_from(arrow_schema)?;
+    let state = SessionContext::new().state();
+    for generated_column in generated_columns {
+        generation_expr(&generated_column, &df_schema, &state).map_err(|err| {
+            DeltaTableError::Generic(format!(
+                "Invalid generation expression for column {}: {err}",
+                generated_column.name
+            ))
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::DataType;
+    use arrow_array::{Array, Int32Array, Int64Array};
+    use datafusion_expr::{col, lit};
+
+    fn generated_columns() -> Vec<GeneratedColumn> {
+        vec![GeneratedColumn::new("b", &DataType::LONG, "a * 2")]
+    }
+
+    #[test]
+    fn test_add_missing_generated_columns_to_batch() {
+        let state = SessionContext::new().state();
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "a",
+            ArrowDataType::Int32,
+            true,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from(vec![Some(1), None]))],
+        )
+        .unwrap();
+
+        let batch =
+            add_missing_generated_columns_to_batch(batch, &generated_columns(), &state).unwrap();
+        let generated = batch
+            .column_by_name("b")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(generated.value(0), 2);
+        assert!(generated.is_null(1));
+
+        // columns that are provided are left untouched
+        let unchanged =
+            add_missing_generated_columns_to_batch(batch.clone(), &generated_columns(), &state)
+                .unwrap();
+        assert_eq!(unchanged, batch);
+    }
+
+    #[test]
+    fn test_generated_column_assignments() {
+        let state = SessionContext::new().state();
+        let table_schema = DFSchema::try_from(ArrowSchema::new(vec![
+            Field::new("a", ArrowDataType::Int32, true),
+            Field::new("b", ArrowDataType::Int64, true),
+        ]))
+        .unwrap();
+
+        let assignments = HashMap::from([(Column::from_name("a"), lit(5))]);
+        let generated = generated_column_assignments(
+            &generated_columns(),
+            &table_schema,
+            &assignments,
+            None,
+            &state,
+        )
+        .unwrap();
+        assert_eq!(generated.len(), 1);
+        assert_eq!(generated[0].0, Column::from_name("b"));
+        assert_eq!(
+            generated[0].1,
+            cast(
+                cast(lit(5), ArrowDataType::Int64) * lit(2_i64),
+                ArrowDataType::Int64
+            )
+        );
+
+        // explicitly assigned generated columns are not computed
+        let assignments = HashMap::from([(Column::from_name("b"), col("a"))]);
+        let generated = generated_column_assignments(
+            &generated_columns(),
+            &table_schema,
+            &assignments,
+            None,
+            &state,
+        )
+        .unwrap();
+        assert!(generated.is_empty());
+    }
+}
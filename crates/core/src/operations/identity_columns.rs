@@ -0,0 +1,527 @@
+//! Helpers for generating the values of identity columns
+//!
+//! Identity columns are `LONG` columns whose values are generated from a start value
+//! and a step when they are not provided. The last generated value is stored as the
+//! high water mark in the column metadata, which is updated within the same commit
+//! as the written data. Concurrent writers therefore both update the table metadata,
+//! so all but the first one to commit fail with a conflict instead of handing out
+//! overlapping values.
+
+use std::sync::{Arc, Mutex};
+
+use arrow::compute::cast;
+use arrow_array::{Array, ArrayRef, Int64Array, RecordBatch};
+use arrow_schema::{DataType as ArrowDataType, Field, Schema as ArrowSchema, SchemaRef};
+use datafusion::error::Result as DataFusionResult;
+use datafusion::execution::context::TaskContext;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionPlan, PlanProperties, RecordBatchStream,
+    SendableRecordBatchStream,
+};
+use datafusion_common::{DataFusionError, ScalarValue};
+use datafusion_physical_expr::{expressions, PhysicalExpr};
+use futures::{Stream, StreamExt};
+
+use crate::errors::{DeltaResult, DeltaTableError};
+use crate::kernel::{
+    Action, ColumnMetadataKey, IdentityColumn, Metadata, MetadataValue, StructType,
+};
+
+/// Generates the values of the identity columns of a table.
+///
+/// The generator can be shared between the partitions of a plan, values are
+/// allocated under a lock so every generated value is unique.
+#[derive(Debug, Clone)]
+pub(crate) struct IdentityColumnsGenerator {
+    columns: Vec<IdentityColumn>,
+    /// The next value to generate for each column
+    next_values: Arc<Mutex<Vec<i64>>>,
+}
+
+impl IdentityColumnsGenerator {
+    /// Create a generator for the identity columns of the schema, if there are any.
+    pub(crate) fn try_new(schema: &StructType) -> DeltaResult<Option<Self>> {
+        let columns = schema.get_identity_columns()?;
+        if columns.is_empty() {
+            return Ok(None);
+        }
+        let next_values = columns.iter().map(IdentityColumn::next_value).collect();
+        Ok(Some(Self {
+            columns,
+            next_values: Arc::new(Mutex::new(next_values)),
+        }))
+    }
+
+    /// Fail if values are provided for identity columns that don't allow explicit inserts.
+    pub(crate) fn check_explicit_insert<'a>(
+        &self,
+        provided_columns: impl IntoIterator<Item = &'a str>,
+    ) -> DeltaResult<()> {
+        for provided in provided_columns {
+            if let Some(column) = self
+                .columns
+                .iter()
+                .find(|column| column.name == provided && !column.allow_explicit_insert)
+            {
+                return Err(DeltaTableError::Generic(format!(
+                    "Providing values for identity column {} is not allowed",
+                    column.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fail if any of the updated columns is an identity column.
+    pub(crate) fn check_update<'a>(
+        &self,
+        updated_columns: impl IntoIterator<Item = &'a str>,
+    ) -> DeltaResult<()> {
+        for updated in updated_columns {
+            if self.columns.iter().any(|column| column.name == updated) {
+                return Err(DeltaTableError::Generic(format!(
+                    "Updating identity column {updated} is not allowed"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reserve `count` values for the column at `idx`, returning the first value.
+    fn reserve(&self, idx: usize, count: usize) -> DeltaResult<i64> {
+        let mut next_values = self
+            .next_values
+            .lock()
+            .map_err(|_| DeltaTableError::Generic("Identity column state poisoned".into()))?;
+        let step = self.columns[idx].step;
+        let first = next_values[idx];
+        next_values[idx] = (count as i64)
+            .checked_mul(step)
+            .and_then(|offset| first.checked_add(offset))
+            .ok_or_else(|| {
+                DeltaTableError::Generic(format!(
+                    "Values of identity column {} overflowed",
+                    self.columns[idx].name
+                ))
+            })?;
+        Ok(first)
+    }
+
+    /// Replace the nulls in `array` with generated values.
+    ///
+    /// Fails on explicitly provided values if `check_explicit_insert` is set and
+    /// the column doesn't allow them.
+    fn fill(
+        &self,
+        idx: usize,
+        array: Option<&ArrayRef>,
+        num_rows: usize,
+        check_explicit_insert: bool,
+    ) -> DeltaResult<ArrayRef> {
+        let array = match array {
+            Some(array) => cast(array, &ArrowDataType::Int64)?,
+            None => Arc::new(Int64Array::new_null(num_rows)),
+        };
+        let null_count = array.null_count();
+        if check_explicit_insert && null_count < array.len() {
+            self.check_explicit_insert([self.columns[idx].name.as_str()])?;
+        }
+        if null_count == 0 {
+            return Ok(array);
+        }
+
+        let step = self.columns[idx].step;
+        let mut next = self.reserve(idx, null_count)?;
+        let array = array
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .ok_or_else(|| DeltaTableError::Generic("Expected an Int64 array".into()))?;
+        let filled = array
+            .iter()
+            .map(|value| {
+                value.unwrap_or_else(|| {
+                    let value = next;
+                    next += step;
+                    value
+                })
+            })
+            .collect::<Int64Array>();
+        Ok(Arc::new(filled))
+    }
+
+    /// Generate the values of all identity columns that are null or missing from the batch.
+    ///
+    /// Missing identity columns are appended to the end of the batch. If `check_explicit_insert`
+    /// is set, non-null values of columns that don't allow explicit inserts are rejected.
+    pub(crate) fn fill_batch(
+        &self,
+        batch: RecordBatch,
+        check_explicit_insert: bool,
+    ) -> DeltaResult<RecordBatch> {
+        let schema = batch.schema();
+        let mut fields = schema.fields().to_vec();
+        let mut columns = batch.columns().to_vec();
+        for (idx, identity) in self.columns.iter().enumerate() {
+            match schema.index_of(&identity.name) {
+                Ok(pos) => {
+                    columns[pos] = self.fill(
+                        idx,
+                        Some(&columns[pos]),
+                        batch.num_rows(),
+                        check_explicit_insert,
+                    )?;
+                    fields[pos] = Arc::new(
+                        fields[pos]
+                            .as_ref()
+                            .clone()
+                            .with_data_type(ArrowDataType::Int64)
+                            .with_nullable(false),
+                    );
+                }
+                Err(_) => {
+                    columns.push(self.fill(idx, None, batch.num_rows(), false)?);
+                    fields.push(Arc::new(Field::new(
+                        &identity.name,
+                        ArrowDataType::Int64,
+                        false,
+                    )));
+                }
+            }
+        }
+
+        Ok(RecordBatch::try_new(
+            Arc::new(ArrowSchema::new_with_metadata(
+                fields,
+                schema.metadata().clone(),
+            )),
+            columns,
+        )?)
+    }
+
+    /// The high water marks of all columns values were generated for.
+    fn high_water_marks(&self) -> DeltaResult<Vec<(&str, i64)>> {
+        let next_values = self
+            .next_values
+            .lock()
+            .map_err(|_| DeltaTableError::Generic("Identity column state poisoned".into()))?;
+        Ok(self
+            .columns
+            .iter()
+            .zip(next_values.iter())
+            .filter(|(column, next)| column.next_value() != **next)
+            .map(|(column, next)| (column.name.as_str(), next - column.step))
+            .collect())
+    }
+
+    /// Record the high water marks of the generated values in the table metadata.
+    ///
+    /// The metadata action within `actions` is updated if there is one, otherwise
+    /// an updated copy of `metadata` is added to the actions.
+    pub(crate) fn update_high_water_marks(
+        &self,
+        actions: &mut Vec<Action>,
+        metadata: &Metadata,
+    ) -> DeltaResult<()> {
+        let high_water_marks = self.high_water_marks()?;
+        if high_water_marks.is_empty() {
+            return Ok(());
+        }
+
+        let idx = match actions
+            .iter()
+            .position(|action| matches!(action, Action::Metadata(_)))
+        {
+            Some(idx) => idx,
+            None => {
+                actions.push(Action::Metadata(metadata.clone()));
+                actions.len() - 1
+            }
+        };
+        let Action::Metadata(metadata) = &mut actions[idx] else {
+            unreachable!()
+        };
+
+        let schema = metadata.schema()?;
+        let fields = schema.fields().iter().map(|field| {
+            let mut field = field.clone();
+            if let Some((_, high_water_mark)) = high_water_marks
+                .iter()
+                .find(|(name, _)| *name == field.name)
+            {
+                field.metadata.insert(
+                    ColumnMetadataKey::IdentityHighWaterMark
+                        .as_ref()
+                        .to_string(),
+                    MetadataValue::Number(*high_water_mark),
+                );
+            }
+            field
+        });
+        metadata.schema_string = serde_json::to_string(&StructType::new(fields.collect()))?;
+        Ok(())
+    }
+}
+
+/// Generate the values of the identity columns in the output of the plan.
+///
+/// Identity columns that are missing from the output are appended to it.
+pub(crate) fn add_identity_columns(
+    plan: Arc<dyn ExecutionPlan>,
+    generator: IdentityColumnsGenerator,
+    check_explicit_insert: bool,
+) -> DeltaResult<Arc<dyn ExecutionPlan>> {
+    let schema = plan.schema();
+    let missing = generator
+        .columns
+        .iter()
+        .filter(|column| schema.field_with_name(&column.name).is_err())
+        .collect::<Vec<_>>();
+
+    let plan = if missing.is_empty() {
+        plan
+    } else {
+        let mut expressions: Vec<(Arc<dyn PhysicalExpr>, String)> = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                (
+                    Arc::new(expressions::Column::new(field.name(), i)) as Arc<dyn PhysicalExpr>,
+                    field.name().to_owned(),
+                )
+            })
+            .collect();
+        for column in missing {
+            expressions.push((
+                Arc::new(expressions::Literal::new(ScalarValue::Int64(None))),
+                column.name.clone(),
+            ));
+        }
+        Arc::new(ProjectionExec::try_new(expressions, plan)?)
+    };
+
+    Ok(Arc::new(IdentityColumnsExec::new(
+        plan,
+        generator,
+        check_explicit_insert,
+    )))
+}
+
+/// Generates the values of identity columns that are null in the output of its input plan.
+///
+/// All identity columns must be part of the input's schema, see [`add_identity_columns`].
+#[derive(Debug)]
+pub(crate) struct IdentityColumnsExec {
+    input: Arc<dyn ExecutionPlan>,
+    generator: IdentityColumnsGenerator,
+    check_explicit_insert: bool,
+}
+
+impl IdentityColumnsExec {
+    pub(crate) fn new(
+        input: Arc<dyn ExecutionPlan>,
+        generator: IdentityColumnsGenerator,
+        check_explicit_insert: bool,
+    ) -> Self {
+        Self {
+            input,
+            generator,
+            check_explicit_insert,
+        }
+    }
+}
+
+impl DisplayAs for IdentityColumnsExec {
+    fn fmt_as(&self, _: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "IdentityColumnsExec")
+    }
+}
+
+impl ExecutionPlan for IdentityColumnsExec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        self.input.properties()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> DataFusionResult<SendableRecordBatchStream> {
+        Ok(Box::pin(IdentityColumnsStream {
+            schema: self.schema(),
+            input: self.input.execute(partition, context)?,
+            generator: self.generator.clone(),
+            check_explicit_insert: self.check_explicit_insert,
+        }))
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        match children.as_slice() {
+            [input] => Ok(Arc::new(Self::new(
+                input.clone(),
+                self.generator.clone(),
+                self.check_explicit_insert,
+            ))),
+            _ => Err(DataFusionError::External(Box::new(
+                DeltaTableError::Generic("IdentityColumnsExec expects only one child".into()),
+            ))),
+        }
+    }
+}
+
+struct IdentityColumnsStream {
+    schema: SchemaRef,
+    input: SendableRecordBatchStream,
+    generator: IdentityColumnsGenerator,
+    check_explicit_insert: bool,
+}
+
+impl Stream for IdentityColumnsStream {
+    type Item = DataFusionResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.input.poll_next_unpin(cx).map(|x| match x {
+            Some(Ok(batch)) => Some(
+                self.generator
+                    .fill_batch(batch, self.check_explicit_insert)
+                    .and_then(|batch| Ok(batch.with_schema(self.schema.clone())?))
+                    .map_err(|err| DataFusionError::External(Box::new(err))),
+            ),
+            other => other,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl RecordBatchStream for IdentityColumnsStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::{DataType, StructField};
+
+    fn schema(high_water_mark: Option<i64>) -> StructType {
+        let mut metadata = vec![
+            (
+                ColumnMetadataKey::IdentityStart.as_ref(),
+                MetadataValue::from(10),
+            ),
+            (
+                ColumnMetadataKey::IdentityStep.as_ref(),
+                MetadataValue::from(5),
+            ),
+        ];
+        if let Some(high_water_mark) = high_water_mark {
+            metadata.push((
+                ColumnMetadataKey::IdentityHighWaterMark.as_ref(),
+                MetadataValue::from(high_water_mark),
+            ));
+        }
+        StructType::new(vec![
+            StructField::new("id", DataType::LONG, false).with_metadata(metadata),
+            StructField::new("value", DataType::STRING, true),
+        ])
+    }
+
+    #[test]
+    fn test_fill_batch() {
+        let generator = IdentityColumnsGenerator::try_new(&schema(None))
+            .unwrap()
+            .unwrap();
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![Field::new(
+                "id",
+                ArrowDataType::Int64,
+                true,
+            )])),
+            vec![Arc::new(Int64Array::from(vec![None, Some(1), None]))],
+        )
+        .unwrap();
+        assert!(generator.fill_batch(batch.clone(), true).is_err());
+        let batch = generator.fill_batch(batch, false).unwrap();
+        assert_eq!(
+            batch.column(0).as_ref(),
+            &Int64Array::from(vec![10, 1, 15]) as &dyn Array
+        );
+
+        // missing columns are appended
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![Field::new(
+                "value",
+                ArrowDataType::Utf8,
+                true,
+            )])),
+            vec![Arc::new(arrow_array::StringArray::from(vec!["a", "b"]))],
+        )
+        .unwrap();
+        let batch = generator.fill_batch(batch, false).unwrap();
+        assert_eq!(batch.schema().field(1).name(), "id");
+        assert_eq!(
+            batch.column(1).as_ref(),
+            &Int64Array::from(vec![20, 25]) as &dyn Array
+        );
+    }
+
+    #[test]
+    fn test_update_high_water_marks() {
+        let generator = IdentityColumnsGenerator::try_new(&schema(Some(20)))
+            .unwrap()
+            .unwrap();
+        let metadata =
+            Metadata::try_new(schema(Some(20)), Vec::<String>::new(), Default::default()).unwrap();
+
+        // nothing was generated yet
+        let mut actions = Vec::new();
+        generator
+            .update_high_water_marks(&mut actions, &metadata)
+            .unwrap();
+        assert!(actions.is_empty());
+
+        assert_eq!(generator.reserve(0, 3).unwrap(), 25);
+        generator
+            .update_high_water_marks(&mut actions, &metadata)
+            .unwrap();
+        let Action::Metadata(updated) = &actions[0] else {
+            panic!("Expected a metadata action")
+        };
+        let identity_columns = updated.schema().unwrap().get_identity_columns().unwrap();
+        assert_eq!(identity_columns[0].high_water_mark, Some(35));
+    }
+
+    #[test]
+    fn test_check_explicit_insert() {
+        let generator = IdentityColumnsGenerator::try_new(&schema(None))
+            .unwrap()
+            .unwrap();
+        assert!(generator.check_explicit_insert(["value"]).is_ok());
+        assert!(generator.check_explicit_insert(["id"]).is_err());
+        assert!(generator.check_update(["value"]).is_ok());
+        assert!(generator.check_update(["id"]).is_err());
+    }
+}
@@ -3,7 +3,7 @@
 use std::sync::Arc;
 use std::time::SystemTime;
 
-use arrow_schema::{ArrowError, Field};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
 use chrono::{DateTime, Utc};
 use datafusion::datasource::file_format::parquet::ParquetFormat;
 use datafusion::datasource::file_format::FileFormat;
@@ -19,9 +19,17 @@ use crate::delta_datafusion::{register_store, DataFusionMixins};
 use crate::errors::DeltaResult;
 use crate::kernel::{Action, Add, AddCDCFile, CommitInfo};
 use crate::logstore::{get_actions, LogStoreRef};
+use crate::operations::transaction::in_commit_timestamp::{
+    in_commit_timestamps_enabled, read_commit_timestamp,
+};
 use crate::table::state::DeltaTableState;
 use crate::DeltaTableError;
 
+/// The timestamp of a commit, preferring the in-commit timestamp over the wall clock time
+fn commit_timestamp(commit_info: &CommitInfo) -> Option<i64> {
+    commit_info.in_commit_timestamp.or(commit_info.timestamp)
+}
+
 /// Builder for create a read of change data feeds for delta tables
 #[derive(Clone)]
 pub struct CdfLoadBuilder {
@@ -39,6 +47,8 @@ pub struct CdfLoadBuilder {
     ending_timestamp: Option<DateTime<Utc>>,
     /// Provided Datafusion context
     ctx: SessionContext,
+    /// Read the data files added in commits without change data files
+    read_add_files: bool,
 }
 
 impl CdfLoadBuilder {
@@ -52,6 +62,7 @@ impl CdfLoadBuilder {
             starting_timestamp: None,
             ending_timestamp: None,
             ctx: SessionContext::new(),
+            read_add_files: true,
         }
     }
 
@@ -73,6 +84,48 @@ impl CdfLoadBuilder {
         self
     }
 
+    /// Skip the data files added in commits without change data files, which only
+    /// contribute `insert` changes
+    pub(crate) fn with_add_files(mut self, read_add_files: bool) -> Self {
+        self.read_add_files = read_add_files;
+        self
+    }
+
+    /// Version the read starts at
+    pub(crate) fn starting_version(&self) -> i64 {
+        self.starting_version
+    }
+
+    /// Resolve the version the read ends at, the latest table version if none was provided
+    pub(crate) async fn resolve_ending_version(&self) -> DeltaResult<i64> {
+        match self.ending_version {
+            Some(version) => Ok(version),
+            None => {
+                self.log_store
+                    .get_latest_version(self.starting_version)
+                    .await
+            }
+        }
+    }
+
+    /// The schema of the change data produced by the scan
+    pub(crate) fn cdf_schema(&self) -> DeltaResult<SchemaRef> {
+        let partition_columns = &self.snapshot.metadata().partition_columns;
+        let schema = self.snapshot.arrow_schema()?;
+        let mut fields: Vec<Field> = schema
+            .fields()
+            .iter()
+            .filter(|f| !partition_columns.contains(f.name()))
+            .map(|f| f.as_ref().clone())
+            .collect();
+        fields.push(Field::new(CHANGE_TYPE_COL, DataType::Utf8, true));
+        fields.extend(CDC_PARTITION_SCHEMA.iter().cloned());
+        for name in partition_columns {
+            fields.push(schema.field_with_name(name)?.to_owned());
+        }
+        Ok(Arc::new(Schema::new(fields)))
+    }
+
     /// Timestamp (inclusive) to end at
     pub fn with_ending_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
         self.ending_timestamp = Some(timestamp);
@@ -93,9 +146,7 @@ impl CdfLoadBuilder {
         &self,
     ) -> DeltaResult<(Vec<CdcDataSpec<AddCDCFile>>, Vec<CdcDataSpec<Add>>)> {
         let start = self.starting_version;
-        let end = self
-            .ending_version
-            .unwrap_or(self.log_store.get_latest_version(start).await?);
+        let end = self.resolve_ending_version().await?;
 
         if end < start {
             return Err(DeltaTableError::ChangeDataInvalidVersionRange { start, end });
@@ -113,10 +164,13 @@ impl CdfLoadBuilder {
         );
         log::debug!("starting version = {}, ending version = {:?}", start, end);
 
+        let (first, last) = self.in_commit_timestamp_range(start, end).await?;
+        log::debug!("reading versions {} to {}", first, last);
+
         let mut change_files = vec![];
         let mut add_files = vec![];
 
-        for version in start..=end {
+        for version in first..=last {
             let snapshot_bytes = self
                 .log_store
                 .read_commit_entry(version)
@@ -131,12 +185,12 @@ impl CdfLoadBuilder {
                 let version_commit = version_actions
                     .iter()
                     .find(|a| matches!(a, Action::CommitInfo(_)));
-                if let Some(Action::CommitInfo(CommitInfo {
-                    timestamp: Some(t), ..
-                })) = version_commit
-                {
-                    if starting_timestamp.timestamp_millis() > *t
-                        || *t > ending_timestamp.timestamp_millis()
+                if let Some(t) = version_commit.and_then(|a| match a {
+                    Action::CommitInfo(ci) => commit_timestamp(ci),
+                    _ => None,
+                }) {
+                    if starting_timestamp.timestamp_millis() > t
+                        || t > ending_timestamp.timestamp_millis()
                     {
                         log::debug!("Version: {} skipped, due to commit timestamp", version);
                         continue;
@@ -166,7 +220,7 @@ impl CdfLoadBuilder {
                         };
                     }
                     Action::CommitInfo(ci) => {
-                        ts = ci.timestamp.unwrap_or(0);
+                        ts = commit_timestamp(ci).unwrap_or(0);
                     }
                     _ => {}
                 }
@@ -179,7 +233,7 @@ impl CdfLoadBuilder {
                     version
                 );
                 change_files.push(CdcDataSpec::new(version, ts, cdc_actions))
-            } else {
+            } else if self.read_add_files {
                 let add_actions = version_actions
                     .iter()
                     .filter_map(|a| match a {
@@ -202,6 +256,63 @@ impl CdfLoadBuilder {
         Ok((change_files, add_files))
     }
 
+    /// Narrow the range of versions to read to the commits within the requested timestamps
+    ///
+    /// Commits written with in-commit timestamps enabled have monotonically increasing
+    /// timestamps, so the bounds are found by binary search instead of reading every commit.
+    async fn in_commit_timestamp_range(&self, start: i64, end: i64) -> DeltaResult<(i64, i64)> {
+        let config = self.snapshot.table_config();
+        if !in_commit_timestamps_enabled(self.snapshot.protocol(), &config) {
+            return Ok((start, end));
+        }
+        let low = start.max(config.in_commit_timestamp_enablement_version().unwrap_or(0));
+        if low > end {
+            return Ok((start, end));
+        }
+
+        let mut first = start;
+        if let Some(timestamp) = self.starting_timestamp {
+            let timestamp = timestamp.timestamp_millis();
+            let version = self
+                .first_version_where(low, end, |ts| ts >= timestamp)
+                .await?;
+            // commits before the enablement can only be filtered by their commit info
+            if version > low || low == start {
+                first = version;
+            }
+        }
+        let mut last = end;
+        if let Some(timestamp) = self.ending_timestamp {
+            let timestamp = timestamp.timestamp_millis();
+            last = self
+                .first_version_where(low, end, |ts| ts > timestamp)
+                .await?
+                - 1;
+        }
+        Ok((first, last))
+    }
+
+    /// Find the first version in `low..=high` whose commit timestamp matches the predicate,
+    /// or `high + 1` if there is none.
+    async fn first_version_where(
+        &self,
+        mut low: i64,
+        mut high: i64,
+        predicate: impl Fn(i64) -> bool,
+    ) -> DeltaResult<i64> {
+        let mut found = high + 1;
+        while low <= high {
+            let pivot = low + (high - low) / 2;
+            if predicate(read_commit_timestamp(self.log_store.as_ref(), pivot).await?) {
+                found = pivot;
+                high = pivot - 1;
+            } else {
+                low = pivot + 1;
+            }
+        }
+        Ok(found)
+    }
+
     #[inline]
     fn get_add_action_type() -> Option<ScalarValue> {
         Some(ScalarValue::Utf8(Some(String::from("insert"))))
@@ -217,13 +328,11 @@ impl CdfLoadBuilder {
 
         let partition_values = self.snapshot.metadata().partition_columns.clone();
         let schema = self.snapshot.arrow_schema()?;
-        let schema_fields: Vec<Field> = self
-            .snapshot
-            .arrow_schema()?
-            .all_fields()
-            .into_iter()
+        let schema_fields: Vec<Field> = schema
+            .fields()
+            .iter()
             .filter(|f| !partition_values.contains(f.name()))
-            .cloned()
+            .map(|f| f.as_ref().clone())
             .collect();
 
         let this_partition_values = partition_values
@@ -310,7 +419,10 @@ mod tests {
     use datafusion::prelude::SessionContext;
     use datafusion_common::assert_batches_sorted_eq;
 
+    use datafusion::physical_plan::displayable;
+
     use crate::delta_datafusion::cdf::DeltaCdfScan;
+    use crate::delta_datafusion::DeltaSessionContext;
     use crate::operations::collect_sendable_stream;
     use crate::writer::test_utils::TestResult;
     use crate::DeltaOps;
@@ -477,6 +589,201 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_table_changes_function() -> TestResult {
+        let ctx: SessionContext = DeltaSessionContext::default().into();
+        let table = crate::open_table("../test/tests/data/cdf-table").await?;
+        ctx.register_table("cdf_table", Arc::new(table))?;
+
+        let batches = ctx
+            .sql(
+                "SELECT id, name, _change_type, _commit_version \
+                 FROM table_changes('cdf_table', 1, 2) \
+                 WHERE _change_type = 'update_postimage' AND _commit_version > 1",
+            )
+            .await?
+            .collect()
+            .await?;
+        assert_batches_sorted_eq! {
+            ["+----+--------+------------------+-----------------+",
+             "| id | name   | _change_type     | _commit_version |",
+             "+----+--------+------------------+-----------------+",
+             "| 5  | Emily  | update_postimage | 2               |",
+             "| 6  | Carl   | update_postimage | 2               |",
+             "| 7  | Dennis | update_postimage | 2               |",
+             "+----+--------+------------------+-----------------+"],
+            &batches
+        }
+
+        let batches = ctx
+            .sql(
+                "SELECT id, _change_type FROM table_changes(cdf_table, 0, '2023-12-22T17:10:20Z') \
+                 WHERE id < 3",
+            )
+            .await?
+            .collect()
+            .await?;
+        assert_batches_sorted_eq! {
+            ["+----+--------------+",
+             "| id | _change_type |",
+             "+----+--------------+",
+             "| 1  | insert       |",
+             "| 2  | insert       |",
+             "+----+--------------+"],
+            &batches
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_table_changes_filters_prune_commits() -> TestResult {
+        let ctx: SessionContext = DeltaSessionContext::default().into();
+        let table = crate::open_table("../test/tests/data/cdf-table").await?;
+        ctx.register_table("cdf_table", Arc::new(table))?;
+
+        let plan = ctx
+            .sql("SELECT * FROM table_changes('cdf_table', 0) WHERE _commit_version > 3")
+            .await?
+            .create_physical_plan()
+            .await?;
+        let plan = format!("{}", displayable(plan.as_ref()).indent(false));
+        assert!(plan.contains("EmptyExec"), "{plan}");
+
+        // no version is greater than the largest version, instead of wrapping around
+        let plan = ctx
+            .sql(
+                "SELECT * FROM table_changes('cdf_table', 0) \
+                 WHERE _commit_version > 9223372036854775807",
+            )
+            .await?
+            .create_physical_plan()
+            .await?;
+        let plan = format!("{}", displayable(plan.as_ref()).indent(false));
+        assert!(plan.contains("EmptyExec"), "{plan}");
+
+        // Only the change data files of the last commit are read
+        let batches = ctx
+            .sql(
+                "SELECT id, _change_type FROM table_changes('cdf_table', 0) \
+                 WHERE _commit_version BETWEEN 3 AND 10 AND _change_type IN ('delete', 'insert')",
+            )
+            .await?
+            .collect()
+            .await?;
+        assert_batches_sorted_eq! {
+            ["+----+--------------+",
+             "| id | _change_type |",
+             "+----+--------------+",
+             "| 7  | delete       |",
+             "+----+--------------+"],
+            &batches
+        }
+
+        let result = ctx.sql("SELECT * FROM table_changes('missing', 0)").await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    /// A schema loading its only table asynchronously, as a remote catalog would
+    struct AsyncSchema(Arc<dyn datafusion::datasource::TableProvider>);
+
+    #[async_trait::async_trait]
+    impl datafusion::catalog::schema::SchemaProvider for AsyncSchema {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn table_names(&self) -> Vec<String> {
+            vec!["cdf_table".to_string()]
+        }
+
+        async fn table(
+            &self,
+            name: &str,
+        ) -> datafusion_common::Result<Option<Arc<dyn datafusion::datasource::TableProvider>>>
+        {
+            tokio::task::yield_now().await;
+            Ok((name == "cdf_table").then(|| self.0.clone()))
+        }
+
+        fn table_exist(&self, name: &str) -> bool {
+            name == "cdf_table"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_table_changes_async_schema() -> TestResult {
+        let ctx: SessionContext = DeltaSessionContext::default().into();
+        let table = crate::open_table("../test/tests/data/cdf-table").await?;
+        ctx.catalog("datafusion")
+            .unwrap()
+            .register_schema("remote", Arc::new(AsyncSchema(Arc::new(table))))?;
+
+        let err = ctx
+            .sql("SELECT * FROM table_changes('remote.cdf_table', 0)")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("asynchronously"), "{err}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_in_commit_timestamp_range() -> TestResult {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, true)]));
+        let mut table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![crate::kernel::StructField::new(
+                "id",
+                crate::kernel::DataType::INTEGER,
+                true,
+            )])
+            .with_configuration_property(crate::DeltaConfigKey::EnableChangeDataFeed, Some("true"))
+            .with_configuration_property(
+                crate::DeltaConfigKey::EnableInCommitTimestamps,
+                Some("true"),
+            )
+            .await?;
+        for id in 1..=3 {
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(arrow_array::Int32Array::from(vec![id]))],
+            )?;
+            table = DeltaOps(table).write(vec![batch]).await?;
+        }
+
+        let commit_ts = read_commit_timestamp(table.log_store().as_ref(), 2).await?;
+        let datetime = DateTime::from_timestamp_millis(commit_ts).unwrap();
+
+        let ctx = SessionContext::new();
+        let builder = DeltaOps(table)
+            .load_cdf()
+            .with_session_ctx(ctx.clone())
+            .with_starting_timestamp(datetime)
+            .with_ending_timestamp(datetime);
+        let (first, last) = builder.in_commit_timestamp_range(0, 3).await?;
+        assert_eq!((first, last), (2, 2));
+
+        let scan = builder.build().await?;
+        let batches = collect_batches(
+            scan.properties().output_partitioning().partition_count(),
+            scan,
+            ctx,
+        )
+        .await?;
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches)?;
+        assert_eq!(batch.num_rows(), 1);
+        let commit_timestamps = arrow::compute::cast(
+            batch.column_by_name("_commit_timestamp").unwrap(),
+            &DataType::Int64,
+        )?;
+        let commit_timestamps = commit_timestamps
+            .as_any()
+            .downcast_ref::<arrow_array::Int64Array>()
+            .unwrap();
+        assert_eq!(commit_timestamps.value(0), commit_ts);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_load_bad_version_range() -> TestResult {
         let table = DeltaOps::try_from_uri("../test/tests/data/cdf-table-non-partitioned")
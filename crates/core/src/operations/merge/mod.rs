@@ -61,20 +61,31 @@ use serde::Serialize;
 
 use self::barrier::{MergeBarrier, MergeBarrierExec};
 
+use super::cdc::{
+    should_write_cdc, CHANGE_TYPE_DELETE, CHANGE_TYPE_INSERT, CHANGE_TYPE_UPDATE_POSTIMAGE,
+    CHANGE_TYPE_UPDATE_PREIMAGE,
+};
 use super::datafusion_utils::{into_expr, maybe_into_expr, Expression};
+use super::deletion_vector::{use_deletion_vectors, write_deletion_vectors};
+use super::generated_columns::generated_column_assignments;
+use super::identity_columns::{add_identity_columns, IdentityColumnsGenerator};
 use super::transaction::{CommitProperties, PROTOCOL};
+use crate::delta_datafusion::cdf::CHANGE_TYPE_COL;
+use crate::delta_datafusion::deletion_vector::{
+    find_deleted_rows, DeletedRowsCollector, DeletedRowsCollectorExec,
+};
 use crate::delta_datafusion::expr::{fmt_expr_to_sql, parse_predicate_expression};
 use crate::delta_datafusion::logical::MetricObserver;
 use crate::delta_datafusion::physical::{find_metric_node, MetricObserverExec};
 use crate::delta_datafusion::{
-    execute_plan_to_batch, register_store, DeltaColumn, DeltaScanConfigBuilder, DeltaSessionConfig,
-    DeltaTableProvider,
+    execute_plan_to_batch, register_store, DataFusionMixins, DeltaColumn, DeltaScanConfigBuilder,
+    DeltaSessionConfig, DeltaTableProvider,
 };
 use crate::kernel::Action;
 use crate::logstore::LogStoreRef;
 use crate::operations::merge::barrier::find_barrier_node;
 use crate::operations::transaction::CommitBuilder;
-use crate::operations::write::{write_execution_plan, WriterStatsConfig};
+use crate::operations::write::{write_execution_plan, write_execution_plan_cdc, WriterStatsConfig};
 use crate::protocol::{DeltaOperation, MergePredicate};
 use crate::table::state::DeltaTableState;
 use crate::{DeltaResult, DeltaTable, DeltaTableError};
@@ -90,6 +101,7 @@ pub(crate) const TARGET_INSERT_COLUMN: &str = "__delta_rs_target_insert";
 pub(crate) const TARGET_UPDATE_COLUMN: &str = "__delta_rs_target_update";
 pub(crate) const TARGET_DELETE_COLUMN: &str = "__delta_rs_target_delete";
 pub(crate) const TARGET_COPY_COLUMN: &str = "__delta_rs_target_copy";
+const TARGET_MODIFIED_COLUMN: &str = "__delta_rs_target_modified";
 
 const SOURCE_COUNT_METRIC: &str = "num_source_rows";
 const TARGET_COUNT_METRIC: &str = "num_target_rows";
@@ -566,6 +578,8 @@ pub struct MergeMetrics {
     pub num_target_files_added: usize,
     /// Number of files removed from the sink(target)
     pub num_target_files_removed: usize,
+    /// Number of files in the sink(target) that were re-added with a deletion vector
+    pub num_target_deletion_vectors_added: usize,
     /// Time taken to execute the entire operation
     pub execution_time_ms: u64,
     /// Time taken to scan the files for matches
@@ -653,6 +667,15 @@ impl ExtensionPlanner for MergeMetricExtensionPlanner {
             }
         }
 
+        if let Some(collector) = node.as_any().downcast_ref::<DeletedRowsCollector>() {
+            return Ok(Some(Arc::new(DeletedRowsCollectorExec::new(
+                physical_inputs.first().unwrap().clone(),
+                collector.file_column.clone(),
+                collector.row_index_column.clone(),
+                collector.predicate_column.clone(),
+            ))));
+        }
+
         if let Some(barrier) = node.as_any().downcast_ref::<MergeBarrier>() {
             let schema = barrier.input.schema();
             return Ok(Some(Arc::new(MergeBarrierExec::new(
@@ -984,8 +1007,12 @@ async fn execute(
         }),
     });
 
+    // With deletion vectors enabled target records are not copied, instead modified
+    // records are marked as deleted in the files they are contained in.
+    let deletion_vectors = use_deletion_vectors(&snapshot);
     let scan_config = DeltaScanConfigBuilder::default()
         .with_file_column(true)
+        .with_row_index_column(deletion_vectors)
         .with_parquet_pushdown(false)
         .build(&snapshot)?;
 
@@ -1186,6 +1213,44 @@ async fn execute(
 
     let projection = join.with_column(OPERATION_COLUMN, case)?;
 
+    let qualifier = match &target_alias {
+        Some(alias) => Some(TableReference::Bare {
+            table: alias.to_owned().into(),
+        }),
+        None => TableReference::none(),
+    };
+
+    // Values of identity columns are generated for inserted rows and can't be updated
+    let identity_columns = IdentityColumnsGenerator::try_new(snapshot.schema())?;
+    if let Some(generator) = &identity_columns {
+        for (operations, r#type) in ops.iter() {
+            let assigned = operations.keys().map(|column| column.name.as_str());
+            match r#type {
+                OperationType::Insert => generator.check_explicit_insert(assigned)?,
+                OperationType::Update => generator.check_update(assigned)?,
+                _ => {}
+            }
+        }
+    }
+
+    // Generated columns that are not assigned by inserts or updates are computed from the new values
+    let generated_columns = snapshot.schema().get_generated_columns();
+    if !generated_columns.is_empty() {
+        let table_schema: DFSchema = snapshot.input_schema()?.as_ref().clone().try_into()?;
+        for (operations, r#type) in ops.iter_mut() {
+            if matches!(r#type, OperationType::Insert | OperationType::Update) {
+                let generated = generated_column_assignments(
+                    &generated_columns,
+                    &table_schema,
+                    operations,
+                    qualifier.as_ref(),
+                    &state,
+                )?;
+                operations.extend(generated);
+            }
+        }
+    }
+
     let mut new_columns = vec![];
     let mut write_projection = Vec::new();
 
@@ -1193,12 +1258,6 @@ async fn execute(
         let mut when_expr = Vec::with_capacity(operations_size);
         let mut then_expr = Vec::with_capacity(operations_size);
 
-        let qualifier = match &target_alias {
-            Some(alias) => Some(TableReference::Bare {
-                table: alias.to_owned().into(),
-            }),
-            None => TableReference::none(),
-        };
         let name = delta_field.name();
         let column = Column::new(qualifier.clone(), name);
 
@@ -1241,6 +1300,9 @@ async fn execute(
     let mut copy_when = Vec::with_capacity(ops.len());
     let mut copy_then = Vec::with_capacity(ops.len());
 
+    let mut modified_when = Vec::with_capacity(ops.len());
+    let mut modified_then = Vec::with_capacity(ops.len());
+
     for (idx, (_operations, r#type)) in ops.iter().enumerate() {
         let op = idx as i32;
 
@@ -1287,6 +1349,13 @@ async fn execute(
             )
             .otherwise(lit(false))?,
         );
+
+        // Used to indicate the target record must be removed from its file
+        modified_when.push(lit(op));
+        modified_then.push(lit(matches!(
+            r#type,
+            OperationType::Update | OperationType::Delete
+        )));
     }
 
     fn build_case(when: Vec<Expr>, then: Vec<Expr>) -> DataFusionResult<Expr> {
@@ -1319,6 +1388,12 @@ async fn execute(
         TARGET_COPY_COLUMN.to_owned(),
         build_case(copy_when, copy_then)?,
     ));
+    if deletion_vectors {
+        new_columns.push((
+            TARGET_MODIFIED_COLUMN.to_owned(),
+            build_case(modified_when, modified_then)?,
+        ));
+    }
 
     let new_columns = {
         let plan = projection.into_unoptimized_plan();
@@ -1334,15 +1409,26 @@ async fn execute(
         LogicalPlanBuilder::from(plan).project(fields)?.build()?
     };
 
-    let distrbute_expr = col(file_column.as_str());
-
-    let merge_barrier = LogicalPlan::Extension(Extension {
-        node: Arc::new(MergeBarrier {
-            input: new_columns,
-            expr: distrbute_expr,
-            file_column,
+    let merge_barrier = match &scan_config.row_index_column_name {
+        Some(row_index_column) => LogicalPlan::Extension(Extension {
+            node: Arc::new(DeletedRowsCollector {
+                input: new_columns,
+                file_column,
+                row_index_column: Arc::new(row_index_column.clone()),
+                predicate_column: Some(Arc::new(TARGET_MODIFIED_COLUMN.to_owned())),
+            }),
         }),
-    });
+        None => {
+            let distrbute_expr = col(file_column.as_str());
+            LogicalPlan::Extension(Extension {
+                node: Arc::new(MergeBarrier {
+                    input: new_columns,
+                    expr: distrbute_expr,
+                    file_column,
+                }),
+            })
+        }
+    };
 
     let operation_count = LogicalPlan::Extension(Extension {
         node: Arc::new(MetricObserver {
@@ -1353,17 +1439,79 @@ async fn execute(
     });
 
     let operation_count = DataFrame::new(state.clone(), operation_count);
-    let filtered = operation_count.filter(col(DELETE_COLUMN).is_false())?;
+    let filtered = if deletion_vectors {
+        // Unmodified target records remain in their files
+        operation_count.clone().filter(
+            col(DELETE_COLUMN)
+                .is_false()
+                .and(col(TARGET_COPY_COLUMN).is_not_null()),
+        )?
+    } else {
+        operation_count
+            .clone()
+            .filter(col(DELETE_COLUMN).is_false())?
+    };
+
+    // Deleted and updated target records contribute their previous values to the change
+    // data, while updated and inserted records contribute their new values. Values of identity
+    // columns are only generated for the written records.
+    let change_data = if should_write_cdc(&snapshot) {
+        let mut target_image = Vec::new();
+        let mut new_image = Vec::new();
+        for field in snapshot.input_schema()?.fields() {
+            let name = field.name();
+            target_image.push(col(Column::new(qualifier.clone(), name)).alias(name));
+            new_image.push(col(Column::from_name("__delta_rs_c_".to_owned() + name)).alias(name));
+        }
+        target_image.push(
+            when(col(TARGET_DELETE_COLUMN).is_null(), lit(CHANGE_TYPE_DELETE))
+                .otherwise(lit(CHANGE_TYPE_UPDATE_PREIMAGE))?
+                .alias(CHANGE_TYPE_COL),
+        );
+        new_image.push(
+            when(col(TARGET_INSERT_COLUMN).is_null(), lit(CHANGE_TYPE_INSERT))
+                .otherwise(lit(CHANGE_TYPE_UPDATE_POSTIMAGE))?
+                .alias(CHANGE_TYPE_COL),
+        );
+
+        let target_image = operation_count
+            .clone()
+            .filter(
+                col(TARGET_DELETE_COLUMN)
+                    .is_null()
+                    .or(col(TARGET_UPDATE_COLUMN).is_null()),
+            )?
+            .select(target_image)?;
+        let new_image = operation_count
+            .clone()
+            .filter(
+                col(TARGET_INSERT_COLUMN)
+                    .is_null()
+                    .or(col(TARGET_UPDATE_COLUMN).is_null()),
+            )?
+            .select(new_image)?;
+        Some(target_image.union(new_image)?)
+    } else {
+        None
+    };
 
     let project = filtered.select(write_projection)?;
     let merge_final = &project.into_unoptimized_plan();
 
     let write = state.create_physical_plan(merge_final).await?;
+    let write = match &identity_columns {
+        Some(generator) => add_identity_columns(write, generator.clone(), false)?,
+        None => write,
+    };
 
     let err = || DeltaTableError::Generic("Unable to locate expected metric node".into());
     let source_count = find_metric_node(SOURCE_COUNT_ID, &write).ok_or_else(err)?;
     let op_count = find_metric_node(OUTPUT_COUNT_ID, &write).ok_or_else(err)?;
-    let barrier = find_barrier_node(&write).ok_or_else(err)?;
+    let (barrier, deleted_rows) = if deletion_vectors {
+        (None, Some(find_deleted_rows(&write).ok_or_else(err)?))
+    } else {
+        (Some(find_barrier_node(&write).ok_or_else(err)?), None)
+    };
 
     // write projected records
     let table_partition_cols = current_metadata.partition_columns.clone();
@@ -1385,25 +1533,48 @@ async fn execute(
         log_store.object_store(),
         Some(snapshot.table_config().target_file_size() as usize),
         None,
-        writer_properties,
+        writer_properties.clone(),
         safe_cast,
         None,
-        writer_stats_config,
+        writer_stats_config.clone(),
     )
     .await?;
 
+    let change_data = match change_data {
+        Some(change_data) => {
+            let change_data = state
+                .create_physical_plan(&change_data.into_unoptimized_plan())
+                .await?;
+            write_execution_plan_cdc(
+                &snapshot,
+                state.clone(),
+                change_data,
+                table_partition_cols.clone(),
+                log_store.object_store(),
+                Some(snapshot.table_config().target_file_size() as usize),
+                None,
+                writer_properties,
+                safe_cast,
+                writer_stats_config,
+            )
+            .await?
+        }
+        None => Vec::new(),
+    };
+
     metrics.rewrite_time_ms = Instant::now().duration_since(rewrite_start).as_millis() as u64;
 
     let mut actions: Vec<Action> = add_actions.clone();
     metrics.num_target_files_added = actions.len();
+    actions.extend(change_data);
 
-    let survivors = barrier
-        .as_any()
-        .downcast_ref::<MergeBarrierExec>()
-        .unwrap()
-        .survivors();
+    if let Some(barrier) = barrier {
+        let survivors = barrier
+            .as_any()
+            .downcast_ref::<MergeBarrierExec>()
+            .unwrap()
+            .survivors();
 
-    {
         let lock = survivors.lock().unwrap();
         for action in snapshot.log_data() {
             if lock.contains(action.path().as_ref()) {
@@ -1413,6 +1584,16 @@ async fn execute(
         }
     }
 
+    if let Some(deleted_rows) = deleted_rows {
+        let deleted_rows = std::mem::take(&mut *deleted_rows.lock().unwrap());
+        let result =
+            write_deletion_vectors(log_store.clone(), snapshot.file_actions()?, deleted_rows)
+                .await?;
+        metrics.num_target_files_removed = result.num_removed_files;
+        metrics.num_target_deletion_vectors_added = result.num_deletion_vectors_added;
+        actions.extend(result.actions);
+    }
+
     let source_count_metrics = source_count.metrics().unwrap();
     let target_count_metrics = op_count.metrics().unwrap();
     fn get_metric(metrics: &MetricsSet, name: &str) -> usize {
@@ -1423,7 +1604,11 @@ async fn execute(
     metrics.num_target_rows_inserted = get_metric(&target_count_metrics, TARGET_INSERTED_METRIC);
     metrics.num_target_rows_updated = get_metric(&target_count_metrics, TARGET_UPDATED_METRIC);
     metrics.num_target_rows_deleted = get_metric(&target_count_metrics, TARGET_DELETED_METRIC);
-    metrics.num_target_rows_copied = get_metric(&target_count_metrics, TARGET_COPY_METRIC);
+    metrics.num_target_rows_copied = if deletion_vectors {
+        0
+    } else {
+        get_metric(&target_count_metrics, TARGET_COPY_METRIC)
+    };
     metrics.num_output_rows = metrics.num_target_rows_inserted
         + metrics.num_target_rows_updated
         + metrics.num_target_rows_copied;
@@ -1461,6 +1646,10 @@ async fn execute(
         return Ok((snapshot, metrics));
     }
 
+    if let Some(generator) = &identity_columns {
+        generator.update_high_water_marks(&mut actions, snapshot.metadata())?;
+    }
+
     let commit = CommitBuilder::from(commit_properties)
         .with_actions(actions)
         .build(Some(&snapshot), log_store.clone(), operation)?
@@ -1557,10 +1746,11 @@ mod tests {
     use crate::operations::merge::try_construct_early_filter;
     use crate::operations::DeltaOps;
     use crate::protocol::*;
-    use crate::writer::test_utils::datafusion::get_data;
+    use crate::writer::test_utils::datafusion::{get_cdf_data, get_data};
     use crate::writer::test_utils::get_arrow_schema;
     use crate::writer::test_utils::get_delta_schema;
     use crate::writer::test_utils::setup_table_with_configuration;
+    use crate::writer::test_utils::setup_table_with_deletion_vectors;
     use crate::DeltaConfigKey;
     use crate::DeltaTable;
     use arrow::datatypes::Schema as ArrowSchema;
@@ -1750,6 +1940,308 @@ mod tests {
         assert_merge(table, metrics).await;
     }
 
+    #[tokio::test]
+    async fn test_merge_with_deletion_vectors() {
+        let schema = get_arrow_schema(&None);
+        let table = setup_table_with_deletion_vectors(None).await;
+        let table = write_data(table, &schema).await;
+        assert_eq!(table.version(), 1);
+        let source = merge_source(schema);
+
+        let (table, metrics) = DeltaOps(table)
+            .merge(source, col("target.id").eq(col("source.id")))
+            .with_source_alias("source")
+            .with_target_alias("target")
+            .when_matched_update(|update| {
+                update
+                    .update("value", col("source.value"))
+                    .update("modified", col("source.modified"))
+            })
+            .unwrap()
+            .when_not_matched_by_source_delete(|delete| {
+                delete.predicate(col("target.value").eq(lit(100)))
+            })
+            .unwrap()
+            .when_not_matched_insert(|insert| {
+                insert
+                    .set("id", col("source.id"))
+                    .set("value", col("source.value"))
+                    .set("modified", col("source.modified"))
+            })
+            .unwrap()
+            .await
+            .unwrap();
+
+        assert_eq!(table.version(), 2);
+        assert_eq!(metrics.num_target_files_removed, 0);
+        assert_eq!(metrics.num_target_deletion_vectors_added, 1);
+        assert_eq!(metrics.num_target_rows_copied, 0);
+        assert_eq!(metrics.num_target_rows_updated, 2);
+        assert_eq!(metrics.num_target_rows_inserted, 1);
+        assert_eq!(metrics.num_target_rows_deleted, 1);
+        assert_eq!(metrics.num_output_rows, 3);
+        assert_eq!(metrics.num_source_rows, 3);
+
+        let files = table.snapshot().unwrap().file_actions().unwrap();
+        let deleted = files
+            .iter()
+            .filter_map(|f| f.deletion_vector.as_ref())
+            .map(|dv| dv.cardinality)
+            .collect::<Vec<_>>();
+        assert_eq!(deleted, vec![3]);
+
+        let expected = vec![
+            "+----+-------+------------+",
+            "| id | value | modified   |",
+            "+----+-------+------------+",
+            "| A  | 1     | 2021-02-01 |",
+            "| B  | 10    | 2021-02-02 |",
+            "| C  | 20    | 2023-07-04 |",
+            "| X  | 30    | 2023-07-04 |",
+            "+----+-------+------------+",
+        ];
+        let actual = get_data(&table).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+    }
+
+    #[tokio::test]
+    async fn test_merge_with_change_data_feed() {
+        let schema = get_arrow_schema(&None);
+        let table =
+            setup_table_with_configuration(DeltaConfigKey::EnableChangeDataFeed, Some("true"))
+                .await;
+        let table = write_data(table, &schema).await;
+        assert_eq!(table.version(), 1);
+        let source = merge_source(schema);
+
+        let (table, _) = DeltaOps(table)
+            .merge(source, col("target.id").eq(col("source.id")))
+            .with_source_alias("source")
+            .with_target_alias("target")
+            .when_matched_update(|update| {
+                update
+                    .update("value", col("source.value"))
+                    .update("modified", col("source.modified"))
+            })
+            .unwrap()
+            .when_not_matched_by_source_delete(|delete| {
+                delete.predicate(col("target.value").eq(lit(100)))
+            })
+            .unwrap()
+            .when_not_matched_insert(|insert| {
+                insert
+                    .set("id", col("source.id"))
+                    .set("value", col("source.value"))
+                    .set("modified", col("source.modified"))
+            })
+            .unwrap()
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 2);
+
+        let expected = vec![
+            "+----+-------+------------+------------------+-----------------+",
+            "| id | value | modified   | _change_type     | _commit_version |",
+            "+----+-------+------------+------------------+-----------------+",
+            "| B  | 10    | 2021-02-01 | update_preimage  | 2               |",
+            "| B  | 10    | 2021-02-02 | update_postimage | 2               |",
+            "| C  | 10    | 2021-02-02 | update_preimage  | 2               |",
+            "| C  | 20    | 2023-07-04 | update_postimage | 2               |",
+            "| D  | 100   | 2021-02-02 | delete           | 2               |",
+            "| X  | 30    | 2023-07-04 | insert           | 2               |",
+            "+----+-------+------------+------------------+-----------------+",
+        ];
+        let actual = get_cdf_data(&table, 2).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+    }
+
+    #[tokio::test]
+    async fn test_merge_generated_columns() {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_column("id", DataType::Primitive(PrimitiveType::String), true, None)
+            .with_column(
+                "value",
+                DataType::Primitive(PrimitiveType::Integer),
+                true,
+                None,
+            )
+            .with_generated_column(
+                "doubled",
+                DataType::Primitive(PrimitiveType::Long),
+                true,
+                "value * 2",
+            )
+            .await
+            .unwrap();
+
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", ArrowDataType::Utf8, true),
+            Field::new("value", ArrowDataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(arrow::array::StringArray::from(vec!["A", "B"])),
+                Arc::new(arrow::array::Int32Array::from(vec![1, 2])),
+            ],
+        )
+        .unwrap();
+        let table = DeltaOps(table).write(vec![batch]).await.unwrap();
+
+        let ctx = SessionContext::new();
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(arrow::array::StringArray::from(vec!["B", "C"])),
+                Arc::new(arrow::array::Int32Array::from(vec![20, 30])),
+            ],
+        )
+        .unwrap();
+        let source = ctx.read_batch(batch).unwrap();
+
+        let (table, metrics) = DeltaOps(table)
+            .merge(source, col("target.id").eq(col("source.id")))
+            .with_source_alias("source")
+            .with_target_alias("target")
+            .when_matched_update(|update| update.update("value", col("source.value")))
+            .unwrap()
+            .when_not_matched_insert(|insert| {
+                insert
+                    .set("id", col("source.id"))
+                    .set("value", col("source.value"))
+            })
+            .unwrap()
+            .await
+            .unwrap();
+        assert_eq!(metrics.num_target_rows_updated, 1);
+        assert_eq!(metrics.num_target_rows_inserted, 1);
+
+        let expected = vec![
+            "+----+-------+---------+",
+            "| id | value | doubled |",
+            "+----+-------+---------+",
+            "| A  | 1     | 2       |",
+            "| B  | 20    | 40      |",
+            "| C  | 30    | 60      |",
+            "+----+-------+---------+",
+        ];
+        let actual = get_data(&table).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+    }
+
+    #[tokio::test]
+    async fn test_merge_identity_columns() {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_identity_column("key", 1, 1, false)
+            .with_column("id", DataType::Primitive(PrimitiveType::String), true, None)
+            .with_column(
+                "value",
+                DataType::Primitive(PrimitiveType::Integer),
+                true,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", ArrowDataType::Utf8, true),
+            Field::new("value", ArrowDataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(arrow::array::StringArray::from(vec!["A", "B"])),
+                Arc::new(arrow::array::Int32Array::from(vec![1, 2])),
+            ],
+        )
+        .unwrap();
+        let table = DeltaOps(table).write(vec![batch]).await.unwrap();
+
+        let ctx = SessionContext::new();
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(arrow::array::StringArray::from(vec!["B", "C", "D"])),
+                Arc::new(arrow::array::Int32Array::from(vec![20, 30, 40])),
+            ],
+        )
+        .unwrap();
+        let source = ctx.read_batch(batch).unwrap();
+
+        // identity columns can't be assigned by inserts
+        let result = DeltaOps(table.clone())
+            .merge(source.clone(), col("target.id").eq(col("source.id")))
+            .with_source_alias("source")
+            .with_target_alias("target")
+            .when_not_matched_insert(|insert| {
+                insert
+                    .set("key", lit(100_i64))
+                    .set("id", col("source.id"))
+                    .set("value", col("source.value"))
+            })
+            .unwrap()
+            .await;
+        assert!(result.is_err());
+
+        let (table, metrics) = DeltaOps(table)
+            .merge(source, col("target.id").eq(col("source.id")))
+            .with_source_alias("source")
+            .with_target_alias("target")
+            .when_matched_update(|update| update.update("value", col("source.value")))
+            .unwrap()
+            .when_not_matched_insert(|insert| {
+                insert
+                    .set("id", col("source.id"))
+                    .set("value", col("source.value"))
+            })
+            .unwrap()
+            .await
+            .unwrap();
+        assert_eq!(metrics.num_target_rows_updated, 1);
+        assert_eq!(metrics.num_target_rows_inserted, 2);
+
+        let identity = table.get_schema().unwrap().get_identity_columns().unwrap();
+        assert_eq!(identity[0].high_water_mark, Some(4));
+
+        let ctx = SessionContext::new();
+        ctx.register_table("test", Arc::new(table)).unwrap();
+        let batches = ctx
+            .sql("SELECT key, id, value FROM test WHERE key <= 2")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let expected = vec![
+            "+-----+----+-------+",
+            "| key | id | value |",
+            "+-----+----+-------+",
+            "| 1   | A  | 1     |",
+            "| 2   | B  | 20    |",
+            "+-----+----+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+
+        // inserted rows are assigned unique values
+        let batches = ctx
+            .sql("SELECT count(DISTINCT key) AS keys, min(key) AS min, max(key) AS max FROM test WHERE key > 2")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let expected = vec![
+            "+------+-----+-----+",
+            "| keys | min | max |",
+            "+------+-----+-----+",
+            "| 2    | 3   | 4   |",
+            "+------+-----+-----+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+    }
+
     #[tokio::test]
     async fn test_merge_str() {
         // Validate that users can use string predicates
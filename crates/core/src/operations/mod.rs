@@ -7,22 +7,42 @@
 //! with a [data stream][datafusion::physical_plan::SendableRecordBatchStream],
 //! if the operation returns data as well.
 
+use self::add_columns::AddColumnsBuilder;
+use self::change_column::ChangeColumnBuilder;
+use self::change_column_type::ChangeColumnTypeBuilder;
+use self::clone::CloneBuilder;
 use self::create::CreateBuilder;
+use self::drop_columns::DropColumnsBuilder;
+use self::drop_feature::DropFeatureBuilder;
 use self::filesystem_check::FileSystemCheckBuilder;
+use self::rename_column::RenameColumnBuilder;
+use self::set_tbl_properties::{SetTablePropertiesBuilder, UnsetTablePropertiesBuilder};
+use self::upgrade_protocol::UpgradeProtocolBuilder;
 use self::vacuum::VacuumBuilder;
 use crate::errors::{DeltaResult, DeltaTableError};
+use crate::kernel::WriterFeatures;
 use crate::table::builder::DeltaTableBuilder;
 use crate::DeltaTable;
 use std::collections::HashMap;
 
+pub mod add_columns;
 pub mod cast;
+pub mod change_column;
+pub mod change_column_type;
+pub mod clone;
+mod column_references;
 pub mod convert_to_delta;
 pub mod create;
+pub mod drop_columns;
 pub mod drop_constraints;
+pub mod drop_feature;
 pub mod filesystem_check;
 pub mod optimize;
+pub mod rename_column;
 pub mod restore;
+pub mod set_tbl_properties;
 pub mod transaction;
+pub mod upgrade_protocol;
 pub mod vacuum;
 
 #[cfg(feature = "datafusion")]
@@ -38,11 +58,19 @@ use arrow::record_batch::RecordBatch;
 use optimize::OptimizeBuilder;
 use restore::RestoreBuilder;
 
+#[cfg(feature = "datafusion")]
+mod cdc;
 #[cfg(feature = "datafusion")]
 pub mod constraints;
 #[cfg(feature = "datafusion")]
 pub mod delete;
 #[cfg(feature = "datafusion")]
+mod deletion_vector;
+#[cfg(feature = "datafusion")]
+mod generated_columns;
+#[cfg(feature = "datafusion")]
+mod identity_columns;
+#[cfg(feature = "datafusion")]
 mod load;
 #[cfg(feature = "datafusion")]
 pub mod load_cdf;
@@ -190,6 +218,12 @@ impl DeltaOps {
         RestoreBuilder::new(self.0.log_store, self.0.state.unwrap())
     }
 
+    /// Clone the table to another location, referencing or copying its data files
+    #[must_use]
+    pub fn clone(self) -> CloneBuilder {
+        CloneBuilder::new(self.0.log_store, self.0.state.unwrap())
+    }
+
     /// Update data from Delta table
     #[cfg(feature = "datafusion")]
     #[must_use]
@@ -219,6 +253,70 @@ impl DeltaOps {
     pub fn drop_constraints(self) -> DropConstraintBuilder {
         DropConstraintBuilder::new(self.0.log_store, self.0.state.unwrap())
     }
+
+    /// Rename a column of a table with column mapping enabled
+    #[must_use]
+    pub fn rename_column(self) -> RenameColumnBuilder {
+        RenameColumnBuilder::new(self.0.log_store, self.0.state.unwrap())
+    }
+
+    /// Drop columns from a table with column mapping enabled
+    #[must_use]
+    pub fn drop_columns(self) -> DropColumnsBuilder {
+        DropColumnsBuilder::new(self.0.log_store, self.0.state.unwrap())
+    }
+
+    /// Add columns to a table
+    #[must_use]
+    pub fn add_columns(self) -> AddColumnsBuilder {
+        AddColumnsBuilder::new(self.0.log_store, self.0.state.unwrap())
+    }
+
+    /// Change the comment, metadata or nullability of a column of a table
+    #[must_use]
+    pub fn change_column(self) -> ChangeColumnBuilder {
+        ChangeColumnBuilder::new(self.0.log_store, self.0.state.unwrap())
+    }
+
+    /// Widen the data type of a column of a table with type widening enabled
+    #[must_use]
+    pub fn change_column_type(self) -> ChangeColumnTypeBuilder {
+        ChangeColumnTypeBuilder::new(self.0.log_store, self.0.state.unwrap())
+    }
+
+    /// Drop a feature from the protocol of a table
+    #[must_use]
+    pub fn drop_feature(self) -> DropFeatureBuilder {
+        DropFeatureBuilder::new(self.0.log_store, self.0.state.unwrap())
+    }
+
+    /// Set properties of a table
+    #[must_use]
+    pub fn set_tbl_properties(self) -> SetTablePropertiesBuilder {
+        SetTablePropertiesBuilder::new(self.0.log_store, self.0.state.unwrap())
+    }
+
+    /// Unset properties of a table
+    #[must_use]
+    pub fn unset_tbl_properties(self) -> UnsetTablePropertiesBuilder {
+        UnsetTablePropertiesBuilder::new(self.0.log_store, self.0.state.unwrap())
+    }
+
+    /// Upgrade the protocol of a table
+    #[must_use]
+    pub fn upgrade_protocol(self) -> UpgradeProtocolBuilder {
+        UpgradeProtocolBuilder::new(self.0.log_store, self.0.state.unwrap())
+    }
+
+    /// Enable table features, upgrading the protocol of the table as needed
+    #[must_use]
+    pub fn enable_features<I, F>(self, features: I) -> UpgradeProtocolBuilder
+    where
+        I: IntoIterator<Item = F>,
+        F: Into<WriterFeatures>,
+    {
+        self.upgrade_protocol().with_features(features)
+    }
 }
 
 impl From<DeltaTable> for DeltaOps {
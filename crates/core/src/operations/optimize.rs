@@ -12,6 +12,10 @@
 //! optimized files. Optimize does not delete files from storage. To delete
 //! files that were removed, call `vacuum` on [`DeltaTable`].
 //!
+//! Compaction drops the rows deleted by the deletion vectors of the rewritten files, and
+//! rewrites files with deletion vectors even if there is nothing to compact them with.
+//! Z-order skips files with deletion vectors.
+//!
 //! See [`OptimizeBuilder`] for configuration.
 //!
 //! # Example
@@ -20,13 +24,17 @@
 //! let (table, metrics) = OptimizeBuilder::new(table.object_store(), table.state).await?;
 //! ````
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use arrow::datatypes::SchemaRef as ArrowSchemaRef;
-use arrow_array::RecordBatch;
+use arrow::compute::{cast, filter_record_batch};
+use arrow::datatypes::{Schema as ArrowSchema, SchemaRef as ArrowSchemaRef};
+use arrow_array::cast::AsArray;
+use arrow_array::types::Int64Type;
+use arrow_array::{Array, ArrayRef, BooleanArray, Int64Array, RecordBatch};
+use arrow_schema::{DataType, Field};
 use futures::future::BoxFuture;
 use futures::stream::BoxStream;
 use futures::{Future, StreamExt, TryStreamExt};
@@ -37,17 +45,24 @@ use parquet::arrow::async_reader::{ParquetObjectReader, ParquetRecordBatchStream
 use parquet::basic::{Compression, ZstdLevel};
 use parquet::errors::ParquetError;
 use parquet::file::properties::WriterProperties;
+use roaring::RoaringTreemap;
 use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
 use tracing::debug;
 
+use super::transaction::row_tracking::{
+    MATERIALIZED_ROW_COMMIT_VERSION_COLUMN_NAME, MATERIALIZED_ROW_ID_COLUMN_NAME,
+};
 use super::transaction::PROTOCOL;
 use super::writer::{PartitionWriter, PartitionWriterConfig};
 use crate::errors::{DeltaResult, DeltaTableError};
-use crate::kernel::{Action, PartitionsExt, Remove, Scalar};
+use crate::kernel::arrow::column_mapping::PhysicalMapper;
+use crate::kernel::{Action, DeletionVectorDescriptor, LogicalFile, PartitionsExt, Remove, Scalar};
 use crate::logstore::LogStoreRef;
 use crate::operations::transaction::{CommitBuilder, CommitProperties, DEFAULT_RETRIES};
 use crate::protocol::DeltaOperation;
+use crate::storage::utils::is_absolute_path;
 use crate::storage::ObjectStoreRef;
+use crate::table::builder::ensure_table_uri;
 use crate::table::state::DeltaTableState;
 use crate::writer::utils::arrow_schema_without_partitions;
 use crate::{crate_version, DeltaTable, ObjectMeta, PartitionFilter};
@@ -80,6 +95,9 @@ pub struct Metrics {
     pub total_considered_files: usize,
     /// How many files were considered for optimization but were skipped
     pub total_files_skipped: usize,
+    /// How many of the skipped files were skipped because of their deletion vectors
+    #[serde(default)]
+    pub total_deletion_vector_files_skipped: usize,
     /// The order of records from source files is preserved
     pub preserve_insertion_order: bool,
 }
@@ -179,6 +197,9 @@ pub enum OptimizeType {
     /// Compact files into pre-determined bins
     Compact,
     /// Z-order files based on provided columns
+    ///
+    /// Files with deletion vectors are skipped, see
+    /// [`Metrics::total_deletion_vector_files_skipped`].
     ZOrder(Vec<String>),
 }
 
@@ -232,6 +253,9 @@ impl<'a> OptimizeBuilder<'a> {
     }
 
     /// Choose the type of optimization to perform. Defaults to [OptimizeType::Compact].
+    ///
+    /// Compaction rewrites files with deletion vectors without their deleted rows, while
+    /// Z-order skips these files.
     pub fn with_type(mut self, optimize_type: OptimizeType) -> Self {
         self.optimize_type = optimize_type;
         self
@@ -345,6 +369,7 @@ fn create_remove(
     path: &str,
     partitions: &IndexMap<String, Scalar>,
     size: i64,
+    deletion_vector: Option<DeletionVectorDescriptor>,
 ) -> Result<Action, DeltaTableError> {
     // NOTE unwrap is safe since UNIX_EPOCH will always be earlier then now.
     let deletion_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
@@ -371,7 +396,7 @@ fn create_remove(
                 .collect(),
         ),
         size: Some(size),
-        deletion_vector: None,
+        deletion_vector,
         tags: None,
         base_row_id: None,
         default_row_commit_version: None,
@@ -387,7 +412,8 @@ enum OptimizeOperations {
     /// Plan to compact files into pre-determined bins
     ///
     /// Bins are determined by the bin-packing algorithm to reach an optimal size.
-    /// Files that are large enough already are skipped. Bins of size 1 are dropped.
+    /// Files that are large enough already are skipped. Bins of size 1 are dropped,
+    /// unless the file has a deletion vector.
     Compact(HashMap<String, (IndexMap<String, Scalar>, Vec<MergeBin>)>),
     /// Plan to Z-order each partition
     ZOrder(
@@ -428,6 +454,124 @@ pub struct MergeTaskParameters {
     num_indexed_cols: i32,
     /// Stats columns, specific columns to collect stats from, takes precedence over num_indexed_cols
     stats_columns: Option<Vec<String>>,
+    /// Row tracking information materialized into the rewritten files
+    row_tracking: Option<MaterializedRowTracking>,
+    /// Deletion vectors keyed by file location, the rows they delete are not rewritten
+    deletion_vectors: HashMap<String, DeletionVectorDescriptor>,
+}
+
+/// Row ids and row commit versions are materialized into compacted files,
+/// so rows keep their identity when they are moved to a new file.
+#[derive(Debug)]
+struct MaterializedRowTracking {
+    row_id_column: String,
+    row_commit_version_column: String,
+    /// `baseRowId` and `defaultRowCommitVersion` keyed by file location
+    files: HashMap<String, (Option<i64>, Option<i64>)>,
+}
+
+impl MaterializedRowTracking {
+    fn try_new(snapshot: &DeltaTableState) -> DeltaResult<Option<Self>> {
+        let config = snapshot.table_config();
+        if !config.enable_row_tracking() {
+            return Ok(None);
+        }
+        let column_name = |key: &str| {
+            config.0.get(key).cloned().flatten().ok_or_else(|| {
+                DeltaTableError::Generic(format!(
+                    "Row tracking is enabled, but the table property {key} is not set"
+                ))
+            })
+        };
+        Ok(Some(Self {
+            row_id_column: column_name(MATERIALIZED_ROW_ID_COLUMN_NAME)?,
+            row_commit_version_column: column_name(MATERIALIZED_ROW_COMMIT_VERSION_COLUMN_NAME)?,
+            files: snapshot
+                .snapshot()
+                .files()
+                .map(|file| {
+                    (
+                        file.object_store_path().to_string(),
+                        (file.base_row_id(), file.default_row_commit_version()),
+                    )
+                })
+                .collect(),
+        }))
+    }
+
+    fn fields(&self) -> impl Iterator<Item = Field> + '_ {
+        [&self.row_id_column, &self.row_commit_version_column]
+            .into_iter()
+            .map(|name| Field::new(name, DataType::Int64, true))
+    }
+
+    /// Fill the materialized columns of a batch read from `location`, starting at row `offset`
+    /// of the file. Values already materialized in the file are kept.
+    fn apply(
+        &self,
+        batch: RecordBatch,
+        location: &str,
+        offset: usize,
+    ) -> Result<RecordBatch, ParquetError> {
+        let (base_row_id, default_row_commit_version) =
+            self.files.get(location).copied().unwrap_or_default();
+        let row_ids = Self::fill(&batch, &self.row_id_column, |idx| {
+            base_row_id.map(|base| base + (offset + idx) as i64)
+        })?;
+        let versions = Self::fill(&batch, &self.row_commit_version_column, |_| {
+            default_row_commit_version
+        })?;
+
+        let mut fields = batch.schema().fields().to_vec();
+        let mut columns = batch.columns().to_vec();
+        for (field, values) in self.fields().zip([row_ids, versions]) {
+            match batch.schema().index_of(field.name()) {
+                Ok(idx) => {
+                    fields[idx] = Arc::new(field);
+                    columns[idx] = values;
+                }
+                Err(_) => {
+                    fields.push(Arc::new(field));
+                    columns.push(values);
+                }
+            }
+        }
+        Ok(RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(fields)),
+            columns,
+        )?)
+    }
+
+    fn fill(
+        batch: &RecordBatch,
+        column: &str,
+        value: impl Fn(usize) -> Option<i64>,
+    ) -> Result<ArrayRef, ParquetError> {
+        let existing = batch
+            .column_by_name(column)
+            .map(|arr| cast(arr, &DataType::Int64))
+            .transpose()?;
+        let existing = existing.as_ref().map(|arr| arr.as_primitive::<Int64Type>());
+        let values = (0..batch.num_rows())
+            .map(|idx| match existing {
+                Some(arr) if arr.is_valid(idx) => Some(arr.value(idx)),
+                _ => value(idx),
+            })
+            .collect::<Int64Array>();
+        Ok(Arc::new(values))
+    }
+}
+
+/// Drop the rows deleted by a deletion vector from a batch read from `offset` of the file.
+fn remove_deleted_rows(
+    batch: RecordBatch,
+    deleted_rows: &RoaringTreemap,
+    offset: usize,
+) -> Result<RecordBatch, ParquetError> {
+    let keep = (0..batch.num_rows())
+        .map(|idx| Some(!deleted_rows.contains((offset + idx) as u64)))
+        .collect::<BooleanArray>();
+    Ok(filter_record_batch(&batch, &keep)?)
 }
 
 /// A stream of record batches, with a ParquetError on failure.
@@ -457,6 +601,10 @@ impl MergePlan {
                     file_meta.location.as_ref(),
                     &partition_values,
                     file_meta.size as i64,
+                    task_parameters
+                        .deletion_vectors
+                        .get(file_meta.location.as_ref())
+                        .cloned(),
                 )
             })
             .collect::<Result<Vec<_>, DeltaTableError>>()?;
@@ -657,6 +805,7 @@ impl MergePlan {
         commit_properties: CommitProperties,
     ) -> Result<Metrics, DeltaTableError> {
         let operations = std::mem::take(&mut self.operations);
+        let table_root = ensure_table_uri(log_store.root_uri())?;
 
         let stream = match operations {
             OptimizeOperations::Compact(bins) => futures::stream::iter(bins)
@@ -673,14 +822,52 @@ impl MergePlan {
                         debug!("  file {}", file.location);
                     }
                     let object_store_ref = log_store.object_store();
+                    let task_parameters = self.task_parameters.clone();
+                    let table_root = table_root.clone();
                     let batch_stream = futures::stream::iter(files.clone())
                         .then(move |file| {
                             let object_store_ref = object_store_ref.clone();
+                            let task_parameters = task_parameters.clone();
+                            let table_root = table_root.clone();
                             async move {
+                                let location = file.location.to_string();
+                                let deleted_rows = match task_parameters
+                                    .deletion_vectors
+                                    .get(&location)
+                                {
+                                    Some(dv) => Some(
+                                        dv.read(object_store_ref.as_ref(), &table_root)
+                                            .await
+                                            .map_err(|err| ParquetError::External(Box::new(err)))?,
+                                    ),
+                                    None => None,
+                                };
                                 let file_reader = ParquetObjectReader::new(object_store_ref, file);
-                                ParquetRecordBatchStreamBuilder::new(file_reader)
+                                let stream = ParquetRecordBatchStreamBuilder::new(file_reader)
                                     .await?
-                                    .build()
+                                    .build()?;
+                                if task_parameters.row_tracking.is_none() && deleted_rows.is_none()
+                                {
+                                    return Ok::<ParquetReadStream, ParquetError>(stream.boxed());
+                                }
+                                // row ids are assigned by the position of the row in the file,
+                                // so they are filled in before the deleted rows are dropped
+                                let mut offset = 0;
+                                Ok(stream
+                                    .map(move |batch| {
+                                        let mut batch = batch?;
+                                        let num_rows = batch.num_rows();
+                                        if let Some(row_tracking) = &task_parameters.row_tracking {
+                                            batch = row_tracking.apply(batch, &location, offset)?;
+                                        }
+                                        if let Some(deleted_rows) = &deleted_rows {
+                                            batch =
+                                                remove_deleted_rows(batch, deleted_rows, offset)?;
+                                        }
+                                        offset += num_rows;
+                                        Ok(batch)
+                                    })
+                                    .boxed())
                             }
                         })
                         .try_flatten()
@@ -829,7 +1016,7 @@ pub fn create_merge_plan(
     let target_size = target_size.unwrap_or_else(|| snapshot.table_config().target_file_size());
     let partitions_keys = &snapshot.metadata().partition_columns;
 
-    let (operations, metrics) = match optimize_type {
+    let (mut operations, metrics) = match optimize_type {
         OptimizeType::Compact => build_compaction_plan(snapshot, filters, target_size)?,
         OptimizeType::ZOrder(zorder_columns) => {
             build_zorder_plan(zorder_columns, snapshot, partitions_keys, filters)?
@@ -840,8 +1027,63 @@ pub fn create_merge_plan(
         target_size,
         predicate: serde_json::to_string(filters).ok(),
     };
-    let file_schema =
-        arrow_schema_without_partitions(&Arc::new(snapshot.schema().try_into()?), partitions_keys);
+    let mut stats_columns = snapshot
+        .table_config()
+        .stats_columns()
+        .map(|v| v.iter().map(|v| v.to_string()).collect::<Vec<String>>());
+
+    // Files are rewritten as read from storage, so with column mapping enabled
+    // the physical column names are used.
+    let table_schema: ArrowSchema = snapshot.schema().try_into()?;
+    let file_schema = match PhysicalMapper::try_new(
+        &table_schema,
+        snapshot.schema(),
+        snapshot.table_config().column_mapping_mode(),
+    )? {
+        Some(mapper) => {
+            let physical_partitions_keys = partitions_keys
+                .iter()
+                .map(|name| mapper.physical_name(name))
+                .collect::<DeltaResult<Vec<_>>>()?;
+            stats_columns = stats_columns
+                .map(|columns| {
+                    columns
+                        .iter()
+                        .map(|path| mapper.physical_path(path))
+                        .collect::<DeltaResult<_>>()
+                })
+                .transpose()?;
+            if let OptimizeOperations::ZOrder(zorder_columns, _) = &mut operations {
+                *zorder_columns = zorder_columns
+                    .iter()
+                    .map(|name| mapper.physical_name(name))
+                    .collect::<DeltaResult<_>>()?;
+            }
+            arrow_schema_without_partitions(&mapper.schema(), &physical_partitions_keys)
+        }
+        None => arrow_schema_without_partitions(&Arc::new(table_schema), partitions_keys),
+    };
+
+    let row_tracking = MaterializedRowTracking::try_new(snapshot)?;
+    let deletion_vectors = snapshot
+        .snapshot()
+        .files()
+        .filter_map(|file| {
+            file.deletion_vector()
+                .map(|dv| (file.object_store_path().to_string(), dv.descriptor()))
+        })
+        .collect();
+    let file_schema = match &row_tracking {
+        Some(row_tracking) => {
+            let mut fields = file_schema.fields().to_vec();
+            fields.extend(row_tracking.fields().map(Arc::new));
+            Arc::new(ArrowSchema::new_with_metadata(
+                fields,
+                file_schema.metadata().clone(),
+            ))
+        }
+        None => file_schema,
+    };
 
     Ok(MergePlan {
         operations,
@@ -851,10 +1093,9 @@ pub fn create_merge_plan(
             file_schema,
             writer_properties,
             num_indexed_cols: snapshot.table_config().num_indexed_cols(),
-            stats_columns: snapshot
-                .table_config()
-                .stats_columns()
-                .map(|v| v.iter().map(|v| v.to_string()).collect::<Vec<String>>()),
+            stats_columns,
+            row_tracking,
+            deletion_vectors,
         }),
         read_table_version: snapshot.version(),
     })
@@ -904,6 +1145,14 @@ impl IntoIterator for MergeBin {
     }
 }
 
+/// Whether the file is referenced by an absolute URI, e.g. the files of shallow clones.
+///
+/// Files are read from and removed by their location within the store of the table, so files
+/// located relative to another root are not rewritten.
+fn is_external_file(file: &LogicalFile<'_>) -> DeltaResult<bool> {
+    is_absolute_path(&file.path())
+}
+
 fn build_compaction_plan(
     snapshot: &DeltaTableState,
     filters: &[PartitionFilter],
@@ -913,14 +1162,23 @@ fn build_compaction_plan(
 
     let mut partition_files: HashMap<String, (IndexMap<String, Scalar>, Vec<ObjectMeta>)> =
         HashMap::new();
+    // files with deletion vectors are rewritten without the deleted rows
+    let mut deletion_vector_files = HashSet::new();
     for add in snapshot.get_active_add_actions_by_partitions(filters)? {
         let add = add?;
         metrics.total_considered_files += 1;
+        if is_external_file(&add)? {
+            metrics.total_files_skipped += 1;
+            continue;
+        }
         let object_meta = ObjectMeta::try_from(&add)?;
         if (object_meta.size as i64) > target_size {
             metrics.total_files_skipped += 1;
             continue;
         }
+        if add.deletion_vector().is_some() {
+            deletion_vector_files.insert(object_meta.location.clone());
+        }
         let partition_values = add
             .partition_values()?
             .into_iter()
@@ -960,10 +1218,15 @@ fn build_compaction_plan(
         operations.insert(part, (partition, merge_bins));
     }
 
-    // Prune merge bins with only 1 file, since they have no effect
+    // Prune merge bins with only 1 file, since they have no effect unless the file has a
+    // deletion vector
     for (_, (_, bins)) in operations.iter_mut() {
         bins.retain(|bin| {
-            if bin.len() == 1 {
+            if bin.len() == 1
+                && !bin
+                    .iter()
+                    .any(|file| deletion_vector_files.contains(&file.location))
+            {
                 metrics.total_files_skipped += 1;
                 false
             } else {
@@ -989,6 +1252,11 @@ fn build_zorder_plan(
             "Z-order requires at least one column".to_string(),
         ));
     }
+    if snapshot.table_config().enable_row_tracking() {
+        return Err(DeltaTableError::Generic(
+            "Z-order is not supported for tables with row tracking enabled".to_string(),
+        ));
+    }
     let zorder_partition_cols = zorder_columns
         .iter()
         .filter(|col| partition_keys.contains(col))
@@ -1026,6 +1294,16 @@ fn build_zorder_plan(
             .map(|(k, v)| (k.to_string(), v))
             .collect::<IndexMap<_, _>>();
         metrics.total_considered_files += 1;
+        // the files are read directly, so the deleted rows would be resurrected
+        if add.deletion_vector().is_some() {
+            metrics.total_files_skipped += 1;
+            metrics.total_deletion_vector_files_skipped += 1;
+            continue;
+        }
+        if is_external_file(&add)? {
+            metrics.total_files_skipped += 1;
+            continue;
+        }
         let object_meta = ObjectMeta::try_from(&add)?;
 
         partition_files
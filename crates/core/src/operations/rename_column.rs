@@ -0,0 +1,389 @@
+//! Rename a column of a table
+//!
+//! Renaming columns requires column mapping to be enabled on the table, since the
+//! data files keep referring to the column by its physical name. The operation
+//! therefore only updates the table metadata.
+
+use futures::future::BoxFuture;
+
+use super::column_references::ensure_unreferenced;
+use super::transaction::{CommitBuilder, CommitProperties};
+use crate::kernel::{Action, DataType, StructField, StructType};
+use crate::logstore::LogStoreRef;
+use crate::protocol::DeltaOperation;
+use crate::table::config::{ColumnMappingMode, DeltaConfigKey, TableConfig};
+use crate::table::state::DeltaTableState;
+use crate::DeltaTable;
+use crate::{DeltaResult, DeltaTableError};
+
+/// Rename a column of a table
+pub struct RenameColumnBuilder {
+    /// A snapshot of the table's state
+    snapshot: DeltaTableState,
+    /// Dot separated path of the column to rename
+    column: Option<String>,
+    /// New name of the column
+    new_name: Option<String>,
+    /// Delta object store for handling data files
+    log_store: LogStoreRef,
+    /// Additional information to add to the commit
+    commit_properties: CommitProperties,
+}
+
+impl super::Operation<()> for RenameColumnBuilder {}
+
+impl RenameColumnBuilder {
+    /// Create a new builder
+    pub fn new(log_store: LogStoreRef, snapshot: DeltaTableState) -> Self {
+        Self {
+            column: None,
+            new_name: None,
+            snapshot,
+            log_store,
+            commit_properties: CommitProperties::default(),
+        }
+    }
+
+    /// Specify the column to rename.
+    ///
+    /// Nested fields of struct columns are referenced by their dot separated path.
+    pub fn with_column<S: Into<String>>(mut self, column: S) -> Self {
+        self.column = Some(column.into());
+        self
+    }
+
+    /// Specify the new name of the column
+    pub fn with_new_name<S: Into<String>>(mut self, new_name: S) -> Self {
+        self.new_name = Some(new_name.into());
+        self
+    }
+
+    /// Additional metadata to be added to commit info
+    pub fn with_commit_properties(mut self, commit_properties: CommitProperties) -> Self {
+        self.commit_properties = commit_properties;
+        self
+    }
+}
+
+/// Rename the field at the given path, returning the updated fields.
+fn rename_field(
+    fields: &[StructField],
+    path: &[&str],
+    new_name: &str,
+) -> DeltaResult<Vec<StructField>> {
+    let (name, rest) = path
+        .split_first()
+        .ok_or_else(|| DeltaTableError::Generic("No column provided".to_string()))?;
+    let mut found = false;
+    let fields = fields
+        .iter()
+        .map(|field| {
+            if field.name() != name {
+                return Ok(field.clone());
+            }
+            found = true;
+            if rest.is_empty() {
+                return Ok(StructField {
+                    name: new_name.to_string(),
+                    ..field.clone()
+                });
+            }
+            match field.data_type() {
+                DataType::Struct(inner) => Ok(StructField {
+                    data_type: DataType::Struct(Box::new(StructType::new(rename_field(
+                        inner.fields(),
+                        rest,
+                        new_name,
+                    )?))),
+                    ..field.clone()
+                }),
+                _ => Err(DeltaTableError::Generic(format!(
+                    "Column {name} is not a struct"
+                ))),
+            }
+        })
+        .collect::<DeltaResult<Vec<_>>>()?;
+
+    if !found {
+        return Err(DeltaTableError::Generic(format!(
+            "Column {name} does not exist"
+        )));
+    }
+    if rest.is_empty() && fields.iter().filter(|f| f.name() == new_name).count() > 1 {
+        return Err(DeltaTableError::Generic(format!(
+            "Column {new_name} already exists"
+        )));
+    }
+    Ok(fields)
+}
+
+impl std::future::IntoFuture for RenameColumnBuilder {
+    type Output = DeltaResult<DeltaTable>;
+
+    type IntoFuture = BoxFuture<'static, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let this = self;
+
+        Box::pin(async move {
+            let column = this
+                .column
+                .ok_or(DeltaTableError::Generic("No column provided".to_string()))?;
+            let new_name = this
+                .new_name
+                .ok_or(DeltaTableError::Generic("No new name provided".to_string()))?;
+
+            let mut metadata = this.snapshot.metadata().clone();
+            let config = TableConfig(&metadata.configuration);
+            if config.column_mapping_mode() == ColumnMappingMode::None {
+                return Err(DeltaTableError::Generic(
+                    "Column mapping must be enabled to rename columns".to_string(),
+                ));
+            }
+
+            let path = column.split('.').collect::<Vec<_>>();
+            ensure_unreferenced(&metadata, this.snapshot.schema(), &path, &[], "rename")?;
+            let schema = StructType::new(rename_field(
+                this.snapshot.schema().fields(),
+                &path,
+                &new_name,
+            )?);
+
+            // make sure all columns have a physical name, so data files can still be read
+            let mut max_column_id = config.column_mapping_max_column_id();
+            let schema = schema.with_column_mapping(&mut max_column_id);
+            metadata.configuration.insert(
+                DeltaConfigKey::ColumnMappingMaxColumnId
+                    .as_ref()
+                    .to_string(),
+                Some(max_column_id.to_string()),
+            );
+            metadata.schema_string = serde_json::to_string(&schema)?;
+
+            if path.len() == 1 {
+                for partition_column in metadata.partition_columns.iter_mut() {
+                    if *partition_column == column {
+                        *partition_column = new_name.clone();
+                    }
+                }
+            }
+
+            let mut new_path = path[..path.len() - 1].to_vec();
+            new_path.push(&new_name);
+            let operation = DeltaOperation::RenameColumn {
+                old_column_path: column.clone(),
+                new_column_path: new_path.join("."),
+            };
+
+            let actions = vec![Action::Metadata(metadata)];
+
+            let commit = CommitBuilder::from(this.commit_properties)
+                .with_actions(actions)
+                .build(Some(&this.snapshot), this.log_store.clone(), operation)?
+                .await?;
+
+            Ok(DeltaTable::new_with_state(
+                this.log_store,
+                commit.snapshot(),
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "datafusion")]
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{DataType as ArrowDataType, Field, Schema as ArrowSchema};
+    use arrow::record_batch::RecordBatch;
+    use datafusion::assert_batches_sorted_eq;
+    use datafusion::prelude::SessionContext;
+    use std::sync::Arc;
+
+    use crate::kernel::{DataType, PrimitiveType, StructField};
+    use crate::operations::collect_sendable_stream;
+    use crate::protocol::SaveMode;
+    use crate::{DeltaOps, DeltaResult, DeltaTable};
+
+    async fn create_column_mapped_table() -> DeltaResult<DeltaTable> {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![
+                StructField::new("id", DataType::Primitive(PrimitiveType::Integer), true),
+                StructField::new("value", DataType::Primitive(PrimitiveType::String), true),
+            ])
+            .with_partition_columns(["value"])
+            .with_configuration_property(crate::DeltaConfigKey::ColumnMappingMode, Some("name"))
+            .await?;
+
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", ArrowDataType::Int32, true),
+            Field::new("value", ArrowDataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec!["a", "b", "a"])),
+            ],
+        )?;
+        DeltaOps(table)
+            .write(vec![batch])
+            .with_save_mode(SaveMode::Append)
+            .await
+    }
+
+    #[tokio::test]
+    async fn rename_column() -> DeltaResult<()> {
+        let table = create_column_mapped_table().await?;
+        let version = table.version();
+
+        let table = DeltaOps(table)
+            .rename_column()
+            .with_column("id")
+            .with_new_name("key")
+            .await?;
+        assert_eq!(table.version(), version + 1);
+        let table = DeltaOps(table)
+            .rename_column()
+            .with_column("value")
+            .with_new_name("category")
+            .await?;
+
+        let metadata = table.metadata()?;
+        assert_eq!(metadata.partition_columns, vec!["category".to_string()]);
+        let schema = metadata.schema()?;
+        assert!(schema.field_with_name("id").is_err());
+        assert!(schema.field_with_name("key").is_ok());
+
+        let ctx = SessionContext::new();
+        ctx.register_table("test", Arc::new(table))?;
+        let batches = ctx
+            .sql("SELECT key, category FROM test")
+            .await?
+            .collect()
+            .await?;
+        let expected = vec![
+            "+-----+----------+",
+            "| key | category |",
+            "+-----+----------+",
+            "| 1   | a        |",
+            "| 2   | b        |",
+            "| 3   | a        |",
+            "+-----+----------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rename_column_requires_column_mapping() -> DeltaResult<()> {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![StructField::new(
+                "id",
+                DataType::Primitive(PrimitiveType::Integer),
+                true,
+            )])
+            .await?;
+
+        let result = DeltaOps(table)
+            .rename_column()
+            .with_column("id")
+            .with_new_name("key")
+            .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rename_column_referenced_by_constraint() -> DeltaResult<()> {
+        let table = DeltaOps(create_column_mapped_table().await?)
+            .add_constraint()
+            .with_constraint("positive_id", "`id` > 0")
+            .await?;
+        let result = DeltaOps(table)
+            .rename_column()
+            .with_column("id")
+            .with_new_name("key")
+            .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rename_column_referenced_by_generated_column() -> DeltaResult<()> {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![StructField::new("id", DataType::INTEGER, true)])
+            .with_generated_column("doubled", DataType::INTEGER, true, "id * 2")
+            .with_configuration_property(crate::DeltaConfigKey::ColumnMappingMode, Some("name"))
+            .await?;
+
+        let result = DeltaOps(table.clone())
+            .rename_column()
+            .with_column("id")
+            .with_new_name("key")
+            .await;
+        assert!(result.is_err());
+
+        // the generated column itself can be renamed
+        let table = DeltaOps(table)
+            .rename_column()
+            .with_column("doubled")
+            .with_new_name("twice")
+            .await?;
+        assert!(table.get_schema()?.field_with_name("twice").is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rename_column_to_existing_name() -> DeltaResult<()> {
+        let table = create_column_mapped_table().await?;
+        let result = DeltaOps(table)
+            .rename_column()
+            .with_column("id")
+            .with_new_name("value")
+            .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn write_after_rename_column() -> DeltaResult<()> {
+        let table = create_column_mapped_table().await?;
+        let table = DeltaOps(table)
+            .rename_column()
+            .with_column("id")
+            .with_new_name("key")
+            .await?;
+
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("key", ArrowDataType::Int32, true),
+            Field::new("value", ArrowDataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![4])),
+                Arc::new(StringArray::from(vec!["c"])),
+            ],
+        )?;
+        let table = DeltaOps(table).write(vec![batch]).await?;
+
+        let (_, stream) = DeltaOps(table).load().await?;
+        let batches = collect_sendable_stream(stream).await?;
+        let expected = vec![
+            "+-----+-------+",
+            "| key | value |",
+            "+-----+-------+",
+            "| 1   | a     |",
+            "| 2   | b     |",
+            "| 3   | a     |",
+            "| 4   | c     |",
+            "+-----+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+        Ok(())
+    }
+}
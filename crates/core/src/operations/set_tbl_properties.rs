@@ -0,0 +1,500 @@
+//! Set or unset the properties of a table
+//!
+//! Properties in the `delta.` namespace are validated against the known table properties,
+//! all other properties are stored as is. Setting a property that requires a table feature,
+//! e.g. `delta.enableChangeDataFeed`, upgrades the protocol of the table. Table features can
+//! also be added explicitly by setting `delta.feature.<name>` to `supported` (or `enabled`),
+//! and the protocol versions by setting `delta.minReaderVersion` or `delta.minWriterVersion`.
+//! `delta.enableRowTracking` is rejected on tables with files that have no row ids, as existing
+//! files are not backfilled; `delta.feature.rowTracking` only assigns row ids to new files.
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use futures::future::BoxFuture;
+
+use super::transaction::protocol::{features_for_configuration, upgrade_protocol};
+use super::transaction::row_tracking::ensure_row_tracking_enablement;
+use super::transaction::{CommitBuilder, CommitProperties, PROTOCOL};
+use crate::kernel::{Action, WriterFeatures};
+use crate::logstore::LogStoreRef;
+use crate::protocol::DeltaOperation;
+use crate::table::config::{DeltaConfigKey, TableConfig};
+use crate::table::state::DeltaTableState;
+use crate::DeltaTable;
+use crate::{DeltaResult, DeltaTableError};
+
+/// Set properties of a table
+pub struct SetTablePropertiesBuilder {
+    /// A snapshot of the table's state
+    snapshot: DeltaTableState,
+    /// Properties to set
+    properties: HashMap<String, String>,
+    /// Delta object store for handling data files
+    log_store: LogStoreRef,
+    /// Additional information to add to the commit
+    commit_properties: CommitProperties,
+}
+
+/// Unset properties of a table
+pub struct UnsetTablePropertiesBuilder {
+    /// A snapshot of the table's state
+    snapshot: DeltaTableState,
+    /// Properties to unset
+    properties: Vec<String>,
+    /// Raise if a property is not set
+    raise_if_not_exists: bool,
+    /// Delta object store for handling data files
+    log_store: LogStoreRef,
+    /// Additional information to add to the commit
+    commit_properties: CommitProperties,
+}
+
+impl super::Operation<()> for SetTablePropertiesBuilder {}
+
+impl super::Operation<()> for UnsetTablePropertiesBuilder {}
+
+impl SetTablePropertiesBuilder {
+    /// Create a new builder
+    pub fn new(log_store: LogStoreRef, snapshot: DeltaTableState) -> Self {
+        Self {
+            properties: HashMap::new(),
+            snapshot,
+            log_store,
+            commit_properties: CommitProperties::default(),
+        }
+    }
+
+    /// Specify a property to set
+    pub fn with_property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.properties.insert(key.into(), value.into());
+        self
+    }
+
+    /// Specify the properties to set
+    pub fn with_properties(mut self, properties: HashMap<String, String>) -> Self {
+        self.properties.extend(properties);
+        self
+    }
+
+    /// Additional metadata to be added to commit info
+    pub fn with_commit_properties(mut self, commit_properties: CommitProperties) -> Self {
+        self.commit_properties = commit_properties;
+        self
+    }
+}
+
+impl UnsetTablePropertiesBuilder {
+    /// Create a new builder
+    pub fn new(log_store: LogStoreRef, snapshot: DeltaTableState) -> Self {
+        Self {
+            properties: Vec::new(),
+            raise_if_not_exists: true,
+            snapshot,
+            log_store,
+            commit_properties: CommitProperties::default(),
+        }
+    }
+
+    /// Specify a property to unset
+    pub fn with_property(mut self, key: impl Into<String>) -> Self {
+        self.properties.push(key.into());
+        self
+    }
+
+    /// Specify the properties to unset
+    pub fn with_properties<I, S>(mut self, properties: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.properties
+            .extend(properties.into_iter().map(|key| key.into()));
+        self
+    }
+
+    /// Specify if you want to raise if a property is not set
+    pub fn with_raise_if_not_exists(mut self, raise: bool) -> Self {
+        self.raise_if_not_exists = raise;
+        self
+    }
+
+    /// Additional metadata to be added to commit info
+    pub fn with_commit_properties(mut self, commit_properties: CommitProperties) -> Self {
+        self.commit_properties = commit_properties;
+        self
+    }
+}
+
+/// Properties that must not be changed through table properties, if any
+fn check_modifiable(key: &str, config_key: Option<&DeltaConfigKey>) -> DeltaResult<()> {
+    if key.starts_with("delta.constraints.") {
+        return Err(DeltaTableError::Generic(format!(
+            "Table property {key} is a check constraint, use add_constraint or drop_constraint \
+             to change it"
+        )));
+    }
+    match config_key {
+        Some(DeltaConfigKey::ColumnMappingMode) => Err(DeltaTableError::Generic(
+            "The column mapping mode can only be set when creating a table".to_string(),
+        )),
+        Some(
+            DeltaConfigKey::ColumnMappingMaxColumnId
+            | DeltaConfigKey::InCommitTimestampEnablementVersion
+            | DeltaConfigKey::InCommitTimestampEnablementTimestamp
+            | DeltaConfigKey::RequireCheckpointProtectionBeforeVersion,
+        ) => Err(DeltaTableError::Generic(format!(
+            "Table property {key} is managed by the table and cannot be changed"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+impl std::future::IntoFuture for SetTablePropertiesBuilder {
+    type Output = DeltaResult<DeltaTable>;
+
+    type IntoFuture = BoxFuture<'static, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let this = self;
+
+        Box::pin(async move {
+            if this.properties.is_empty() {
+                return Err(DeltaTableError::Generic(
+                    "No properties provided".to_string(),
+                ));
+            }
+
+            let mut metadata = this.snapshot.metadata().clone();
+            let mut features = HashSet::new();
+            let mut reader_version = 0;
+            let mut writer_version = 0;
+            for (key, value) in &this.properties {
+                if let Some(name) = key.strip_prefix("delta.feature.") {
+                    let feature = WriterFeatures::from(name);
+                    if matches!(feature, WriterFeatures::Other(_)) {
+                        return Err(DeltaTableError::Generic(format!(
+                            "Unknown table feature: {name}"
+                        )));
+                    }
+                    if !value.eq_ignore_ascii_case("supported")
+                        && !value.eq_ignore_ascii_case("enabled")
+                    {
+                        return Err(DeltaTableError::Generic(format!(
+                            "Invalid value '{value}' for {key}, expected 'supported' or 'enabled'"
+                        )));
+                    }
+                    features.insert(feature);
+                    continue;
+                }
+                if !key.starts_with("delta.") {
+                    metadata
+                        .configuration
+                        .insert(key.clone(), Some(value.clone()));
+                    continue;
+                }
+
+                let config_key = DeltaConfigKey::from_str(key).map_err(|_| {
+                    DeltaTableError::Generic(format!("Unknown table property: {key}"))
+                })?;
+                check_modifiable(key, Some(&config_key))?;
+                config_key
+                    .validate(value)
+                    .map_err(|err| DeltaTableError::Generic(err.to_string()))?;
+                match config_key {
+                    // protocol versions are recorded in the protocol, not the table properties
+                    DeltaConfigKey::MinReaderVersion => {
+                        reader_version = value.parse().unwrap_or_default()
+                    }
+                    DeltaConfigKey::MinWriterVersion => {
+                        writer_version = value.parse().unwrap_or_default()
+                    }
+                    _ => {
+                        metadata
+                            .configuration
+                            .insert(config_key.as_ref().to_string(), Some(value.clone()));
+                    }
+                }
+            }
+            if reader_version > 3 || writer_version > 7 {
+                return Err(DeltaTableError::Generic(format!(
+                    "Invalid protocol versions: reader version {reader_version}, writer version \
+                     {writer_version}"
+                )));
+            }
+
+            let current = &this.snapshot.metadata().configuration;
+            let changed = metadata
+                .configuration
+                .iter()
+                .filter(|(key, value)| current.get(*key) != Some(*value))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            ensure_row_tracking_enablement(this.snapshot.snapshot(), &changed, &[])?;
+            features.extend(features_for_configuration(&changed));
+
+            let mut actions = vec![Action::Metadata(metadata)];
+            if let Some(protocol) = upgrade_protocol(
+                this.snapshot.protocol(),
+                reader_version,
+                writer_version,
+                features,
+            ) {
+                PROTOCOL.can_write_protocol(&protocol)?;
+                actions.push(Action::Protocol(protocol));
+            }
+
+            let operation = DeltaOperation::SetTableProperties {
+                properties: this.properties,
+            };
+            let commit = CommitBuilder::from(this.commit_properties)
+                .with_actions(actions)
+                .build(Some(&this.snapshot), this.log_store.clone(), operation)?
+                .await?;
+
+            Ok(DeltaTable::new_with_state(
+                this.log_store,
+                commit.snapshot(),
+            ))
+        })
+    }
+}
+
+impl std::future::IntoFuture for UnsetTablePropertiesBuilder {
+    type Output = DeltaResult<DeltaTable>;
+
+    type IntoFuture = BoxFuture<'static, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let this = self;
+
+        Box::pin(async move {
+            if this.properties.is_empty() {
+                return Err(DeltaTableError::Generic(
+                    "No properties provided".to_string(),
+                ));
+            }
+
+            let mut metadata = this.snapshot.metadata().clone();
+            let column_mapping_enabled =
+                TableConfig(&metadata.configuration).column_mapping_mode() != Default::default();
+            let mut removed = false;
+            for key in &this.properties {
+                let config_key = DeltaConfigKey::from_str(key).ok();
+                match config_key {
+                    Some(
+                        DeltaConfigKey::ColumnMappingMode
+                        | DeltaConfigKey::ColumnMappingMaxColumnId,
+                    ) if !column_mapping_enabled => {}
+                    _ => check_modifiable(key, config_key.as_ref())?,
+                }
+                if metadata.configuration.remove(key).is_some() {
+                    removed = true;
+                } else if this.raise_if_not_exists {
+                    return Err(DeltaTableError::Generic(format!(
+                        "Table property {key} does not exist"
+                    )));
+                }
+            }
+            if !removed {
+                return Ok(DeltaTable::new_with_state(this.log_store, this.snapshot));
+            }
+
+            let operation = DeltaOperation::UnsetTableProperties {
+                properties: this.properties,
+                if_exists: !this.raise_if_not_exists,
+            };
+            let commit = CommitBuilder::from(this.commit_properties)
+                .with_actions(vec![Action::Metadata(metadata)])
+                .build(Some(&this.snapshot), this.log_store.clone(), operation)?
+                .await?;
+
+            Ok(DeltaTable::new_with_state(
+                this.log_store,
+                commit.snapshot(),
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "datafusion")]
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::kernel::{Protocol, ReaderFeatures};
+    use crate::writer::test_utils::{create_initialized_table, get_record_batch};
+    use crate::DeltaOps;
+
+    #[tokio::test]
+    async fn test_set_properties() {
+        let table = create_initialized_table(&[]).await;
+        let table = DeltaOps(table)
+            .set_tbl_properties()
+            .with_property("delta.logRetentionDuration", "interval 2 days")
+            .with_property("delta.targetFileSize", "1024")
+            .with_property("delta.dataSkippingStatsColumns", "id,value")
+            .with_property("owner", "delta-rs")
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 1);
+        // no table feature is required
+        let protocol = table.protocol().unwrap();
+        assert_eq!(
+            (protocol.min_reader_version, protocol.min_writer_version),
+            (1, 2)
+        );
+
+        let metadata = table.metadata().unwrap();
+        let config = TableConfig(&metadata.configuration);
+        assert_eq!(
+            config.log_retention_duration(),
+            Duration::from_secs(2 * 24 * 60 * 60)
+        );
+        assert_eq!(config.target_file_size(), 1024);
+        assert_eq!(config.stats_columns(), Some(vec!["id", "value"]));
+        assert_eq!(
+            metadata.configuration.get("owner"),
+            Some(&Some("delta-rs".to_string()))
+        );
+
+        let history = table.history(Some(1)).await.unwrap();
+        assert_eq!(history[0].operation.as_deref(), Some("SET TBLPROPERTIES"));
+        let parameters = history[0].operation_parameters.as_ref().unwrap();
+        assert!(parameters["properties"]
+            .as_str()
+            .unwrap()
+            .contains("delta.targetFileSize"));
+    }
+
+    #[tokio::test]
+    async fn test_set_properties_upgrades_protocol() {
+        let table = create_initialized_table(&[]).await;
+        let table = DeltaOps(table)
+            .set_tbl_properties()
+            .with_property("delta.enableChangeDataFeed", "true")
+            .await
+            .unwrap();
+        assert_eq!(table.protocol().unwrap(), &Protocol::new(1, 4));
+
+        let table = DeltaOps(table)
+            .set_tbl_properties()
+            .with_property("delta.enableDeletionVectors", "true")
+            .with_property("delta.feature.timestampNtz", "supported")
+            .await
+            .unwrap();
+        let protocol = table.protocol().unwrap();
+        assert_eq!(protocol.min_reader_version, 3);
+        assert_eq!(protocol.min_writer_version, 7);
+        assert_eq!(
+            protocol.reader_features,
+            Some(HashSet::from([
+                ReaderFeatures::DeletionVectors,
+                ReaderFeatures::TimestampWithoutTimezone
+            ]))
+        );
+        assert!(protocol
+            .writer_features
+            .as_ref()
+            .unwrap()
+            .is_superset(&HashSet::from([
+                WriterFeatures::ChangeDataFeed,
+                WriterFeatures::DeletionVectors,
+                WriterFeatures::TimestampWithoutTimezone
+            ])));
+        // explicitly added features are not stored as table properties
+        assert!(!table
+            .metadata()
+            .unwrap()
+            .configuration
+            .contains_key("delta.feature.timestampNtz"));
+    }
+
+    #[tokio::test]
+    async fn test_set_row_tracking_on_table_with_data() {
+        let table = DeltaOps::new_in_memory()
+            .write(vec![get_record_batch(None, false)])
+            .await
+            .unwrap();
+        // existing files have no row ids, so row tracking cannot be enabled
+        let result = DeltaOps(table.clone())
+            .set_tbl_properties()
+            .with_property("delta.enableRowTracking", "true")
+            .await;
+        assert!(result.is_err());
+
+        // supporting the feature assigns row ids to new files only
+        let table = DeltaOps(table)
+            .set_tbl_properties()
+            .with_property("delta.feature.rowTracking", "supported")
+            .await
+            .unwrap();
+        assert!(table
+            .protocol()
+            .unwrap()
+            .writer_features
+            .as_ref()
+            .unwrap()
+            .contains(&WriterFeatures::RowTracking));
+        assert!(!table
+            .snapshot()
+            .unwrap()
+            .table_config()
+            .enable_row_tracking());
+    }
+
+    #[tokio::test]
+    async fn test_set_invalid_properties() {
+        let table = create_initialized_table(&[]).await;
+        for (key, value) in [
+            ("delta.unknownProperty", "true"),
+            ("delta.appendOnly", "yes"),
+            ("delta.logRetentionDuration", "2 days"),
+            ("delta.columnMapping.mode", "name"),
+            ("delta.constraints.id", "id > 0"),
+            ("delta.feature.unknownFeature", "supported"),
+            ("delta.minWriterVersion", "8"),
+        ] {
+            let result = DeltaOps(table.clone())
+                .set_tbl_properties()
+                .with_property(key, value)
+                .await;
+            assert!(result.is_err(), "{key}={value} should be rejected");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unset_properties() {
+        let table = create_initialized_table(&[]).await;
+        let table = DeltaOps(table)
+            .set_tbl_properties()
+            .with_property("delta.targetFileSize", "1024")
+            .with_property("owner", "delta-rs")
+            .await
+            .unwrap();
+
+        let result = DeltaOps(table.clone())
+            .unset_tbl_properties()
+            .with_property("delta.appendOnly")
+            .await;
+        assert!(result.is_err());
+
+        let table = DeltaOps(table)
+            .unset_tbl_properties()
+            .with_properties(["delta.targetFileSize", "owner", "delta.appendOnly"])
+            .with_raise_if_not_exists(false)
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 2);
+        let metadata = table.metadata().unwrap();
+        assert!(!metadata.configuration.contains_key("owner"));
+        assert_eq!(
+            TableConfig(&metadata.configuration).target_file_size(),
+            104857600
+        );
+
+        let history = table.history(Some(1)).await.unwrap();
+        assert_eq!(history[0].operation.as_deref(), Some("UNSET TBLPROPERTIES"));
+    }
+}
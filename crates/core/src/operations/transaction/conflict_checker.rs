@@ -1,12 +1,13 @@
 //! Helper module to check if a transaction can be committed in case of conflicting commits.
 use std::collections::HashSet;
 
+use super::row_tracking::ROW_TRACKING_DOMAIN;
 use super::CommitInfo;
 #[cfg(feature = "datafusion")]
 use crate::delta_datafusion::DataFusionMixins;
 use crate::errors::DeltaResult;
 use crate::kernel::EagerSnapshot;
-use crate::kernel::{Action, Add, Metadata, Protocol, Remove};
+use crate::kernel::{Action, Add, DomainMetadata, Metadata, Protocol, Remove};
 use crate::logstore::{get_actions, LogStore};
 use crate::protocol::DeltaOperation;
 use crate::table::config::IsolationLevel;
@@ -49,6 +50,11 @@ pub enum CommitConflictError {
     #[error("Concurrent transaction failed.")]
     ConcurrentTransaction,
 
+    /// This exception occurs when a concurrent transaction updates a metadata domain
+    /// that the current transaction also updates.
+    #[error("Commit failed: a concurrent transaction updated the metadata domain {0}.")]
+    DomainMetadataChanged(String),
+
     /// This exception can occur in the following cases:
     /// - When your Delta table is upgraded to a new version. For future operations to succeed
     ///   you may need to upgrade your Delta Lake version.
@@ -262,6 +268,17 @@ impl WinningCommitSummary {
             .collect()
     }
 
+    pub fn domain_metadata(&self) -> Vec<DomainMetadata> {
+        self.actions
+            .iter()
+            .cloned()
+            .filter_map(|action| match action {
+                Action::DomainMetadata(domain) => Some(domain),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn protocol(&self) -> Vec<Protocol> {
         self.actions
             .iter()
@@ -307,7 +324,13 @@ impl WinningCommitSummary {
         if self.is_blind_append().unwrap_or(false) {
             vec![]
         } else {
+            // Files that are re-added with an updated deletion vector don't contain any new data
+            let removed_paths: HashSet<String> =
+                self.removed_files().into_iter().map(|r| r.path).collect();
             self.added_files()
+                .into_iter()
+                .filter(|add| add.deletion_vector.is_none() || !removed_paths.contains(&add.path))
+                .collect()
         }
     }
 
@@ -380,6 +403,7 @@ impl<'a> ConflictChecker<'a> {
         self.check_for_deleted_files_against_current_txn_read_files()?;
         self.check_for_deleted_files_against_current_txn_deleted_files()?;
         self.check_for_updated_application_transaction_ids_that_current_txn_depends_on()?;
+        self.check_for_updated_domain_metadata()?;
         Ok(())
     }
 
@@ -577,6 +601,36 @@ impl<'a> ConflictChecker<'a> {
             Ok(())
         }
     }
+
+    /// Checks if the winning transaction updated a metadata domain the current transaction
+    /// also updates.
+    ///
+    /// The row tracking domain is exempt, since the row id high water mark is re-assigned
+    /// when the current transaction is retried.
+    fn check_for_updated_domain_metadata(&self) -> Result<(), CommitConflictError> {
+        let winning_domains: HashSet<String> = self
+            .winning_commit_summary
+            .domain_metadata()
+            .into_iter()
+            .map(|dm| dm.domain)
+            .collect();
+        let conflict = self
+            .txn_info
+            .actions
+            .iter()
+            .find_map(|action| match action {
+                Action::DomainMetadata(dm)
+                    if dm.domain != ROW_TRACKING_DOMAIN && winning_domains.contains(&dm.domain) =>
+                {
+                    Some(dm.domain.clone())
+                }
+                _ => None,
+            });
+        match conflict {
+            Some(domain) => Err(CommitConflictError::DomainMetadataChanged(domain)),
+            None => Ok(()),
+        }
+    }
 }
 
 // implementation and comments adopted from
@@ -751,6 +805,35 @@ mod tests {
         // );
         // assert!(result.is_ok());
 
+        // disjoint domain metadata
+        // concurrently update a different metadata domain
+        let domain = |name: &str| {
+            Action::DomainMetadata(DomainMetadata {
+                domain: name.to_string(),
+                configuration: "{}".to_string(),
+                removed: false,
+            })
+        };
+        let result = execute_test(
+            None,
+            None,
+            vec![domain("domain1")],
+            vec![domain("domain2")],
+            false,
+        );
+        assert!(result.is_ok());
+
+        // row tracking domain
+        // the high water mark is re-assigned when retrying, so concurrent updates are fine
+        let result = execute_test(
+            None,
+            None,
+            vec![domain(ROW_TRACKING_DOMAIN)],
+            vec![domain(ROW_TRACKING_DOMAIN)],
+            false,
+        );
+        assert!(result.is_ok());
+
         // TODO disjoint transactions
     }
 
@@ -865,6 +948,19 @@ mod tests {
             Err(CommitConflictError::ConcurrentDeleteRead)
         ));
 
+        // domain metadata / domain metadata
+        // current and concurrent transactions update the same metadata domain
+        let domain = Action::DomainMetadata(DomainMetadata {
+            domain: "domain1".to_string(),
+            configuration: "{}".to_string(),
+            removed: false,
+        });
+        let result = execute_test(None, None, vec![domain.clone()], vec![domain], false);
+        assert!(matches!(
+            result,
+            Err(CommitConflictError::DomainMetadataChanged(d)) if d == "domain1"
+        ));
+
         // TODO "add in part=2 / read from part=1,2 and write to part=1"
 
         // TODO conflicting txns
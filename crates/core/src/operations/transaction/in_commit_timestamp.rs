@@ -0,0 +1,209 @@
+//! In-commit timestamps
+//!
+//! When the `inCommitTimestamp` table feature is enabled, every commit stores a monotonically
+//! increasing `inCommitTimestamp` in its commit info, which must be the first action of the
+//! commit. Unlike file modification times, these timestamps survive copying or restoring the
+//! log, so they are used to resolve timestamps to table versions.
+//!
+//! See <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#in-commit-timestamps>
+use chrono::Utc;
+use object_store::ObjectStore;
+
+use super::TableReference;
+use crate::errors::DeltaResult;
+use crate::kernel::{Action, CommitInfo, Metadata, Protocol, WriterFeatures};
+use crate::logstore::LogStore;
+use crate::storage::commit_uri_from_version;
+use crate::table::config::{DeltaConfigKey, TableConfig};
+
+/// Returns true if commits of a table with the given protocol and configuration carry
+/// in-commit timestamps
+pub(crate) fn in_commit_timestamps_enabled(protocol: &Protocol, config: &TableConfig<'_>) -> bool {
+    protocol
+        .writer_features
+        .as_ref()
+        .map(|features| features.contains(&WriterFeatures::InCommitTimestamp))
+        .unwrap_or_default()
+        && config.enable_in_commit_timestamps()
+}
+
+/// Get the timestamp of the commit at the given version
+///
+/// This is the `inCommitTimestamp` of the commit if present, otherwise the modification
+/// time of the commit file.
+pub(crate) async fn read_commit_timestamp(
+    log_store: &dyn LogStore,
+    version: i64,
+) -> DeltaResult<i64> {
+    let result = log_store
+        .object_store()
+        .get(&commit_uri_from_version(version))
+        .await?;
+    let last_modified = result.meta.last_modified.timestamp_millis();
+    let bytes = result.bytes().await?;
+    for line in bytes.split(|b| *b == b'\n') {
+        if line.iter().all(|b| b.is_ascii_whitespace()) {
+            continue;
+        }
+        // writers put the commit info first, so only the leading action needs to be parsed
+        return match serde_json::from_slice::<Action>(line)? {
+            Action::CommitInfo(CommitInfo {
+                in_commit_timestamp: Some(timestamp),
+                ..
+            }) => Ok(timestamp),
+            _ => Ok(last_modified),
+        };
+    }
+    Ok(last_modified)
+}
+
+/// Assigns the in-commit timestamp to a commit
+#[derive(Debug, Clone)]
+pub(crate) struct InCommitTimestampAssigner {
+    /// Metadata of the table, if this commit enables in-commit timestamps on an existing table
+    enablement: Option<Metadata>,
+}
+
+impl InCommitTimestampAssigner {
+    /// Create an assigner for the given actions, if the table has in-commit timestamps
+    /// enabled after the commit
+    pub fn try_new(actions: &[Action], table: Option<&dyn TableReference>) -> Option<Self> {
+        let protocol = actions
+            .iter()
+            .find_map(|action| match action {
+                Action::Protocol(protocol) => Some(protocol),
+                _ => None,
+            })
+            .or(table.map(|table| table.protocol()))?;
+        let metadata = actions
+            .iter()
+            .find_map(|action| match action {
+                Action::Metadata(metadata) => Some(metadata),
+                _ => None,
+            })
+            .or(table.map(|table| table.metadata()))?;
+        if !in_commit_timestamps_enabled(protocol, &TableConfig(&metadata.configuration)) {
+            return None;
+        }
+        let enablement = match table {
+            Some(table) if !in_commit_timestamps_enabled(table.protocol(), &table.config()) => {
+                Some(metadata.clone())
+            }
+            _ => None,
+        };
+        Some(Self { enablement })
+    }
+
+    /// Assign a timestamp following the `previous_timestamp` to the commit info and move
+    /// it to the front of the actions
+    ///
+    /// If the commit enables in-commit timestamps, the enablement version and timestamp are
+    /// recorded in the table metadata. Assigning again, e.g. when retrying a commit at a later
+    /// version, overwrites previously assigned values.
+    pub fn assign(
+        &self,
+        actions: &mut Vec<Action>,
+        previous_timestamp: Option<i64>,
+        version: i64,
+    ) -> DeltaResult<()> {
+        let now = Utc::now().timestamp_millis();
+        let timestamp = match previous_timestamp {
+            Some(previous) => now.max(previous + 1),
+            None => now,
+        };
+
+        let mut commit_info = match actions
+            .iter()
+            .position(|action| matches!(action, Action::CommitInfo(_)))
+            .map(|idx| actions.remove(idx))
+        {
+            Some(Action::CommitInfo(commit_info)) => commit_info,
+            _ => CommitInfo::default(),
+        };
+        commit_info.in_commit_timestamp = Some(timestamp);
+        actions.insert(0, Action::CommitInfo(commit_info));
+
+        if let Some(metadata) = &self.enablement {
+            let mut metadata = metadata.clone();
+            metadata.configuration.insert(
+                DeltaConfigKey::InCommitTimestampEnablementVersion
+                    .as_ref()
+                    .to_string(),
+                Some(version.to_string()),
+            );
+            metadata.configuration.insert(
+                DeltaConfigKey::InCommitTimestampEnablementTimestamp
+                    .as_ref()
+                    .to_string(),
+                Some(timestamp.to_string()),
+            );
+            match actions
+                .iter_mut()
+                .find(|action| matches!(action, Action::Metadata(_)))
+            {
+                Some(action) => *action = Action::Metadata(metadata),
+                None => actions.push(Action::Metadata(metadata)),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::Add;
+
+    fn protocol() -> Protocol {
+        Protocol {
+            min_reader_version: 1,
+            min_writer_version: 7,
+            reader_features: None,
+            writer_features: Some([WriterFeatures::InCommitTimestamp].into()),
+        }
+    }
+
+    fn metadata(enabled: bool) -> Metadata {
+        let mut metadata = Metadata::default();
+        metadata.configuration.insert(
+            DeltaConfigKey::EnableInCommitTimestamps
+                .as_ref()
+                .to_string(),
+            Some(enabled.to_string()),
+        );
+        metadata
+    }
+
+    #[test]
+    fn test_assign_in_commit_timestamp() {
+        let actions = vec![
+            Action::Protocol(protocol()),
+            Action::Metadata(metadata(false)),
+        ];
+        assert!(InCommitTimestampAssigner::try_new(&actions, None).is_none());
+
+        let mut actions = vec![
+            Action::Protocol(protocol()),
+            Action::Metadata(metadata(true)),
+            Action::Add(Add::default()),
+            Action::CommitInfo(CommitInfo::default()),
+        ];
+        let assigner = InCommitTimestampAssigner::try_new(&actions, None).unwrap();
+        let future = Utc::now().timestamp_millis() + 60_000;
+        assigner.assign(&mut actions, Some(future), 0).unwrap();
+        assert_eq!(actions.len(), 4);
+        assert!(matches!(
+            &actions[0],
+            Action::CommitInfo(info) if info.in_commit_timestamp == Some(future + 1)
+        ));
+
+        // re-assigning keeps the commit info in front
+        assigner.assign(&mut actions, Some(future + 1), 1).unwrap();
+        assert_eq!(actions.len(), 4);
+        assert!(matches!(
+            &actions[0],
+            Action::CommitInfo(info) if info.in_commit_timestamp == Some(future + 2)
+        ));
+        assert!(matches!(&actions[3], Action::Add(_)));
+    }
+}
@@ -80,13 +80,16 @@ use futures::future::BoxFuture;
 use object_store::path::Path;
 use object_store::{Error as ObjectStoreError, ObjectStore};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use self::conflict_checker::{CommitConflictError, TransactionInfo, WinningCommitSummary};
-use crate::checkpoints::create_checkpoint_for;
+use self::in_commit_timestamp::InCommitTimestampAssigner;
+use self::row_tracking::RowIdAssigner;
+use crate::checkpoints::{create_checkpoint_for, create_log_compaction_for};
 use crate::errors::DeltaTableError;
 use crate::kernel::{
-    Action, CommitInfo, EagerSnapshot, Metadata, Protocol, ReaderFeatures, WriterFeatures,
+    Action, CommitInfo, DomainMetadata, EagerSnapshot, Metadata, Protocol, ReaderFeatures,
+    WriterFeatures,
 };
 use crate::logstore::LogStoreRef;
 use crate::protocol::DeltaOperation;
@@ -97,7 +100,9 @@ use crate::{crate_version, DeltaResult};
 pub use self::protocol::INSTANCE as PROTOCOL;
 
 mod conflict_checker;
-mod protocol;
+pub(crate) mod in_commit_timestamp;
+pub(crate) mod protocol;
+pub(crate) mod row_tracking;
 #[cfg(feature = "datafusion")]
 mod state;
 #[cfg(test)]
@@ -186,7 +191,15 @@ impl From<TransactionError> for DeltaTableError {
 
 /// Error raised while commititng transaction
 #[derive(thiserror::Error, Debug)]
-pub enum CommitBuilderError {}
+pub enum CommitBuilderError {
+    /// Error returned when a commit contains more than one action for the same metadata domain
+    #[error("A commit must not contain more than one action for metadata domain: {0}")]
+    DuplicateDomainMetadata(String),
+
+    /// Error returned when a system-controlled metadata domain is set by the user
+    #[error("Metadata domain {0} is reserved for system-controlled domains")]
+    ReservedDomainMetadata(String),
+}
 
 impl From<CommitBuilderError> for DeltaTableError {
     fn from(err: CommitBuilderError) -> Self {
@@ -262,6 +275,17 @@ impl CommitData {
         operation: DeltaOperation,
         mut app_metadata: HashMap<String, Value>,
     ) -> Result<Self, CommitBuilderError> {
+        let mut domains = HashSet::new();
+        for action in &actions {
+            if let Action::DomainMetadata(dm) = action {
+                if !domains.insert(dm.domain.as_str()) {
+                    return Err(CommitBuilderError::DuplicateDomainMetadata(
+                        dm.domain.clone(),
+                    ));
+                }
+            }
+        }
+
         if !actions.iter().any(|a| matches!(a, Action::CommitInfo(..))) {
             let mut commit_info = operation.get_commit_info();
             commit_info.timestamp = Some(Utc::now().timestamp_millis());
@@ -305,6 +329,7 @@ impl CommitData {
 /// Properties for post commit hook.
 pub struct PostCommitHookProperties {
     create_checkpoint: bool,
+    log_compaction_interval: Option<u64>,
 }
 
 #[derive(Clone, Debug)]
@@ -314,6 +339,7 @@ pub struct CommitProperties {
     pub(crate) app_metadata: HashMap<String, Value>,
     max_retries: usize,
     create_checkpoint: bool,
+    log_compaction_interval: Option<u64>,
 }
 
 impl Default for CommitProperties {
@@ -322,6 +348,7 @@ impl Default for CommitProperties {
             app_metadata: Default::default(),
             max_retries: DEFAULT_RETRIES,
             create_checkpoint: true,
+            log_compaction_interval: None,
         }
     }
 }
@@ -341,6 +368,13 @@ impl CommitProperties {
         self.create_checkpoint = create_checkpoint;
         self
     }
+
+    /// Specify the number of commits after which the most recent commits are compacted into a
+    /// single log compaction file
+    pub fn with_log_compaction_interval(mut self, interval: u64) -> Self {
+        self.log_compaction_interval = Some(interval);
+        self
+    }
 }
 
 impl From<CommitProperties> for CommitBuilder {
@@ -350,6 +384,7 @@ impl From<CommitProperties> for CommitBuilder {
             app_metadata: value.app_metadata,
             post_commit_hook: PostCommitHookProperties {
                 create_checkpoint: value.create_checkpoint,
+                log_compaction_interval: value.log_compaction_interval,
             }
             .into(),
             ..Default::default()
@@ -360,6 +395,7 @@ impl From<CommitProperties> for CommitBuilder {
 /// Prepare data to be committed to the Delta log and control how the commit is performed
 pub struct CommitBuilder {
     actions: Vec<Action>,
+    domain_metadata: Vec<DomainMetadata>,
     app_metadata: HashMap<String, Value>,
     max_retries: usize,
     post_commit_hook: Option<PostCommitHookProperties>,
@@ -369,6 +405,7 @@ impl Default for CommitBuilder {
     fn default() -> Self {
         CommitBuilder {
             actions: Vec::new(),
+            domain_metadata: Vec::new(),
             app_metadata: HashMap::new(),
             max_retries: DEFAULT_RETRIES,
             post_commit_hook: None,
@@ -383,6 +420,14 @@ impl<'a> CommitBuilder {
         self
     }
 
+    /// Configuration of metadata domains to be set or removed with the commit
+    ///
+    /// Domains prefixed with `delta.` are reserved for system-controlled domains.
+    pub fn with_domain_metadata(mut self, domain_metadata: Vec<DomainMetadata>) -> Self {
+        self.domain_metadata = domain_metadata;
+        self
+    }
+
     /// Metadata for the operation performed like metrics, user, and notebook
     pub fn with_app_metadata(mut self, app_metadata: HashMap<String, Value>) -> Self {
         self.app_metadata = app_metadata;
@@ -408,7 +453,14 @@ impl<'a> CommitBuilder {
         log_store: LogStoreRef,
         operation: DeltaOperation,
     ) -> Result<PreCommit<'a>, CommitBuilderError> {
-        let data = CommitData::new(self.actions, operation, self.app_metadata)?;
+        let mut actions = self.actions;
+        for domain in self.domain_metadata {
+            if domain.domain.starts_with("delta.") {
+                return Err(CommitBuilderError::ReservedDomainMetadata(domain.domain));
+            }
+            actions.push(Action::DomainMetadata(domain));
+        }
+        let data = CommitData::new(actions, operation, self.app_metadata)?;
         Ok(PreCommit {
             log_store,
             table_data,
@@ -442,13 +494,59 @@ impl<'a> std::future::IntoFuture for PreCommit<'a> {
 impl<'a> PreCommit<'a> {
     /// Prepare the commit but do not finalize it
     pub fn into_prepared_commit_future(self) -> BoxFuture<'a, DeltaResult<PreparedCommit<'a>>> {
-        let this = self;
+        let mut this = self;
 
         Box::pin(async move {
             if let Some(table_reference) = this.table_data {
+                // Setting table properties that require a table feature upgrades the protocol.
+                if let Some(protocol) =
+                    protocol::protocol_for_metadata_update(table_reference, &this.data.actions)?
+                {
+                    PROTOCOL.can_write_protocol(&protocol)?;
+                    this.data.actions.push(Action::Protocol(protocol));
+                }
                 PROTOCOL.can_commit(table_reference, &this.data.actions, &this.data.operation)?;
             }
 
+            // Assign the commit timestamp, assuming this commit will become the next version.
+            // This moves the commit info in front of all other actions, so it must happen
+            // before the positions of new files are recorded for row tracking.
+            let timestamp_assigner =
+                InCommitTimestampAssigner::try_new(&this.data.actions, this.table_data);
+            if let Some(assigner) = &timestamp_assigner {
+                let (previous_timestamp, version) = match this.table_data {
+                    Some(table) => {
+                        let version = table.eager_snapshot().version();
+                        let timestamp = in_commit_timestamp::read_commit_timestamp(
+                            this.log_store.as_ref(),
+                            version,
+                        )
+                        .await?;
+                        (Some(timestamp), version + 1)
+                    }
+                    None => (None, 0),
+                };
+                assigner.assign(&mut this.data.actions, previous_timestamp, version)?;
+            }
+
+            // Assign row ids to new files, assuming this commit will become the next version.
+            let row_id_assigner = RowIdAssigner::try_new(
+                &this.data.actions,
+                this.table_data.map(|table| table.protocol()),
+            );
+            let mut row_id_high_water_mark = None;
+            if let Some(assigner) = &row_id_assigner {
+                let version = match this.table_data {
+                    Some(table) => {
+                        let snapshot = table.eager_snapshot();
+                        row_id_high_water_mark = row_tracking::read_high_water_mark(snapshot)?;
+                        snapshot.version() + 1
+                    }
+                    None => 0,
+                };
+                assigner.assign(&mut this.data.actions, row_id_high_water_mark, version)?;
+            }
+
             // Serialize all actions that are part of this log entry.
             let log_entry = this.data.get_bytes()?;
 
@@ -466,6 +564,9 @@ impl<'a> PreCommit<'a> {
                 max_retries: this.max_retries,
                 data: this.data,
                 post_commit: this.post_commit_hook,
+                row_id_assigner,
+                row_id_high_water_mark,
+                timestamp_assigner,
             })
         })
     }
@@ -479,6 +580,9 @@ pub struct PreparedCommit<'a> {
     table_data: Option<&'a dyn TableReference>,
     max_retries: usize,
     post_commit: Option<PostCommitHookProperties>,
+    row_id_assigner: Option<RowIdAssigner>,
+    row_id_high_water_mark: Option<i64>,
+    timestamp_assigner: Option<InCommitTimestampAssigner>,
 }
 
 impl<'a> PreparedCommit<'a> {
@@ -493,10 +597,11 @@ impl<'a> std::future::IntoFuture for PreparedCommit<'a> {
     type IntoFuture = BoxFuture<'a, Self::Output>;
 
     fn into_future(self) -> Self::IntoFuture {
-        let this = self;
+        let mut this = self;
 
         Box::pin(async move {
-            let tmp_commit = &this.path;
+            let tmp_commit = this.path.clone();
+            let tmp_commit = &tmp_commit;
 
             if this.table_data.is_none() {
                 this.log_store.write_commit_entry(0, tmp_commit).await?;
@@ -504,6 +609,7 @@ impl<'a> std::future::IntoFuture for PreparedCommit<'a> {
                     version: 0,
                     data: this.data,
                     create_checkpoint: false,
+                    log_compaction_interval: None,
                     log_store: this.log_store,
                     table_data: this.table_data,
                 });
@@ -525,6 +631,9 @@ impl<'a> std::future::IntoFuture for PreparedCommit<'a> {
                                 .post_commit
                                 .map(|v| v.create_checkpoint)
                                 .unwrap_or_default(),
+                            log_compaction_interval: this
+                                .post_commit
+                                .and_then(|v| v.log_compaction_interval),
                             log_store: this.log_store,
                             table_data: this.table_data,
                         });
@@ -536,6 +645,12 @@ impl<'a> std::future::IntoFuture for PreparedCommit<'a> {
                             version,
                         )
                         .await?;
+                        let winning_high_water_mark = match &this.row_id_assigner {
+                            Some(_) => {
+                                row_tracking::high_water_mark_from_actions(&summary.actions)?
+                            }
+                            None => None,
+                        };
                         let transaction_info = TransactionInfo::try_new(
                             read_snapshot,
                             this.data.operation.read_predicate(),
@@ -550,6 +665,39 @@ impl<'a> std::future::IntoFuture for PreparedCommit<'a> {
                         match conflict_checker.check_conflicts() {
                             Ok(_) => {
                                 attempt_number += 1;
+                                // the commit timestamp must follow the one of the winning commit
+                                if let Some(assigner) = &this.timestamp_assigner {
+                                    let winning_timestamp =
+                                        in_commit_timestamp::read_commit_timestamp(
+                                            this.log_store.as_ref(),
+                                            version,
+                                        )
+                                        .await?;
+                                    assigner.assign(
+                                        &mut this.data.actions,
+                                        Some(winning_timestamp),
+                                        version + 1,
+                                    )?;
+                                }
+                                // row ids must follow the ones assigned by the winning commit
+                                if let Some(assigner) = &this.row_id_assigner {
+                                    this.row_id_high_water_mark =
+                                        this.row_id_high_water_mark.max(winning_high_water_mark);
+                                    assigner.assign(
+                                        &mut this.data.actions,
+                                        this.row_id_high_water_mark,
+                                        version + 1,
+                                    )?;
+                                }
+                                if this.timestamp_assigner.is_some()
+                                    || this.row_id_assigner.is_some()
+                                {
+                                    let log_entry = this.data.get_bytes()?;
+                                    this.log_store
+                                        .object_store()
+                                        .put(tmp_commit, log_entry)
+                                        .await?;
+                                }
                             }
                             Err(err) => {
                                 this.log_store
@@ -580,6 +728,7 @@ pub struct PostCommit<'a> {
     /// The data that was comitted to the log store
     pub data: CommitData,
     create_checkpoint: bool,
+    log_compaction_interval: Option<u64>,
     log_store: LogStoreRef,
     table_data: Option<&'a dyn TableReference>,
 }
@@ -608,6 +757,10 @@ impl<'a> PostCommit<'a> {
                 self.create_checkpoint(&state, &self.log_store, self.version)
                     .await?;
             }
+            if let Some(interval) = self.log_compaction_interval {
+                self.create_log_compaction(&self.log_store, self.version, interval as i64)
+                    .await?;
+            }
             Ok(state)
         } else {
             let state = DeltaTableState::try_new(
@@ -632,6 +785,18 @@ impl<'a> PostCommit<'a> {
         }
         Ok(())
     }
+
+    async fn create_log_compaction(
+        &self,
+        log_store: &LogStoreRef,
+        version: i64,
+        interval: i64,
+    ) -> DeltaResult<()> {
+        if interval > 1 && ((version + 1) % interval) == 0 {
+            create_log_compaction_for(version + 1 - interval, version, log_store.as_ref()).await?
+        }
+        Ok(())
+    }
 }
 
 /// A commit that successfully completed
@@ -726,4 +891,240 @@ mod tests {
         // succeeds for next version
         log_store.write_commit_entry(1, &tmp_path).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_commit_domain_metadata() {
+        use crate::kernel::{DataType, PrimitiveType, StructField};
+        use crate::protocol::SaveMode;
+        use crate::DeltaOps;
+
+        let domain = |name: &str, configuration: &str, removed: bool| DomainMetadata {
+            domain: name.to_string(),
+            configuration: configuration.to_string(),
+            removed,
+        };
+        fn commit(
+            table: &DeltaTableState,
+            log_store: LogStoreRef,
+            domains: Vec<DomainMetadata>,
+        ) -> Result<PreCommit<'_>, CommitBuilderError> {
+            CommitBuilder::default()
+                .with_domain_metadata(domains)
+                .build(
+                    Some(table as &dyn TableReference),
+                    log_store,
+                    DeltaOperation::Write {
+                        mode: SaveMode::Append,
+                        partition_by: None,
+                        predicate: None,
+                    },
+                )
+        }
+        let columns = vec![StructField::new(
+            "id".to_string(),
+            DataType::Primitive(PrimitiveType::Long),
+            true,
+        )];
+
+        // tables must support the domain metadata feature
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(columns.clone())
+            .await
+            .unwrap();
+        let result = commit(
+            table.snapshot().unwrap(),
+            table.log_store(),
+            vec![domain("test.domain", "{}", false)],
+        )
+        .unwrap()
+        .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::WriterFeaturesRequired(WriterFeatures::DomainMetadata)
+            })
+        ));
+
+        let mut table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(columns)
+            .with_actions([Action::Protocol(Protocol {
+                min_reader_version: 1,
+                min_writer_version: 7,
+                reader_features: None,
+                writer_features: Some([WriterFeatures::DomainMetadata].into()),
+            })])
+            .with_save_mode(SaveMode::ErrorIfExists)
+            .await
+            .unwrap();
+
+        // system-controlled domains and duplicate domains are rejected
+        let result = commit(
+            table.snapshot().unwrap(),
+            table.log_store(),
+            vec![domain("delta.rowTracking", "{}", false)],
+        );
+        assert!(matches!(
+            result,
+            Err(CommitBuilderError::ReservedDomainMetadata(_))
+        ));
+        let result = commit(
+            table.snapshot().unwrap(),
+            table.log_store(),
+            vec![
+                domain("test.domain", "{}", false),
+                domain("test.domain", "{}", false),
+            ],
+        );
+        assert!(matches!(
+            result,
+            Err(CommitBuilderError::DuplicateDomainMetadata(_))
+        ));
+
+        commit(
+            table.snapshot().unwrap(),
+            table.log_store(),
+            vec![
+                domain("test.domain", r#"{"value":1}"#, false),
+                domain("test.other", r#"{"value":2}"#, false),
+            ],
+        )
+        .unwrap()
+        .await
+        .unwrap();
+        table.load().await.unwrap();
+        let state = table.snapshot().unwrap();
+        assert_eq!(state.domain_metadata("test.domain"), Some(r#"{"value":1}"#));
+        assert_eq!(state.domain_metadata("test.missing"), None);
+
+        // concurrent updates of the same domain conflict
+        let read_state = table.snapshot().unwrap().clone();
+        let finalized = commit(
+            &read_state,
+            table.log_store(),
+            vec![domain("test.domain", r#"{"value":3}"#, false)],
+        )
+        .unwrap()
+        .await
+        .unwrap();
+        // the snapshot advanced by the commit sees the new domain configuration
+        assert_eq!(
+            finalized.snapshot.domain_metadata("test.domain"),
+            Some(r#"{"value":3}"#)
+        );
+        let result = commit(
+            &read_state,
+            table.log_store(),
+            vec![domain("test.domain", r#"{"value":4}"#, false)],
+        )
+        .unwrap()
+        .await;
+        assert!(matches!(
+            result,
+            Err(DeltaTableError::Transaction {
+                source: TransactionError::CommitConflict(
+                    CommitConflictError::DomainMetadataChanged(_)
+                )
+            })
+        ));
+
+        // domains are carried over into checkpoints, removed domains are dropped
+        commit(
+            &read_state,
+            table.log_store(),
+            vec![domain("test.other", r#"{"value":2}"#, true)],
+        )
+        .unwrap()
+        .await
+        .unwrap();
+        table.load().await.unwrap();
+        create_checkpoint_for(
+            table.version(),
+            table.snapshot().unwrap(),
+            table.log_store().as_ref(),
+        )
+        .await
+        .unwrap();
+        table.load().await.unwrap();
+        assert_eq!(
+            table.snapshot().unwrap().all_domain_metadata(),
+            vec![domain("test.domain", r#"{"value":3}"#, false)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_enable_row_tracking_on_existing_files() {
+        use crate::writer::test_utils::{get_delta_schema, get_record_batch};
+        use crate::DeltaOps;
+
+        fn set_properties<'a>(
+            table: &'a DeltaTableState,
+            log_store: LogStoreRef,
+            properties: &[(&str, &str)],
+        ) -> PreCommit<'a> {
+            let mut metadata = table.metadata().clone();
+            for (key, value) in properties {
+                metadata
+                    .configuration
+                    .insert(key.to_string(), Some(value.to_string()));
+            }
+            CommitBuilder::default()
+                .with_actions(vec![Action::Metadata(metadata)])
+                .build(
+                    Some(table as &dyn TableReference),
+                    log_store,
+                    DeltaOperation::SetTableProperties {
+                        properties: properties
+                            .iter()
+                            .map(|(key, value)| (key.to_string(), value.to_string()))
+                            .collect(),
+                    },
+                )
+                .unwrap()
+        }
+
+        // tables without files can enable row tracking
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(get_delta_schema().fields().clone())
+            .await
+            .unwrap();
+        let finalized = set_properties(
+            table.snapshot().unwrap(),
+            table.log_store(),
+            &[("delta.enableRowTracking", "true")],
+        )
+        .await
+        .unwrap();
+        assert!(row_tracking::supports_row_tracking(
+            finalized.snapshot.protocol()
+        ));
+
+        // files written before row tracking is supported have no row ids
+        let table = DeltaOps::new_in_memory()
+            .write(vec![get_record_batch(None, false)])
+            .await
+            .unwrap();
+        let result = set_properties(
+            table.snapshot().unwrap(),
+            table.log_store(),
+            &[("delta.enableRowTracking", "true")],
+        )
+        .await;
+        assert!(matches!(result, Err(DeltaTableError::Generic(_))));
+
+        // supporting the feature only assigns row ids to new files
+        let finalized = set_properties(
+            table.snapshot().unwrap(),
+            table.log_store(),
+            &[("delta.feature.rowTracking", "supported")],
+        )
+        .await
+        .unwrap();
+        assert!(row_tracking::supports_row_tracking(
+            finalized.snapshot.protocol()
+        ));
+        assert!(!finalized.snapshot.table_config().enable_row_tracking());
+    }
 }
@@ -1,13 +1,16 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use lazy_static::lazy_static;
 use once_cell::sync::Lazy;
 
+use super::row_tracking::ensure_row_tracking_enablement;
 use super::{TableReference, TransactionError};
+use crate::errors::DeltaResult;
 use crate::kernel::{
-    Action, DataType, EagerSnapshot, ReaderFeatures, Schema, StructField, WriterFeatures,
+    Action, DataType, EagerSnapshot, Protocol, ReaderFeatures, Schema, StructField, WriterFeatures,
 };
 use crate::protocol::DeltaOperation;
+use crate::table::config::{CheckpointPolicy, ColumnMappingMode, TableConfig};
 use crate::table::state::DeltaTableState;
 
 lazy_static! {
@@ -127,12 +130,17 @@ impl ProtocolChecker {
 
     /// Check if delta-rs can read form the given delta table.
     pub fn can_read_from(&self, snapshot: &dyn TableReference) -> Result<(), TransactionError> {
-        let required_features: Option<&HashSet<ReaderFeatures>> =
-            match snapshot.protocol().min_reader_version {
-                0 | 1 => None,
-                2 => Some(&READER_V2),
-                _ => snapshot.protocol().reader_features.as_ref(),
-            };
+        self.can_read_protocol(snapshot.protocol())
+    }
+
+    /// Check if delta-rs can read from a table with the given protocol.
+    pub fn can_read_protocol(&self, protocol: &Protocol) -> Result<(), TransactionError> {
+        let required_features: Option<&HashSet<ReaderFeatures>> = match protocol.min_reader_version
+        {
+            0 | 1 => None,
+            2 => Some(&READER_V2),
+            _ => protocol.reader_features.as_ref(),
+        };
         if let Some(features) = required_features {
             let mut diff = features.difference(&self.reader_features).peekable();
             if diff.peek().is_some() {
@@ -146,19 +154,24 @@ impl ProtocolChecker {
 
     /// Check if delta-rs can write to the given delta table.
     pub fn can_write_to(&self, snapshot: &dyn TableReference) -> Result<(), TransactionError> {
-        // NOTE: writers must always support all required reader features
-        self.can_read_from(snapshot)?;
+        self.can_write_protocol(snapshot.protocol())
+    }
 
-        let required_features: Option<&HashSet<WriterFeatures>> =
-            match snapshot.protocol().min_writer_version {
-                0 | 1 => None,
-                2 => Some(&WRITER_V2),
-                3 => Some(&WRITER_V3),
-                4 => Some(&WRITER_V4),
-                5 => Some(&WRITER_V5),
-                6 => Some(&WRITER_V6),
-                _ => snapshot.protocol().writer_features.as_ref(),
-            };
+    /// Check if delta-rs can write to a table with the given protocol.
+    pub fn can_write_protocol(&self, protocol: &Protocol) -> Result<(), TransactionError> {
+        // NOTE: writers must always support all required reader features
+        self.can_read_protocol(protocol)?;
+
+        let required_features: Option<&HashSet<WriterFeatures>> = match protocol.min_writer_version
+        {
+            0 | 1 => None,
+            2 => Some(&WRITER_V2),
+            3 => Some(&WRITER_V3),
+            4 => Some(&WRITER_V4),
+            5 => Some(&WRITER_V5),
+            6 => Some(&WRITER_V6),
+            _ => protocol.writer_features.as_ref(),
+        };
 
         if let Some(features) = required_features {
             let mut diff = features.difference(&self.writer_features).peekable();
@@ -179,6 +192,30 @@ impl ProtocolChecker {
     ) -> Result<(), TransactionError> {
         self.can_write_to(snapshot)?;
 
+        // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#domain-metadata
+        if actions
+            .iter()
+            .any(|action| matches!(action, Action::DomainMetadata(_)))
+        {
+            let protocol = actions
+                .iter()
+                .find_map(|action| match action {
+                    Action::Protocol(protocol) => Some(protocol),
+                    _ => None,
+                })
+                .unwrap_or(snapshot.protocol());
+            let supported = protocol
+                .writer_features
+                .as_ref()
+                .map(|features| features.contains(&WriterFeatures::DomainMetadata))
+                .unwrap_or_default();
+            if !supported {
+                return Err(TransactionError::WriterFeaturesRequired(
+                    WriterFeatures::DomainMetadata,
+                ));
+            }
+        }
+
         // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#append-only-tables
         let append_only_enabled = if snapshot.protocol().min_writer_version < 2 {
             false
@@ -213,6 +250,200 @@ impl ProtocolChecker {
     }
 }
 
+/// Returns the lowest legacy writer version that supports the feature, if any
+fn legacy_writer_version(feature: &WriterFeatures) -> Option<i32> {
+    match feature {
+        WriterFeatures::AppendOnly | WriterFeatures::Invariants => Some(2),
+        WriterFeatures::CheckConstraints => Some(3),
+        WriterFeatures::ChangeDataFeed | WriterFeatures::GeneratedColumns => Some(4),
+        WriterFeatures::ColumnMapping => Some(5),
+        WriterFeatures::IdentityColumns => Some(6),
+        _ => None,
+    }
+}
+
+/// Writer features supported by a legacy writer version
+fn legacy_writer_features(version: i32) -> HashSet<WriterFeatures> {
+    match version {
+        2 => WRITER_V2.clone(),
+        3 => WRITER_V3.clone(),
+        4 => WRITER_V4.clone(),
+        5 => WRITER_V5.clone(),
+        6 => WRITER_V6.clone(),
+        _ => HashSet::new(),
+    }
+}
+
+/// Returns the reader feature that is required along with a writer feature, if any
+fn reader_feature_for(feature: &WriterFeatures) -> Option<ReaderFeatures> {
+    match feature {
+        WriterFeatures::ColumnMapping => Some(ReaderFeatures::ColumnMapping),
+        WriterFeatures::DeletionVectors => Some(ReaderFeatures::DeletionVectors),
+        WriterFeatures::TimestampWithoutTimezone => Some(ReaderFeatures::TimestampWithoutTimezone),
+        WriterFeatures::V2Checkpoint => Some(ReaderFeatures::V2Checkpoint),
+        WriterFeatures::TypeWidening => Some(ReaderFeatures::TypeWidening),
+        _ => None,
+    }
+}
+
+/// Compute the minimal protocol that supports the features of the `current` protocol, the
+/// requested `features` and at least the given versions.
+///
+/// Legacy protocol versions are kept as long as they support all features. Otherwise the
+/// protocol is upgraded to writer version 7, and to reader version 3 if a reader feature is
+/// required, explicitly listing the features supported by the previous versions.
+///
+/// Returns `None` if the current protocol already satisfies all requirements.
+pub(crate) fn upgrade_protocol(
+    current: &Protocol,
+    min_reader_version: i32,
+    min_writer_version: i32,
+    features: impl IntoIterator<Item = WriterFeatures>,
+) -> Option<Protocol> {
+    let mut features: HashSet<WriterFeatures> = features.into_iter().collect();
+    // https://github.com/delta-io/delta/blob/master/PROTOCOL.md#row-tracking
+    if features.contains(&WriterFeatures::RowTracking) {
+        features.insert(WriterFeatures::DomainMetadata);
+    }
+    let supported = if current.min_writer_version >= 7 {
+        current.writer_features.clone().unwrap_or_default()
+    } else {
+        legacy_writer_features(current.min_writer_version)
+    };
+    features.retain(|feature| !supported.contains(feature));
+
+    let reader_version = current.min_reader_version.max(min_reader_version);
+    let writer_version = current.min_writer_version.max(min_writer_version);
+    if features.is_empty()
+        && reader_version == current.min_reader_version
+        && writer_version == current.min_writer_version
+    {
+        return None;
+    }
+    let reader_features: HashSet<ReaderFeatures> =
+        features.iter().filter_map(reader_feature_for).collect();
+
+    if reader_version < 3
+        && writer_version < 7
+        && features.iter().all(|f| legacy_writer_version(f).is_some())
+    {
+        let writer_version = features
+            .iter()
+            .filter_map(legacy_writer_version)
+            .fold(writer_version, i32::max);
+        let reader_version = if reader_features.is_empty() {
+            reader_version
+        } else {
+            reader_version.max(2)
+        };
+        return Some(Protocol::new(reader_version, writer_version));
+    }
+
+    let mut writer_features = supported;
+    writer_features.extend(features);
+    let mut protocol = Protocol::new(reader_version, 7);
+    protocol.writer_features = Some(writer_features);
+    if reader_version >= 3 || !reader_features.is_subset(&READER_V2) {
+        let mut features = match current.min_reader_version {
+            0 | 1 => HashSet::new(),
+            2 => READER_V2.clone(),
+            _ => current.reader_features.clone().unwrap_or_default(),
+        };
+        features.extend(reader_features);
+        protocol.min_reader_version = 3;
+        protocol.reader_features = Some(features);
+    } else if !reader_features.is_empty() {
+        protocol.min_reader_version = reader_version.max(2);
+    }
+    Some(protocol)
+}
+
+/// Returns the features required by table properties
+///
+/// Besides the properties enabling a feature, e.g. `delta.enableChangeDataFeed`, features
+/// can be required explicitly by setting `delta.feature.<name>` to `supported`.
+pub(crate) fn features_for_configuration(
+    configuration: &HashMap<String, Option<String>>,
+) -> HashSet<WriterFeatures> {
+    let config = TableConfig(configuration);
+    let mut features = HashSet::new();
+    let enabled = [
+        (config.append_only(), WriterFeatures::AppendOnly),
+        (
+            config.enable_change_data_feed(),
+            WriterFeatures::ChangeDataFeed,
+        ),
+        (
+            config.enable_deletion_vectors(),
+            WriterFeatures::DeletionVectors,
+        ),
+        (config.enable_row_tracking(), WriterFeatures::RowTracking),
+        (config.enable_type_widening(), WriterFeatures::TypeWidening),
+        (
+            config.enable_in_commit_timestamps(),
+            WriterFeatures::InCommitTimestamp,
+        ),
+        (
+            config.checkpoint_policy() == CheckpointPolicy::V2,
+            WriterFeatures::V2Checkpoint,
+        ),
+        (
+            config.column_mapping_mode() != ColumnMappingMode::None,
+            WriterFeatures::ColumnMapping,
+        ),
+    ];
+    for (enabled, feature) in enabled {
+        if enabled {
+            features.insert(feature);
+        }
+    }
+    for (key, value) in configuration {
+        if let (Some(name), Some(value)) = (key.strip_prefix("delta.feature."), value) {
+            if value.eq_ignore_ascii_case("supported") || value.eq_ignore_ascii_case("enabled") {
+                features.insert(WriterFeatures::from(name));
+            }
+        }
+    }
+    features
+}
+
+/// Returns the upgraded protocol if a commit sets table properties that require features
+/// the protocol of the table does not support yet
+///
+/// Fails if the table properties enable row tracking on a table with files without row ids.
+/// Commits that contain a protocol action are expected to handle the protocol themselves.
+pub(crate) fn protocol_for_metadata_update(
+    table: &dyn TableReference,
+    actions: &[Action],
+) -> DeltaResult<Option<Protocol>> {
+    if actions
+        .iter()
+        .any(|action| matches!(action, Action::Protocol(_)))
+    {
+        return Ok(None);
+    }
+    let Some(metadata) = actions.iter().find_map(|action| match action {
+        Action::Metadata(metadata) => Some(metadata),
+        _ => None,
+    }) else {
+        return Ok(None);
+    };
+    let current = &table.metadata().configuration;
+    let changed = metadata
+        .configuration
+        .iter()
+        .filter(|(key, value)| current.get(*key) != Some(*value))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    ensure_row_tracking_enablement(table.eager_snapshot(), &changed, actions)?;
+    Ok(upgrade_protocol(
+        table.protocol(),
+        0,
+        0,
+        features_for_configuration(&changed),
+    ))
+}
+
 /// The global protocol checker instance to validate table versions and features.
 ///
 /// This instance is used by default in all transaction operations, since feature
@@ -223,20 +454,33 @@ impl ProtocolChecker {
 pub static INSTANCE: Lazy<ProtocolChecker> = Lazy::new(|| {
     let mut reader_features = HashSet::new();
     reader_features.insert(ReaderFeatures::TimestampWithoutTimezone);
-    // reader_features.insert(ReaderFeatures::ColumnMapping);
+    reader_features.insert(ReaderFeatures::V2Checkpoint);
+    #[cfg(feature = "datafusion")]
+    {
+        reader_features.insert(ReaderFeatures::DeletionVectors);
+        reader_features.insert(ReaderFeatures::ColumnMapping);
+        reader_features.insert(ReaderFeatures::TypeWidening);
+    }
 
     let mut writer_features = HashSet::new();
     writer_features.insert(WriterFeatures::AppendOnly);
     writer_features.insert(WriterFeatures::TimestampWithoutTimezone);
+    writer_features.insert(WriterFeatures::DomainMetadata);
+    writer_features.insert(WriterFeatures::RowTracking);
+    writer_features.insert(WriterFeatures::V2Checkpoint);
+    writer_features.insert(WriterFeatures::InCommitTimestamp);
+    writer_features.insert(WriterFeatures::CheckpointProtection);
     #[cfg(feature = "datafusion")]
     {
         writer_features.insert(WriterFeatures::Invariants);
         writer_features.insert(WriterFeatures::CheckConstraints);
+        writer_features.insert(WriterFeatures::DeletionVectors);
+        writer_features.insert(WriterFeatures::ColumnMapping);
+        writer_features.insert(WriterFeatures::GeneratedColumns);
+        writer_features.insert(WriterFeatures::IdentityColumns);
+        writer_features.insert(WriterFeatures::ChangeDataFeed);
+        writer_features.insert(WriterFeatures::TypeWidening);
     }
-    // writer_features.insert(WriterFeatures::ChangeDataFeed);
-    // writer_features.insert(WriterFeatures::GeneratedColumns);
-    // writer_features.insert(WriterFeatures::ColumnMapping);
-    // writer_features.insert(WriterFeatures::IdentityColumns);
 
     ProtocolChecker::new(reader_features, writer_features)
 });
@@ -554,4 +798,63 @@ mod tests {
         assert!(checker_7.can_read_from(eager_7).is_ok());
         assert!(checker_7.can_write_to(eager_7).is_ok());
     }
+
+    #[test]
+    fn test_upgrade_protocol() {
+        let legacy = Protocol::new(1, 2);
+        assert_eq!(upgrade_protocol(&legacy, 1, 2, []), None);
+        assert_eq!(
+            upgrade_protocol(&legacy, 0, 0, [WriterFeatures::ColumnMapping]),
+            Some(Protocol::new(2, 5))
+        );
+
+        // column mapping keeps reader version 2 when upgrading to writer version 7
+        let column_mapping = Protocol::new(2, 5);
+        let upgraded =
+            upgrade_protocol(&column_mapping, 0, 0, [WriterFeatures::InCommitTimestamp]).unwrap();
+        assert_eq!(upgraded.min_reader_version, 2);
+        assert_eq!(upgraded.reader_features, None);
+        assert_eq!(upgraded.min_writer_version, 7);
+        assert!(upgraded
+            .writer_features
+            .as_ref()
+            .unwrap()
+            .is_superset(&WRITER_V5));
+
+        let upgraded =
+            upgrade_protocol(&column_mapping, 0, 0, [WriterFeatures::TypeWidening]).unwrap();
+        assert_eq!(upgraded.min_reader_version, 3);
+        assert_eq!(
+            upgraded.reader_features,
+            Some(HashSet::from([
+                ReaderFeatures::ColumnMapping,
+                ReaderFeatures::TypeWidening
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_features_for_configuration() {
+        let configuration = HashMap::from([
+            (
+                DeltaConfigKey::EnableRowTracking.as_ref().to_string(),
+                Some("true".to_string()),
+            ),
+            (
+                DeltaConfigKey::EnableDeletionVectors.as_ref().to_string(),
+                Some("false".to_string()),
+            ),
+            (
+                "delta.feature.timestampNtz".to_string(),
+                Some("supported".to_string()),
+            ),
+        ]);
+        assert_eq!(
+            features_for_configuration(&configuration),
+            HashSet::from([
+                WriterFeatures::RowTracking,
+                WriterFeatures::TimestampWithoutTimezone
+            ])
+        );
+    }
 }
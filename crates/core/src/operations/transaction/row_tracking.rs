@@ -0,0 +1,288 @@
+//! Row tracking
+//!
+//! When the `rowTracking` table feature is supported, every file added to the table is assigned
+//! a `baseRowId` and a `defaultRowCommitVersion`. The row id of a row is its `baseRowId` plus
+//! its position within the file, unless a stable row id was materialized into the data file.
+//! The highest row id assigned so far is tracked in the `delta.rowTracking` metadata domain.
+//!
+//! See <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#row-tracking>
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{DeltaResult, DeltaTableError};
+use crate::kernel::{Action, DomainMetadata, EagerSnapshot, Protocol, WriterFeatures};
+use crate::table::config::TableConfig;
+
+/// The metadata domain tracking the row id high water mark
+pub(crate) const ROW_TRACKING_DOMAIN: &str = "delta.rowTracking";
+/// Table property holding the name of the data column materialized row ids are stored in
+pub(crate) const MATERIALIZED_ROW_ID_COLUMN_NAME: &str =
+    "delta.rowTracking.materializedRowIdColumnName";
+/// Table property holding the name of the data column materialized row commit versions are stored in
+pub(crate) const MATERIALIZED_ROW_COMMIT_VERSION_COLUMN_NAME: &str =
+    "delta.rowTracking.materializedRowCommitVersionColumnName";
+
+/// Configuration of the `delta.rowTracking` metadata domain
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RowTrackingDomain {
+    pub row_id_high_water_mark: i64,
+}
+
+impl RowTrackingDomain {
+    fn try_from_domain_metadata(domain: &DomainMetadata) -> DeltaResult<Self> {
+        serde_json::from_str(&domain.configuration).map_err(|err| {
+            DeltaTableError::Generic(format!(
+                "Invalid configuration for domain {ROW_TRACKING_DOMAIN}: {err}"
+            ))
+        })
+    }
+
+    fn to_domain_metadata(self) -> DeltaResult<DomainMetadata> {
+        Ok(DomainMetadata {
+            domain: ROW_TRACKING_DOMAIN.to_string(),
+            configuration: serde_json::to_string(&self)?,
+            removed: false,
+        })
+    }
+}
+
+/// Returns true if writers must assign row ids for the given protocol
+pub(crate) fn supports_row_tracking(protocol: &Protocol) -> bool {
+    protocol
+        .writer_features
+        .as_ref()
+        .map(|features| features.contains(&WriterFeatures::RowTracking))
+        .unwrap_or_default()
+}
+
+/// Fail if the changed table properties enable row tracking while the table keeps files that
+/// have no row ids.
+///
+/// Every file of a table with row tracking enabled must have row ids, and assigning them to
+/// existing files is not supported. Setting `delta.feature.rowTracking` to `supported` instead
+/// only assigns row ids to the files added from then on.
+pub(crate) fn ensure_row_tracking_enablement(
+    snapshot: &EagerSnapshot,
+    changed: &HashMap<String, Option<String>>,
+    actions: &[Action],
+) -> DeltaResult<()> {
+    if !TableConfig(changed).enable_row_tracking() {
+        return Ok(());
+    }
+    let removed: HashSet<&str> = actions
+        .iter()
+        .filter_map(|action| match action {
+            Action::Remove(remove) => Some(remove.path.as_str()),
+            _ => None,
+        })
+        .collect();
+    if let Some(file) = snapshot
+        .files()
+        .find(|file| file.base_row_id().is_none() && !removed.contains(file.path().as_ref()))
+    {
+        return Err(DeltaTableError::Generic(format!(
+            "Cannot enable row tracking on a table with files without row ids, found {}",
+            file.path()
+        )));
+    }
+    Ok(())
+}
+
+/// Generate names for the columns row ids and row commit versions are materialized in,
+/// unless they are already configured
+pub(crate) fn with_materialized_column_names(configuration: &mut HashMap<String, Option<String>>) {
+    for (key, prefix) in [
+        (MATERIALIZED_ROW_ID_COLUMN_NAME, "_row-id-col-"),
+        (
+            MATERIALIZED_ROW_COMMIT_VERSION_COLUMN_NAME,
+            "_row-commit-version-col-",
+        ),
+    ] {
+        configuration
+            .entry(key.to_string())
+            .or_insert_with(|| Some(format!("{prefix}{}", uuid::Uuid::new_v4())));
+    }
+}
+
+/// Get the highest row id assigned in the table so far
+///
+/// Next to the `delta.rowTracking` domain, the row ids of all active files are considered,
+/// so row ids are never reused even if the domain is missing or lagging behind.
+pub(crate) fn read_high_water_mark(snapshot: &EagerSnapshot) -> DeltaResult<Option<i64>> {
+    let from_domain = snapshot
+        .snapshot()
+        .domain_metadata(ROW_TRACKING_DOMAIN)
+        .map(RowTrackingDomain::try_from_domain_metadata)
+        .transpose()?
+        .map(|domain| domain.row_id_high_water_mark);
+    let from_files = snapshot
+        .files()
+        .filter_map(|file| {
+            let base_row_id = file.base_row_id()?;
+            Some(base_row_id + file.num_records().unwrap_or_default() as i64 - 1)
+        })
+        .max();
+    Ok(from_domain.max(from_files))
+}
+
+/// Get the highest row id assigned by the given actions
+pub(crate) fn high_water_mark_from_actions<'a>(
+    actions: impl IntoIterator<Item = &'a Action>,
+) -> DeltaResult<Option<i64>> {
+    let mut high_water_mark = None;
+    for action in actions {
+        let value = match action {
+            Action::DomainMetadata(domain) if domain.domain == ROW_TRACKING_DOMAIN => {
+                Some(RowTrackingDomain::try_from_domain_metadata(domain)?.row_id_high_water_mark)
+            }
+            Action::Add(add) => match add.base_row_id {
+                Some(base_row_id) => Some(base_row_id + num_records(add)? - 1),
+                None => None,
+            },
+            _ => None,
+        };
+        high_water_mark = high_water_mark.max(value);
+    }
+    Ok(high_water_mark)
+}
+
+fn num_records(add: &crate::kernel::Add) -> DeltaResult<i64> {
+    add.get_stats()?
+        .map(|stats| stats.num_records)
+        .ok_or_else(|| {
+            DeltaTableError::Generic(format!(
+                "Cannot assign row ids to file {} without a record count",
+                add.path
+            ))
+        })
+}
+
+/// Assigns fresh row ids to the files added by a commit
+#[derive(Debug, Clone)]
+pub(crate) struct RowIdAssigner {
+    /// Positions of the add actions that need fresh row ids
+    files: Vec<usize>,
+}
+
+impl RowIdAssigner {
+    /// Create an assigner for the given actions, if the protocol requires row tracking
+    ///
+    /// Files that already carry a `baseRowId`, e.g. when re-added with a deletion vector,
+    /// keep their row ids.
+    pub fn try_new(actions: &[Action], table_protocol: Option<&Protocol>) -> Option<Self> {
+        let protocol = actions
+            .iter()
+            .find_map(|action| match action {
+                Action::Protocol(protocol) => Some(protocol),
+                _ => None,
+            })
+            .or(table_protocol)?;
+        if !supports_row_tracking(protocol) {
+            return None;
+        }
+        let files = actions
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, action)| match action {
+                Action::Add(add) if add.base_row_id.is_none() => Some(idx),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        (!files.is_empty()).then_some(Self { files })
+    }
+
+    /// Assign row ids following the `high_water_mark` and the commit version to the new files
+    ///
+    /// The `delta.rowTracking` domain is updated with the new high water mark. Assigning again,
+    /// e.g. when retrying a commit at a later version, overwrites previously assigned values.
+    pub fn assign(
+        &self,
+        actions: &mut Vec<Action>,
+        high_water_mark: Option<i64>,
+        version: i64,
+    ) -> DeltaResult<()> {
+        let mut high_water_mark = high_water_mark.unwrap_or(-1);
+        for idx in &self.files {
+            if let Some(Action::Add(add)) = actions.get_mut(*idx) {
+                let num_records = num_records(add)?;
+                add.base_row_id = Some(high_water_mark + 1);
+                add.default_row_commit_version = Some(version);
+                high_water_mark += num_records;
+            }
+        }
+
+        let domain = RowTrackingDomain {
+            row_id_high_water_mark: high_water_mark,
+        }
+        .to_domain_metadata()?;
+        match actions.iter_mut().find(
+            |action| matches!(action, Action::DomainMetadata(d) if d.domain == ROW_TRACKING_DOMAIN),
+        ) {
+            Some(action) => *action = Action::DomainMetadata(domain),
+            None => actions.push(Action::DomainMetadata(domain)),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::Add;
+
+    fn add(path: &str, num_records: i64) -> Action {
+        Action::Add(Add {
+            path: path.to_string(),
+            stats: Some(format!("{{\"numRecords\":{num_records}}}")),
+            ..Default::default()
+        })
+    }
+
+    fn protocol() -> Protocol {
+        Protocol {
+            min_reader_version: 1,
+            min_writer_version: 7,
+            reader_features: None,
+            writer_features: Some(
+                [WriterFeatures::RowTracking, WriterFeatures::DomainMetadata].into(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_assign_row_ids() {
+        let mut actions = vec![add("a", 3), add("b", 2)];
+        assert!(RowIdAssigner::try_new(&actions, None).is_none());
+        assert!(RowIdAssigner::try_new(&actions, Some(&Protocol::default())).is_none());
+
+        let assigner = RowIdAssigner::try_new(&actions, Some(&protocol())).unwrap();
+        assigner.assign(&mut actions, None, 0).unwrap();
+        let base_row_ids = actions
+            .iter()
+            .filter_map(|a| match a {
+                Action::Add(add) => Some((add.base_row_id, add.default_row_commit_version)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(base_row_ids, vec![(Some(0), Some(0)), (Some(3), Some(0))]);
+        assert_eq!(high_water_mark_from_actions(&actions).unwrap(), Some(4));
+
+        // re-assigning replaces the previous assignment and domain
+        assigner.assign(&mut actions, Some(9), 2).unwrap();
+        assert_eq!(actions.len(), 3);
+        assert!(matches!(
+            &actions[1],
+            Action::Add(add) if add.base_row_id == Some(13) && add.default_row_commit_version == Some(2)
+        ));
+        assert_eq!(high_water_mark_from_actions(&actions).unwrap(), Some(14));
+    }
+
+    #[test]
+    fn test_assign_requires_num_records() {
+        let mut actions = vec![Action::Add(Add::default())];
+        let assigner = RowIdAssigner::try_new(&actions, Some(&protocol())).unwrap();
+        assert!(assigner.assign(&mut actions, None, 0).is_err());
+    }
+}
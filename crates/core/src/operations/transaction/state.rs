@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use arrow::array::{ArrayRef, BooleanArray};
@@ -13,13 +13,18 @@ use itertools::Itertools;
 use object_store::ObjectStore;
 use parquet::arrow::arrow_reader::ArrowReaderOptions;
 use parquet::arrow::async_reader::{ParquetObjectReader, ParquetRecordBatchStreamBuilder};
+use url::Url;
 
 use crate::delta_datafusion::{
     get_null_of_arrow_type, logical_expr_to_physical_expr, to_correct_scalar_value,
     DataFusionMixins,
 };
-use crate::errors::DeltaResult;
-use crate::kernel::{Add, EagerSnapshot};
+use crate::errors::{DeltaResult, DeltaTableError};
+use crate::kernel::{Add, ColumnMetadataKey, EagerSnapshot, StructType};
+use crate::logstore::LogStore;
+use crate::storage::utils::is_absolute_path;
+use crate::storage::{root_store_for, store_root, StorageOptions};
+use crate::table::config::ColumnMappingMode;
 use crate::table::state::DeltaTableState;
 
 impl DeltaTableState {
@@ -27,15 +32,48 @@ impl DeltaTableState {
     ///
     /// This will construct a schema derived from the parquet schema of the latest data file,
     /// and fields for partition columns from the schema defined in table meta data.
+    ///
+    /// Columns which have been widened keep the type defined in the table meta data, so
+    /// files written before the type change are upcast when they are read.
+    ///
+    /// Files referenced by absolute URIs, e.g. in shallow clones, are read with the default
+    /// storage options of their store.
     pub async fn physical_arrow_schema(
         &self,
         object_store: Arc<dyn ObjectStore>,
+    ) -> DeltaResult<ArrowSchemaRef> {
+        self.physical_arrow_schema_from(object_store, &StorageOptions::default())
+            .await
+    }
+
+    /// Get the physical table schema, reading files referenced by absolute URIs with the
+    /// storage options of the log store.
+    pub(crate) async fn physical_arrow_schema_for(
+        &self,
+        log_store: &dyn LogStore,
+    ) -> DeltaResult<ArrowSchemaRef> {
+        self.physical_arrow_schema_from(log_store.object_store(), &log_store.config().options)
+            .await
+    }
+
+    async fn physical_arrow_schema_from(
+        &self,
+        object_store: Arc<dyn ObjectStore>,
+        options: &StorageOptions,
     ) -> DeltaResult<ArrowSchemaRef> {
         if let Some(add) = self
             .file_actions()?
             .iter()
             .max_by_key(|obj| obj.modification_time)
         {
+            // files referenced by absolute URIs are read from the store of their root
+            let object_store = if is_absolute_path(&add.path)? {
+                let location = Url::parse(&add.path)
+                    .map_err(|_| DeltaTableError::InvalidTableLocation(add.path.clone()))?;
+                root_store_for(&store_root(&location), options)?
+            } else {
+                object_store
+            };
             let file_meta = add.try_into()?;
             let file_reader = ParquetObjectReader::new(object_store, file_meta);
             let file_schema = ParquetRecordBatchStreamBuilder::new_with_options(
@@ -55,6 +93,9 @@ impl DeltaTableState {
                     .map(|field| {
                         // field is an &Arc<Field>
                         let owned_field: ArrowField = field.as_ref().clone();
+                        if has_type_changes(&owned_field) {
+                            return owned_field;
+                        }
                         file_schema
                             .field_with_name(field.name())
                             // yielded with &Field
@@ -71,10 +112,29 @@ impl DeltaTableState {
     }
 }
 
+/// Determine if the type of the field or any of its nested fields has been widened.
+fn has_type_changes(field: &ArrowField) -> bool {
+    if field
+        .metadata()
+        .contains_key(ColumnMetadataKey::TypeChanges.as_ref())
+    {
+        return true;
+    }
+    match field.data_type() {
+        DataType::Struct(fields) => fields.iter().any(|f| has_type_changes(f)),
+        DataType::List(element) | DataType::LargeList(element) | DataType::Map(element, _) => {
+            has_type_changes(element)
+        }
+        _ => false,
+    }
+}
+
 pub struct AddContainer<'a> {
     inner: &'a Vec<Add>,
     partition_columns: &'a Vec<String>,
     schema: ArrowSchemaRef,
+    /// Names used in statistics and partition values for columns renamed by column mapping
+    physical_names: HashMap<String, String>,
 }
 
 impl<'a> AddContainer<'a> {
@@ -88,9 +148,34 @@ impl<'a> AddContainer<'a> {
             inner: adds,
             partition_columns,
             schema,
+            physical_names: HashMap::new(),
         }
     }
 
+    /// Resolve statistics and partition values by the physical column names of the table schema.
+    pub(crate) fn with_column_mapping(
+        mut self,
+        table_schema: &StructType,
+        mode: ColumnMappingMode,
+    ) -> DeltaResult<Self> {
+        self.physical_names = table_schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let physical_name = field.physical_name_for(mode)?.to_string();
+                Ok((field.name().clone(), physical_name))
+            })
+            .collect::<DeltaResult<_>>()?;
+        Ok(self)
+    }
+
+    fn physical_name<'b>(&'b self, column: &'b Column) -> &'b str {
+        self.physical_names
+            .get(&column.name)
+            .map(String::as_str)
+            .unwrap_or(&column.name)
+    }
+
     pub fn get_prune_stats(&self, column: &Column, get_max: bool) -> Option<ArrayRef> {
         let (_, field) = self.schema.column_with_name(&column.name)?;
 
@@ -100,10 +185,11 @@ impl<'a> AddContainer<'a> {
         }
 
         let data_type = field.data_type();
+        let physical_name = self.physical_name(column);
 
         let values = self.inner.iter().map(|add| {
             if self.partition_columns.contains(&column.name) {
-                let value = add.partition_values.get(&column.name).unwrap();
+                let value = add.partition_values.get(physical_name).unwrap();
                 let value = match value {
                     Some(v) => serde_json::Value::String(v.to_string()),
                     None => serde_json::Value::Null,
@@ -122,7 +208,7 @@ impl<'a> AddContainer<'a> {
                 };
 
                 values
-                    .get(&column.name)
+                    .get(physical_name)
                     .and_then(|f| {
                         to_correct_scalar_value(f.as_value()?, data_type)
                             .ok()
@@ -186,10 +272,11 @@ impl<'a> PruningStatistics for AddContainer<'a> {
     ///
     /// Note: the returned array must contain `num_containers()` rows.
     fn null_counts(&self, column: &Column) -> Option<ArrayRef> {
+        let physical_name = self.physical_name(column);
         let values = self.inner.iter().map(|add| {
             if let Ok(Some(statistics)) = add.get_stats() {
                 if self.partition_columns.contains(&column.name) {
-                    let value = add.partition_values.get(&column.name).unwrap();
+                    let value = add.partition_values.get(physical_name).unwrap();
                     match value {
                         Some(_) => ScalarValue::UInt64(Some(0)),
                         None => ScalarValue::UInt64(Some(statistics.num_records as u64)),
@@ -197,12 +284,12 @@ impl<'a> PruningStatistics for AddContainer<'a> {
                 } else {
                     statistics
                         .null_count
-                        .get(&column.name)
+                        .get(physical_name)
                         .map(|f| ScalarValue::UInt64(f.as_value().map(|val| val as u64)))
                         .unwrap_or(ScalarValue::UInt64(None))
                 }
             } else if self.partition_columns.contains(&column.name) {
-                let value = add.partition_values.get(&column.name).unwrap();
+                let value = add.partition_values.get(physical_name).unwrap();
                 match value {
                     Some(_) => ScalarValue::UInt64(Some(0)),
                     None => ScalarValue::UInt64(None),
@@ -242,7 +329,9 @@ impl PruningStatistics for EagerSnapshot {
     fn min_values(&self, column: &Column) -> Option<ArrayRef> {
         let files = self.file_actions().ok()?.collect_vec();
         let partition_columns = &self.metadata().partition_columns;
-        let container = AddContainer::new(&files, partition_columns, self.arrow_schema().ok()?);
+        let container = AddContainer::new(&files, partition_columns, self.arrow_schema().ok()?)
+            .with_column_mapping(self.schema(), self.table_config().column_mapping_mode())
+            .ok()?;
         container.min_values(column)
     }
 
@@ -251,7 +340,9 @@ impl PruningStatistics for EagerSnapshot {
     fn max_values(&self, column: &Column) -> Option<ArrayRef> {
         let files = self.file_actions().ok()?.collect_vec();
         let partition_columns = &self.metadata().partition_columns;
-        let container = AddContainer::new(&files, partition_columns, self.arrow_schema().ok()?);
+        let container = AddContainer::new(&files, partition_columns, self.arrow_schema().ok()?)
+            .with_column_mapping(self.schema(), self.table_config().column_mapping_mode())
+            .ok()?;
         container.max_values(column)
     }
 
@@ -268,7 +359,9 @@ impl PruningStatistics for EagerSnapshot {
     fn null_counts(&self, column: &Column) -> Option<ArrayRef> {
         let files = self.file_actions().ok()?.collect_vec();
         let partition_columns = &self.metadata().partition_columns;
-        let container = AddContainer::new(&files, partition_columns, self.arrow_schema().ok()?);
+        let container = AddContainer::new(&files, partition_columns, self.arrow_schema().ok()?)
+            .with_column_mapping(self.schema(), self.table_config().column_mapping_mode())
+            .ok()?;
         container.null_counts(column)
     }
 
@@ -279,7 +372,9 @@ impl PruningStatistics for EagerSnapshot {
     fn row_counts(&self, column: &Column) -> Option<ArrayRef> {
         let files = self.file_actions().ok()?.collect_vec();
         let partition_columns = &self.metadata().partition_columns;
-        let container = AddContainer::new(&files, partition_columns, self.arrow_schema().ok()?);
+        let container = AddContainer::new(&files, partition_columns, self.arrow_schema().ok()?)
+            .with_column_mapping(self.schema(), self.table_config().column_mapping_mode())
+            .ok()?;
         container.row_counts(column)
     }
 
@@ -28,7 +28,10 @@ use arrow::datatypes::Schema as ArrowSchema;
 use arrow_schema::Field;
 use datafusion::{
     execution::context::SessionState,
-    physical_plan::{metrics::MetricBuilder, projection::ProjectionExec, ExecutionPlan},
+    physical_plan::{
+        filter::FilterExec, metrics::MetricBuilder, projection::ProjectionExec, union::UnionExec,
+        ExecutionPlan,
+    },
     prelude::SessionContext,
 };
 use datafusion_common::{Column, DFSchema, ScalarValue};
@@ -41,17 +44,25 @@ use futures::future::BoxFuture;
 use parquet::file::properties::WriterProperties;
 use serde::Serialize;
 
-use super::write::write_execution_plan;
+use super::cdc::{
+    should_write_cdc, with_change_type, CHANGE_TYPE_UPDATE_POSTIMAGE, CHANGE_TYPE_UPDATE_PREIMAGE,
+};
+use super::deletion_vector::{use_deletion_vectors, write_deletion_vectors};
+use super::generated_columns::generated_column_assignments;
+use super::identity_columns::IdentityColumnsGenerator;
+use super::write::{write_execution_plan, write_execution_plan_cdc};
 use super::{
     datafusion_utils::Expression,
     transaction::{CommitBuilder, CommitProperties},
 };
 use super::{transaction::PROTOCOL, write::WriterStatsConfig};
 use crate::delta_datafusion::{
-    create_physical_expr_fix, expr::fmt_expr_to_sql, physical::MetricObserverExec,
-    DataFusionMixins, DeltaColumn, DeltaSessionContext,
+    create_physical_expr_fix, deletion_vector::DeletedRowsCollectorExec, expr::fmt_expr_to_sql,
+    physical::MetricObserverExec, DataFusionMixins, DeltaColumn, DeltaSessionContext,
+};
+use crate::delta_datafusion::{
+    find_files, register_store, DeltaScanBuilder, DeltaScanConfigBuilder,
 };
-use crate::delta_datafusion::{find_files, register_store, DeltaScanBuilder};
 use crate::kernel::{Action, Remove};
 use crate::logstore::LogStoreRef;
 use crate::protocol::DeltaOperation;
@@ -91,6 +102,8 @@ pub struct UpdateMetrics {
     pub num_updated_rows: usize,
     /// Number of rows just copied over in the process of updating files.
     pub num_copied_rows: usize,
+    /// Number of files that were re-added with a deletion vector.
+    pub num_deletion_vectors_added: usize,
     /// Time taken to execute the entire operation.
     pub execution_time_ms: u64,
     /// Time taken to scan the files for matches.
@@ -163,6 +176,39 @@ impl UpdateBuilder {
     }
 }
 
+/// Build the previous and the new values of the updated records as change data.
+///
+/// The plan yields the updated records with their previous values in the table columns,
+/// and the new values of the updated columns at the indices in `updated_columns`.
+fn change_data_plan(
+    snapshot: &DeltaTableState,
+    updated: Arc<dyn ExecutionPlan>,
+    updated_columns: &HashMap<String, usize>,
+) -> DeltaResult<Arc<dyn ExecutionPlan>> {
+    let schema = updated.schema();
+    let mut preimage: Vec<(Arc<dyn PhysicalExpr>, String)> = Vec::new();
+    let mut postimage: Vec<(Arc<dyn PhysicalExpr>, String)> = Vec::new();
+    for field in snapshot.input_schema()?.fields() {
+        let name = field.name();
+        let index = schema.index_of(name)?;
+        preimage.push((
+            Arc::new(expressions::Column::new(name, index)),
+            name.to_owned(),
+        ));
+        let index = updated_columns.get(name).copied().unwrap_or(index);
+        postimage.push((
+            Arc::new(expressions::Column::new(name, index)),
+            name.to_owned(),
+        ));
+    }
+    let preimage = Arc::new(ProjectionExec::try_new(preimage, updated.clone())?);
+    let postimage = Arc::new(ProjectionExec::try_new(postimage, updated)?);
+    Ok(Arc::new(UnionExec::new(vec![
+        with_change_type(preimage, CHANGE_TYPE_UPDATE_PREIMAGE)?,
+        with_change_type(postimage, CHANGE_TYPE_UPDATE_POSTIMAGE)?,
+    ])))
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn execute(
     predicate: Option<Expression>,
@@ -199,7 +245,7 @@ async fn execute(
         None => None,
     };
 
-    let updates: HashMap<Column, Expr> = updates
+    let mut updates: HashMap<Column, Expr> = updates
         .into_iter()
         .map(|(key, expr)| match expr {
             Expression::DataFusion(e) => Ok((key, e)),
@@ -209,6 +255,24 @@ async fn execute(
         })
         .collect::<Result<HashMap<Column, Expr>, _>>()?;
 
+    if let Some(generator) = IdentityColumnsGenerator::try_new(snapshot.schema())? {
+        generator.check_update(updates.keys().map(|column| column.name.as_str()))?;
+    }
+
+    // Generated columns that are not updated explicitly are computed from the updated values
+    let generated_columns = snapshot.schema().get_generated_columns();
+    if !generated_columns.is_empty() {
+        let table_schema: DFSchema = snapshot.input_schema()?.as_ref().clone().try_into()?;
+        let generated = generated_column_assignments(
+            &generated_columns,
+            &table_schema,
+            &updates,
+            None,
+            &state,
+        )?;
+        updates.extend(generated);
+    }
+
     let current_metadata = snapshot.metadata();
     let table_partition_cols = current_metadata.partition_columns.clone();
 
@@ -223,10 +287,20 @@ async fn execute(
     let predicate = predicate.unwrap_or(Expr::Literal(ScalarValue::Boolean(Some(true))));
 
     let execution_props = state.execution_props();
+
+    // With deletion vectors enabled only the updated records are written, while their
+    // previous versions are marked as deleted in the files they are contained in.
+    let deletion_vectors = use_deletion_vectors(&snapshot);
+    let scan_config = DeltaScanConfigBuilder::new()
+        .with_file_column(deletion_vectors)
+        .with_row_index_column(deletion_vectors)
+        .build(&snapshot)?;
+
     // For each rewrite evaluate the predicate and then modify each expression
     // to either compute the new value or obtain the old one then write these batches
     let scan = DeltaScanBuilder::new(&snapshot, log_store.clone(), &state)
         .with_files(&candidates.candidates)
+        .with_scan_config(scan_config.clone())
         .build()
         .await?;
     let scan = Arc::new(scan);
@@ -238,6 +312,16 @@ async fn execute(
     for field in input_schema.fields.iter() {
         fields.push(field.to_owned());
     }
+    let scan_schema = scan.schema();
+    for name in [
+        &scan_config.file_column_name,
+        &scan_config.row_index_column_name,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        fields.push(Arc::new(scan_schema.field_with_name(name)?.to_owned()));
+    }
     fields.push(Arc::new(Field::new(
         "__delta_rs_update_predicate",
         arrow_schema::DataType::Boolean,
@@ -288,13 +372,43 @@ async fn execute(
         },
     ));
 
+    let predicate_column: Arc<dyn PhysicalExpr> = Arc::new(expressions::Column::new(
+        "__delta_rs_update_predicate",
+        count_plan
+            .schema()
+            .index_of("__delta_rs_update_predicate")?,
+    ));
+
+    // Only the updated records are written when deletion vectors are used, and their
+    // positions are recorded so they can be marked as deleted in the original files.
+    let (update_input, deleted_rows): (Arc<dyn ExecutionPlan>, _) = match (
+        &scan_config.file_column_name,
+        &scan_config.row_index_column_name,
+    ) {
+        (Some(file_column), Some(row_index_column)) => {
+            let filter = Arc::new(FilterExec::try_new(
+                predicate_column.clone(),
+                count_plan.clone(),
+            )?);
+            let collector = DeletedRowsCollectorExec::new(
+                filter,
+                Arc::new(file_column.clone()),
+                Arc::new(row_index_column.clone()),
+                None,
+            );
+            let deleted_rows = collector.deleted_rows();
+            (Arc::new(collector), Some(deleted_rows))
+        }
+        _ => (count_plan.clone(), None),
+    };
+
     // Perform another projection but instead calculate updated values based on
     // the predicate value.  If the predicate is true then evalute the user
     // provided expression otherwise return the original column value
     //
     // For each update column a new column with a name of __delta_rs_ + `original name` is created
     let mut expressions: Vec<(Arc<dyn PhysicalExpr>, String)> = Vec::new();
-    let scan_schema = count_plan.schema();
+    let scan_schema = update_input.schema();
     for (i, field) in scan_schema.fields().into_iter().enumerate() {
         expressions.push((
             Arc::new(expressions::Column::new(field.name(), i)),
@@ -306,6 +420,8 @@ async fn execute(
     let mut map = HashMap::<String, usize>::new();
     let mut control_columns = HashSet::<String>::new();
     control_columns.insert("__delta_rs_update_predicate".to_owned());
+    control_columns.extend(scan_config.file_column_name.clone());
+    control_columns.extend(scan_config.row_index_column_name.clone());
 
     for (column, expr) in updates {
         let expr = case(col("__delta_rs_update_predicate"))
@@ -318,8 +434,21 @@ async fn execute(
         control_columns.insert(c);
     }
 
+    // The change data is computed with the same expressions from the updated records, below
+    // the nodes that collect metrics and deleted rows, which must only observe the write.
+    let change_data = if should_write_cdc(&snapshot) {
+        let updated = Arc::new(FilterExec::try_new(
+            predicate_column,
+            projection_predicate.clone(),
+        )?);
+        let updated = Arc::new(ProjectionExec::try_new(expressions.clone(), updated)?);
+        Some(change_data_plan(&snapshot, updated, &map)?)
+    } else {
+        None
+    };
+
     let projection_update: Arc<dyn ExecutionPlan> =
-        Arc::new(ProjectionExec::try_new(expressions, count_plan.clone())?);
+        Arc::new(ProjectionExec::try_new(expressions, update_input)?);
 
     // Project again to remove __delta_rs columns and rename update columns to their original name
     let mut expressions: Vec<(Arc<dyn PhysicalExpr>, String)> = Vec::new();
@@ -364,13 +493,32 @@ async fn execute(
         log_store.object_store().clone(),
         Some(snapshot.table_config().target_file_size() as usize),
         None,
-        writer_properties,
+        writer_properties.clone(),
         safe_cast,
         None,
-        writer_stats_config,
+        writer_stats_config.clone(),
     )
     .await?;
 
+    let change_data = match change_data {
+        Some(change_data) => {
+            write_execution_plan_cdc(
+                &snapshot,
+                state.clone(),
+                change_data,
+                table_partition_cols.clone(),
+                log_store.object_store(),
+                Some(snapshot.table_config().target_file_size() as usize),
+                None,
+                writer_properties,
+                safe_cast,
+                writer_stats_config,
+            )
+            .await?
+        }
+        None => Vec::new(),
+    };
+
     let count_metrics = count_plan.metrics().unwrap();
 
     metrics.num_updated_rows = count_metrics
@@ -383,28 +531,41 @@ async fn execute(
         .map(|m| m.as_usize())
         .unwrap_or(0);
 
-    let deletion_timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as i64;
     let mut actions: Vec<Action> = add_actions.clone();
-
     metrics.num_added_files = actions.len();
-    metrics.num_removed_files = candidates.candidates.len();
-
-    for action in candidates.candidates {
-        actions.push(Action::Remove(Remove {
-            path: action.path,
-            deletion_timestamp: Some(deletion_timestamp),
-            data_change: true,
-            extended_file_metadata: Some(true),
-            partition_values: Some(action.partition_values),
-            size: Some(action.size),
-            deletion_vector: action.deletion_vector,
-            tags: None,
-            base_row_id: None,
-            default_row_commit_version: None,
-        }))
+    actions.extend(change_data);
+
+    if let Some(deleted_rows) = deleted_rows {
+        let deleted_rows = std::mem::take(&mut *deleted_rows.lock().unwrap());
+        let result =
+            write_deletion_vectors(log_store.clone(), candidates.candidates, deleted_rows).await?;
+
+        metrics.num_copied_rows = 0;
+        metrics.num_removed_files = result.num_removed_files;
+        metrics.num_deletion_vectors_added = result.num_deletion_vectors_added;
+        actions.extend(result.actions);
+    } else {
+        let deletion_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+
+        metrics.num_removed_files = candidates.candidates.len();
+
+        for action in candidates.candidates {
+            actions.push(Action::Remove(Remove {
+                path: action.path,
+                deletion_timestamp: Some(deletion_timestamp),
+                data_change: true,
+                extended_file_metadata: Some(true),
+                partition_values: Some(action.partition_values),
+                size: Some(action.size),
+                deletion_vector: action.deletion_vector,
+                tags: None,
+                base_row_id: None,
+                default_row_commit_version: None,
+            }))
+        }
     }
 
     metrics.execution_time_ms = Instant::now().duration_since(exec_start).as_millis() as u64;
@@ -476,11 +637,13 @@ mod tests {
     use crate::kernel::PrimitiveType;
     use crate::kernel::StructField;
     use crate::kernel::StructType;
+    use crate::kernel::{Action, Protocol, ReaderFeatures, WriterFeatures};
     use crate::operations::DeltaOps;
-    use crate::writer::test_utils::datafusion::get_data;
     use crate::writer::test_utils::datafusion::write_batch;
+    use crate::writer::test_utils::datafusion::{get_cdf_data, get_data};
     use crate::writer::test_utils::{
         get_arrow_schema, get_delta_schema, get_record_batch, setup_table_with_configuration,
+        setup_table_with_deletion_vectors,
     };
     use crate::DeltaConfigKey;
     use crate::DeltaTable;
@@ -593,6 +756,156 @@ mod tests {
         assert_batches_sorted_eq!(&expected, &actual);
     }
 
+    #[tokio::test]
+    async fn test_update_with_deletion_vectors() {
+        let schema = get_arrow_schema(&None);
+        let table = setup_table_with_deletion_vectors(Some(vec!["modified"])).await;
+
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(arrow::array::StringArray::from(vec!["A", "B", "A", "A"])),
+                Arc::new(arrow::array::Int32Array::from(vec![1, 10, 10, 100])),
+                Arc::new(arrow::array::StringArray::from(vec![
+                    "2021-02-02",
+                    "2021-02-02",
+                    "2021-02-03",
+                    "2021-02-03",
+                ])),
+            ],
+        )
+        .unwrap();
+
+        let table = write_batch(table, batch).await;
+        assert_eq!(table.version(), 1);
+        assert_eq!(table.get_files_count(), 2);
+
+        let (table, metrics) = DeltaOps(table)
+            .update()
+            .with_predicate(col("value").eq(lit(10)))
+            .with_update("value", col("value") + lit(1))
+            .await
+            .unwrap();
+
+        assert_eq!(table.version(), 2);
+        assert_eq!(metrics.num_added_files, 2);
+        assert_eq!(metrics.num_removed_files, 0);
+        assert_eq!(metrics.num_deletion_vectors_added, 2);
+        assert_eq!(metrics.num_updated_rows, 2);
+        assert_eq!(metrics.num_copied_rows, 0);
+
+        // the original files are kept with a deletion vector for the updated rows
+        let files = table.snapshot().unwrap().file_actions().unwrap();
+        assert_eq!(files.len(), 4);
+        assert_eq!(
+            files
+                .iter()
+                .filter_map(|f| f.deletion_vector.as_ref())
+                .map(|dv| dv.cardinality)
+                .collect::<Vec<_>>(),
+            vec![1, 1]
+        );
+
+        let expected = vec![
+            "+----+-------+------------+",
+            "| id | value | modified   |",
+            "+----+-------+------------+",
+            "| A  | 1     | 2021-02-02 |",
+            "| A  | 11    | 2021-02-03 |",
+            "| A  | 100   | 2021-02-03 |",
+            "| B  | 11    | 2021-02-02 |",
+            "+----+-------+------------+",
+        ];
+        let actual = get_data(&table).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+    }
+
+    #[tokio::test]
+    async fn test_update_with_change_data_feed() {
+        for deletion_vectors in [false, true] {
+            let mut writer_features = vec![WriterFeatures::ChangeDataFeed];
+            if deletion_vectors {
+                writer_features.push(WriterFeatures::DeletionVectors);
+            }
+            let table = DeltaOps::new_in_memory()
+                .create()
+                .with_columns(get_delta_schema().fields().clone())
+                .with_partition_columns(["modified"])
+                .with_configuration_property(DeltaConfigKey::EnableChangeDataFeed, Some("true"))
+                .with_configuration_property(
+                    DeltaConfigKey::EnableDeletionVectors,
+                    Some(deletion_vectors.to_string()),
+                )
+                .with_actions(vec![Action::Protocol(Protocol {
+                    min_reader_version: if deletion_vectors { 3 } else { 1 },
+                    min_writer_version: 7,
+                    reader_features: deletion_vectors
+                        .then(|| [ReaderFeatures::DeletionVectors].into()),
+                    writer_features: Some(writer_features.into_iter().collect()),
+                })])
+                .await
+                .unwrap();
+            let batch = RecordBatch::try_new(
+                get_arrow_schema(&None),
+                vec![
+                    Arc::new(arrow::array::StringArray::from(vec!["A", "B", "A", "A"])),
+                    Arc::new(arrow::array::Int32Array::from(vec![1, 10, 10, 100])),
+                    Arc::new(arrow::array::StringArray::from(vec![
+                        "2021-02-02",
+                        "2021-02-02",
+                        "2021-02-03",
+                        "2021-02-03",
+                    ])),
+                ],
+            )
+            .unwrap();
+            let table = write_batch(table, batch).await;
+            assert_eq!(table.version(), 1);
+
+            let (table, metrics) = DeltaOps(table)
+                .update()
+                .with_predicate(col("value").eq(lit(10)))
+                .with_update("value", col("value") + lit(1))
+                .with_update("modified", lit("2021-02-04"))
+                .await
+                .unwrap();
+            assert_eq!(table.version(), 2);
+            // writing the change data doesn't affect the metrics of the rewrite
+            assert_eq!(metrics.num_updated_rows, 2);
+            if deletion_vectors {
+                assert_eq!(metrics.num_deletion_vectors_added, 2);
+            } else {
+                assert_eq!(metrics.num_copied_rows, 2);
+            }
+
+            let expected = vec![
+                "+----+-------+------------------+-----------------+------------+",
+                "| id | value | _change_type     | _commit_version | modified   |",
+                "+----+-------+------------------+-----------------+------------+",
+                "| A  | 10    | update_preimage  | 2               | 2021-02-03 |",
+                "| A  | 11    | update_postimage | 2               | 2021-02-04 |",
+                "| B  | 10    | update_preimage  | 2               | 2021-02-02 |",
+                "| B  | 11    | update_postimage | 2               | 2021-02-04 |",
+                "+----+-------+------------------+-----------------+------------+",
+            ];
+            let actual = get_cdf_data(&table, 2).await;
+            assert_batches_sorted_eq!(&expected, &actual);
+
+            let expected = vec![
+                "+----+-------+------------+",
+                "| id | value | modified   |",
+                "+----+-------+------------+",
+                "| A  | 1     | 2021-02-02 |",
+                "| A  | 100   | 2021-02-03 |",
+                "| A  | 11    | 2021-02-04 |",
+                "| B  | 11    | 2021-02-04 |",
+                "+----+-------+------------+",
+            ];
+            let actual = get_data(&table).await;
+            assert_batches_sorted_eq!(&expected, &actual);
+        }
+    }
+
     #[tokio::test]
     async fn test_update_non_partition() {
         let schema = get_arrow_schema(&None);
@@ -969,4 +1282,73 @@ mod tests {
             .await;
         assert!(res.is_err());
     }
+
+    #[tokio::test]
+    async fn test_update_generated_columns() {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_column(
+                "id",
+                DeltaDataType::Primitive(PrimitiveType::String),
+                true,
+                None,
+            )
+            .with_column(
+                "value",
+                DeltaDataType::Primitive(PrimitiveType::Integer),
+                true,
+                None,
+            )
+            .with_generated_column(
+                "doubled",
+                DeltaDataType::Primitive(PrimitiveType::Long),
+                true,
+                "value * 2",
+            )
+            .await
+            .unwrap();
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("value", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(arrow::array::StringArray::from(vec!["A", "B", "C"])),
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+            ],
+        )
+        .unwrap();
+        let table = DeltaOps(table).write(vec![batch]).await.unwrap();
+
+        // generated columns are recomputed from the updated values
+        let (table, metrics) = DeltaOps(table)
+            .update()
+            .with_predicate(col("id").eq(lit("A")))
+            .with_update("value", col("value") + lit(10))
+            .await
+            .unwrap();
+        assert_eq!(metrics.num_updated_rows, 1);
+
+        let expected = [
+            "+----+-------+---------+",
+            "| id | value | doubled |",
+            "+----+-------+---------+",
+            "| A  | 11    | 22      |",
+            "| B  | 2     | 4       |",
+            "| C  | 3     | 6       |",
+            "+----+-------+---------+",
+        ];
+        let actual = get_data(&table).await;
+        assert_batches_sorted_eq!(&expected, &actual);
+
+        // explicitly assigned values must match the generation expression
+        let res = DeltaOps(table)
+            .update()
+            .with_predicate(col("id").eq(lit("B")))
+            .with_update("doubled", lit(5_i64))
+            .await;
+        assert!(res.is_err());
+    }
 }
@@ -0,0 +1,285 @@
+//! Upgrade the protocol of a table and enable table features
+//!
+//! The upgraded protocol is the minimal protocol supporting the requested versions and
+//! features. Tables keep legacy protocol versions as long as these support all features,
+//! otherwise they are upgraded to writer version 7, and reader version 3 for reader-writer
+//! features, listing the supported features explicitly.
+//!
+//! See <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#table-features>
+
+use futures::future::BoxFuture;
+
+use super::transaction::protocol::upgrade_protocol;
+use super::transaction::{CommitBuilder, CommitProperties, PROTOCOL};
+use crate::kernel::{Action, WriterFeatures};
+use crate::logstore::LogStoreRef;
+use crate::protocol::DeltaOperation;
+use crate::table::state::DeltaTableState;
+use crate::DeltaTable;
+use crate::{DeltaResult, DeltaTableError};
+
+/// Upgrade the protocol of a table
+pub struct UpgradeProtocolBuilder {
+    /// A snapshot of the table's state
+    snapshot: DeltaTableState,
+    /// Minimum reader version of the upgraded protocol
+    reader_version: Option<i32>,
+    /// Minimum writer version of the upgraded protocol
+    writer_version: Option<i32>,
+    /// Features supported by the upgraded protocol
+    features: Vec<WriterFeatures>,
+    /// Delta object store for handling data files
+    log_store: LogStoreRef,
+    /// Additional information to add to the commit
+    commit_properties: CommitProperties,
+}
+
+impl super::Operation<()> for UpgradeProtocolBuilder {}
+
+impl UpgradeProtocolBuilder {
+    /// Create a new builder
+    pub fn new(log_store: LogStoreRef, snapshot: DeltaTableState) -> Self {
+        Self {
+            reader_version: None,
+            writer_version: None,
+            features: Vec::new(),
+            snapshot,
+            log_store,
+            commit_properties: CommitProperties::default(),
+        }
+    }
+
+    /// Specify the minimum reader version of the table
+    pub fn with_reader_version(mut self, version: i32) -> Self {
+        self.reader_version = Some(version);
+        self
+    }
+
+    /// Specify the minimum writer version of the table
+    pub fn with_writer_version(mut self, version: i32) -> Self {
+        self.writer_version = Some(version);
+        self
+    }
+
+    /// Specify a feature the table should support, e.g. `deletionVectors`
+    pub fn with_feature<F: Into<WriterFeatures>>(mut self, feature: F) -> Self {
+        self.features.push(feature.into());
+        self
+    }
+
+    /// Specify features the table should support
+    pub fn with_features<I, F>(mut self, features: I) -> Self
+    where
+        I: IntoIterator<Item = F>,
+        F: Into<WriterFeatures>,
+    {
+        self.features
+            .extend(features.into_iter().map(|feature| feature.into()));
+        self
+    }
+
+    /// Additional metadata to be added to commit info
+    pub fn with_commit_properties(mut self, commit_properties: CommitProperties) -> Self {
+        self.commit_properties = commit_properties;
+        self
+    }
+}
+
+impl std::future::IntoFuture for UpgradeProtocolBuilder {
+    type Output = DeltaResult<DeltaTable>;
+
+    type IntoFuture = BoxFuture<'static, Self::Output>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let this = self;
+
+        Box::pin(async move {
+            let reader_version = this.reader_version.unwrap_or_default();
+            if reader_version > 3 {
+                return Err(DeltaTableError::Generic(format!(
+                    "Invalid reader version {reader_version}, the highest reader version is 3"
+                )));
+            }
+            let writer_version = this.writer_version.unwrap_or_default();
+            if writer_version > 7 {
+                return Err(DeltaTableError::Generic(format!(
+                    "Invalid writer version {writer_version}, the highest writer version is 7"
+                )));
+            }
+            if let Some(WriterFeatures::Other(name)) = this
+                .features
+                .iter()
+                .find(|feature| matches!(feature, WriterFeatures::Other(_)))
+            {
+                return Err(DeltaTableError::Generic(format!(
+                    "Unknown table feature: {name}"
+                )));
+            }
+
+            let Some(protocol) = upgrade_protocol(
+                this.snapshot.protocol(),
+                reader_version,
+                writer_version,
+                this.features,
+            ) else {
+                return Ok(DeltaTable::new_with_state(this.log_store, this.snapshot));
+            };
+            PROTOCOL.can_write_protocol(&protocol)?;
+
+            let operation = DeltaOperation::UpgradeProtocol {
+                new_protocol: protocol.clone(),
+            };
+            let commit = CommitBuilder::from(this.commit_properties)
+                .with_actions(vec![Action::Protocol(protocol)])
+                .build(Some(&this.snapshot), this.log_store.clone(), operation)?
+                .await?;
+
+            Ok(DeltaTable::new_with_state(
+                this.log_store,
+                commit.snapshot(),
+            ))
+        })
+    }
+}
+
+#[cfg(feature = "datafusion")]
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::kernel::{Protocol, ReaderFeatures};
+    use crate::writer::test_utils::create_initialized_table;
+    use crate::{DeltaConfigKey, DeltaOps};
+
+    async fn legacy_table() -> DeltaTable {
+        let table = create_initialized_table(&[]).await;
+        let protocol = table.protocol().unwrap();
+        assert_eq!(
+            (protocol.min_reader_version, protocol.min_writer_version),
+            (1, 2)
+        );
+        table
+    }
+
+    #[tokio::test]
+    async fn test_enable_legacy_feature() {
+        let table = DeltaOps(legacy_table().await)
+            .enable_features(["changeDataFeed"])
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 1);
+        assert_eq!(table.protocol().unwrap(), &Protocol::new(1, 4));
+
+        let history = table.history(Some(1)).await.unwrap();
+        assert_eq!(history[0].operation.as_deref(), Some("UPGRADE PROTOCOL"));
+
+        // features that are already supported don't change the protocol
+        let table = DeltaOps(table)
+            .enable_features([WriterFeatures::AppendOnly])
+            .await
+            .unwrap();
+        assert_eq!(table.version(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_enable_reader_writer_feature() {
+        let table = DeltaOps(legacy_table().await)
+            .enable_features(["deletionVectors"])
+            .await
+            .unwrap();
+        let protocol = table.protocol().unwrap();
+        assert_eq!(protocol.min_reader_version, 3);
+        assert_eq!(protocol.min_writer_version, 7);
+        assert_eq!(
+            protocol.reader_features,
+            Some(HashSet::from([ReaderFeatures::DeletionVectors]))
+        );
+        assert_eq!(
+            protocol.writer_features,
+            Some(HashSet::from([
+                WriterFeatures::AppendOnly,
+                WriterFeatures::Invariants,
+                WriterFeatures::DeletionVectors,
+            ]))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_to_table_features() {
+        let table = DeltaOps(legacy_table().await)
+            .upgrade_protocol()
+            .with_writer_version(7)
+            .with_feature(WriterFeatures::RowTracking)
+            .await
+            .unwrap();
+        let protocol = table.protocol().unwrap();
+        assert_eq!(protocol.min_reader_version, 1);
+        assert_eq!(protocol.reader_features, None);
+        assert_eq!(
+            protocol.writer_features,
+            Some(HashSet::from([
+                WriterFeatures::AppendOnly,
+                WriterFeatures::Invariants,
+                WriterFeatures::RowTracking,
+                WriterFeatures::DomainMetadata,
+            ]))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_upgrade() {
+        let result = DeltaOps(legacy_table().await)
+            .enable_features(["unknownFeature"])
+            .await;
+        assert!(result.is_err());
+
+        let result = DeltaOps(legacy_table().await)
+            .upgrade_protocol()
+            .with_reader_version(4)
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_setting_properties_upgrades_protocol() {
+        let table = legacy_table().await;
+        let mut metadata = table.metadata().unwrap().clone();
+        metadata.configuration.insert(
+            DeltaConfigKey::EnableChangeDataFeed.as_ref().to_string(),
+            Some("true".to_string()),
+        );
+        metadata.configuration.insert(
+            "delta.feature.v2Checkpoint".to_string(),
+            Some("supported".to_string()),
+        );
+        let commit = CommitBuilder::default()
+            .with_actions(vec![Action::Metadata(metadata)])
+            .build(
+                Some(table.snapshot().unwrap()),
+                table.log_store(),
+                DeltaOperation::SetTableProperties {
+                    properties: Default::default(),
+                },
+            )
+            .unwrap()
+            .await
+            .unwrap();
+
+        let protocol = commit.snapshot().protocol().clone();
+        assert_eq!(protocol.min_reader_version, 3);
+        assert_eq!(
+            protocol.reader_features,
+            Some(HashSet::from([ReaderFeatures::V2Checkpoint]))
+        );
+        assert_eq!(
+            protocol.writer_features,
+            Some(HashSet::from([
+                WriterFeatures::AppendOnly,
+                WriterFeatures::Invariants,
+                WriterFeatures::ChangeDataFeed,
+                WriterFeatures::V2Checkpoint,
+            ]))
+        );
+    }
+}
@@ -12,6 +12,9 @@
 //! When you run vacuum then you cannot use time travel to a version older than
 //! the specified retention period.
 //!
+//! Only files within the table root are deleted. Files referenced by absolute URIs, like the
+//! files of a shallow clone which belong to the source table, are never deleted.
+//!
 //! Warning: Vacuum does not support partitioned tables on Windows. This is due
 //! to Windows not using unix style paths. See #682
 //!
@@ -36,6 +39,7 @@ use super::transaction::{CommitBuilder, CommitProperties};
 use crate::errors::{DeltaResult, DeltaTableError};
 use crate::logstore::LogStoreRef;
 use crate::protocol::DeltaOperation;
+use crate::storage::utils::is_absolute_path;
 use crate::table::state::DeltaTableState;
 use crate::DeltaTable;
 
@@ -395,6 +399,9 @@ async fn get_stale_files(
             // then it's considered as a stale file
             tombstone.deletion_timestamp.unwrap_or(0) < tombstone_retention_timestamp
         })
+        // files referenced by absolute URIs, e.g. the files of shallow clones, belong to
+        // other tables and are never deleted
+        .filter(|tombstone| matches!(is_absolute_path(&tombstone.path), Ok(false)))
         .map(|tombstone| tombstone.path)
         .collect::<HashSet<_>>())
 }
@@ -31,18 +31,27 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use std::vec;
 
 use arrow_array::RecordBatch;
-use arrow_cast::can_cast_types;
+use arrow_cast::{can_cast_types, CastOptions};
 use arrow_schema::{ArrowError, DataType, Fields, SchemaRef as ArrowSchemaRef};
 use datafusion::execution::context::{SessionContext, SessionState, TaskContext};
 use datafusion::physical_plan::filter::FilterExec;
+use datafusion::physical_plan::projection::ProjectionExec;
 use datafusion::physical_plan::{memory::MemoryExec, ExecutionPlan};
 use datafusion_common::DFSchema;
 use datafusion_expr::Expr;
+use datafusion_physical_expr::expressions::{self, CastExpr};
+use datafusion_physical_expr::PhysicalExpr;
 use futures::future::BoxFuture;
 use futures::StreamExt;
+use object_store::prefix::PrefixStore;
 use parquet::file::properties::WriterProperties;
 
+use super::cdc::CHANGE_DATA_FOLDER;
 use super::datafusion_utils::Expression;
+use super::generated_columns::{
+    add_missing_generated_columns, add_missing_generated_columns_to_batch,
+};
+use super::identity_columns::{add_identity_columns, IdentityColumnsGenerator};
 use super::transaction::{CommitBuilder, CommitProperties, TableReference, PROTOCOL};
 use super::writer::{DeltaWriter, WriterConfig};
 use super::CreateBuilder;
@@ -53,12 +62,12 @@ use crate::delta_datafusion::{
 };
 use crate::delta_datafusion::{DataFusionMixins, DeltaDataChecker};
 use crate::errors::{DeltaResult, DeltaTableError};
-use crate::kernel::{Action, Add, Metadata, PartitionsExt, Remove, StructType};
+use crate::kernel::{Action, Add, AddCDCFile, Metadata, PartitionsExt, Remove, StructType};
 use crate::logstore::LogStoreRef;
 use crate::operations::cast::{cast_record_batch, merge_schema};
 use crate::protocol::{DeltaOperation, SaveMode};
 use crate::storage::ObjectStoreRef;
-use crate::table::config::DEFAULT_NUM_INDEX_COLS;
+use crate::table::config::{ColumnMappingMode, TableConfig, DEFAULT_NUM_INDEX_COLS};
 use crate::table::state::DeltaTableState;
 use crate::table::Constraint as DeltaConstraint;
 use crate::writer::record_batch::divide_by_partition_values;
@@ -361,6 +370,7 @@ impl WriterStatsConfig {
 async fn write_execution_plan_with_predicate(
     predicate: Option<Expr>,
     snapshot: Option<&DeltaTableState>,
+    table_metadata: Option<&Metadata>,
     state: SessionState,
     plan: Arc<dyn ExecutionPlan>,
     partition_columns: Vec<String>,
@@ -394,13 +404,22 @@ async fn write_execution_plan_with_predicate(
         _ => checker,
     };
 
+    // Data files of tables with column mapping are written using the physical column names
+    let column_mapping = match table_metadata {
+        Some(metadata) => Some((
+            metadata.schema()?,
+            TableConfig(&metadata.configuration).column_mapping_mode(),
+        )),
+        None => None,
+    };
+
     // Write data to disk
     let mut tasks = vec![];
     for i in 0..plan.properties().output_partitioning().partition_count() {
         let inner_plan = plan.clone();
         let inner_schema = schema.clone();
         let task_ctx = Arc::new(TaskContext::from(&state));
-        let config = WriterConfig::new(
+        let mut config = WriterConfig::new(
             inner_schema.clone(),
             partition_columns.clone(),
             writer_properties.clone(),
@@ -409,6 +428,9 @@ async fn write_execution_plan_with_predicate(
             writer_stats_config.num_indexed_cols,
             writer_stats_config.stats_columns.clone(),
         );
+        if let Some((table_schema, mode)) = &column_mapping {
+            config = config.with_column_mapping(table_schema, *mode)?;
+        }
         let mut writer = DeltaWriter::new(object_store.clone(), config);
         let checker_stream = checker.clone();
         let mut stream = inner_plan.execute(i, task_ctx)?;
@@ -465,6 +487,7 @@ pub(crate) async fn write_execution_plan(
     write_execution_plan_with_predicate(
         None,
         snapshot,
+        snapshot.map(|snapshot| snapshot.metadata()),
         state,
         plan,
         partition_columns,
@@ -479,6 +502,79 @@ pub(crate) async fn write_execution_plan(
     .await
 }
 
+/// Write the output of a plan to change data files, returning the `cdc` actions.
+///
+/// The plan must produce the table columns together with the `_change_type` column.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn write_execution_plan_cdc(
+    snapshot: &DeltaTableState,
+    state: SessionState,
+    plan: Arc<dyn ExecutionPlan>,
+    partition_columns: Vec<String>,
+    object_store: ObjectStoreRef,
+    target_file_size: Option<usize>,
+    write_batch_size: Option<usize>,
+    writer_properties: Option<WriterProperties>,
+    safe_cast: bool,
+    writer_stats_config: WriterStatsConfig,
+) -> DeltaResult<Vec<Action>> {
+    // Change data is written using the types of the table columns, but isn't validated
+    // against the constraints of the table
+    let table_schema = snapshot.input_schema()?;
+    let cast_options = CastOptions {
+        safe: safe_cast,
+        ..Default::default()
+    };
+    let mut expressions: Vec<(Arc<dyn PhysicalExpr>, String)> = Vec::new();
+    for (i, field) in plan.schema().fields().iter().enumerate() {
+        let column: Arc<dyn PhysicalExpr> = Arc::new(expressions::Column::new(field.name(), i));
+        let expr: Arc<dyn PhysicalExpr> = match table_schema.field_with_name(field.name()) {
+            Ok(table_field) if table_field.data_type() != field.data_type() => {
+                Arc::new(CastExpr::new(
+                    column,
+                    table_field.data_type().clone(),
+                    Some(cast_options.clone()),
+                ))
+            }
+            _ => column,
+        };
+        expressions.push((expr, field.name().to_owned()));
+    }
+    let plan: Arc<dyn ExecutionPlan> = Arc::new(ProjectionExec::try_new(expressions, plan)?);
+
+    let cdc_store: ObjectStoreRef = Arc::new(PrefixStore::new(object_store, CHANGE_DATA_FOLDER));
+    let actions = write_execution_plan_with_predicate(
+        None,
+        None,
+        Some(snapshot.metadata()),
+        state,
+        plan,
+        partition_columns,
+        cdc_store,
+        target_file_size,
+        write_batch_size,
+        writer_properties,
+        false,
+        None,
+        writer_stats_config,
+    )
+    .await?;
+
+    Ok(actions
+        .into_iter()
+        .map(|action| match action {
+            Action::Add(add) => Action::Cdc(AddCDCFile {
+                path: format!("{CHANGE_DATA_FOLDER}/{}", add.path),
+                size: add.size,
+                partition_values: add.partition_values,
+                data_change: false,
+                tags: add.tags,
+            }),
+            action => action,
+        })
+        .collect())
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn execute_non_empty_expr(
     snapshot: &DeltaTableState,
@@ -602,6 +698,32 @@ impl std::future::IntoFuture for WriteBuilder {
             // Create table actions to initialize table in case it does not yet exist and should be created
             let mut actions = this.check_preconditions().await?;
 
+            // The metadata of the table the data is written to, including a table that is
+            // created by this operation
+            let table_metadata = actions
+                .iter()
+                .find_map(|action| match action {
+                    Action::Metadata(metadata) => Some(metadata.clone()),
+                    _ => None,
+                })
+                .or_else(|| {
+                    this.snapshot
+                        .as_ref()
+                        .map(|snapshot| snapshot.metadata().clone())
+                });
+            let column_mapping_mode = table_metadata
+                .as_ref()
+                .map(|metadata| TableConfig(&metadata.configuration).column_mapping_mode())
+                .unwrap_or_default();
+            if this.snapshot.is_some()
+                && this.schema_mode.is_some()
+                && column_mapping_mode != ColumnMappingMode::None
+            {
+                return Err(DeltaTableError::Generic(
+                    "Schema evolution is not supported for tables with column mapping".to_string(),
+                ));
+            }
+
             let active_partitions = this
                 .snapshot
                 .as_ref()
@@ -624,6 +746,26 @@ impl std::future::IntoFuture for WriteBuilder {
             } else {
                 Ok(this.partition_columns.unwrap_or_default())
             }?;
+            let state = match this.state {
+                Some(state) => state,
+                None => {
+                    let ctx = SessionContext::new();
+                    register_store(this.log_store.clone(), ctx.runtime_env());
+                    ctx.state()
+                }
+            };
+
+            // Values of generated columns missing from the data are computed
+            let generated_columns = match &table_metadata {
+                Some(metadata) => metadata.schema()?.get_generated_columns(),
+                None => vec![],
+            };
+            // Values of identity columns are generated unless explicitly provided
+            let identity_columns = match &table_metadata {
+                Some(metadata) => IdentityColumnsGenerator::try_new(&metadata.schema()?)?,
+                None => None,
+            };
+
             let mut schema_drift = false;
             let plan = if let Some(plan) = this.input {
                 if this.schema_mode == Some(SchemaMode::Merge) {
@@ -631,17 +773,35 @@ impl std::future::IntoFuture for WriteBuilder {
                         "Schema merge not supported yet for Datafusion".to_string(),
                     ));
                 }
-                Ok(plan)
+                let plan = match &identity_columns {
+                    Some(generator) => add_identity_columns(plan, generator.clone(), true)?,
+                    None => plan,
+                };
+                add_missing_generated_columns(plan, &generated_columns, &state)
             } else if let Some(batches) = this.batches {
                 if batches.is_empty() {
-                    Err(WriteError::MissingData)
+                    Err(WriteError::MissingData.into())
                 } else {
+                    let batches = batches
+                        .into_iter()
+                        .map(|batch| {
+                            let batch = match &identity_columns {
+                                Some(generator) => generator.fill_batch(batch, true)?,
+                                None => batch,
+                            };
+                            add_missing_generated_columns_to_batch(
+                                batch,
+                                &generated_columns,
+                                &state,
+                            )
+                        })
+                        .collect::<DeltaResult<Vec<_>>>()?;
                     let schema = batches[0].schema();
 
                     let mut new_schema = None;
                     if let Some(snapshot) = &this.snapshot {
                         let table_schema = snapshot
-                            .physical_arrow_schema(this.log_store.object_store().clone())
+                            .physical_arrow_schema_for(this.log_store.as_ref())
                             .await
                             .or_else(|_| snapshot.arrow_schema())
                             .unwrap_or(schema.clone());
@@ -715,7 +875,7 @@ impl std::future::IntoFuture for WriteBuilder {
                     )?) as Arc<dyn ExecutionPlan>)
                 }
             } else {
-                Err(WriteError::MissingData)
+                Err(WriteError::MissingData.into())
             }?;
             let schema = plan.schema();
             if this.schema_mode == Some(SchemaMode::Merge) && schema_drift {
@@ -729,15 +889,6 @@ impl std::future::IntoFuture for WriteBuilder {
                     actions.push(schema_action);
                 }
             }
-            let state = match this.state {
-                Some(state) => state,
-                None => {
-                    let ctx = SessionContext::new();
-                    register_store(this.log_store.clone(), ctx.runtime_env());
-                    ctx.state()
-                }
-            };
-
             let (predicate_str, predicate) = match this.predicate {
                 Some(predicate) => {
                     let pred = match predicate {
@@ -769,6 +920,7 @@ impl std::future::IntoFuture for WriteBuilder {
             let add_actions = write_execution_plan_with_predicate(
                 predicate.clone(),
                 this.snapshot.as_ref(),
+                table_metadata.as_ref(),
                 state.clone(),
                 plan,
                 partition_columns.clone(),
@@ -788,12 +940,17 @@ impl std::future::IntoFuture for WriteBuilder {
                 if matches!(this.mode, SaveMode::Overwrite) {
                     // Update metadata with new schema
                     let table_schema = snapshot
-                        .physical_arrow_schema(this.log_store.object_store().clone())
+                        .physical_arrow_schema_for(this.log_store.as_ref())
                         .await
                         .or_else(|_| snapshot.arrow_schema())
                         .unwrap_or(schema.clone());
 
-                    if schema != table_schema {
+                    // the schema of tables with column mapping or identity columns can not be
+                    // changed by writes, since the metadata of the columns would be lost
+                    if schema != table_schema
+                        && column_mapping_mode == ColumnMappingMode::None
+                        && identity_columns.is_none()
+                    {
                         let mut metadata = snapshot.metadata().clone();
                         let delta_schema: StructType = schema.as_ref().try_into()?;
                         metadata.schema_string = serde_json::to_string(&delta_schema)?;
@@ -833,6 +990,10 @@ impl std::future::IntoFuture for WriteBuilder {
                 }
             }
 
+            if let (Some(generator), Some(metadata)) = (&identity_columns, &table_metadata) {
+                generator.update_high_water_marks(&mut actions, metadata)?;
+            }
+
             let operation = DeltaOperation::Write {
                 mode: this.mode,
                 partition_by: if !partition_columns.is_empty() {
@@ -1696,4 +1857,270 @@ mod tests {
         let actual = get_data_sorted(&table, "id,value,modified").await;
         assert_batches_sorted_eq!(&expected, &actual);
     }
+
+    #[tokio::test]
+    async fn test_write_column_mapping() {
+        let batch = get_record_batch(None, false);
+        let table = DeltaOps::new_in_memory()
+            .write(vec![batch.clone()])
+            .with_partition_columns(["modified"])
+            .with_configuration([("delta.columnMapping.mode", Some("id"))])
+            .await
+            .unwrap();
+        let table = DeltaOps(table).write(vec![batch]).await.unwrap();
+
+        let protocol = table.protocol().unwrap();
+        assert_eq!(protocol.min_reader_version, 2);
+        assert!(protocol
+            .writer_features
+            .as_ref()
+            .unwrap()
+            .contains(&crate::kernel::WriterFeatures::ColumnMapping));
+
+        let schema = table.get_schema().unwrap();
+        let physical_name = |name: &str| {
+            schema
+                .field_with_name(name)
+                .unwrap()
+                .physical_name()
+                .unwrap()
+                .to_string()
+        };
+        assert_ne!(physical_name("modified"), "modified");
+        for add in table.snapshot().unwrap().file_actions().unwrap() {
+            assert!(add
+                .partition_values
+                .contains_key(&physical_name("modified")));
+            let stats = add.get_stats().unwrap().unwrap();
+            assert!(stats.min_values.contains_key(&physical_name("value")));
+        }
+
+        let ctx = SessionContext::new();
+        ctx.register_table("test", Arc::new(table)).unwrap();
+        let actual = ctx
+            .sql("SELECT modified, count(*) AS n, sum(value) AS total FROM test GROUP BY modified")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let expected = vec![
+            "+------------+----+-------+",
+            "| modified   | n  | total |",
+            "+------------+----+-------+",
+            "| 2021-02-01 | 16 | 120   |",
+            "| 2021-02-02 | 6  | 12    |",
+            "+------------+----+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &actual);
+    }
+
+    #[tokio::test]
+    async fn test_write_generated_columns() {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_column("id", crate::kernel::DataType::STRING, true, None)
+            .with_column("value", crate::kernel::DataType::INTEGER, true, None)
+            .with_generated_column("doubled", crate::kernel::DataType::LONG, true, "value * 2")
+            .with_partition_columns(["doubled"])
+            .await
+            .unwrap();
+        assert!(table
+            .protocol()
+            .unwrap()
+            .writer_features
+            .as_ref()
+            .unwrap()
+            .contains(&crate::kernel::WriterFeatures::GeneratedColumns));
+
+        // values of missing generated columns are computed
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("value", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec!["A", "B"])),
+                Arc::new(Int32Array::from(vec![Some(1), None])),
+            ],
+        )
+        .unwrap();
+        let table = DeltaOps(table).write(vec![batch]).await.unwrap();
+
+        // provided values must match the generation expression
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Utf8, true),
+            Field::new("value", DataType::Int32, true),
+            Field::new("doubled", DataType::Int64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["C"])),
+                Arc::new(Int32Array::from(vec![3])),
+                Arc::new(arrow_array::Int64Array::from(vec![6])),
+            ],
+        )
+        .unwrap();
+        let table = DeltaOps(table).write(vec![batch]).await.unwrap();
+
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec!["D"])),
+                Arc::new(Int32Array::from(vec![4])),
+                Arc::new(arrow_array::Int64Array::from(vec![5])),
+            ],
+        )
+        .unwrap();
+        let result = DeltaOps(table.clone()).write(vec![batch]).await;
+        assert!(matches!(result, Err(DeltaTableError::InvalidData { .. })));
+
+        let expected = [
+            "+----+-------+---------+",
+            "| id | value | doubled |",
+            "+----+-------+---------+",
+            "| A  | 1     | 2       |",
+            "| B  |       |         |",
+            "| C  | 3     | 6       |",
+            "+----+-------+---------+",
+        ];
+        let actual = get_data_sorted(&table, "id, value, doubled").await;
+        assert_batches_sorted_eq!(&expected, &actual);
+    }
+
+    #[tokio::test]
+    async fn test_write_identity_columns() {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_identity_column("id", 1, 10, false)
+            .with_column("value", crate::kernel::DataType::STRING, true, None)
+            .await
+            .unwrap();
+
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "value",
+            DataType::Utf8,
+            true,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from(vec!["A", "B"]))],
+        )
+        .unwrap();
+        let table = DeltaOps(table).write(vec![batch]).await.unwrap();
+        let identity = table.get_schema().unwrap().get_identity_columns().unwrap();
+        assert_eq!(identity[0].high_water_mark, Some(11));
+
+        // generated values continue after the high water mark
+        let batch =
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(StringArray::from(vec!["C"]))])
+                .unwrap();
+        let table = DeltaOps(table).write(vec![batch]).await.unwrap();
+
+        // values can't be provided explicitly
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![
+                Field::new("id", DataType::Int64, true),
+                Field::new("value", DataType::Utf8, true),
+            ])),
+            vec![
+                Arc::new(arrow_array::Int64Array::from(vec![100])),
+                Arc::new(StringArray::from(vec!["D"])),
+            ],
+        )
+        .unwrap();
+        let result = DeltaOps(table.clone()).write(vec![batch]).await;
+        assert!(result.is_err());
+
+        let expected = [
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 1  | A     |",
+            "| 11 | B     |",
+            "| 21 | C     |",
+            "+----+-------+",
+        ];
+        let actual = get_data_sorted(&table, "id, value").await;
+        assert_batches_sorted_eq!(&expected, &actual);
+
+        // concurrent writers would generate the same values, so only one of them may commit
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(StringArray::from(vec!["E"]))]).unwrap();
+        DeltaOps(table.clone())
+            .write(vec![batch.clone()])
+            .await
+            .unwrap();
+        let result = DeltaOps(table).write(vec![batch]).await;
+        assert!(matches!(result, Err(DeltaTableError::Transaction { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_write_row_tracking() {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_column("value", crate::kernel::DataType::STRING, true, None)
+            .with_configuration_property(DeltaConfigKey::EnableRowTracking, Some("true"))
+            .await
+            .unwrap();
+        let protocol = table.protocol().unwrap();
+        assert_eq!(protocol.min_writer_version, 7);
+        assert!(protocol
+            .writer_features
+            .as_ref()
+            .unwrap()
+            .contains(&crate::kernel::WriterFeatures::RowTracking));
+
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "value",
+            DataType::Utf8,
+            true,
+        )]));
+        let batch = |values: Vec<&str>| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(StringArray::from(values))]).unwrap()
+        };
+        let table = DeltaOps(table)
+            .write(vec![batch(vec!["A", "B", "C"])])
+            .await
+            .unwrap();
+        let table = DeltaOps(table)
+            .write(vec![batch(vec!["D", "E"])])
+            .await
+            .unwrap();
+
+        // concurrent appends get row ids following the winning commit
+        DeltaOps(table.clone())
+            .write(vec![batch(vec!["F"])])
+            .await
+            .unwrap();
+        let mut table = DeltaOps(table)
+            .write(vec![batch(vec!["G", "H"])])
+            .await
+            .unwrap();
+        table.load().await.unwrap();
+
+        let mut row_ids = table
+            .snapshot()
+            .unwrap()
+            .file_actions()
+            .unwrap()
+            .into_iter()
+            .map(|add| {
+                (
+                    add.base_row_id.unwrap(),
+                    add.default_row_commit_version.unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        row_ids.sort();
+        assert_eq!(row_ids, vec![(0, 1), (3, 2), (5, 3), (6, 4)]);
+
+        let high_water_mark = crate::operations::transaction::row_tracking::read_high_water_mark(
+            &table.snapshot().unwrap().snapshot,
+        )
+        .unwrap();
+        assert_eq!(high_water_mark, Some(7));
+    }
 }
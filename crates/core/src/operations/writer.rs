@@ -15,8 +15,10 @@ use tracing::debug;
 
 use crate::crate_version;
 use crate::errors::{DeltaResult, DeltaTableError};
-use crate::kernel::{Add, PartitionsExt, Scalar};
+use crate::kernel::arrow::column_mapping::PhysicalMapper;
+use crate::kernel::{Add, PartitionsExt, Scalar, StructType};
 use crate::storage::ObjectStoreRef;
+use crate::table::config::ColumnMappingMode;
 use crate::writer::record_batch::{divide_by_partition_values, PartitionResult};
 use crate::writer::stats::create_add;
 use crate::writer::utils::{
@@ -83,6 +85,8 @@ pub struct WriterConfig {
     num_indexed_cols: i32,
     /// Stats columns, specific columns to collect stats from, takes precedence over num_indexed_cols
     stats_columns: Option<Vec<String>>,
+    /// Mapping of the data to the physical columns, if column mapping is enabled
+    column_mapping: Option<PhysicalMapper>,
 }
 
 impl WriterConfig {
@@ -112,9 +116,41 @@ impl WriterConfig {
             write_batch_size,
             num_indexed_cols,
             stats_columns,
+            column_mapping: None,
         }
     }
 
+    /// Write the data files using the physical column names and field ids of a
+    /// table with column mapping enabled.
+    ///
+    /// The data passed to the writer is still expected to use the logical column names.
+    pub fn with_column_mapping(
+        mut self,
+        table_schema: &StructType,
+        mode: ColumnMappingMode,
+    ) -> DeltaResult<Self> {
+        let Some(mapper) = PhysicalMapper::try_new(&self.table_schema, table_schema, mode)? else {
+            return Ok(self);
+        };
+        self.table_schema = mapper.schema();
+        self.partition_columns = self
+            .partition_columns
+            .iter()
+            .map(|name| mapper.physical_name(name))
+            .collect::<DeltaResult<_>>()?;
+        self.stats_columns = self
+            .stats_columns
+            .map(|columns| {
+                columns
+                    .iter()
+                    .map(|path| mapper.physical_path(path))
+                    .collect::<DeltaResult<_>>()
+            })
+            .transpose()?;
+        self.column_mapping = Some(mapper);
+        Ok(self)
+    }
+
     /// Schema of files written to disk
     pub fn file_schema(&self) -> ArrowSchemaRef {
         arrow_schema_without_partitions(&self.table_schema, &self.partition_columns)
@@ -167,6 +203,27 @@ impl DeltaWriter {
         &mut self,
         record_batch: RecordBatch,
         partition_values: &IndexMap<String, Scalar>,
+    ) -> DeltaResult<()> {
+        match &self.config.column_mapping {
+            Some(mapper) => {
+                let record_batch = mapper.map_batch(&record_batch)?;
+                let partition_values = mapper.map_partition_values(partition_values)?;
+                self.write_physical_partition(record_batch, &partition_values)
+                    .await
+            }
+            None => {
+                self.write_physical_partition(record_batch, partition_values)
+                    .await
+            }
+        }
+    }
+
+    /// Write a batch to a partition, where both the batch and the partition values
+    /// already refer to the physical columns.
+    async fn write_physical_partition(
+        &mut self,
+        record_batch: RecordBatch,
+        partition_values: &IndexMap<String, Scalar>,
     ) -> DeltaResult<()> {
         let partition_key = Path::parse(partition_values.hive_partition_path())?;
 
@@ -205,8 +262,12 @@ impl DeltaWriter {
     /// The `close` method has to be invoked to write all data still buffered
     /// and get the list of all written files.
     pub async fn write(&mut self, batch: &RecordBatch) -> DeltaResult<()> {
-        for result in self.divide_by_partition_values(batch)? {
-            self.write_partition(result.record_batch, &result.partition_values)
+        let batch = match &self.config.column_mapping {
+            Some(mapper) => mapper.map_batch(batch)?,
+            None => batch.clone(),
+        };
+        for result in self.divide_by_partition_values(&batch)? {
+            self.write_physical_partition(result.record_batch, &result.partition_values)
                 .await?;
         }
         Ok(())
@@ -1,13 +1,16 @@
 //! Implementation for writing delta checkpoints.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::iter::Iterator;
+use std::ops::Range;
+use std::sync::Arc;
 
 use arrow_json::ReaderBuilder;
-use arrow_schema::ArrowError;
+use arrow_schema::{ArrowError, SchemaRef as ArrowSchemaRef};
 
 use chrono::{Datelike, NaiveDate, NaiveDateTime, Utc};
-use futures::{StreamExt, TryStreamExt};
+use futures::{Future, StreamExt, TryStreamExt};
+use itertools::Itertools;
 use lazy_static::lazy_static;
 use object_store::{Error, ObjectStore};
 use parquet::arrow::ArrowWriter;
@@ -15,17 +18,21 @@ use parquet::basic::Compression;
 use parquet::errors::ParquetError;
 use parquet::file::properties::WriterProperties;
 use regex::Regex;
-use serde_json::Value;
+use serde_json::{json, Value};
 use tracing::{debug, error};
+use uuid::Uuid;
 
 use super::{time_utils, ProtocolError};
-use crate::kernel::arrow::delta_log_schema_for_table;
+use crate::kernel::arrow::{delta_log_schema_for_table, delta_log_schema_for_v2_checkpoint};
 use crate::kernel::{
-    Action, Add as AddAction, DataType, PrimitiveType, Protocol, Remove, StructField, Txn,
+    Action, Add as AddAction, CheckpointMetadata, DataType, DeletionVectorDescriptor,
+    DomainMetadata, PrimitiveType, Protocol, Remove, Sidecar, StructField, Txn, WriterFeatures,
+    SIDECAR_FOLDER,
 };
 use crate::logstore::LogStore;
+use crate::table::config::CheckpointPolicy;
 use crate::table::state::DeltaTableState;
-use crate::table::{get_partition_col_data_types, CheckPoint, CheckPointBuilder};
+use crate::table::{CheckPoint, CheckPointBuilder};
 use crate::{open_table_with_version, DeltaTable};
 type SchemaPath = Vec<String>;
 
@@ -41,6 +48,14 @@ enum CheckpointError {
     #[error("Attempted to create a checkpoint for a version {0} that does not match the table state {1}")]
     StaleTableVersion(i64, i64),
 
+    /// Error returned when V2 checkpoints are requested for a table without the table feature
+    #[error("The checkpoint policy v2 requires the table feature v2Checkpoint")]
+    V2CheckpointNotSupported,
+
+    /// Error returned when a log compaction is requested for an invalid range of commits
+    #[error("Invalid log compaction range: start version {0}, end version {1}")]
+    InvalidCompactionRange(i64, i64),
+
     /// Error returned when the parquet writer fails while writing the checkpoint.
     #[error("Failed to write parquet: {}", .source)]
     Parquet {
@@ -63,7 +78,9 @@ impl From<CheckpointError> for ProtocolError {
         match value {
             CheckpointError::PartitionValueNotParseable(_) => Self::InvalidField(value.to_string()),
             CheckpointError::Arrow { source } => Self::Arrow { source },
-            CheckpointError::StaleTableVersion(..) => Self::Generic(value.to_string()),
+            CheckpointError::StaleTableVersion(..)
+            | CheckpointError::V2CheckpointNotSupported
+            | CheckpointError::InvalidCompactionRange(..) => Self::Generic(value.to_string()),
             CheckpointError::Parquet { source } => Self::ParquetParseError { source },
         }
     }
@@ -79,6 +96,15 @@ impl From<Utf8Error> for ProtocolError {
 /// The record batch size for checkpoint parquet file
 pub const CHECKPOINT_RECORD_BATCH_SIZE: usize = 5000;
 
+/// The maximum number of actions written to a single part of a multi-part checkpoint
+pub const CHECKPOINT_MAX_ACTIONS_PER_PART: usize = 5_000_000;
+
+/// The maximum number of file actions written to a single sidecar file of a V2 checkpoint
+pub const CHECKPOINT_SIDECAR_MAX_ACTIONS: usize = 100_000;
+
+/// The number of checkpoint parts or sidecar files written concurrently
+const CHECKPOINT_WRITE_CONCURRENCY: usize = 10;
+
 /// Creates checkpoint at current table version
 pub async fn create_checkpoint(table: &DeltaTable) -> Result<(), ProtocolError> {
     create_checkpoint_for(
@@ -92,14 +118,34 @@ pub async fn create_checkpoint(table: &DeltaTable) -> Result<(), ProtocolError>
 
 /// Delete expires log files before given version from table. The table log retention is based on
 /// the `logRetentionDuration` property of the Delta Table, 30 days by default.
+///
+/// If the table has the `checkpointProtection` feature, log files before the protected version
+/// are only deleted once all of them have expired.
 pub async fn cleanup_metadata(table: &DeltaTable) -> Result<usize, ProtocolError> {
+    let snapshot = table.snapshot().map_err(|_| ProtocolError::NoMetaData)?;
     let log_retention_timestamp = Utc::now().timestamp_millis()
-        - table
-            .snapshot()
-            .map_err(|_| ProtocolError::NoMetaData)?
-            .table_config()
-            .log_retention_duration()
-            .as_millis() as i64;
+        - snapshot.table_config().log_retention_duration().as_millis() as i64;
+
+    let protected_version = snapshot
+        .table_config()
+        .require_checkpoint_protection_before_version();
+    let requires_protection = snapshot
+        .protocol()
+        .writer_features
+        .as_ref()
+        .is_some_and(|features| features.contains(&WriterFeatures::CheckpointProtection));
+    if requires_protection && protected_version > 0 {
+        let last_protected = crate::storage::commit_uri_from_version(protected_version - 1);
+        match table.log_store.object_store().head(&last_protected).await {
+            Ok(meta) if meta.last_modified.timestamp_millis() > log_retention_timestamp => {
+                debug!("Skipping cleanup of log files protected until {protected_version}");
+                return Ok(0);
+            }
+            Ok(_) | Err(Error::NotFound { .. }) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+
     cleanup_expired_logs_for(
         table.version(),
         table.log_store.as_ref(),
@@ -134,10 +180,32 @@ pub async fn create_checkpoint_from_table_uri_and_cleanup(
 }
 
 /// Creates checkpoint for a given table version, table state and object store
+///
+/// Writes a V2 checkpoint with sidecar files if the table's `delta.checkpointPolicy` is `v2`,
+/// a classic checkpoint otherwise. Classic checkpoints with more than
+/// [`CHECKPOINT_MAX_ACTIONS_PER_PART`] actions are split into multiple parts.
 pub async fn create_checkpoint_for(
     version: i64,
     state: &DeltaTableState,
     log_store: &dyn LogStore,
+) -> Result<(), ProtocolError> {
+    let max_actions_per_part = match state.table_config().checkpoint_policy() {
+        CheckpointPolicy::V2 => CHECKPOINT_SIDECAR_MAX_ACTIONS,
+        _ => CHECKPOINT_MAX_ACTIONS_PER_PART,
+    };
+    create_checkpoint_with_part_size(version, state, log_store, max_actions_per_part).await
+}
+
+/// Creates checkpoint for a given table version, writing at most `max_actions_per_part`
+/// actions to a single file.
+///
+/// Classic checkpoints exceeding this size are written as multi-part checkpoints, while
+/// V2 checkpoints write the file actions to sidecar files of at most this size.
+pub async fn create_checkpoint_with_part_size(
+    version: i64,
+    state: &DeltaTableState,
+    log_store: &dyn LogStore,
+    max_actions_per_part: usize,
 ) -> Result<(), ProtocolError> {
     if version != state.version() {
         error!(
@@ -147,9 +215,6 @@ pub async fn create_checkpoint_for(
         return Err(CheckpointError::StaleTableVersion(version, state.version()).into());
     }
 
-    // TODO: checkpoints _can_ be multi-part... haven't actually found a good reference for
-    // an appropriate split point yet though so only writing a single part currently.
-    // See https://github.com/delta-io/delta-rs/issues/288
     let last_checkpoint_path = log_store.log_path().child("_last_checkpoint");
 
     debug!("Writing parquet bytes to checkpoint buffer.");
@@ -158,26 +223,302 @@ pub async fn create_checkpoint_for(
         .await
         .map_err(|_| ProtocolError::Generic("filed to get tombstones".into()))?
         .collect::<Vec<_>>();
-    let (checkpoint, parquet_bytes) = parquet_bytes_from_state(state, tombstones)?;
-
-    let file_name = format!("{version:020}.checkpoint.parquet");
-    let checkpoint_path = log_store.log_path().child(file_name);
-
-    let object_store = log_store.object_store();
-    debug!("Writing checkpoint to {:?}.", checkpoint_path);
-    object_store.put(&checkpoint_path, parquet_bytes).await?;
+    let domain_metadata = state.all_domain_metadata();
+    let actions = checkpoint_actions_from_state(state, tombstones, domain_metadata)?;
+
+    let checkpoint = match state.table_config().checkpoint_policy() {
+        CheckpointPolicy::V2 => {
+            let supported = state
+                .protocol()
+                .writer_features
+                .as_ref()
+                .map(|features| features.contains(&WriterFeatures::V2Checkpoint))
+                .unwrap_or_default();
+            if !supported {
+                return Err(CheckpointError::V2CheckpointNotSupported.into());
+            }
+            write_v2_checkpoint(version, actions, log_store, max_actions_per_part).await?
+        }
+        _ => write_classic_checkpoint(version, actions, log_store, max_actions_per_part).await?,
+    };
 
     let last_checkpoint_content: Value = serde_json::to_value(checkpoint)?;
     let last_checkpoint_content = bytes::Bytes::from(serde_json::to_vec(&last_checkpoint_content)?);
 
     debug!("Writing _last_checkpoint to {:?}.", last_checkpoint_path);
-    object_store
+    log_store
+        .object_store()
         .put(&last_checkpoint_path, last_checkpoint_content)
         .await?;
 
     Ok(())
 }
 
+/// Write a classic checkpoint
+///
+/// All actions are written to a single file if they fit into `max_actions_per_part`,
+/// otherwise they are split into the parts of a multi-part checkpoint which are written
+/// concurrently. The non-file actions are always contained in the first part.
+async fn write_classic_checkpoint(
+    version: i64,
+    actions: CheckpointActions,
+    log_store: &dyn LogStore,
+    max_actions_per_part: usize,
+) -> Result<CheckPoint, ProtocolError> {
+    let object_store = log_store.object_store();
+    let num_non_file_actions = actions.non_file_actions.len();
+    let num_actions = num_non_file_actions + actions.file_actions.len();
+    // a checkpoint always contains at least the protocol and metadata actions
+    let max_actions_per_part = max_actions_per_part.max(1);
+    let num_parts = (num_actions + max_actions_per_part - 1) / max_actions_per_part;
+
+    let mut part_writes = Vec::new();
+    for idx in 0..num_parts {
+        // the actions of each part, with the non-file actions preceding the file actions
+        let start = idx * max_actions_per_part;
+        let end = num_actions.min(start + max_actions_per_part);
+        let non_file_actions = actions.non_file_actions
+            [start.min(num_non_file_actions)..end.min(num_non_file_actions)]
+            .to_vec();
+        let file_actions =
+            start.saturating_sub(num_non_file_actions)..end.saturating_sub(num_non_file_actions);
+        let object_store = object_store.clone();
+        let file_name = if num_parts == 1 {
+            format!("{version:020}.checkpoint.parquet")
+        } else {
+            format!(
+                "{version:020}.checkpoint.{:010}.{num_parts:010}.parquet",
+                idx + 1
+            )
+        };
+        let checkpoint_path = log_store.log_path().child(file_name);
+        let parquet_bytes = actions.encode(actions.schema.clone(), non_file_actions, file_actions);
+        part_writes.push(async move {
+            let parquet_bytes = parquet_bytes.await?;
+            let size_in_bytes = parquet_bytes.len() as i64;
+            debug!("Writing checkpoint to {:?}.", checkpoint_path);
+            object_store.put(&checkpoint_path, parquet_bytes).await?;
+            Ok::<_, ProtocolError>(size_in_bytes)
+        });
+    }
+    let size_in_bytes = futures::stream::iter(part_writes)
+        .buffer_unordered(CHECKPOINT_WRITE_CONCURRENCY)
+        .try_fold(0, |acc, size| async move { Ok(acc + size) })
+        .await?;
+
+    let mut checkpoint =
+        CheckPointBuilder::new(version, num_actions as i64).with_size_in_bytes(size_in_bytes);
+    if num_parts > 1 {
+        checkpoint = checkpoint.with_parts(num_parts as u32);
+    }
+    Ok(checkpoint.build())
+}
+
+/// Write a V2 checkpoint
+///
+/// The file actions are split into sidecar files of at most `sidecar_max_actions` actions,
+/// which are written concurrently to `_delta_log/_sidecars`. The top level checkpoint file
+/// contains the `checkpointMetadata` action, all non-file actions and references to the
+/// sidecar files.
+async fn write_v2_checkpoint(
+    version: i64,
+    actions: CheckpointActions,
+    log_store: &dyn LogStore,
+    sidecar_max_actions: usize,
+) -> Result<CheckPoint, ProtocolError> {
+    let object_store = log_store.object_store();
+    let sidecar_root = log_store.log_path().child(SIDECAR_FOLDER);
+    let sidecar_fields = ["add", "remove"]
+        .iter()
+        .filter_map(|name| actions.schema.index_of(name).ok())
+        .collect::<Vec<_>>();
+    let sidecar_schema = Arc::new(actions.schema.project(&sidecar_fields)?);
+
+    let mut sidecar_writes = Vec::new();
+    let sidecar_max_actions = sidecar_max_actions.max(1);
+    for start in (0..actions.file_actions.len()).step_by(sidecar_max_actions) {
+        let end = actions.file_actions.len().min(start + sidecar_max_actions);
+        let object_store = object_store.clone();
+        let sidecar_root = sidecar_root.clone();
+        let parquet_bytes = actions.encode(sidecar_schema.clone(), Vec::new(), start..end);
+        sidecar_writes.push(async move {
+            let parquet_bytes = parquet_bytes.await?;
+            let sidecar = Sidecar {
+                path: format!("{}.parquet", Uuid::new_v4()),
+                size_in_bytes: parquet_bytes.len() as i64,
+                modification_time: Utc::now().timestamp_millis(),
+                tags: None,
+            };
+            let sidecar_path = sidecar_root.child(sidecar.path.as_str());
+            debug!("Writing checkpoint sidecar to {:?}.", sidecar_path);
+            object_store.put(&sidecar_path, parquet_bytes).await?;
+            Ok::<_, ProtocolError>(sidecar)
+        });
+    }
+    let sidecars = futures::stream::iter(sidecar_writes)
+        .buffer_unordered(CHECKPOINT_WRITE_CONCURRENCY)
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let checkpoint_metadata = CheckpointMetadata {
+        version,
+        tags: None,
+    };
+    let mut jsons = vec![json!({ "checkpointMetadata": checkpoint_metadata })];
+    jsons.extend(actions.non_file_actions.iter().cloned());
+    for sidecar in sidecars {
+        jsons.push(json!({ "sidecar": sidecar }));
+    }
+    let num_actions = jsons.len() + actions.file_actions.len();
+    let parquet_bytes = actions
+        .encode(
+            delta_log_schema_for_v2_checkpoint(&actions.schema),
+            jsons,
+            0..0,
+        )
+        .await?;
+
+    let file_name = format!("{version:020}.checkpoint.{}.parquet", Uuid::new_v4());
+    let checkpoint_path = log_store.log_path().child(file_name);
+    debug!("Writing checkpoint to {:?}.", checkpoint_path);
+    object_store
+        .put(&checkpoint_path, parquet_bytes.clone())
+        .await?;
+
+    Ok(CheckPointBuilder::new(version, num_actions as i64)
+        .with_size_in_bytes(parquet_bytes.len() as i64)
+        .build())
+}
+
+/// Creates a log compaction file `<start>.<end>.compacted.json` which contains the reconciled
+/// actions of the commits `start_version..=end_version`.
+///
+/// Readers may use the compaction file in place of the commits it covers, as long as all of
+/// these commits are also part of the log segment being read.
+pub async fn create_log_compaction_for(
+    start_version: i64,
+    end_version: i64,
+    log_store: &dyn LogStore,
+) -> Result<(), ProtocolError> {
+    if start_version < 0 || end_version <= start_version {
+        return Err(CheckpointError::InvalidCompactionRange(start_version, end_version).into());
+    }
+
+    let mut futures = Vec::new();
+    for version in start_version..=end_version {
+        futures.push(async move {
+            let bytes = log_store
+                .read_commit_entry(version)
+                .await
+                .map_err(|err| ProtocolError::Generic(err.to_string()))?
+                .ok_or_else(|| {
+                    ProtocolError::Generic(format!(
+                        "Commit {version} is required for log compaction but was not found"
+                    ))
+                })?;
+            crate::logstore::get_actions(version, bytes)
+                .await
+                .map_err(|err| ProtocolError::Generic(err.to_string()))
+        });
+    }
+    let commits: Vec<Vec<Action>> = futures::stream::iter(futures)
+        .buffered(CHECKPOINT_WRITE_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let actions = reconcile_compaction_actions(commits.into_iter().rev());
+    let mut buffer = Vec::new();
+    for action in actions {
+        serde_json::to_writer(&mut buffer, &action)?;
+        buffer.push(b'\n');
+    }
+
+    let compaction_path = log_store.log_path().child(format!(
+        "{:020}.{:020}.compacted.json",
+        start_version, end_version
+    ));
+    debug!("Writing log compaction to {:?}.", compaction_path);
+    log_store
+        .object_store()
+        .put(&compaction_path, bytes::Bytes::from(buffer))
+        .await?;
+
+    Ok(())
+}
+
+/// Reconciles the actions of consecutive commits, given from newest to oldest.
+///
+/// The newest protocol, metadata, transaction and domain metadata actions win, and only the
+/// newest action is kept for each logical file. Commit info and cdc actions are dropped.
+fn reconcile_compaction_actions(commits: impl Iterator<Item = Vec<Action>>) -> Vec<Action> {
+    let mut protocol = None;
+    let mut metadata = None;
+    let mut txns: HashMap<String, Txn> = HashMap::new();
+    let mut domains: HashMap<String, DomainMetadata> = HashMap::new();
+    let mut seen_files = HashSet::new();
+    let mut file_actions = Vec::new();
+
+    for actions in commits {
+        for action in actions {
+            match action {
+                Action::Protocol(p) => {
+                    protocol.get_or_insert(p);
+                }
+                Action::Metadata(m) => {
+                    metadata.get_or_insert(m);
+                }
+                Action::Txn(t) => {
+                    txns.entry(t.app_id.clone()).or_insert(t);
+                }
+                Action::DomainMetadata(d) => {
+                    domains.entry(d.domain.clone()).or_insert(d);
+                }
+                Action::Add(add) => {
+                    let key = file_action_key(&add.path, add.deletion_vector.as_ref());
+                    if seen_files.insert(key) {
+                        file_actions.push(Action::Add(add));
+                    }
+                }
+                Action::Remove(remove) => {
+                    let key = file_action_key(&remove.path, remove.deletion_vector.as_ref());
+                    if seen_files.insert(key) {
+                        file_actions.push(Action::Remove(remove));
+                    }
+                }
+                Action::CommitInfo(_) | Action::Cdc(_) => {}
+            }
+        }
+    }
+
+    let mut txns = txns.into_values().collect::<Vec<_>>();
+    txns.sort_by(|a, b| a.app_id.cmp(&b.app_id));
+    let mut domains = domains.into_values().collect::<Vec<_>>();
+    domains.sort_by(|a, b| a.domain.cmp(&b.domain));
+
+    protocol
+        .map(Action::Protocol)
+        .into_iter()
+        .chain(metadata.map(Action::Metadata))
+        .chain(txns.into_iter().map(Action::Txn))
+        .chain(domains.into_iter().map(Action::DomainMetadata))
+        .chain(file_actions)
+        .collect()
+}
+
+/// The key identifying a logical file, i.e. its path combined with the unique id of its
+/// deletion vector.
+fn file_action_key(path: &str, dv: Option<&DeletionVectorDescriptor>) -> (String, Option<String>) {
+    let dv_id = dv.map(|dv| match dv.offset {
+        Some(offset) => format!(
+            "{}{}@{offset}",
+            dv.storage_type.as_ref(),
+            dv.path_or_inline_dv
+        ),
+        None => format!("{}{}", dv.storage_type.as_ref(), dv.path_or_inline_dv),
+    });
+    (path.to_string(), dv_id)
+}
+
 /// Deletes all delta log commits that are older than the cutoff time
 /// and less than the specified version.
 pub async fn cleanup_expired_logs_for(
@@ -186,8 +527,10 @@ pub async fn cleanup_expired_logs_for(
     cutoff_timestamp: i64,
 ) -> Result<usize, ProtocolError> {
     lazy_static! {
-        static ref DELTA_LOG_REGEX: Regex =
-            Regex::new(r"_delta_log/(\d{20})\.(json|checkpoint|json.tmp).*$").unwrap();
+        static ref DELTA_LOG_REGEX: Regex = Regex::new(
+            r"_delta_log/(\d{20})\.((\d{20})\.compacted\.json|json|checkpoint|json.tmp).*$"
+        )
+        .unwrap();
     }
 
     let object_store = log_store.object_store();
@@ -223,7 +566,8 @@ pub async fn cleanup_expired_logs_for(
 
                     match DELTA_LOG_REGEX.captures(meta.location.as_ref()) {
                         Some(captures) => {
-                            let log_ver_str = captures.get(1).unwrap().as_str();
+                            // compaction files are expired based on the last commit they cover
+                            let log_ver_str = captures.get(3).or(captures.get(1)).unwrap().as_str();
                             let log_ver: i64 = log_ver_str.parse().unwrap();
                             if log_ver < until_version && ts <= cutoff_timestamp {
                                 // This location is ready to be deleted
@@ -244,14 +588,102 @@ pub async fn cleanup_expired_logs_for(
     Ok(deleted.len())
 }
 
-fn parquet_bytes_from_state(
+/// The actions of a checkpoint and the schema of the checkpoint files
+struct CheckpointActions {
+    /// Arrow schema of the checkpoint parquet file
+    schema: ArrowSchemaRef,
+    /// The protocol, metadata, txn and domain metadata actions
+    non_file_actions: Vec<Value>,
+    /// The remove and add actions, which are encoded by the task writing the file they are
+    /// contained in
+    file_actions: Arc<Vec<Action>>,
+    /// Encoder for the file actions
+    encoder: Arc<FileActionEncoder>,
+}
+
+impl CheckpointActions {
+    /// Encode the given non-file actions followed by a range of the file actions as a parquet
+    /// file with the given schema.
+    ///
+    /// Encoding is CPU bound, so it runs on a blocking task. The returned future does not
+    /// borrow the actions, so files can be encoded concurrently.
+    fn encode(
+        &self,
+        schema: ArrowSchemaRef,
+        non_file_actions: Vec<Value>,
+        file_actions: Range<usize>,
+    ) -> impl Future<Output = Result<bytes::Bytes, ProtocolError>> + Send + 'static {
+        let actions = self.file_actions.clone();
+        let encoder = self.encoder.clone();
+        let task = tokio::task::spawn_blocking(move || {
+            let jsons = non_file_actions.into_iter().map(Ok).chain(
+                actions[file_actions]
+                    .iter()
+                    .map(|action| encoder.encode(action)),
+            );
+            parquet_bytes_from_jsons(schema, jsons)
+        });
+        async move {
+            task.await
+                .map_err(|err| ProtocolError::Generic(err.to_string()))?
+        }
+    }
+}
+
+/// Encodes file actions as they are represented in checkpoints
+struct FileActionEncoder {
+    partition_col_data_types: Vec<(String, DataType)>,
+    stats_conversions: Vec<(SchemaPath, DataType)>,
+    write_stats_as_json: bool,
+    write_stats_as_struct: bool,
+}
+
+impl FileActionEncoder {
+    fn encode(&self, action: &Action) -> Result<Value, ProtocolError> {
+        match action {
+            Action::Add(add) => checkpoint_add_from_state(
+                add,
+                self.partition_col_data_types.as_slice(),
+                &self.stats_conversions,
+                self.write_stats_as_json,
+                self.write_stats_as_struct,
+            ),
+            action => Ok(serde_json::to_value(action)?),
+        }
+    }
+}
+
+fn checkpoint_actions_from_state(
     state: &DeltaTableState,
     mut tombstones: Vec<Remove>,
-) -> Result<(CheckPoint, bytes::Bytes), ProtocolError> {
+    domain_metadata: Vec<DomainMetadata>,
+) -> Result<CheckpointActions, ProtocolError> {
     let current_metadata = state.metadata();
-    let schema = current_metadata.schema()?;
-
-    let partition_col_data_types = get_partition_col_data_types(&schema, current_metadata);
+    let table_config = state.table_config();
+    let write_stats_as_json = table_config.write_stats_as_json();
+    let write_stats_as_struct = table_config.write_stats_as_struct();
+
+    // partition values and statistics are keyed by the physical column names in the log
+    let column_mapping_mode = table_config.column_mapping_mode();
+    let logical_schema = current_metadata.schema()?;
+    let schema = logical_schema.make_physical(column_mapping_mode)?;
+    let partition_columns = current_metadata
+        .partition_columns
+        .iter()
+        .map(|name| {
+            Ok(logical_schema
+                .field_with_name(name)?
+                .physical_name_for(column_mapping_mode)?
+                .to_string())
+        })
+        .collect::<Result<Vec<_>, ProtocolError>>()?;
+
+    let partition_col_data_types = schema
+        .fields()
+        .iter()
+        .filter(|f| partition_columns.contains(f.name()))
+        .map(|f| (f.name().clone(), f.data_type().clone()))
+        .collect::<Vec<_>>();
 
     // Collect a map of paths that require special stats conversion.
     let mut stats_conversions: Vec<(SchemaPath, DataType)> = Vec::new();
@@ -277,7 +709,7 @@ fn parquet_bytes_from_state(
     }
     let files = state.file_actions().unwrap();
     // protocol
-    let jsons = std::iter::once(Action::Protocol(Protocol {
+    let non_file_actions = std::iter::once(Action::Protocol(Protocol {
         min_reader_version: state.protocol().min_reader_version,
         min_writer_version: state.protocol().min_writer_version,
         writer_features: if state.protocol().min_writer_version >= 7 {
@@ -306,31 +738,53 @@ fn parquet_bytes_from_state(
                 })
             }),
     )
-    // removes
-    .chain(tombstones.iter().map(|r| {
-        let mut r = (*r).clone();
+    // domainMetadata
+    .chain(domain_metadata.into_iter().map(Action::DomainMetadata))
+    .map(|a| serde_json::to_value(a).map_err(ProtocolError::from))
+    .collect::<Result<Vec<_>, _>>()?;
 
-        // As a "new writer", we should always set `extendedFileMetadata` when writing, and include/ignore the other three fields accordingly.
-        // https://github.com/delta-io/delta/blob/fb0452c2fb142310211c6d3604eefb767bb4a134/core/src/main/scala/org/apache/spark/sql/delta/actions/actions.scala#L311-L314
-        if r.extended_file_metadata.is_none() {
-            r.extended_file_metadata = Some(false);
-        }
+    // removes
+    let file_actions = tombstones
+        .into_iter()
+        .map(|mut r| {
+            // As a "new writer", we should always set `extendedFileMetadata` when writing, and include/ignore the other three fields accordingly.
+            // https://github.com/delta-io/delta/blob/fb0452c2fb142310211c6d3604eefb767bb4a134/core/src/main/scala/org/apache/spark/sql/delta/actions/actions.scala#L311-L314
+            if r.extended_file_metadata.is_none() {
+                r.extended_file_metadata = Some(false);
+            }
 
-        Action::Remove(r)
-    }))
-    .map(|a| serde_json::to_value(a).map_err(ProtocolError::from))
-    // adds
-    .chain(files.iter().map(|f| {
-        checkpoint_add_from_state(f, partition_col_data_types.as_slice(), &stats_conversions)
-    }));
+            Action::Remove(r)
+        })
+        // adds
+        .chain(files.into_iter().map(Action::Add))
+        .collect::<Vec<_>>();
 
     // Create the arrow schema that represents the Checkpoint parquet file.
     let arrow_schema = delta_log_schema_for_table(
         (&schema).try_into()?,
-        current_metadata.partition_columns.as_slice(),
+        partition_columns.as_slice(),
         use_extended_remove_schema,
+        write_stats_as_json,
+        write_stats_as_struct,
     );
 
+    Ok(CheckpointActions {
+        schema: arrow_schema,
+        non_file_actions,
+        file_actions: Arc::new(file_actions),
+        encoder: Arc::new(FileActionEncoder {
+            partition_col_data_types,
+            stats_conversions,
+            write_stats_as_json,
+            write_stats_as_struct,
+        }),
+    })
+}
+
+fn parquet_bytes_from_jsons(
+    arrow_schema: ArrowSchemaRef,
+    jsons: impl IntoIterator<Item = Result<Value, ProtocolError>>,
+) -> Result<bytes::Bytes, ProtocolError> {
     debug!("Writing to checkpoint parquet buffer...");
     // Write the Checkpoint parquet file.
     let mut bytes = vec![];
@@ -346,37 +800,45 @@ fn parquet_bytes_from_state(
     let mut decoder = ReaderBuilder::new(arrow_schema)
         .with_batch_size(CHECKPOINT_RECORD_BATCH_SIZE)
         .build_decoder()?;
-    let jsons = jsons.collect::<Result<Vec<serde_json::Value>, _>>()?;
-    decoder.serialize(&jsons)?;
-
-    while let Some(batch) = decoder.flush()? {
-        writer.write(&batch)?;
+    for chunk in &jsons.into_iter().chunks(CHECKPOINT_RECORD_BATCH_SIZE) {
+        let chunk = chunk.collect::<Result<Vec<_>, _>>()?;
+        decoder.serialize(&chunk)?;
+        while let Some(batch) = decoder.flush()? {
+            writer.write(&batch)?;
+        }
     }
 
     let _ = writer.close()?;
     debug!("Finished writing checkpoint parquet buffer.");
 
-    let checkpoint = CheckPointBuilder::new(state.version(), jsons.len() as i64)
-        .with_size_in_bytes(bytes.len() as i64)
-        .build();
-    Ok((checkpoint, bytes::Bytes::from(bytes)))
+    Ok(bytes::Bytes::from(bytes))
 }
 
 fn checkpoint_add_from_state(
     add: &AddAction,
-    partition_col_data_types: &[(&String, &DataType)],
+    partition_col_data_types: &[(String, DataType)],
     stats_conversions: &[(SchemaPath, DataType)],
+    write_stats_as_json: bool,
+    write_stats_as_struct: bool,
 ) -> Result<Value, ProtocolError> {
     let mut v = serde_json::to_value(Action::Add(add.clone()))
         .map_err(|err| ArrowError::JsonError(err.to_string()))?;
 
     v["add"]["dataChange"] = Value::Bool(false);
+    if !write_stats_as_json {
+        if let Some(add) = v["add"].as_object_mut() {
+            add.remove("stats");
+        }
+    }
+    if !write_stats_as_struct {
+        return Ok(v);
+    }
 
     if !add.partition_values.is_empty() {
         let mut partition_values_parsed: HashMap<String, Value> = HashMap::new();
 
         for (field_name, data_type) in partition_col_data_types.iter() {
-            if let Some(string_value) = add.partition_values.get(*field_name) {
+            if let Some(string_value) = add.partition_values.get(field_name) {
                 let v = typed_partition_value_from_option_string(string_value, data_type)?;
 
                 partition_values_parsed.insert(field_name.to_string(), v);
@@ -827,7 +1289,7 @@ mod tests {
         );
     }
 
-    async fn setup_table() -> DeltaTable {
+    fn table_batch() -> RecordBatch {
         use arrow_schema::{DataType, Field};
         let schema = Arc::new(ArrowSchema::new(vec![Field::new(
             "id",
@@ -837,7 +1299,11 @@ mod tests {
 
         let data =
             vec![Arc::new(arrow::array::StringArray::from(vec!["A", "B", "C", "D"])) as ArrayRef];
-        let batches = vec![RecordBatch::try_new(schema.clone(), data).unwrap()];
+        RecordBatch::try_new(schema, data).unwrap()
+    }
+
+    async fn setup_table() -> DeltaTable {
+        let batches = vec![table_batch()];
 
         let table = DeltaOps::new_in_memory()
             .write(batches.clone())
@@ -1044,6 +1510,382 @@ mod tests {
         create_checkpoint(&table).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_create_multi_part_checkpoint() {
+        use futures::TryStreamExt;
+
+        let mut table = setup_table().await;
+        for _ in 0..3 {
+            table = DeltaOps(table).write(vec![table_batch()]).await.unwrap();
+        }
+        assert_eq!(table.version(), 4);
+        assert_eq!(table.get_files_count(), 4);
+
+        // protocol, metadata, 4 adds and 1 remove
+        create_checkpoint_with_part_size(
+            table.version(),
+            table.snapshot().unwrap(),
+            table.log_store().as_ref(),
+            3,
+        )
+        .await
+        .unwrap();
+
+        let store = table.object_store();
+        let mut parts: Vec<_> = store
+            .list(Some(&Path::from("_delta_log")))
+            .map_ok(|meta| meta.location.filename().unwrap().to_string())
+            .try_filter(|name| {
+                futures::future::ready(name.starts_with("00000000000000000004.checkpoint"))
+            })
+            .try_collect()
+            .await
+            .unwrap();
+        parts.sort();
+        assert_eq!(
+            parts,
+            vec![
+                "00000000000000000004.checkpoint.0000000001.0000000003.parquet",
+                "00000000000000000004.checkpoint.0000000002.0000000003.parquet",
+                "00000000000000000004.checkpoint.0000000003.0000000003.parquet",
+            ]
+        );
+
+        let last_checkpoint = store
+            .get(&Path::from("_delta_log/_last_checkpoint"))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let last_checkpoint: CheckPoint = serde_json::from_slice(&last_checkpoint).unwrap();
+        assert_eq!(last_checkpoint.version, 4);
+        assert_eq!(last_checkpoint.parts, Some(3));
+        assert_eq!(last_checkpoint.size, 7);
+
+        // drop the commits covered by the checkpoint to make sure the state is read from it
+        for version in 0..=4 {
+            store
+                .delete(&Path::from(format!("_delta_log/{version:020}.json")))
+                .await
+                .unwrap();
+        }
+        let mut loaded = DeltaTable::new(table.log_store(), Default::default());
+        loaded.load().await.unwrap();
+        assert_eq!(loaded.version(), 4);
+        let mut expected = table.get_files_iter().unwrap().collect::<Vec<_>>();
+        let mut files = loaded.get_files_iter().unwrap().collect::<Vec<_>>();
+        expected.sort();
+        files.sort();
+        assert_eq!(files, expected);
+        let tombstones = loaded
+            .snapshot()
+            .unwrap()
+            .all_tombstones(loaded.object_store())
+            .await
+            .unwrap()
+            .count();
+        assert_eq!(tombstones, 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_log_compaction() {
+        let mut table = setup_table().await;
+        for _ in 0..3 {
+            table = DeltaOps(table).write(vec![table_batch()]).await.unwrap();
+        }
+        assert_eq!(table.version(), 4);
+
+        create_log_compaction_for(0, 4, table.log_store().as_ref())
+            .await
+            .unwrap();
+
+        let store = table.object_store();
+        let compaction_path =
+            Path::from("_delta_log/00000000000000000000.00000000000000000004.compacted.json");
+        let bytes = store
+            .get(&compaction_path)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let actions = crate::logstore::get_actions(4, bytes).await.unwrap();
+        // protocol, metadata, 4 adds and 1 remove
+        assert_eq!(actions.len(), 7);
+        assert!(matches!(actions[0], Action::Protocol(_)));
+        assert!(matches!(actions[1], Action::Metadata(_)));
+        assert!(!actions
+            .iter()
+            .any(|a| matches!(a, Action::CommitInfo(_) | Action::Cdc(_))));
+
+        // invalidate the commits covered by the compaction to make sure it is read instead
+        for version in 0..=4 {
+            store
+                .put(
+                    &Path::from(format!("_delta_log/{version:020}.json")),
+                    bytes::Bytes::from_static(b"not a commit"),
+                )
+                .await
+                .unwrap();
+        }
+        let mut loaded = DeltaTable::new(table.log_store(), Default::default());
+        loaded.load().await.unwrap();
+        assert_eq!(loaded.version(), 4);
+        let mut expected = table.get_files_iter().unwrap().collect::<Vec<_>>();
+        let mut files = loaded.get_files_iter().unwrap().collect::<Vec<_>>();
+        expected.sort();
+        files.sort();
+        assert_eq!(files, expected);
+        let tombstones = loaded
+            .snapshot()
+            .unwrap()
+            .all_tombstones(loaded.object_store())
+            .await
+            .unwrap()
+            .count();
+        assert_eq!(tombstones, 1);
+
+        let result = create_log_compaction_for(3, 3, table.log_store().as_ref()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_log_compaction_interval() {
+        use crate::operations::transaction::CommitProperties;
+
+        let mut table = setup_table().await;
+        for _ in 0..2 {
+            table = DeltaOps(table)
+                .write(vec![table_batch()])
+                .with_commit_properties(CommitProperties::default().with_log_compaction_interval(2))
+                .await
+                .unwrap();
+        }
+        assert_eq!(table.version(), 3);
+
+        let store = table.object_store();
+        store
+            .head(&Path::from(
+                "_delta_log/00000000000000000002.00000000000000000003.compacted.json",
+            ))
+            .await
+            .unwrap();
+        assert!(store
+            .head(&Path::from(
+                "_delta_log/00000000000000000001.00000000000000000002.compacted.json",
+            ))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_checkpoint_with_struct_stats() {
+        use crate::kernel::{Scalar, StructField};
+        use crate::table::config::DeltaConfigKey;
+        use arrow_array::StringArray;
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![
+                StructField::new("id", crate::kernel::DataType::STRING, true),
+                StructField::new("part", crate::kernel::DataType::INTEGER, true),
+            ])
+            .with_partition_columns(["part"])
+            .with_configuration_property(DeltaConfigKey::CheckpointWriteStatsAsStruct, Some("true"))
+            .with_configuration_property(DeltaConfigKey::CheckpointWriteStatsAsJson, Some("false"))
+            .await
+            .unwrap();
+
+        let schema = Arc::new(ArrowSchema::new(vec![
+            arrow_schema::Field::new("id", arrow_schema::DataType::Utf8, true),
+            arrow_schema::Field::new("part", arrow_schema::DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef,
+                Arc::new(Int32Array::from(vec![1, 1, 2])) as ArrayRef,
+            ],
+        )
+        .unwrap();
+        let table = DeltaOps(table).write(vec![batch]).await.unwrap();
+        assert_eq!(table.version(), 1);
+        create_checkpoint(&table).await.unwrap();
+
+        let store = table.object_store();
+        let checkpoint = store
+            .get(&Path::from(
+                "_delta_log/00000000000000000001.checkpoint.parquet",
+            ))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(checkpoint).unwrap();
+        let add_fields = match reader.schema().field_with_name("add").unwrap().data_type() {
+            arrow_schema::DataType::Struct(fields) => fields.clone(),
+            _ => unreachable!(),
+        };
+        assert!(add_fields.find("stats").is_none());
+        assert!(add_fields.find("stats_parsed").is_some());
+        assert!(add_fields.find("partitionValues_parsed").is_some());
+
+        for version in 0..=1 {
+            store
+                .delete(&Path::from(format!("_delta_log/{version:020}.json")))
+                .await
+                .unwrap();
+        }
+        let mut loaded = DeltaTable::new(table.log_store(), Default::default());
+        loaded.load().await.unwrap();
+        assert_eq!(loaded.version(), 1);
+
+        let mut files = loaded
+            .snapshot()
+            .unwrap()
+            .log_data()
+            .into_iter()
+            .map(|file| {
+                let part = file.partition_values().unwrap().get("part").cloned();
+                (part, file.num_records(), file.min_values())
+            })
+            .collect::<Vec<_>>();
+        files.sort_by_key(|(_, num_records, _)| *num_records);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].0, Some(Scalar::Integer(2)));
+        assert_eq!(files[0].1, Some(1));
+        assert_eq!(files[1].0, Some(Scalar::Integer(1)));
+        assert_eq!(files[1].1, Some(2));
+        assert!(files[1].2.is_some());
+
+        // json statistics are restored from the typed statistics
+        for add in loaded.snapshot().unwrap().file_actions().unwrap() {
+            assert!(add.get_stats().unwrap().is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_v2_checkpoint_with_sidecars() {
+        use crate::kernel::{ReaderFeatures, StructField};
+        use crate::table::config::DeltaConfigKey;
+        use futures::TryStreamExt;
+
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![StructField::new(
+                "id",
+                crate::kernel::DataType::STRING,
+                false,
+            )])
+            .with_configuration_property(DeltaConfigKey::CheckpointPolicy, Some("v2"))
+            .await
+            .unwrap();
+        let protocol = table.protocol().unwrap();
+        assert!(protocol
+            .reader_features
+            .as_ref()
+            .is_some_and(|f| f.contains(&ReaderFeatures::V2Checkpoint)));
+        assert!(protocol
+            .writer_features
+            .as_ref()
+            .is_some_and(|f| f.contains(&WriterFeatures::V2Checkpoint)));
+
+        let schema = Arc::new(ArrowSchema::new(vec![arrow_schema::Field::new(
+            "id",
+            arrow_schema::DataType::Utf8,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(arrow::array::StringArray::from(vec!["A", "B"])) as ArrayRef],
+        )
+        .unwrap();
+        let mut table = table;
+        for _ in 0..3 {
+            table = DeltaOps(table).write(vec![batch.clone()]).await.unwrap();
+        }
+        assert_eq!(table.version(), 3);
+        assert_eq!(table.get_files_count(), 3);
+
+        create_checkpoint_with_part_size(
+            table.version(),
+            table.snapshot().unwrap(),
+            table.log_store().as_ref(),
+            2,
+        )
+        .await
+        .unwrap();
+
+        let store = table.object_store();
+        let sidecars: Vec<_> = store
+            .list(Some(&Path::from("_delta_log/_sidecars")))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(sidecars.len(), 2);
+
+        let log_files: Vec<_> = store
+            .list(Some(&Path::from("_delta_log")))
+            .try_collect()
+            .await
+            .unwrap();
+        let checkpoints: Vec<_> = log_files
+            .iter()
+            .filter_map(|m| m.location.filename())
+            .filter(|name| name.starts_with("00000000000000000003.checkpoint."))
+            .collect();
+        assert_eq!(checkpoints.len(), 1);
+        assert!(checkpoints[0].ends_with(".parquet"));
+
+        let last_checkpoint = store
+            .get(&Path::from("_delta_log/_last_checkpoint"))
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let last_checkpoint: CheckPoint = serde_json::from_slice(&last_checkpoint).unwrap();
+        assert_eq!(last_checkpoint.version, 3);
+
+        // drop the commits covered by the checkpoint to make sure the state is read from it
+        for version in 0..=3 {
+            store
+                .delete(&Path::from(format!("_delta_log/{version:020}.json")))
+                .await
+                .unwrap();
+        }
+        let mut loaded = DeltaTable::new(table.log_store(), Default::default());
+        loaded.load().await.unwrap();
+        assert_eq!(loaded.version(), 3);
+        assert_eq!(loaded.get_files_count(), 3);
+        assert_eq!(loaded.metadata().unwrap(), table.metadata().unwrap());
+        assert_eq!(loaded.protocol().unwrap(), table.protocol().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_create_v2_checkpoint_requires_feature() {
+        use crate::kernel::{Action, Protocol, StructField};
+        use crate::table::config::DeltaConfigKey;
+
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![StructField::new(
+                "id",
+                crate::kernel::DataType::STRING,
+                false,
+            )])
+            .with_actions(vec![Action::Protocol(Protocol::new(1, 2))])
+            .with_configuration_property(DeltaConfigKey::CheckpointPolicy, Some("v2"))
+            .await
+            .unwrap();
+        let res =
+            create_checkpoint_for(0, table.snapshot().unwrap(), table.log_store.as_ref()).await;
+        assert!(res.is_err());
+    }
+
     lazy_static! {
         static ref SCHEMA: Value = json!({
             "type": "struct",
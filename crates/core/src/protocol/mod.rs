@@ -21,7 +21,7 @@ use std::str::FromStr;
 use tracing::{debug, error};
 
 use crate::errors::{DeltaResult, DeltaTableError};
-use crate::kernel::{Add, CommitInfo, Metadata, Protocol, Remove};
+use crate::kernel::{Add, CommitInfo, DataType, Metadata, Protocol, Remove, StructField};
 use crate::logstore::LogStore;
 use crate::table::CheckPoint;
 
@@ -377,6 +377,97 @@ pub enum DeltaOperation {
         name: String,
     },
 
+    /// Renames a column of a table
+    #[serde(rename_all = "camelCase")]
+    RenameColumn {
+        /// Path of the column to rename
+        old_column_path: String,
+        /// New path of the column
+        new_column_path: String,
+    },
+
+    /// Drops columns from a table
+    DropColumns {
+        /// Names of the dropped columns
+        columns: Vec<String>,
+    },
+
+    /// Changes the data type of a column
+    #[serde(rename_all = "camelCase")]
+    ChangeColumnType {
+        /// Path of the changed column
+        column_path: String,
+        /// Previous data type of the column
+        from_type: DataType,
+        /// New data type of the column
+        to_type: DataType,
+    },
+
+    /// Adds columns to a table
+    #[serde(rename_all = "camelCase")]
+    AddColumns {
+        /// Paths of the added columns
+        columns: Vec<String>,
+    },
+
+    /// Changes the comment, metadata or nullability of a column
+    #[serde(rename_all = "camelCase")]
+    ChangeColumn {
+        /// Path of the changed column
+        column_path: String,
+        /// The changed column
+        field: StructField,
+    },
+
+    /// Sets properties of a table
+    SetTableProperties {
+        /// The properties that were set
+        properties: HashMap<String, String>,
+    },
+
+    /// Unsets properties of a table
+    #[serde(rename_all = "camelCase")]
+    UnsetTableProperties {
+        /// The properties that were unset
+        properties: Vec<String>,
+        /// Whether properties that were not set were ignored
+        if_exists: bool,
+    },
+
+    /// Rewrites data files of a table without changing its data
+    #[serde(rename_all = "camelCase")]
+    Reorg {
+        /// Whether rows deleted by deletion vectors were purged from the files
+        apply_purge: bool,
+    },
+
+    /// Drops a feature from the protocol of a table
+    #[serde(rename_all = "camelCase")]
+    DropFeature {
+        /// Name of the dropped feature
+        feature_name: String,
+        /// Whether the history of the table was truncated
+        truncate_history: bool,
+    },
+
+    /// Upgrades the protocol of a table
+    #[serde(rename_all = "camelCase")]
+    UpgradeProtocol {
+        /// The upgraded protocol
+        new_protocol: Protocol,
+    },
+
+    /// Clones a version of a table to a new table
+    #[serde(rename_all = "camelCase")]
+    Clone {
+        /// The location of the source table
+        source: String,
+        /// The cloned version of the source table
+        source_version: i64,
+        /// Whether the clone references the data files of the source table
+        is_shallow: bool,
+    },
+
     /// Merge data with a source data with the following predicate
     #[serde(rename_all = "camelCase")]
     Merge {
@@ -468,6 +559,17 @@ impl DeltaOperation {
             DeltaOperation::VacuumEnd { .. } => "VACUUM END",
             DeltaOperation::AddConstraint { .. } => "ADD CONSTRAINT",
             DeltaOperation::DropConstraint { .. } => "DROP CONSTRAINT",
+            DeltaOperation::RenameColumn { .. } => "RENAME COLUMN",
+            DeltaOperation::DropColumns { .. } => "DROP COLUMNS",
+            DeltaOperation::ChangeColumnType { .. } => "CHANGE COLUMN",
+            DeltaOperation::AddColumns { .. } => "ADD COLUMNS",
+            DeltaOperation::ChangeColumn { .. } => "CHANGE COLUMN",
+            DeltaOperation::SetTableProperties { .. } => "SET TBLPROPERTIES",
+            DeltaOperation::UnsetTableProperties { .. } => "UNSET TBLPROPERTIES",
+            DeltaOperation::Reorg { .. } => "REORG",
+            DeltaOperation::DropFeature { .. } => "DROP FEATURE",
+            DeltaOperation::UpgradeProtocol { .. } => "UPGRADE PROTOCOL",
+            DeltaOperation::Clone { .. } => "CLONE",
         }
     }
 
@@ -507,7 +609,17 @@ impl DeltaOperation {
             | Self::VacuumStart { .. }
             | Self::VacuumEnd { .. }
             | Self::AddConstraint { .. }
-            | Self::DropConstraint { .. } => false,
+            | Self::DropConstraint { .. }
+            | Self::RenameColumn { .. }
+            | Self::DropColumns { .. }
+            | Self::ChangeColumnType { .. }
+            | Self::AddColumns { .. }
+            | Self::ChangeColumn { .. }
+            | Self::SetTableProperties { .. }
+            | Self::UnsetTableProperties { .. }
+            | Self::Reorg { .. }
+            | Self::DropFeature { .. }
+            | Self::UpgradeProtocol { .. } => false,
             Self::Create { .. }
             | Self::FileSystemCheck {}
             | Self::StreamingUpdate { .. }
@@ -515,7 +627,8 @@ impl DeltaOperation {
             | Self::Delete { .. }
             | Self::Merge { .. }
             | Self::Update { .. }
-            | Self::Restore { .. } => true,
+            | Self::Restore { .. }
+            | Self::Clone { .. } => true,
         }
     }
 
@@ -105,6 +105,27 @@ pub fn store_for(url: &Url) -> DeltaResult<ObjectStoreRef> {
     }
 }
 
+/// Get the root of the store holding an absolute URI, e.g. of a data file referenced by a
+/// shallow clone
+pub(crate) fn store_root(location: &Url) -> Url {
+    let mut root = location.clone();
+    root.set_path("/");
+    root.set_query(None);
+    root.set_fragment(None);
+    root
+}
+
+/// Get the store for the given root configured with the given options
+pub(crate) fn root_store_for(root: &Url, options: &StorageOptions) -> DeltaResult<ObjectStoreRef> {
+    let scheme = Url::parse(&format!("{}://", root.scheme())).unwrap();
+    if let Some(factory) = factories().get(&scheme) {
+        let (store, _prefix) = factory.parse_url_opts(root, options)?;
+        Ok(store)
+    } else {
+        Err(DeltaTableError::InvalidTableLocation(root.clone().into()))
+    }
+}
+
 /// Options used for configuring backend storage
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct StorageOptions(pub HashMap<String, String>);
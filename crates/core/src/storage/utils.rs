@@ -4,6 +4,7 @@ use chrono::DateTime;
 use futures::TryStreamExt;
 use object_store::path::Path;
 use object_store::{DynObjectStore, ObjectMeta, Result as ObjectStoreResult};
+use url::{ParseError, Url};
 
 use crate::errors::{DeltaResult, DeltaTableError};
 use crate::kernel::Add;
@@ -20,6 +21,18 @@ pub async fn flatten_list_stream(
         .await
 }
 
+/// Check if a path of a file action is an absolute URI rather than relative to the table root
+pub(crate) fn is_absolute_path(path: &str) -> DeltaResult<bool> {
+    match Url::parse(path) {
+        Ok(_) => Ok(true),
+        Err(ParseError::RelativeUrlWithoutBase) => Ok(false),
+        Err(_) => Err(DeltaTableError::Generic(format!(
+            "Unable to parse path: {}",
+            &path
+        ))),
+    }
+}
+
 impl TryFrom<Add> for ObjectMeta {
     type Error = DeltaTableError;
 
@@ -39,9 +52,17 @@ impl TryFrom<&Add> for ObjectMeta {
             ))),
         )?;
 
+        // Files referenced by absolute URIs are located relative to the root of their store.
+        let location = if is_absolute_path(&value.path)? {
+            let url = Url::parse(&value.path)
+                .map_err(|_| DeltaTableError::InvalidTableLocation(value.path.clone()))?;
+            Path::from_url_path(url.path())?
+        } else {
+            Path::parse(value.path.as_str())?
+        };
+
         Ok(Self {
-            // TODO this won't work for absolute paths, since Paths are always relative to store.
-            location: Path::parse(value.path.as_str())?,
+            location,
             last_modified,
             size: value.size as usize,
             e_tag: None,
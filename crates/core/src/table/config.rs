@@ -39,6 +39,9 @@ pub enum DeltaConfigKey {
     /// Parquet columns that use different names.
     ColumnMappingMode,
 
+    /// The highest column id assigned to a column of the table while column mapping is enabled.
+    ColumnMappingMaxColumnId,
+
     /// The number of columns for Delta Lake to collect statistics about for data skipping.
     /// A value of -1 means to collect statistics for all columns. Updating this property does
     /// not automatically collect statistics again; instead, it redefines the statistics schema
@@ -69,6 +72,25 @@ pub enum DeltaConfigKey {
     /// true to enable deletion vectors and predictive I/O for updates.
     EnableDeletionVectors,
 
+    /// true to assign stable row ids and row commit versions to all rows written to the table.
+    EnableRowTracking,
+
+    /// true to allow widening the data types of columns without rewriting data files.
+    EnableTypeWidening,
+
+    /// true to write a monotonically increasing `inCommitTimestamp` into the commit info of each commit.
+    EnableInCommitTimestamps,
+
+    /// The table version at which in-commit timestamps were enabled.
+    InCommitTimestampEnablementVersion,
+
+    /// The in-commit timestamp of the commit that enabled in-commit timestamps.
+    InCommitTimestampEnablementTimestamp,
+
+    /// The version before which log entries may only be cleaned up all at once, since a
+    /// feature was dropped from the protocol at this version.
+    RequireCheckpointProtectionBeforeVersion,
+
     /// The degree to which a transaction must be isolated from modifications made by concurrent transactions.
     ///
     /// Valid values are `Serializable` and `WriteSerializable`.
@@ -127,11 +149,22 @@ impl AsRef<str> for DeltaConfigKey {
             Self::CheckpointWriteStatsAsStruct => "delta.checkpoint.writeStatsAsStruct",
             Self::CheckpointPolicy => "delta.checkpointPolicy",
             Self::ColumnMappingMode => "delta.columnMapping.mode",
+            Self::ColumnMappingMaxColumnId => "delta.columnMapping.maxColumnId",
             Self::DataSkippingNumIndexedCols => "delta.dataSkippingNumIndexedCols",
             Self::DataSkippingStatsColumns => "delta.dataSkippingStatsColumns",
             Self::DeletedFileRetentionDuration => "delta.deletedFileRetentionDuration",
             Self::EnableChangeDataFeed => "delta.enableChangeDataFeed",
             Self::EnableDeletionVectors => "delta.enableDeletionVectors",
+            Self::EnableRowTracking => "delta.enableRowTracking",
+            Self::EnableTypeWidening => "delta.enableTypeWidening",
+            Self::EnableInCommitTimestamps => "delta.enableInCommitTimestamps",
+            Self::InCommitTimestampEnablementVersion => "delta.inCommitTimestampEnablementVersion",
+            Self::InCommitTimestampEnablementTimestamp => {
+                "delta.inCommitTimestampEnablementTimestamp"
+            }
+            Self::RequireCheckpointProtectionBeforeVersion => {
+                "delta.requireCheckpointProtectionBeforeVersion"
+            }
             Self::IsolationLevel => "delta.isolationLevel",
             Self::LogRetentionDuration => "delta.logRetentionDuration",
             Self::EnableExpiredLogCleanup => "delta.enableExpiredLogCleanup",
@@ -159,6 +192,7 @@ impl FromStr for DeltaConfigKey {
             "delta.checkpoint.writeStatsAsStruct" => Ok(Self::CheckpointWriteStatsAsStruct),
             "delta.checkpointPolicy" => Ok(Self::CheckpointPolicy),
             "delta.columnMapping.mode" => Ok(Self::ColumnMappingMode),
+            "delta.columnMapping.maxColumnId" => Ok(Self::ColumnMappingMaxColumnId),
             "delta.dataSkippingNumIndexedCols" => Ok(Self::DataSkippingNumIndexedCols),
             "delta.dataSkippingStatsColumns" => Ok(Self::DataSkippingStatsColumns),
             "delta.deletedFileRetentionDuration" | "deletedFileRetentionDuration" => {
@@ -166,6 +200,18 @@ impl FromStr for DeltaConfigKey {
             }
             "delta.enableChangeDataFeed" => Ok(Self::EnableChangeDataFeed),
             "delta.enableDeletionVectors" => Ok(Self::EnableDeletionVectors),
+            "delta.enableRowTracking" => Ok(Self::EnableRowTracking),
+            "delta.enableTypeWidening" => Ok(Self::EnableTypeWidening),
+            "delta.enableInCommitTimestamps" => Ok(Self::EnableInCommitTimestamps),
+            "delta.inCommitTimestampEnablementVersion" => {
+                Ok(Self::InCommitTimestampEnablementVersion)
+            }
+            "delta.inCommitTimestampEnablementTimestamp" => {
+                Ok(Self::InCommitTimestampEnablementTimestamp)
+            }
+            "delta.requireCheckpointProtectionBeforeVersion" => {
+                Ok(Self::RequireCheckpointProtectionBeforeVersion)
+            }
             "delta.isolationLevel" => Ok(Self::IsolationLevel),
             "delta.logRetentionDuration" | "logRetentionDuration" => Ok(Self::LogRetentionDuration),
             "delta.enableExpiredLogCleanup" | "enableExpiredLogCleanup" => {
@@ -183,6 +229,71 @@ impl FromStr for DeltaConfigKey {
     }
 }
 
+impl DeltaConfigKey {
+    /// Validate that the value of a property has the type expected for this key
+    pub fn validate(&self, value: &str) -> Result<(), DeltaConfigError> {
+        let invalid = |expected: &str| {
+            DeltaConfigError::Validation(format!(
+                "Invalid value '{value}' for {}, expected {expected}",
+                self.as_ref()
+            ))
+        };
+        match self {
+            Self::AppendOnly
+            | Self::AutoOptimizeAutoCompact
+            | Self::AutoOptimizeOptimizeWrite
+            | Self::CheckpointWriteStatsAsJson
+            | Self::CheckpointWriteStatsAsStruct
+            | Self::EnableChangeDataFeed
+            | Self::EnableDeletionVectors
+            | Self::EnableRowTracking
+            | Self::EnableTypeWidening
+            | Self::EnableInCommitTimestamps
+            | Self::EnableExpiredLogCleanup
+            | Self::RandomizeFilePrefixes
+            | Self::TuneFileSizesForRewrites => {
+                value.parse::<bool>().map_err(|_| invalid("a boolean"))?;
+            }
+            Self::CheckpointInterval
+            | Self::DataSkippingNumIndexedCols
+            | Self::MinReaderVersion
+            | Self::MinWriterVersion
+            | Self::RandomPrefixLength => {
+                value.parse::<i32>().map_err(|_| invalid("an integer"))?;
+            }
+            Self::ColumnMappingMaxColumnId
+            | Self::InCommitTimestampEnablementVersion
+            | Self::InCommitTimestampEnablementTimestamp
+            | Self::RequireCheckpointProtectionBeforeVersion
+            | Self::TargetFileSize => {
+                value.parse::<i64>().map_err(|_| invalid("an integer"))?;
+            }
+            Self::DeletedFileRetentionDuration
+            | Self::LogRetentionDuration
+            | Self::SetTransactionRetentionDuration => {
+                parse_interval(value)?;
+            }
+            Self::IsolationLevel => {
+                value
+                    .parse::<IsolationLevel>()
+                    .map_err(|_| invalid("an isolation level"))?;
+            }
+            Self::CheckpointPolicy => {
+                value
+                    .parse::<CheckpointPolicy>()
+                    .map_err(|_| invalid("a checkpoint policy"))?;
+            }
+            Self::ColumnMappingMode => {
+                value
+                    .parse::<ColumnMappingMode>()
+                    .map_err(|_| invalid("a column mapping mode"))?;
+            }
+            Self::DataSkippingStatsColumns => {}
+        }
+        Ok(())
+    }
+}
+
 /// Delta configuration error
 #[derive(thiserror::Error, Debug, PartialEq, Eq)]
 pub enum DeltaConfigError {
@@ -258,6 +369,27 @@ impl<'a> TableConfig<'a> {
             // https://learn.microsoft.com/en-us/azure/databricks/administration-guide/workspace-settings/deletion-vectors
             false
         ),
+        (
+            "true to assign stable row ids and row commit versions to all rows written to the table.",
+            DeltaConfigKey::EnableRowTracking,
+            enable_row_tracking,
+            bool,
+            false
+        ),
+        (
+            "true to allow widening the data types of columns without rewriting data files.",
+            DeltaConfigKey::EnableTypeWidening,
+            enable_type_widening,
+            bool,
+            false
+        ),
+        (
+            "true to write a monotonically increasing `inCommitTimestamp` into the commit info of each commit.",
+            DeltaConfigKey::EnableInCommitTimestamps,
+            enable_in_commit_timestamps,
+            bool,
+            false
+        ),
         (
             "The number of columns for Delta Lake to collect statistics about for data skipping.",
             DeltaConfigKey::DataSkippingNumIndexedCols,
@@ -279,6 +411,20 @@ impl<'a> TableConfig<'a> {
             i32,
             100
         ),
+        (
+            "The highest column id assigned to a column of the table by column mapping",
+            DeltaConfigKey::ColumnMappingMaxColumnId,
+            column_mapping_max_column_id,
+            i64,
+            0
+        ),
+        (
+            "The version before which log entries may only be cleaned up all at once",
+            DeltaConfigKey::RequireCheckpointProtectionBeforeVersion,
+            require_checkpoint_protection_before_version,
+            i64,
+            0
+        ),
     );
 
     /// The shortest duration for Delta Lake to keep logically deleted data files before deleting
@@ -342,6 +488,24 @@ impl<'a> TableConfig<'a> {
             .unwrap_or_default()
     }
 
+    /// The table version at which in-commit timestamps were enabled.
+    ///
+    /// `None` if the feature was enabled when the table was created.
+    pub fn in_commit_timestamp_enablement_version(&self) -> Option<i64> {
+        self.0
+            .get(DeltaConfigKey::InCommitTimestampEnablementVersion.as_ref())
+            .and_then(|o| o.as_ref().and_then(|v| v.parse().ok()))
+    }
+
+    /// The in-commit timestamp of the commit that enabled in-commit timestamps.
+    ///
+    /// `None` if the feature was enabled when the table was created.
+    pub fn in_commit_timestamp_enablement_timestamp(&self) -> Option<i64> {
+        self.0
+            .get(DeltaConfigKey::InCommitTimestampEnablementTimestamp.as_ref())
+            .and_then(|o| o.as_ref().and_then(|v| v.parse().ok()))
+    }
+
     /// Return the check constraints on the current table
     pub fn get_constraints(&self) -> Vec<Constraint> {
         self.0
@@ -699,6 +863,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn validate_config_value_test() {
+        assert!(DeltaConfigKey::AppendOnly.validate("true").is_ok());
+        assert!(DeltaConfigKey::AppendOnly.validate("1").is_err());
+        assert!(DeltaConfigKey::TargetFileSize.validate("1024").is_ok());
+        assert!(DeltaConfigKey::TargetFileSize.validate("1kb").is_err());
+        assert!(DeltaConfigKey::LogRetentionDuration
+            .validate("interval 1 day")
+            .is_ok());
+        assert_eq!(
+            DeltaConfigKey::LogRetentionDuration.validate("1 day"),
+            Err(DeltaConfigError::Validation(
+                "'1 day' is not an interval".to_string()
+            ))
+        );
+        assert!(DeltaConfigKey::CheckpointPolicy.validate("v2").is_ok());
+        assert!(DeltaConfigKey::IsolationLevel.validate("none").is_err());
+        assert!(DeltaConfigKey::DataSkippingStatsColumns
+            .validate("a,b.c")
+            .is_ok());
+    }
+
     #[test]
     fn parse_interval_invalid_test() {
         assert_eq!(
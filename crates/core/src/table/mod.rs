@@ -16,9 +16,12 @@ use tracing::debug;
 use self::builder::DeltaTableConfig;
 use self::state::DeltaTableState;
 use crate::kernel::{
-    Action, CommitInfo, DataCheck, DataType, LogicalFile, Metadata, Protocol, StructType,
+    Action, CommitInfo, DataCheck, DataType, LogicalFile, Metadata, Protocol, Snapshot, StructType,
 };
 use crate::logstore::{self, extract_version_from_filename, LogStoreConfig, LogStoreRef};
+use crate::operations::transaction::in_commit_timestamp::{
+    in_commit_timestamps_enabled, read_commit_timestamp,
+};
 use crate::partitions::PartitionFilter;
 use crate::storage::{commit_uri_from_version, ObjectStoreRef};
 use crate::{DeltaResult, DeltaTableError};
@@ -275,7 +278,7 @@ impl DeltaTable {
     ///
     /// NOTE: This is for advanced users. If you don't know why you need to use this method,
     /// please call one of the `open_table` helper methods instead.
-    pub(crate) fn new_with_state(log_store: LogStoreRef, state: DeltaTableState) -> Self {
+    pub fn new_with_state(log_store: LogStoreRef, state: DeltaTableState) -> Self {
         Self {
             state: Some(state),
             log_store,
@@ -371,7 +374,20 @@ impl DeltaTable {
         self.update_incremental(Some(version)).await
     }
 
-    pub(crate) async fn get_version_timestamp(&self, version: i64) -> Result<i64, DeltaTableError> {
+    /// Get the timestamp of the commit at the given version, given the version and timestamp
+    /// from which on commits carry in-commit timestamps
+    pub(crate) async fn get_version_timestamp(
+        &self,
+        version: i64,
+        in_commit_timestamp_enablement: Option<(i64, Option<i64>)>,
+    ) -> Result<i64, DeltaTableError> {
+        // commits written with in-commit timestamps enabled carry their timestamp in the
+        // commit info, the modification time of the commit file may have changed since
+        if let Some((enablement_version, _)) = in_commit_timestamp_enablement {
+            if version >= enablement_version {
+                return read_commit_timestamp(self.log_store.as_ref(), version).await;
+            }
+        }
         match self
             .state
             .as_ref()
@@ -389,6 +405,25 @@ impl DeltaTable {
         }
     }
 
+    /// Version and timestamp from which on commits carry in-commit timestamps, read from the
+    /// protocol and metadata of the given version of the table
+    async fn in_commit_timestamp_enablement(
+        &self,
+        version: i64,
+    ) -> DeltaResult<Option<(i64, Option<i64>)>> {
+        if let Some(state) = self.state.as_ref().filter(|s| s.version() == version) {
+            return Ok(in_commit_timestamp_enablement(state.snapshot().snapshot()));
+        }
+        let snapshot = Snapshot::try_new(
+            &Path::default(),
+            self.object_store(),
+            self.config.clone(),
+            Some(version),
+        )
+        .await?;
+        Ok(in_commit_timestamp_enablement(&snapshot))
+    }
+
     /// Returns provenance information, including the operation, user, and so on, for each write to a table.
     /// The table history retention is based on the `logRetentionDuration` property of the Delta Table, 30 days by default.
     /// If `limit` is given, this returns the information of the latest `limit` commits made to this table. Otherwise,
@@ -402,7 +437,23 @@ impl DeltaTable {
             .await?
             .try_collect::<Vec<_>>()
             .await?;
-        Ok(infos.into_iter().flatten().collect())
+        Ok(infos.into_iter().filter_map(|(_, info)| info).collect())
+    }
+
+    /// Returns the version and provenance information of each commit to the table, latest
+    /// first, like [`history`](Self::history). Commits without provenance information are
+    /// included without it.
+    pub async fn versioned_history(
+        &self,
+        limit: Option<usize>,
+    ) -> Result<Vec<(i64, Option<CommitInfo>)>, DeltaTableError> {
+        self.snapshot()?
+            .snapshot
+            .snapshot()
+            .commit_infos(self.object_store(), limit)
+            .await?
+            .try_collect()
+            .await
     }
 
     /// Obtain Add actions for files that match the filter
@@ -535,15 +586,26 @@ impl DeltaTable {
             }
         }
         let mut max_version = self.get_latest_version().await?;
+        let target_ts = datetime.timestamp_millis();
+
+        // in-commit timestamps and file modification times are not comparable, so only the
+        // commits on the matching side of the feature's enablement are searched
+        let enablement = self.in_commit_timestamp_enablement(max_version).await?;
+        if let Some((enablement_version, Some(enablement_ts))) = enablement {
+            if target_ts >= enablement_ts {
+                min_version = min_version.max(enablement_version);
+            } else {
+                max_version = max_version.min(enablement_version - 1);
+            }
+        }
         let mut version = min_version;
         let lowest_table_version = min_version;
-        let target_ts = datetime.timestamp_millis();
 
         // binary search
         while min_version <= max_version {
             let pivot = (max_version + min_version) / 2;
             version = pivot;
-            let pts = self.get_version_timestamp(pivot).await?;
+            let pts = self.get_version_timestamp(pivot, enablement).await?;
             match pts.cmp(&target_ts) {
                 Ordering::Equal => {
                     break;
@@ -566,6 +628,19 @@ impl DeltaTable {
     }
 }
 
+/// Version and timestamp from which on commits of the snapshot's table carry in-commit
+/// timestamps, if the feature is enabled
+fn in_commit_timestamp_enablement(snapshot: &Snapshot) -> Option<(i64, Option<i64>)> {
+    let config = snapshot.table_config();
+    if !in_commit_timestamps_enabled(snapshot.protocol(), &config) {
+        return None;
+    }
+    Some((
+        config.in_commit_timestamp_enablement_version().unwrap_or(0),
+        config.in_commit_timestamp_enablement_timestamp(),
+    ))
+}
+
 impl fmt::Display for DeltaTable {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "DeltaTable({})", self.table_uri())?;
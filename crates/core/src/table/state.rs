@@ -11,8 +11,8 @@ use serde::{Deserialize, Serialize};
 use super::config::TableConfig;
 use super::{get_partition_col_data_types, DeltaTableConfig};
 use crate::kernel::{
-    Action, Add, AddCDCFile, DataType, EagerSnapshot, LogDataHandler, LogicalFile, Metadata,
-    Protocol, Remove, StructType,
+    Action, Add, AddCDCFile, DataType, DomainMetadata, EagerSnapshot, LogDataHandler, LogicalFile,
+    Metadata, Protocol, Remove, StructType,
 };
 
 use crate::logstore::LogStore;
@@ -131,6 +131,16 @@ impl DeltaTableState {
             .filter(move |t| t.deletion_timestamp.unwrap_or(0) > retention_timestamp))
     }
 
+    /// Latest [`DomainMetadata`] of all domains set on the table.
+    pub fn all_domain_metadata(&self) -> Vec<DomainMetadata> {
+        self.snapshot.all_domain_metadata()
+    }
+
+    /// Configuration of the given metadata domain, if it is set on the table.
+    pub fn domain_metadata(&self, domain: &str) -> Option<&str> {
+        self.snapshot.domain_metadata(domain)
+    }
+
     /// Full list of add actions representing all parquet files that are part of the current
     /// delta table state.
     pub fn file_actions(&self) -> DeltaResult<Vec<Add>> {
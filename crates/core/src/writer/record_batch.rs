@@ -28,11 +28,12 @@ use super::utils::{
 };
 use super::{DeltaWriter, DeltaWriterError, WriteMode};
 use crate::errors::DeltaTableError;
+use crate::kernel::arrow::column_mapping::PhysicalMapper;
 use crate::kernel::{Action, Add, PartitionsExt, Scalar, StructType};
 use crate::operations::cast::merge_schema;
 use crate::storage::ObjectStoreRetryExt;
 use crate::table::builder::DeltaTableBuilder;
-use crate::table::config::DEFAULT_NUM_INDEX_COLS;
+use crate::table::config::{TableConfig, DEFAULT_NUM_INDEX_COLS};
 use crate::DeltaTable;
 
 /// Writes messages to a delta lake table.
@@ -44,6 +45,7 @@ pub struct RecordBatchWriter {
     should_evolve: bool,
     partition_columns: Vec<String>,
     arrow_writers: HashMap<String, PartitionWriter>,
+    column_mapping: Option<PhysicalMapper>,
 }
 
 impl std::fmt::Debug for RecordBatchWriter {
@@ -79,6 +81,7 @@ impl RecordBatchWriter {
             partition_columns: partition_columns.unwrap_or_default(),
             should_evolve: false,
             arrow_writers: HashMap::new(),
+            column_mapping: None,
         })
     }
 
@@ -86,8 +89,13 @@ impl RecordBatchWriter {
     pub fn for_table(table: &DeltaTable) -> Result<Self, DeltaTableError> {
         // Initialize an arrow schema ref from the delta table schema
         let metadata = table.metadata()?;
-        let arrow_schema =
-            <ArrowSchema as TryFrom<&StructType>>::try_from(&metadata.schema()?.clone())?;
+        let table_schema = metadata.schema()?;
+        let arrow_schema = <ArrowSchema as TryFrom<&StructType>>::try_from(&table_schema)?;
+        let column_mapping = PhysicalMapper::try_new(
+            &arrow_schema,
+            &table_schema,
+            TableConfig(&metadata.configuration).column_mapping_mode(),
+        )?;
         let arrow_schema_ref = Arc::new(arrow_schema);
         let partition_columns = metadata.partition_columns.clone();
 
@@ -105,6 +113,7 @@ impl RecordBatchWriter {
             partition_columns,
             should_evolve: false,
             arrow_writers: HashMap::new(),
+            column_mapping,
         })
     }
 
@@ -140,18 +149,37 @@ impl RecordBatchWriter {
         partition_values: &IndexMap<String, Scalar>,
         mode: WriteMode,
     ) -> Result<ArrowSchemaRef, DeltaTableError> {
-        let arrow_schema =
-            arrow_schema_without_partitions(&self.arrow_schema_ref, &self.partition_columns);
+        // data files of tables with column mapping use the physical column names
+        let (record_batch, partition_values, arrow_schema) = match &self.column_mapping {
+            Some(mapper) => {
+                let partition_columns = self
+                    .partition_columns
+                    .iter()
+                    .map(|name| mapper.physical_name(name))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (
+                    record_batch_without_partitions(
+                        &mapper.map_batch(&record_batch)?,
+                        &partition_columns,
+                    )?,
+                    mapper.map_partition_values(partition_values)?,
+                    arrow_schema_without_partitions(&mapper.schema(), &partition_columns),
+                )
+            }
+            None => (
+                record_batch_without_partitions(&record_batch, &self.partition_columns)?,
+                partition_values.clone(),
+                arrow_schema_without_partitions(&self.arrow_schema_ref, &self.partition_columns),
+            ),
+        };
         let partition_key = partition_values.hive_partition_path();
 
-        let record_batch = record_batch_without_partitions(&record_batch, &self.partition_columns)?;
-
         let written_schema = match self.arrow_writers.get_mut(&partition_key) {
             Some(writer) => writer.write(&record_batch, mode)?,
             None => {
                 let mut writer = PartitionWriter::new(
                     arrow_schema,
-                    partition_values.clone(),
+                    partition_values,
                     self.writer_properties.clone(),
                 )?;
                 let schema = writer.write(&record_batch, mode)?;
@@ -159,6 +187,10 @@ impl RecordBatchWriter {
                 schema
             }
         };
+        if self.column_mapping.is_some() {
+            // the schema of tables with column mapping is not evolved by the writer
+            return Ok(self.arrow_schema_ref.clone());
+        }
         Ok(written_schema)
     }
 
@@ -199,6 +231,11 @@ impl DeltaWriter<RecordBatch> for RecordBatchWriter {
                     .to_owned(),
             ));
         }
+        if mode == WriteMode::MergeSchema && self.column_mapping.is_some() {
+            return Err(DeltaTableError::Generic(
+                "Merging Schemas of tables with column mapping is currently unsupported".to_owned(),
+            ));
+        }
         // Set the should_evolve flag for later in case the writer should perform schema evolution
         // on its flush_and_commit
         self.should_evolve = mode == WriteMode::MergeSchema;
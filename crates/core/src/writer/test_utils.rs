@@ -7,7 +7,10 @@ use arrow::compute::take;
 use arrow_array::{Int32Array, Int64Array, RecordBatch, StringArray, StructArray, UInt32Array};
 use arrow_schema::{DataType, Field, Schema as ArrowSchema};
 
-use crate::kernel::{DataType as DeltaDataType, Metadata, PrimitiveType, StructField, StructType};
+use crate::kernel::{
+    Action, DataType as DeltaDataType, Metadata, PrimitiveType, Protocol, ReaderFeatures,
+    StructField, StructType, WriterFeatures,
+};
 use crate::operations::create::CreateBuilder;
 use crate::operations::DeltaOps;
 use crate::{DeltaConfigKey, DeltaTable, DeltaTableBuilder};
@@ -282,6 +285,25 @@ pub async fn setup_table_with_configuration(
         .expect("Failed to create table")
 }
 
+/// Create an in-memory table with deletion vectors enabled, optionally partitioned
+pub async fn setup_table_with_deletion_vectors(partitions: Option<Vec<&str>>) -> DeltaTable {
+    let table_schema = get_delta_schema();
+    let protocol = Protocol {
+        min_reader_version: 3,
+        min_writer_version: 7,
+        reader_features: Some([ReaderFeatures::DeletionVectors].into()),
+        writer_features: Some([WriterFeatures::DeletionVectors].into()),
+    };
+    DeltaOps::new_in_memory()
+        .create()
+        .with_columns(table_schema.fields().clone())
+        .with_partition_columns(partitions.unwrap_or_default())
+        .with_configuration_property(DeltaConfigKey::EnableDeletionVectors, Some("true"))
+        .with_actions(vec![Action::Protocol(protocol)])
+        .await
+        .expect("Failed to create table")
+}
+
 pub fn create_bare_table() -> DeltaTable {
     let table_dir = tempfile::tempdir().unwrap();
     let table_path = table_dir.path();
@@ -345,6 +367,34 @@ pub mod datafusion {
         .unwrap()
     }
 
+    /// Read the change data feed starting at the given version, without the commit timestamps
+    pub async fn get_cdf_data(table: &DeltaTable, starting_version: i64) -> Vec<RecordBatch> {
+        let ctx = SessionContext::new();
+        let scan = DeltaOps(table.clone())
+            .load_cdf()
+            .with_session_ctx(ctx.clone())
+            .with_starting_version(starting_version)
+            .build()
+            .await
+            .unwrap();
+        datafusion::physical_plan::collect(Arc::new(scan), ctx.task_ctx())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|batch| {
+                let indices = batch
+                    .schema()
+                    .fields()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, field)| field.name() != "_commit_timestamp")
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>();
+                batch.project(&indices).unwrap()
+            })
+            .collect()
+    }
+
     pub async fn write_batch(table: DeltaTable, batch: RecordBatch) -> DeltaTable {
         DeltaOps(table)
             .write(vec![batch.clone()])
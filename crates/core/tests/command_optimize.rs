@@ -512,6 +512,7 @@ async fn test_idempotent_metrics() -> Result<(), Box<dyn Error>> {
         num_batches: 0,
         total_considered_files: 1,
         total_files_skipped: 1,
+        total_deletion_vector_files_skipped: 0,
         preserve_insertion_order: true,
         files_added: expected_metric_details.clone(),
         files_removed: expected_metric_details,
@@ -633,6 +634,80 @@ async fn test_commit_info() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[cfg(feature = "datafusion")]
+#[tokio::test]
+/// Validate that deleted rows are dropped when files with deletion vectors are rewritten
+async fn test_optimize_deletion_vectors() -> Result<(), Box<dyn Error>> {
+    use datafusion::prelude::{col, lit};
+    use deltalake_core::operations::collect_sendable_stream;
+    use deltalake_core::DeltaConfigKey;
+
+    async fn values(dt: &DeltaTable) -> Result<Vec<i32>, Box<dyn Error>> {
+        let (_, stream) = DeltaOps(dt.clone()).load().await?;
+        let mut values = vec![];
+        for batch in collect_sendable_stream(stream).await? {
+            let x = batch.column_by_name("x").unwrap();
+            let x = x.as_any().downcast_ref::<Int32Array>().unwrap();
+            values.extend(x.values().iter().copied());
+        }
+        values.sort();
+        Ok(values)
+    }
+
+    let context = setup_test(false).await?;
+    let dt = DeltaOps(context.table)
+        .set_tbl_properties()
+        .with_property(DeltaConfigKey::EnableDeletionVectors.as_ref(), "true")
+        .await?;
+    let dt = DeltaOps(dt)
+        .write(vec![tuples_to_batch(vec![(1, 1), (2, 2), (3, 3)], "a")?])
+        .await?;
+    let dt = DeltaOps(dt)
+        .write(vec![tuples_to_batch(vec![(4, 4), (5, 5), (6, 6)], "a")?])
+        .await?;
+    let (dt, metrics) = DeltaOps(dt)
+        .delete()
+        .with_predicate(col("x").eq(lit(2)))
+        .await?;
+    assert_eq!(metrics.num_deletion_vectors_added, 1);
+
+    // z-order reads the files directly, so it skips files with deletion vectors
+    let (dt, metrics) = DeltaOps(dt)
+        .optimize()
+        .with_type(OptimizeType::ZOrder(vec!["x".to_string()]))
+        .await?;
+    assert_eq!(metrics.total_deletion_vector_files_skipped, 1);
+    assert_eq!(values(&dt).await?, vec![1, 3, 4, 5, 6]);
+
+    let (dt, metrics) = DeltaOps(dt).optimize().await?;
+    assert_eq!(metrics.num_files_removed, 2);
+    assert_eq!(metrics.num_files_added, 1);
+    assert_eq!(metrics.total_deletion_vector_files_skipped, 0);
+    assert_eq!(values(&dt).await?, vec![1, 3, 4, 5, 6]);
+    let files = dt.snapshot()?.file_actions()?;
+    assert!(files.iter().all(|add| add.deletion_vector.is_none()));
+
+    // a single file with a deletion vector is rewritten as well
+    let (dt, _) = DeltaOps(dt)
+        .delete()
+        .with_predicate(col("x").eq(lit(5)))
+        .await?;
+    let (dt, metrics) = DeltaOps(dt).optimize().await?;
+    assert_eq!(metrics.num_files_removed, 1);
+    assert_eq!(metrics.num_files_added, 1);
+    assert_eq!(values(&dt).await?, vec![1, 3, 4, 6]);
+    let files = dt.snapshot()?.file_actions()?;
+    assert!(files.iter().all(|add| add.deletion_vector.is_none()));
+
+    // files without deletion vectors are not rewritten again
+    let version = dt.version();
+    let (dt, metrics) = DeltaOps(dt).optimize().await?;
+    assert_eq!(metrics.num_files_removed, 0);
+    assert_eq!(dt.version(), version);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_zorder_rejects_zero_columns() -> Result<(), Box<dyn Error>> {
     let context = setup_test(true).await?;
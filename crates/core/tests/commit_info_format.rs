@@ -35,3 +35,122 @@ async fn test_operational_parameters() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_in_commit_timestamps() -> Result<(), Box<dyn Error>> {
+    let path = tempfile::tempdir().unwrap();
+    let config = [(
+        "delta.enableInCommitTimestamps".to_string(),
+        Some("true".to_string()),
+    )];
+    let mut table =
+        fs_common::create_table(path.path().to_str().unwrap(), Some(config.into())).await;
+    for _ in 0..3 {
+        fs_common::commit_add(&mut table, &fs_common::add(0)).await;
+    }
+
+    // the commit info is written first and carries monotonic timestamps
+    let log_dir = path.path().join("_delta_log");
+    let mut timestamps = vec![];
+    for version in 0..=3 {
+        let commit = std::fs::read_to_string(log_dir.join(format!("{version:020}.json")))?;
+        let first_action: serde_json::Value = serde_json::from_str(commit.lines().next().unwrap())?;
+        timestamps.push(
+            first_action["commitInfo"]["inCommitTimestamp"]
+                .as_i64()
+                .unwrap(),
+        );
+    }
+    assert!(timestamps.windows(2).all(|w| w[0] < w[1]));
+
+    // copy the log in reverse order, so the file modification times are reversed
+    let copy = tempfile::tempdir().unwrap();
+    let copy_log_dir = copy.path().join("_delta_log");
+    std::fs::create_dir_all(&copy_log_dir)?;
+    for version in (0..=3).rev() {
+        let name = format!("{version:020}.json");
+        std::fs::copy(log_dir.join(&name), copy_log_dir.join(&name))?;
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    let copy_uri = copy.path().to_str().unwrap();
+    for (version, timestamp) in timestamps.iter().enumerate() {
+        let datetime = chrono::DateTime::from_timestamp_millis(*timestamp).unwrap();
+        let table = deltalake_core::open_table_with_ds(copy_uri, datetime.to_rfc3339()).await?;
+        assert_eq!(table.version(), version as i64);
+
+        let mut table = deltalake_core::open_table(copy_uri).await?;
+        table.load_with_datetime(datetime).await?;
+        assert_eq!(table.version(), version as i64);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_in_commit_timestamps_enabled_later() -> Result<(), Box<dyn Error>> {
+    let path = tempfile::tempdir().unwrap();
+    let table_uri = path.path().to_str().unwrap();
+    let mut table = fs_common::create_table(table_uri, None).await;
+    fs_common::commit_add(&mut table, &fs_common::add(0)).await;
+    let mut table = deltalake_core::DeltaOps(table)
+        .set_tbl_properties()
+        .with_property("delta.enableInCommitTimestamps", "true")
+        .await?;
+    fs_common::commit_add(&mut table, &fs_common::add(0)).await;
+    let (enablement_version, enablement_timestamp) = {
+        let config = table.snapshot()?.table_config();
+        (
+            config.in_commit_timestamp_enablement_version().unwrap(),
+            config.in_commit_timestamp_enablement_timestamp().unwrap(),
+        )
+    };
+    assert_eq!(enablement_version, 2);
+
+    // commits before the enablement are resolved by their modification time, the later ones
+    // by their in-commit timestamp, even if their modification time is far off
+    let log_dir = path.path().join("_delta_log");
+    let set_modified = |version: i64, millis: i64| {
+        let file = std::fs::File::options()
+            .write(true)
+            .open(log_dir.join(format!("{version:020}.json")))
+            .unwrap();
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_millis(millis as u64);
+        file.set_modified(time).unwrap();
+    };
+    let hour = 60 * 60 * 1000;
+    set_modified(0, enablement_timestamp - 2 * hour);
+    set_modified(1, enablement_timestamp - hour);
+    set_modified(2, enablement_timestamp + 24 * hour);
+    set_modified(3, enablement_timestamp + 48 * hour);
+    let last_timestamp = deltalake_core::open_table(table_uri)
+        .await?
+        .history(Some(1))
+        .await?[0]
+        .in_commit_timestamp
+        .unwrap();
+
+    for (timestamp, version) in [
+        (enablement_timestamp - 2 * hour, 0),
+        (enablement_timestamp - hour - 1, 0),
+        (enablement_timestamp - hour, 1),
+        (enablement_timestamp - 1, 1),
+        (enablement_timestamp, 2),
+        (last_timestamp, 3),
+    ] {
+        let datetime = chrono::DateTime::from_timestamp_millis(timestamp).unwrap();
+        let table = deltalake_core::open_table_with_ds(table_uri, datetime.to_rfc3339()).await?;
+        assert_eq!(table.version(), version, "opened at {datetime}");
+    }
+
+    // the modification times of the earlier commits are not compared to in-commit timestamps
+    set_modified(0, enablement_timestamp + 24 * hour);
+    set_modified(1, enablement_timestamp + 48 * hour);
+    for (timestamp, version) in [(enablement_timestamp, 2), (last_timestamp, 3)] {
+        let datetime = chrono::DateTime::from_timestamp_millis(timestamp).unwrap();
+        let table = deltalake_core::open_table_with_ds(table_uri, datetime.to_rfc3339()).await?;
+        assert_eq!(table.version(), version, "opened at {datetime}");
+    }
+
+    Ok(())
+}
@@ -919,6 +919,194 @@ mod local {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_datafusion_scan_deletion_vectors() -> Result<()> {
+        let ctx = SessionContext::new();
+        let table = open_table("../test/tests/data/table-with-dv-small")
+            .await
+            .unwrap();
+        ctx.register_table("demo", Arc::new(table))?;
+
+        let batches = ctx.sql("SELECT * FROM demo").await?.collect().await?;
+
+        let expected = vec![
+            "+-------+",
+            "| value |",
+            "+-------+",
+            "| 1     |",
+            "| 2     |",
+            "| 3     |",
+            "| 4     |",
+            "| 5     |",
+            "| 6     |",
+            "| 7     |",
+            "| 8     |",
+            "+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+
+        let batches = ctx
+            .sql("SELECT count(*) AS n FROM demo WHERE value < 5")
+            .await?
+            .collect()
+            .await?;
+        let expected = vec!["+---+", "| n |", "+---+", "| 4 |", "+---+"];
+        assert_batches_sorted_eq!(&expected, &batches);
+
+        let batches = ctx
+            .sql("SELECT count(*) AS n, min(value) AS lo FROM demo")
+            .await?
+            .collect()
+            .await?;
+        let expected = vec![
+            "+---+----+",
+            "| n | lo |",
+            "+---+----+",
+            "| 8 | 1  |",
+            "+---+----+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_datafusion_scan_column_mapping() -> Result<()> {
+        let ctx = SessionContext::new();
+        let table = open_table("../test/tests/data/table_with_column_mapping")
+            .await
+            .unwrap();
+        ctx.register_table("demo", Arc::new(table))?;
+
+        let batches = ctx.sql("SELECT * FROM demo").await?.collect().await?;
+        let expected = vec![
+            "+------------------------+--------------------+",
+            "| Super Name             | Company Very Short |",
+            "+------------------------+--------------------+",
+            "| Anthony Johnson        | BMS                |",
+            "| Mr. Daniel Ferguson MD | BMS                |",
+            "| Nathan Bennett         | BMS                |",
+            "| Stephanie Mcgrath      | BMS                |",
+            "| Timothy Lamb           | BME                |",
+            "+------------------------+--------------------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+
+        let batches = ctx
+            .sql(
+                r#"SELECT "Super Name" FROM demo
+                WHERE "Company Very Short" = 'BMS' AND "Super Name" > 'N'"#,
+            )
+            .await?
+            .collect()
+            .await?;
+        let expected = vec![
+            "+-------------------+",
+            "| Super Name        |",
+            "+-------------------+",
+            "| Nathan Bennett    |",
+            "| Stephanie Mcgrath |",
+            "+-------------------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_datafusion_scan_column_mapping_id_mode() -> Result<()> {
+        use parquet::arrow::ArrowWriter;
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let table_path = tmp_dir.path();
+        std::fs::create_dir(table_path.join("_delta_log")).unwrap();
+
+        // the data file uses different column names than the physical names of the table,
+        // columns can only be matched by their field ids.
+        let field_id = |id: &str| HashMap::from([("PARQUET:field_id".to_string(), id.to_string())]);
+        let file_schema = Arc::new(ArrowSchema::new(vec![
+            ArrowField::new("a", ArrowDataType::Int32, true).with_metadata(field_id("1")),
+            ArrowField::new("b", ArrowDataType::Utf8, true).with_metadata(field_id("2")),
+        ]));
+        let batch = RecordBatch::try_new(
+            file_schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec!["x", "y", "z"])),
+            ],
+        )?;
+        let data_file = std::fs::File::create(table_path.join("part-00000.parquet")).unwrap();
+        let mut writer = ArrowWriter::try_new(data_file, file_schema, None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        let size = std::fs::metadata(table_path.join("part-00000.parquet"))
+            .unwrap()
+            .len();
+
+        let schema = serde_json::json!({
+            "type": "struct",
+            "fields": [
+                {"name": "id", "type": "integer", "nullable": true, "metadata": {
+                    "delta.columnMapping.id": 1, "delta.columnMapping.physicalName": "col-1"
+                }},
+                {"name": "value", "type": "string", "nullable": true, "metadata": {
+                    "delta.columnMapping.id": 2, "delta.columnMapping.physicalName": "col-2"
+                }}
+            ]
+        });
+        let stats = serde_json::json!({
+            "numRecords": 3,
+            "minValues": {"col-1": 1, "col-2": "x"},
+            "maxValues": {"col-1": 3, "col-2": "z"},
+            "nullCount": {"col-1": 0, "col-2": 0}
+        });
+        let actions = [
+            serde_json::json!({"protocol": {"minReaderVersion": 2, "minWriterVersion": 5}}),
+            serde_json::json!({"metaData": {
+                "id": "a0a1d3d8-7b4f-4d3d-9b5e-0a3b6c5d6e7f",
+                "format": {"provider": "parquet", "options": {}},
+                "schemaString": schema.to_string(),
+                "partitionColumns": [],
+                "configuration": {
+                    "delta.columnMapping.mode": "id",
+                    "delta.columnMapping.maxColumnId": "2"
+                },
+                "createdTime": 1700000000000i64
+            }}),
+            serde_json::json!({"add": {
+                "path": "part-00000.parquet",
+                "partitionValues": {},
+                "size": size,
+                "modificationTime": 1700000000000i64,
+                "dataChange": true,
+                "stats": stats.to_string()
+            }}),
+        ];
+        let log = actions.map(|a| a.to_string()).join("\n");
+        std::fs::write(table_path.join("_delta_log/00000000000000000000.json"), log).unwrap();
+
+        let table = open_table(table_path.to_str().unwrap()).await.unwrap();
+        let ctx = SessionContext::new();
+        ctx.register_table("demo", Arc::new(table))?;
+
+        let batches = ctx
+            .sql("SELECT id, value FROM demo WHERE id > 1")
+            .await?
+            .collect()
+            .await?;
+        let expected = vec![
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 2  | y     |",
+            "| 3  | z     |",
+            "+----+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_issue_1292_datafusion_sql_projection() -> Result<()> {
         let ctx = SessionContext::new();
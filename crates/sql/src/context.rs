@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::DataType;
+use datafusion::datasource::{provider_as_source, TableProvider};
+use datafusion::execution::context::SessionState;
+use datafusion::variable::VarType;
+use datafusion_common::config::ConfigOptions;
+use datafusion_common::{
+    plan_datafusion_err, plan_err, ResolvedTableReference, Result as DFResult,
+};
+use datafusion_expr::{AggregateUDF, ScalarUDF, TableSource, WindowUDF};
+use datafusion_sql::planner::{object_name_to_table_reference, ContextProvider};
+use datafusion_sql::sqlparser::ast::{visit_relations, ObjectName, Visit};
+use datafusion_sql::TableReference;
+use deltalake_core::delta_datafusion::{DeltaScanConfig, DeltaTableProvider};
+use deltalake_core::{DeltaTable, DeltaTableBuilder};
+
+use crate::parser::{MergeAction, Statement, TableVersion};
+
+/// Delta [`ContextProvider`] resolving the tables registered in a [`SessionState`].
+///
+/// Table references using the time travel shorthand of [`TableVersion`] resolve to a
+/// [`DeltaTableProvider`] of the snapshot of the registered Delta table at that version.
+pub struct DeltaContextProvider<'a> {
+    state: &'a SessionState,
+    tables: HashMap<String, Arc<dyn TableSource>>,
+}
+
+impl<'a> DeltaContextProvider<'a> {
+    /// Create a provider for the tables referenced in `statement`.
+    ///
+    /// Getting table providers is async but planning is not, so the tables are loaded here.
+    pub async fn try_new(state: &'a SessionState, statement: &Statement) -> DFResult<Self> {
+        let enable_ident_normalization =
+            state.config_options().sql_parser.enable_ident_normalization;
+        let references = match statement {
+            Statement::Datafusion(statement) => state.resolve_table_references(statement)?,
+            statement => relations(statement)
+                .into_iter()
+                .map(|relation| {
+                    object_name_to_table_reference(relation, enable_ident_normalization)
+                })
+                .collect::<DFResult<_>>()?,
+        };
+
+        let mut provider = Self {
+            state,
+            tables: HashMap::with_capacity(references.len()),
+        };
+        for reference in references {
+            let resolved = provider.resolve_table_ref(reference);
+            let name = resolved.to_string();
+            if provider.tables.contains_key(&name) {
+                continue;
+            }
+            if let Some(table) = provider.table_provider(resolved).await? {
+                provider.tables.insert(name, provider_as_source(table));
+            }
+        }
+        Ok(provider)
+    }
+
+    fn resolve_table_ref<'b>(
+        &'b self,
+        reference: TableReference<'b>,
+    ) -> ResolvedTableReference<'b> {
+        let catalog = &self.state.config_options().catalog;
+        reference.resolve(&catalog.default_catalog, &catalog.default_schema)
+    }
+
+    async fn table_provider(
+        &self,
+        reference: ResolvedTableReference<'_>,
+    ) -> DFResult<Option<Arc<dyn TableProvider>>> {
+        let Some(schema) = self
+            .state
+            .catalog_list()
+            .catalog(&reference.catalog)
+            .and_then(|catalog| catalog.schema(&reference.schema))
+        else {
+            return Ok(None);
+        };
+        if let Some(table) = schema.table(&reference.table).await? {
+            return Ok(Some(table));
+        }
+
+        let Some((name, version)) = TableVersion::split_table_name(&reference.table) else {
+            return Ok(None);
+        };
+        let Some(table) = schema.table(name).await? else {
+            return Ok(None);
+        };
+        let Some(table) = delta_table(table.as_ref()) else {
+            return plan_err!("Table '{name}' is not a Delta table and cannot be time traveled");
+        };
+
+        let log_store = table.log_store();
+        let config = log_store.config();
+        let builder = DeltaTableBuilder::from_valid_uri(log_store.root_uri())?
+            .with_storage_backend(log_store.object_store(), config.location.clone())
+            .with_storage_options(config.options.0.clone());
+        let builder = match version {
+            TableVersion::Version(version) => builder.with_version(version),
+            TableVersion::Timestamp(timestamp) => builder.with_timestamp(timestamp),
+        };
+        let table = builder.load().await?;
+        let provider = DeltaTableProvider::try_new(
+            table.snapshot()?.clone(),
+            table.log_store(),
+            DeltaScanConfig::default(),
+        )?;
+        Ok(Some(Arc::new(provider)))
+    }
+}
+
+/// The Delta table behind a table registered in the session, which is either a
+/// [`DeltaTable`] or a [`DeltaTableProvider`] of a snapshot of the table.
+pub(crate) fn delta_table(provider: &dyn TableProvider) -> Option<DeltaTable> {
+    let any = provider.as_any();
+    if let Some(table) = any.downcast_ref::<DeltaTable>() {
+        return Some(table.clone());
+    }
+    any.downcast_ref::<DeltaTableProvider>().map(|provider| {
+        DeltaTable::new_with_state(provider.log_store(), provider.snapshot().clone())
+    })
+}
+
+/// Tables referenced in a Delta statement, including the tables of subqueries
+fn relations(statement: &Statement) -> Vec<ObjectName> {
+    fn visit<V: Visit>(node: &V, relations: &mut Vec<ObjectName>) {
+        let _ = visit_relations(node, |relation| {
+            relations.push(relation.clone());
+            ControlFlow::<()>::Continue(())
+        });
+    }
+
+    let mut relations = Vec::new();
+    match statement {
+        Statement::Datafusion(_) => (),
+        Statement::Describe(describe) => relations.push(describe.table.clone()),
+        Statement::Vacuum(vacuum) => relations.push(vacuum.table.clone()),
+        Statement::Delete(delete) => {
+            relations.push(delete.table.clone());
+            visit(&delete.predicate, &mut relations);
+        }
+        Statement::Update(update) => {
+            relations.push(update.table.clone());
+            visit(&update.assignments, &mut relations);
+            visit(&update.predicate, &mut relations);
+        }
+        Statement::Merge(merge) => {
+            relations.push(merge.target.clone());
+            visit(&merge.source, &mut relations);
+            visit(&merge.on, &mut relations);
+            for clause in &merge.clauses {
+                visit(&clause.predicate, &mut relations);
+                match &clause.action {
+                    MergeAction::Update(assignments) => visit(assignments, &mut relations),
+                    MergeAction::Insert { values, .. } => visit(values, &mut relations),
+                    _ => (),
+                }
+            }
+        }
+    }
+    relations
+}
+
+impl<'a> ContextProvider for DeltaContextProvider<'a> {
+    fn get_table_source(&self, name: TableReference) -> DFResult<Arc<dyn TableSource>> {
+        let name = self.resolve_table_ref(name).to_string();
+        self.tables
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| plan_datafusion_err!("table '{name}' not found"))
+    }
+
+    fn get_function_meta(&self, name: &str) -> Option<Arc<ScalarUDF>> {
+        self.state.scalar_functions().get(name).cloned()
+    }
+
+    fn get_aggregate_meta(&self, name: &str) -> Option<Arc<AggregateUDF>> {
+        self.state.aggregate_functions().get(name).cloned()
+    }
+
+    fn get_window_meta(&self, name: &str) -> Option<Arc<WindowUDF>> {
+        self.state.window_functions().get(name).cloned()
+    }
+
+    fn get_variable_type(&self, variable_names: &[String]) -> Option<DataType> {
+        let provider_type = if variable_names.first()?.starts_with("@@") {
+            VarType::System
+        } else {
+            VarType::UserDefined
+        };
+        self.state
+            .execution_props()
+            .var_providers
+            .as_ref()
+            .and_then(|providers| providers.get(&provider_type)?.get_type(variable_names))
+    }
+
+    fn options(&self) -> &ConfigOptions {
+        self.state.config_options()
+    }
+
+    fn udfs_names(&self) -> Vec<String> {
+        self.state.scalar_functions().keys().cloned().collect()
+    }
+
+    fn udafs_names(&self) -> Vec<String> {
+        self.state.aggregate_functions().keys().cloned().collect()
+    }
+
+    fn udwfs_names(&self) -> Vec<String> {
+        self.state.window_functions().keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::Int32Array;
+    use arrow::record_batch::RecordBatch;
+    use arrow_schema::{Field, Schema};
+    use datafusion::assert_batches_sorted_eq;
+    use datafusion::prelude::SessionContext;
+    use deltalake_core::{DeltaOps, ObjectStore, Path};
+
+    use super::*;
+    use crate::parser::DeltaParser;
+    use crate::planner::DeltaSqlToRel;
+
+    async fn create_table() -> DeltaTable {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, true)]));
+        let mut table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![deltalake_core::kernel::StructField::new(
+                "id",
+                deltalake_core::kernel::DataType::INTEGER,
+                true,
+            )])
+            .await
+            .unwrap();
+        for ids in [vec![1, 2], vec![3]] {
+            let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(ids))])
+                .unwrap();
+            table = DeltaOps(table).write(vec![batch]).await.unwrap();
+        }
+        table
+    }
+
+    async fn query(ctx: &SessionContext, sql: &str) -> Vec<RecordBatch> {
+        let state = ctx.state();
+        let statement = DeltaParser::parse_sql(sql).unwrap().pop_front().unwrap();
+        let provider = DeltaContextProvider::try_new(&state, &statement)
+            .await
+            .unwrap();
+        let plan = DeltaSqlToRel::new(&provider)
+            .statement_to_plan(statement)
+            .unwrap();
+        ctx.execute_logical_plan(plan)
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_time_travel() {
+        let table = create_table().await;
+        // time travel by timestamp uses the modification time of the commit files
+        let commit = table
+            .object_store()
+            .head(&Path::from("_delta_log/00000000000000000001.json"))
+            .await
+            .unwrap();
+        let ctx = SessionContext::new();
+        ctx.register_table("data", Arc::new(table)).unwrap();
+
+        let expected = [
+            "+----+", "| id |", "+----+", "| 1  |", "| 2  |", "| 3  |", "+----+",
+        ];
+        assert_batches_sorted_eq!(&expected, &query(&ctx, "SELECT * FROM data").await);
+
+        let expected = ["+----+", "| id |", "+----+", "| 1  |", "| 2  |", "+----+"];
+        let batches = query(&ctx, "SELECT data.id FROM data VERSION AS OF 1").await;
+        assert_batches_sorted_eq!(&expected, &batches);
+        let batches = query(&ctx, "SELECT * FROM data@v1").await;
+        assert_batches_sorted_eq!(&expected, &batches);
+
+        let sql = format!(
+            "SELECT * FROM data TIMESTAMP AS OF '{}'",
+            commit.last_modified.to_rfc3339()
+        );
+        assert_batches_sorted_eq!(&expected, &query(&ctx, &sql).await);
+
+        let batches = query(
+            &ctx,
+            "SELECT count(*) AS count FROM data VERSION AS OF 0 d JOIN data@v2 AS v2 ON d.id = v2.id",
+        )
+        .await;
+        let expected = [
+            "+-------+",
+            "| count |",
+            "+-------+",
+            "| 0     |",
+            "+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn test_time_travel_provider() {
+        let table = create_table().await;
+        let provider = DeltaTableProvider::try_new(
+            table.snapshot().unwrap().clone(),
+            table.log_store(),
+            DeltaScanConfig::default(),
+        )
+        .unwrap();
+        let ctx = SessionContext::new();
+        ctx.register_table("data", Arc::new(provider)).unwrap();
+
+        let expected = ["+----+", "| id |", "+----+", "| 1  |", "| 2  |", "+----+"];
+        let batches = query(&ctx, "SELECT * FROM data VERSION AS OF 1").await;
+        assert_batches_sorted_eq!(&expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn test_time_travel_errors() {
+        let ctx = SessionContext::new();
+        ctx.register_table("data", Arc::new(create_table().await))
+            .unwrap();
+        let state = ctx.state();
+
+        let statement = DeltaParser::parse_sql("SELECT * FROM data VERSION AS OF 10")
+            .unwrap()
+            .pop_front()
+            .unwrap();
+        assert!(DeltaContextProvider::try_new(&state, &statement)
+            .await
+            .is_err());
+
+        let statement = DeltaParser::parse_sql("SELECT * FROM missing VERSION AS OF 1")
+            .unwrap()
+            .pop_front()
+            .unwrap();
+        let provider = DeltaContextProvider::try_new(&state, &statement)
+            .await
+            .unwrap();
+        assert!(DeltaSqlToRel::new(&provider)
+            .statement_to_plan(statement)
+            .is_err());
+    }
+}
@@ -1,5 +1,7 @@
+pub mod context;
 pub mod logical_plan;
 pub mod parser;
+pub mod physical_plan;
 pub mod planner;
 
 #[cfg(test)]
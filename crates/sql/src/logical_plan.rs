@@ -1,7 +1,8 @@
 use std::fmt::{self, Debug, Display};
 use std::sync::Arc;
 
-use datafusion_common::{DFSchema, DFSchemaRef, OwnedTableReference};
+use datafusion_common::arrow::datatypes::{DataType, Field, Fields, Schema, TimeUnit};
+use datafusion_common::{DFSchema, DFSchemaRef, OwnedTableReference, Result as DFResult};
 use datafusion_expr::logical_plan::LogicalPlan;
 use datafusion_expr::{Expr, UserDefinedLogicalNodeCore};
 
@@ -15,6 +16,12 @@ pub enum DeltaStatement {
     DescribeFiles(DescribeFiles),
     /// Remove unused files from a table directory.
     Vacuum(Vacuum),
+    /// Delete the rows matching a predicate from a table.
+    Delete(Delete),
+    /// Update the rows matching a predicate in a table.
+    Update(Update),
+    /// Merge a source into a table, updating, deleting and inserting rows.
+    Merge(Merge),
 }
 
 impl Debug for DeltaStatement {
@@ -45,13 +52,54 @@ impl DeltaStatement {
                         }
                     }
                     DeltaStatement::DescribeHistory(DescribeHistory { table, .. }) => {
-                        write!(f, "DescribeHistory: {table:?}")
+                        write!(f, "DescribeHistory: {table}")
                     }
                     DeltaStatement::DescribeDetails(DescribeDetails { table, .. }) => {
-                        write!(f, "DescribeDetails: {table:?}")
+                        write!(f, "DescribeDetails: {table}")
                     }
                     DeltaStatement::DescribeFiles(DescribeFiles { table, .. }) => {
-                        write!(f, "DescribeFiles: {table:?}")
+                        write!(f, "DescribeFiles: {table}")
+                    }
+                    DeltaStatement::Delete(Delete {
+                        ref table,
+                        ref predicate,
+                        ..
+                    }) => {
+                        write!(f, "Delete: {table}")?;
+                        if let Some(predicate) = predicate {
+                            write!(f, " predicate={predicate}")?;
+                        }
+                        Ok(())
+                    }
+                    DeltaStatement::Update(Update {
+                        ref table,
+                        ref assignments,
+                        ref predicate,
+                        ..
+                    }) => {
+                        let assignments = assignments
+                            .iter()
+                            .map(|(column, value)| format!("{column} = {value}"))
+                            .collect::<Vec<_>>();
+                        write!(f, "Update: {table} set=[{}]", assignments.join(", "))?;
+                        if let Some(predicate) = predicate {
+                            write!(f, " predicate={predicate}")?;
+                        }
+                        Ok(())
+                    }
+                    DeltaStatement::Merge(Merge {
+                        ref table,
+                        ref target_alias,
+                        ref source_alias,
+                        ref predicate,
+                        ref operations,
+                        ..
+                    }) => {
+                        write!(
+                            f,
+                            "Merge: {table} target_alias={target_alias} source_alias={source_alias} predicate={predicate} operations={}",
+                            operations.len()
+                        )
                     }
                 }
             }
@@ -67,18 +115,29 @@ impl UserDefinedLogicalNodeCore for DeltaStatement {
             Self::DescribeHistory(_) => "DescribeHistory",
             Self::DescribeFiles(_) => "DescribeFiles",
             Self::Vacuum(_) => "Vacuum",
+            Self::Delete(_) => "Delete",
+            Self::Update(_) => "Update",
+            Self::Merge(_) => "Merge",
         }
     }
 
     fn schema(&self) -> &DFSchemaRef {
         match self {
             Self::Vacuum(Vacuum { schema, .. }) => schema,
-            _ => todo!(),
+            Self::Delete(Delete { schema, .. }) => schema,
+            Self::Update(Update { schema, .. }) => schema,
+            Self::Merge(Merge { schema, .. }) => schema,
+            Self::DescribeHistory(DescribeHistory { schema, .. }) => schema,
+            Self::DescribeDetails(DescribeDetails { schema, .. }) => schema,
+            Self::DescribeFiles(DescribeFiles { schema, .. }) => schema,
         }
     }
 
     fn inputs(&self) -> Vec<&LogicalPlan> {
-        vec![]
+        match self {
+            Self::Merge(Merge { source, .. }) => vec![source.as_ref()],
+            _ => vec![],
+        }
     }
 
     fn expressions(&self) -> Vec<Expr> {
@@ -91,12 +150,24 @@ impl UserDefinedLogicalNodeCore for DeltaStatement {
 
     fn from_template(&self, exprs: &[Expr], inputs: &[LogicalPlan]) -> Self {
         match self {
-            Self::Vacuum(_) | Self::DescribeHistory(_) => {
+            Self::Vacuum(_)
+            | Self::DescribeHistory(_)
+            | Self::DescribeDetails(_)
+            | Self::DescribeFiles(_)
+            | Self::Delete(_)
+            | Self::Update(_) => {
                 assert_eq!(inputs.len(), 0, "input size inconsistent");
                 assert_eq!(exprs.len(), 0, "expression size inconsistent");
                 self.clone()
             }
-            _ => todo!(),
+            Self::Merge(merge) => {
+                assert_eq!(inputs.len(), 1, "input size inconsistent");
+                assert_eq!(exprs.len(), 0, "expression size inconsistent");
+                Self::Merge(Merge {
+                    source: Arc::new(inputs[0].clone()),
+                    ..merge.clone()
+                })
+            }
         }
     }
 }
@@ -112,7 +183,7 @@ pub struct Vacuum {
     pub retention_hours: Option<i32>,
     /// Return a list of up to 1000 files to be deleted.
     pub dry_run: bool,
-    /// Schema for Vacuum's empty return table
+    /// Schema for the files deleted by Vacuum, one row per file
     pub schema: DFSchemaRef,
 }
 
@@ -122,7 +193,208 @@ impl Vacuum {
             table,
             retention_hours,
             dry_run,
-            schema: Arc::new(DFSchema::empty()),
+            schema: plan_schema(vec![Field::new("files_deleted", DataType::Utf8, false)]),
+        }
+    }
+}
+
+fn plan_schema(fields: Vec<Field>) -> DFSchemaRef {
+    Arc::new(DFSchema::try_from(Schema::new(fields)).expect("field names are unique"))
+}
+
+/// Schema of a batch with the numeric metrics of an operation, one column per metric
+fn metrics_schema(metrics: &[&str]) -> DFSchemaRef {
+    plan_schema(
+        metrics
+            .iter()
+            .map(|name| Field::new(*name, DataType::UInt64, true))
+            .collect(),
+    )
+}
+
+fn timestamp_field(name: &str, nullable: bool) -> Field {
+    Field::new(
+        name,
+        DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+        nullable,
+    )
+}
+
+/// Field of a map of strings, as built by arrow's `MapBuilder`
+fn string_map_field(name: &str, nullable: bool) -> Field {
+    let entries = Fields::from(vec![
+        Field::new("keys", DataType::Utf8, false),
+        Field::new("values", DataType::Utf8, true),
+    ]);
+    Field::new(
+        name,
+        DataType::Map(
+            Arc::new(Field::new("entries", DataType::Struct(entries), false)),
+            false,
+        ),
+        nullable,
+    )
+}
+
+/// Logical Plan for [Delete] operation.
+///
+/// [Delete]: https://learn.microsoft.com/en-us/azure/databricks/sql/language-manual/delta-delete-from
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Delete {
+    /// A reference to the table rows are deleted from
+    pub table: OwnedTableReference,
+    /// Only delete rows matching the predicate, all rows if `None`
+    pub predicate: Option<Expr>,
+    /// Schema for the operation metrics returned by Delete
+    pub schema: DFSchemaRef,
+}
+
+impl Delete {
+    /// Metrics of [`DeleteMetrics`](deltalake_core::operations::delete::DeleteMetrics)
+    /// returned by the operation
+    pub const METRICS: &'static [&'static str] = &[
+        "num_added_files",
+        "num_removed_files",
+        "num_deleted_rows",
+        "num_copied_rows",
+        "num_deletion_vectors_added",
+        "execution_time_ms",
+        "scan_time_ms",
+        "rewrite_time_ms",
+    ];
+
+    pub fn new(table: OwnedTableReference, predicate: Option<Expr>) -> Self {
+        Self {
+            table,
+            predicate,
+            schema: metrics_schema(Self::METRICS),
+        }
+    }
+}
+
+/// Logical Plan for [Update] operation.
+///
+/// [Update]: https://learn.microsoft.com/en-us/azure/databricks/sql/language-manual/delta-update
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Update {
+    /// A reference to the table being updated
+    pub table: OwnedTableReference,
+    /// Names of the updated columns and their new values
+    pub assignments: Vec<(String, Expr)>,
+    /// Only update rows matching the predicate, all rows if `None`
+    pub predicate: Option<Expr>,
+    /// Schema for the operation metrics returned by Update
+    pub schema: DFSchemaRef,
+}
+
+impl Update {
+    /// Metrics of [`UpdateMetrics`](deltalake_core::operations::update::UpdateMetrics)
+    /// returned by the operation
+    pub const METRICS: &'static [&'static str] = &[
+        "num_added_files",
+        "num_removed_files",
+        "num_updated_rows",
+        "num_copied_rows",
+        "num_deletion_vectors_added",
+        "execution_time_ms",
+        "scan_time_ms",
+    ];
+
+    pub fn new(
+        table: OwnedTableReference,
+        assignments: Vec<(String, Expr)>,
+        predicate: Option<Expr>,
+    ) -> Self {
+        Self {
+            table,
+            assignments,
+            predicate,
+            schema: metrics_schema(Self::METRICS),
+        }
+    }
+}
+
+/// Operation of a `WHEN` clause of a [Merge]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MergeOperation {
+    /// Update matched target rows
+    MatchedUpdate {
+        predicate: Option<Expr>,
+        assignments: Vec<(String, Expr)>,
+    },
+    /// Delete matched target rows
+    MatchedDelete { predicate: Option<Expr> },
+    /// Insert source rows without a matching target row
+    NotMatchedInsert {
+        predicate: Option<Expr>,
+        values: Vec<(String, Expr)>,
+    },
+    /// Update target rows without a matching source row
+    NotMatchedBySourceUpdate {
+        predicate: Option<Expr>,
+        assignments: Vec<(String, Expr)>,
+    },
+    /// Delete target rows without a matching source row
+    NotMatchedBySourceDelete { predicate: Option<Expr> },
+}
+
+/// Logical Plan for [Merge] operation.
+///
+/// Expressions refer to the columns of the target and source qualified by their aliases.
+///
+/// [Merge]: https://learn.microsoft.com/en-us/azure/databricks/sql/language-manual/delta-merge-into
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Merge {
+    /// A reference to the target table
+    pub table: OwnedTableReference,
+    /// Alias of the target table
+    pub target_alias: String,
+    /// Plan of the source merged into the target
+    pub source: Arc<LogicalPlan>,
+    /// Alias of the source
+    pub source_alias: String,
+    /// Predicate matching source and target rows
+    pub predicate: Expr,
+    /// Operations of the `WHEN` clauses, in order
+    pub operations: Vec<MergeOperation>,
+    /// Schema for the operation metrics returned by Merge
+    pub schema: DFSchemaRef,
+}
+
+impl Merge {
+    /// Metrics of [`MergeMetrics`](deltalake_core::operations::merge::MergeMetrics)
+    /// returned by the operation
+    pub const METRICS: &'static [&'static str] = &[
+        "num_source_rows",
+        "num_target_rows_inserted",
+        "num_target_rows_updated",
+        "num_target_rows_deleted",
+        "num_target_rows_copied",
+        "num_output_rows",
+        "num_target_files_added",
+        "num_target_files_removed",
+        "num_target_deletion_vectors_added",
+        "execution_time_ms",
+        "scan_time_ms",
+        "rewrite_time_ms",
+    ];
+
+    pub fn new(
+        table: OwnedTableReference,
+        target_alias: String,
+        source: LogicalPlan,
+        source_alias: String,
+        predicate: Expr,
+        operations: Vec<MergeOperation>,
+    ) -> Self {
+        Self {
+            table,
+            target_alias,
+            source: Arc::new(source),
+            source_alias,
+            predicate,
+            operations,
+            schema: metrics_schema(Self::METRICS),
         }
     }
 }
@@ -142,19 +414,33 @@ impl DescribeHistory {
     pub fn new(table: OwnedTableReference) -> Self {
         Self {
             table,
-            // TODO: add proper schema
             // https://learn.microsoft.com/en-us/azure/databricks/delta/history#history-schema
-            schema: Arc::new(DFSchema::empty()),
+            schema: plan_schema(vec![
+                Field::new("version", DataType::Int64, false),
+                timestamp_field("timestamp", true),
+                Field::new("userId", DataType::Utf8, true),
+                Field::new("userName", DataType::Utf8, true),
+                Field::new("operation", DataType::Utf8, true),
+                string_map_field("operationParameters", true),
+                Field::new("readVersion", DataType::Int64, true),
+                Field::new("isolationLevel", DataType::Utf8, true),
+                Field::new("isBlindAppend", DataType::Boolean, true),
+                string_map_field("operationMetrics", true),
+                Field::new("userMetadata", DataType::Utf8, true),
+                Field::new("engineInfo", DataType::Utf8, true),
+            ]),
         }
     }
 }
 
-/// Logical Plan for DescribeDetails operation.
+/// Logical Plan for [DescribeDetails] operation.
+///
+/// [DescribeDetails]: https://learn.microsoft.com/en-us/azure/databricks/sql/language-manual/delta-describe-detail
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct DescribeDetails {
     /// A reference to the table
     pub table: OwnedTableReference,
-    /// Schema for commit provenence information
+    /// Schema for the details of the table, a single row
     pub schema: DFSchemaRef,
 }
 
@@ -162,8 +448,26 @@ impl DescribeDetails {
     pub fn new(table: OwnedTableReference) -> Self {
         Self {
             table,
-            // TODO: add proper schema
-            schema: Arc::new(DFSchema::empty()),
+            schema: plan_schema(vec![
+                Field::new("format", DataType::Utf8, false),
+                Field::new("id", DataType::Utf8, false),
+                Field::new("name", DataType::Utf8, true),
+                Field::new("description", DataType::Utf8, true),
+                Field::new("location", DataType::Utf8, false),
+                timestamp_field("createdAt", true),
+                Field::new(
+                    "partitionColumns",
+                    DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+                    false,
+                ),
+                // JSON serialized schema of the table
+                Field::new("schema", DataType::Utf8, false),
+                Field::new("numFiles", DataType::Int64, false),
+                Field::new("sizeInBytes", DataType::Int64, false),
+                string_map_field("properties", false),
+                Field::new("minReaderVersion", DataType::Int32, false),
+                Field::new("minWriterVersion", DataType::Int32, false),
+            ]),
         }
     }
 }
@@ -173,17 +477,18 @@ impl DescribeDetails {
 pub struct DescribeFiles {
     /// A reference to the table
     pub table: OwnedTableReference,
-    /// Schema for commit provenence information
+    /// Schema for the flattened add actions of the table, one row per file.
+    ///
+    /// Columns for partition values and file statistics depend on the table.
     pub schema: DFSchemaRef,
 }
 
 impl DescribeFiles {
-    pub fn new(table: OwnedTableReference) -> Self {
-        Self {
+    pub fn try_new(table: OwnedTableReference, schema: Schema) -> DFResult<Self> {
+        Ok(Self {
             table,
-            // TODO: add proper schema
-            schema: Arc::new(DFSchema::empty()),
-        }
+            schema: Arc::new(DFSchema::try_from(schema)?),
+        })
     }
 }
 
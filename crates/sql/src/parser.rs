@@ -1,11 +1,13 @@
 use std::collections::VecDeque;
 use std::fmt;
 
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use datafusion_sql::parser::{DFParser, Statement as DFStatement};
-use datafusion_sql::sqlparser::ast::{ObjectName, Value};
-use datafusion_sql::sqlparser::dialect::{keywords::Keyword, Dialect, GenericDialect};
-use datafusion_sql::sqlparser::parser::{Parser, ParserError};
-use datafusion_sql::sqlparser::tokenizer::{Token, TokenWithLocation, Tokenizer};
+use datafusion_sql::sqlparser::ast::{Assignment, Expr, Ident, ObjectName, TableFactor, Value};
+use datafusion_sql::sqlparser::dialect::keywords::{Keyword, RESERVED_FOR_TABLE_ALIAS};
+use datafusion_sql::sqlparser::dialect::{Dialect, GenericDialect};
+use datafusion_sql::sqlparser::parser::{IsOptional, Parser, ParserError};
+use datafusion_sql::sqlparser::tokenizer::{Token, TokenWithLocation, Tokenizer, Whitespace, Word};
 
 // Use `Parser::expected` instead, if possible
 macro_rules! parser_err {
@@ -14,6 +16,71 @@ macro_rules! parser_err {
     };
 }
 
+/// Format of timestamps in the `table@yyyyMMddHHmmssSSS` time travel shorthand
+const TIMESTAMP_SHORTHAND_FORMAT: &str = "%Y%m%d%H%M%S%3f";
+
+/// Version of a table read with time travel.
+///
+/// `table VERSION AS OF 12` and `table TIMESTAMP AS OF '2024-01-01'` are parsed into the
+/// `table@v12` and `table@20240101000000000` shorthands, so time travel is expressed in the
+/// name of the table reference.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TableVersion {
+    /// The version of the table, `table@v<version>`
+    Version(i64),
+    /// The latest version committed at or before the timestamp, `table@yyyyMMddHHmmssSSS`
+    Timestamp(DateTime<Utc>),
+}
+
+impl TableVersion {
+    /// Split a table name using the time travel shorthand into the name and the version
+    /// of the table. Returns `None` for names without a version.
+    pub fn split_table_name(name: &str) -> Option<(&str, Self)> {
+        let (table, version) = name.rsplit_once('@')?;
+        if table.is_empty() {
+            return None;
+        }
+        let version = match version.strip_prefix('v') {
+            Some(version) => Self::Version(version.parse().ok()?),
+            None if version.len() == 17 && version.bytes().all(|b| b.is_ascii_digit()) => {
+                let timestamp =
+                    NaiveDateTime::parse_from_str(version, TIMESTAMP_SHORTHAND_FORMAT).ok()?;
+                Self::Timestamp(timestamp.and_utc())
+            }
+            None => return None,
+        };
+        Some((table, version))
+    }
+
+    /// Parse the timestamp of a `TIMESTAMP AS OF` clause, either a date, a timestamp or
+    /// an RFC 3339 timestamp. Timestamps without time zone are read as UTC.
+    fn parse_timestamp(value: &str) -> Result<Self, ParserError> {
+        let timestamp = if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+            timestamp.with_timezone(&Utc)
+        } else if let Ok(timestamp) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f"))
+        {
+            timestamp.and_utc()
+        } else if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+            date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+        } else {
+            return parser_err!(format!("Invalid timestamp '{value}' in TIMESTAMP AS OF"));
+        };
+        Ok(Self::Timestamp(timestamp))
+    }
+}
+
+impl fmt::Display for TableVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Version(version) => write!(f, "v{version}"),
+            Self::Timestamp(timestamp) => {
+                write!(f, "{}", timestamp.format(TIMESTAMP_SHORTHAND_FORMAT))
+            }
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum DescribeOperation {
     Detail,
@@ -34,6 +101,66 @@ pub struct VacuumStatement {
     pub dry_run: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeleteStatement {
+    pub table: ObjectName,
+    pub alias: Option<Ident>,
+    pub predicate: Option<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateStatement {
+    pub table: ObjectName,
+    pub alias: Option<Ident>,
+    pub assignments: Vec<Assignment>,
+    pub predicate: Option<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeStatement {
+    pub target: ObjectName,
+    pub target_alias: Option<Ident>,
+    pub source: TableFactor,
+    pub on: Expr,
+    pub clauses: Vec<MergeClause>,
+}
+
+/// Rows a `WHEN` clause of a `MERGE` statement applies to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MergeClauseKind {
+    /// `WHEN MATCHED`
+    Matched,
+    /// `WHEN NOT MATCHED [BY TARGET]`
+    NotMatched,
+    /// `WHEN NOT MATCHED BY SOURCE`
+    NotMatchedBySource,
+}
+
+/// Action of a `WHEN` clause of a `MERGE` statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeAction {
+    /// `UPDATE SET column = value, ...`
+    Update(Vec<Assignment>),
+    /// `UPDATE SET *`, setting all target columns to the source column of the same name
+    UpdateAll,
+    /// `DELETE`
+    Delete,
+    /// `INSERT (column, ...) VALUES (value, ...)`
+    Insert {
+        columns: Vec<Ident>,
+        values: Vec<Expr>,
+    },
+    /// `INSERT *`, inserting all target columns from the source column of the same name
+    InsertAll,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeClause {
+    pub kind: MergeClauseKind,
+    pub predicate: Option<Expr>,
+    pub action: MergeAction,
+}
+
 /// Delta Lake Statement representations.
 ///
 /// Tokens parsed by [`DeltaParser`] are converted into these values.
@@ -41,10 +168,16 @@ pub struct VacuumStatement {
 pub enum Statement {
     /// Datafusion AST node (from datafusion-sql)
     Datafusion(DFStatement),
-    /// Extension: `DESCRIBE [HISTORY | DETAIL] table_name`
+    /// Extension: `DESCRIBE {HISTORY | DETAIL | FILES} table_name`
     Describe(DescribeStatement),
     /// Extension: `VACUUM table_name [RETAIN num HOURS] [DRY RUN]`
     Vacuum(VacuumStatement),
+    /// Extension: `DELETE FROM table_name [WHERE predicate]`
+    Delete(DeleteStatement),
+    /// Extension: `UPDATE table_name SET column = value, ... [WHERE predicate]`
+    Update(UpdateStatement),
+    /// Extension: `MERGE INTO target USING source ON predicate WHEN ... THEN ...`
+    Merge(MergeStatement),
 }
 
 impl From<DFStatement> for Statement {
@@ -59,6 +192,9 @@ impl fmt::Display for Statement {
             Statement::Datafusion(stmt) => write!(f, "{stmt}"),
             Statement::Describe(_) => write!(f, "DESCRIBE TABLE ..."),
             Statement::Vacuum(_) => write!(f, "VACUUM TABLE ..."),
+            Statement::Delete(_) => write!(f, "DELETE FROM ..."),
+            Statement::Update(_) => write!(f, "UPDATE TABLE ..."),
+            Statement::Merge(_) => write!(f, "MERGE INTO ..."),
         }
     }
 }
@@ -66,10 +202,10 @@ impl fmt::Display for Statement {
 /// Delta Lake SQL Parser based on [`sqlparser`](https://crates.io/crates/sqlparser)
 ///
 /// This parser handles Delta Lake specific statements, delegating to
-/// [`DFParser`]for other SQL statements.
+/// [`DFParser`]for other SQL statements. Time travel clauses of table references are
+/// rewritten to the shorthand described in [`TableVersion`].
 pub struct DeltaParser<'a> {
-    sql: &'a str,
-    parser: Parser<'a>,
+    parser: DFParser<'a>,
 }
 
 impl<'a> DeltaParser<'a> {
@@ -83,11 +219,12 @@ impl<'a> DeltaParser<'a> {
     /// specified dialect.
     pub fn new_with_dialect(sql: &'a str, dialect: &'a dyn Dialect) -> Result<Self, ParserError> {
         let mut tokenizer = Tokenizer::new(dialect, sql);
-        let tokens = tokenizer.tokenize()?;
+        let tokens = rewrite_time_travel(tokenizer.tokenize()?)?;
 
         Ok(Self {
-            sql,
-            parser: Parser::new(dialect).with_tokens(tokens),
+            parser: DFParser {
+                parser: Parser::new(dialect).with_tokens(tokens),
+            },
         })
     }
 
@@ -109,15 +246,15 @@ impl<'a> DeltaParser<'a> {
         let mut expecting_statement_delimiter = false;
         loop {
             // ignore empty statements (between successive statement delimiters)
-            while parser.parser.consume_token(&Token::SemiColon) {
+            while parser.parser.parser.consume_token(&Token::SemiColon) {
                 expecting_statement_delimiter = false;
             }
 
-            if parser.parser.peek_token() == Token::EOF {
+            if parser.parser.parser.peek_token() == Token::EOF {
                 break;
             }
             if expecting_statement_delimiter {
-                return parser.expected("end of statement", parser.parser.peek_token());
+                return parser.expected("end of statement", parser.parser.parser.peek_token());
             }
 
             let statement = parser.parse_statement()?;
@@ -135,41 +272,62 @@ impl<'a> DeltaParser<'a> {
 
     /// Parse a new expression
     pub fn parse_statement(&mut self) -> Result<Statement, ParserError> {
-        match self.parser.peek_token().token {
-            Token::Word(w) => {
-                match w.keyword {
-                    Keyword::VACUUM => {
-                        self.parser.next_token();
-                        self.parse_vacuum()
-                    }
-                    _ => {
-                        // use the native parser
-                        // TODO fix for multiple statememnts and keeping parsers in sync
-                        let mut df = DFParser::new(self.sql)?;
-                        let stmt = df.parse_statement()?;
-                        self.parser.parse_statement()?;
-                        Ok(Statement::Datafusion(stmt))
-                    }
-                }
+        match self.parser.parser.peek_token().token {
+            // `DESCRIBE table_name` is handled by the native parser
+            Token::Word(w) if w.keyword == Keyword::DESCRIBE && self.is_describe_operation() => {
+                self.parser.parser.next_token();
+                self.parse_describe()
             }
-            _ => {
-                // use the native parser
-                // TODO fix for multiple statememnts and keeping parsers in sync
-                let mut df = DFParser::new(self.sql)?;
-                let stmt = df.parse_statement()?;
-                self.parser.parse_statement()?;
-                Ok(Statement::Datafusion(stmt))
+            Token::Word(w) if w.keyword == Keyword::VACUUM => {
+                self.parser.parser.next_token();
+                self.parse_vacuum()
+            }
+            Token::Word(w) if w.keyword == Keyword::DELETE => {
+                self.parser.parser.next_token();
+                self.parse_delete()
             }
+            Token::Word(w) if w.keyword == Keyword::UPDATE => {
+                self.parser.parser.next_token();
+                self.parse_update()
+            }
+            Token::Word(w) if w.keyword == Keyword::MERGE => {
+                self.parser.parser.next_token();
+                self.parse_merge()
+            }
+            // use the native parser
+            _ => Ok(Statement::Datafusion(self.parser.parse_statement()?)),
         }
     }
 
+    /// Whether the next tokens are `DESCRIBE {HISTORY | DETAIL | FILES} table_name`
+    fn is_describe_operation(&self) -> bool {
+        let is_operation = matches!(
+            self.parser.parser.peek_nth_token(1).token,
+            Token::Word(w) if matches!(w.keyword, Keyword::HISTORY | Keyword::DETAIL | Keyword::FILES)
+        );
+        // otherwise the word is the name of the table to describe
+        is_operation && matches!(self.parser.parser.peek_nth_token(2).token, Token::Word(_))
+    }
+
+    pub fn parse_describe(&mut self) -> Result<Statement, ParserError> {
+        let token = self.parser.parser.next_token();
+        let operation = match &token.token {
+            Token::Word(w) if w.keyword == Keyword::HISTORY => DescribeOperation::History,
+            Token::Word(w) if w.keyword == Keyword::DETAIL => DescribeOperation::Detail,
+            Token::Word(w) if w.keyword == Keyword::FILES => DescribeOperation::Files,
+            _ => return self.expected("HISTORY, DETAIL or FILES", token),
+        };
+        let table = self.parser.parser.parse_object_name(false)?;
+        Ok(Statement::Describe(DescribeStatement { table, operation }))
+    }
+
     pub fn parse_vacuum(&mut self) -> Result<Statement, ParserError> {
-        let table_name = self.parser.parse_object_name(false)?;
-        match self.parser.peek_token().token {
+        let table_name = self.parser.parser.parse_object_name(false)?;
+        match self.parser.parser.peek_token().token {
             Token::Word(w) => match w.keyword {
                 Keyword::RETAIN => {
-                    self.parser.next_token();
-                    let retention_hours = match self.parser.parse_number_value()? {
+                    self.parser.parser.next_token();
+                    let retention_hours = match self.parser.parser.parse_number_value()? {
                         Value::Number(value_str, _) => value_str
                             .parse()
                             .map_err(|_| ParserError::ParserError(format!("Unexpected token {w}"))),
@@ -177,7 +335,7 @@ impl<'a> DeltaParser<'a> {
                             "Expected numeric value for retention hours".to_string(),
                         )),
                     }?;
-                    if !self.parser.parse_keyword(Keyword::HOURS) {
+                    if !self.parser.parser.parse_keyword(Keyword::HOURS) {
                         return Err(ParserError::ParserError(
                             "Expected keyword 'HOURS'".to_string(),
                         ));
@@ -185,12 +343,15 @@ impl<'a> DeltaParser<'a> {
                     Ok(Statement::Vacuum(VacuumStatement {
                         table: table_name,
                         retention_hours: Some(retention_hours),
-                        dry_run: self.parser.parse_keywords(&[Keyword::DRY, Keyword::RUN]),
+                        dry_run: self
+                            .parser
+                            .parser
+                            .parse_keywords(&[Keyword::DRY, Keyword::RUN]),
                     }))
                 }
                 Keyword::DRY => {
-                    self.parser.next_token();
-                    if self.parser.parse_keyword(Keyword::RUN) {
+                    self.parser.parser.next_token();
+                    if self.parser.parser.parse_keyword(Keyword::RUN) {
                         Ok(Statement::Vacuum(VacuumStatement {
                             table: table_name,
                             retention_hours: None,
@@ -205,7 +366,7 @@ impl<'a> DeltaParser<'a> {
                 _ => Err(ParserError::ParserError(format!("Unexpected token {w}"))),
             },
             _ => {
-                let token = self.parser.next_token();
+                let token = self.parser.parser.next_token();
                 if token == Token::EOF || token == Token::SemiColon {
                     Ok(Statement::Vacuum(VacuumStatement {
                         table: table_name,
@@ -220,6 +381,349 @@ impl<'a> DeltaParser<'a> {
             }
         }
     }
+
+    pub fn parse_delete(&mut self) -> Result<Statement, ParserError> {
+        self.parser.parser.expect_keyword(Keyword::FROM)?;
+        let (table, alias) = self.parse_table_with_alias()?;
+        Ok(Statement::Delete(DeleteStatement {
+            table,
+            alias,
+            predicate: self.parse_where()?,
+        }))
+    }
+
+    pub fn parse_update(&mut self) -> Result<Statement, ParserError> {
+        let (table, alias) = self.parse_table_with_alias()?;
+        self.parser.parser.expect_keyword(Keyword::SET)?;
+        let assignments = self
+            .parser
+            .parser
+            .parse_comma_separated(Parser::parse_assignment)?;
+        Ok(Statement::Update(UpdateStatement {
+            table,
+            alias,
+            assignments,
+            predicate: self.parse_where()?,
+        }))
+    }
+
+    pub fn parse_merge(&mut self) -> Result<Statement, ParserError> {
+        // INTO is optional
+        let _ = self.parser.parser.parse_keyword(Keyword::INTO);
+        let (target, target_alias) = self.parse_table_with_alias()?;
+        self.parser.parser.expect_keyword(Keyword::USING)?;
+        let source = self.parser.parser.parse_table_factor()?;
+        self.parser.parser.expect_keyword(Keyword::ON)?;
+        let on = self.parser.parser.parse_expr()?;
+
+        let mut clauses = Vec::new();
+        while self.parser.parser.parse_keyword(Keyword::WHEN) {
+            clauses.push(self.parse_merge_clause()?);
+        }
+        if clauses.is_empty() {
+            return self.expected("WHEN", self.parser.parser.peek_token());
+        }
+        Ok(Statement::Merge(MergeStatement {
+            target,
+            target_alias,
+            source,
+            on,
+            clauses,
+        }))
+    }
+
+    fn parse_merge_clause(&mut self) -> Result<MergeClause, ParserError> {
+        let kind = if self.parser.parser.parse_keyword(Keyword::MATCHED) {
+            MergeClauseKind::Matched
+        } else if self
+            .parser
+            .parser
+            .parse_keywords(&[Keyword::NOT, Keyword::MATCHED])
+        {
+            if !self.parser.parser.parse_keyword(Keyword::BY) {
+                MergeClauseKind::NotMatched
+            } else if self.parse_word("SOURCE") {
+                MergeClauseKind::NotMatchedBySource
+            } else if self.parse_word("TARGET") {
+                MergeClauseKind::NotMatched
+            } else {
+                return self.expected("SOURCE or TARGET", self.parser.parser.peek_token());
+            }
+        } else {
+            return self.expected("MATCHED or NOT MATCHED", self.parser.parser.peek_token());
+        };
+        let predicate = if self.parser.parser.parse_keyword(Keyword::AND) {
+            Some(self.parser.parser.parse_expr()?)
+        } else {
+            None
+        };
+        self.parser.parser.expect_keyword(Keyword::THEN)?;
+
+        let action = match self.parser.parser.parse_one_of_keywords(&[
+            Keyword::UPDATE,
+            Keyword::DELETE,
+            Keyword::INSERT,
+        ]) {
+            Some(Keyword::UPDATE) => {
+                self.parser.parser.expect_keyword(Keyword::SET)?;
+                if self.parser.parser.consume_token(&Token::Mul) {
+                    MergeAction::UpdateAll
+                } else {
+                    MergeAction::Update(
+                        self.parser
+                            .parser
+                            .parse_comma_separated(Parser::parse_assignment)?,
+                    )
+                }
+            }
+            Some(Keyword::DELETE) => MergeAction::Delete,
+            Some(_) => {
+                if self.parser.parser.consume_token(&Token::Mul) {
+                    MergeAction::InsertAll
+                } else {
+                    let columns = self
+                        .parser
+                        .parser
+                        .parse_parenthesized_column_list(IsOptional::Mandatory, false)?;
+                    self.parser.parser.expect_keyword(Keyword::VALUES)?;
+                    self.parser.parser.expect_token(&Token::LParen)?;
+                    let values = self
+                        .parser
+                        .parser
+                        .parse_comma_separated(Parser::parse_expr)?;
+                    self.parser.parser.expect_token(&Token::RParen)?;
+                    if columns.len() != values.len() {
+                        return parser_err!(format!(
+                            "INSERT has {} columns but {} values",
+                            columns.len(),
+                            values.len()
+                        ));
+                    }
+                    MergeAction::Insert { columns, values }
+                }
+            }
+            None => {
+                return self.expected("UPDATE, DELETE or INSERT", self.parser.parser.peek_token())
+            }
+        };
+
+        let supported = match kind {
+            MergeClauseKind::Matched => {
+                !matches!(action, MergeAction::Insert { .. } | MergeAction::InsertAll)
+            }
+            MergeClauseKind::NotMatched => {
+                matches!(action, MergeAction::Insert { .. } | MergeAction::InsertAll)
+            }
+            MergeClauseKind::NotMatchedBySource => {
+                matches!(action, MergeAction::Update(_) | MergeAction::Delete)
+            }
+        };
+        if !supported {
+            return parser_err!(format!(
+                "Unsupported action {action:?} for {kind:?} clause of MERGE"
+            ));
+        }
+
+        Ok(MergeClause {
+            kind,
+            predicate,
+            action,
+        })
+    }
+
+    /// Parse a table name, optionally followed by an alias
+    fn parse_table_with_alias(&mut self) -> Result<(ObjectName, Option<Ident>), ParserError> {
+        let table = self.parser.parser.parse_object_name(false)?;
+        let alias = self
+            .parser
+            .parser
+            .parse_optional_alias(RESERVED_FOR_TABLE_ALIAS)?;
+        Ok((table, alias))
+    }
+
+    /// Parse an optional `WHERE predicate` clause
+    fn parse_where(&mut self) -> Result<Option<Expr>, ParserError> {
+        if self.parser.parser.parse_keyword(Keyword::WHERE) {
+            Ok(Some(self.parser.parser.parse_expr()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Consume the next token if it is the (non-keyword) word `word`
+    fn parse_word(&mut self, word: &str) -> bool {
+        match self.parser.parser.peek_token().token {
+            Token::Word(w) if w.value.eq_ignore_ascii_case(word) => {
+                self.parser.parser.next_token();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Rewrite `VERSION AS OF` and `TIMESTAMP AS OF` clauses following a table name into the
+/// time travel shorthand of the name. Unless the table reference has an alias, the table is
+/// aliased by its name so columns can still be qualified with it.
+///
+/// Only clauses following the name of a relation in a FROM clause, a JOIN or the source of a
+/// MERGE are rewritten, so e.g. columns named `version` can still be aliased.
+fn rewrite_time_travel(tokens: Vec<Token>) -> Result<Vec<Token>, ParserError> {
+    let mut rewritten: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let starts_clause = matches!(
+            &tokens[idx],
+            Token::Word(w) if matches!(w.keyword, Keyword::VERSION | Keyword::TIMESTAMP)
+        );
+        let clause = if starts_clause && follows_relation(&rewritten) {
+            parse_time_travel(&tokens, idx)?
+        } else {
+            None
+        };
+        let Some((version, end)) = clause else {
+            rewritten.push(tokens[idx].clone());
+            idx += 1;
+            continue;
+        };
+        let table = rewritten
+            .iter_mut()
+            .rev()
+            .find(|token| !matches!(token, Token::Whitespace(_)));
+        let Some(Token::Word(table)) = table else {
+            return parser_err!("Expected table name before time travel clause");
+        };
+        let alias = table.clone();
+        table.value = format!("{}@{version}", alias.value);
+        table.keyword = Keyword::NoKeyword;
+
+        idx = end;
+        let has_alias = matches!(
+            tokens[idx..].iter().find(|token| !matches!(token, Token::Whitespace(_))),
+            Some(Token::Word(w)) if w.keyword == Keyword::AS || !RESERVED_FOR_TABLE_ALIAS.contains(&w.keyword)
+        );
+        if !has_alias {
+            rewritten.extend([
+                Token::Whitespace(Whitespace::Space),
+                Token::make_keyword("AS"),
+                Token::Whitespace(Whitespace::Space),
+                Token::Word(Word {
+                    keyword: Keyword::NoKeyword,
+                    ..alias
+                }),
+            ]);
+        }
+    }
+    Ok(rewritten)
+}
+
+/// Whether the tokens end with the name of a relation in a FROM clause, a JOIN or the source
+/// of a MERGE, e.g. `FROM a AS x, db.b`.
+fn follows_relation(tokens: &[Token]) -> bool {
+    let reversed = tokens
+        .iter()
+        .rev()
+        .filter(|token| !matches!(token, Token::Whitespace(_)))
+        .collect::<Vec<_>>();
+    matches!(reversed.first(), Some(Token::Word(_)))
+        && skip_relation(&reversed, 0).is_some_and(|idx| ends_relation_list(&reversed, idx))
+}
+
+/// Whether the reversed tokens starting at `idx` end a list of relations introduced by FROM,
+/// JOIN or USING.
+fn ends_relation_list(reversed: &[&Token], idx: usize) -> bool {
+    match reversed.get(idx) {
+        Some(Token::Word(w)) => matches!(w.keyword, Keyword::FROM | Keyword::JOIN | Keyword::USING),
+        // the previous relation of the list, without an alias, with an alias or with `AS alias`
+        Some(Token::Comma) => {
+            let aliased_with_as =
+                matches!(reversed.get(idx + 2), Some(Token::Word(w)) if w.keyword == Keyword::AS);
+            [idx + 1, idx + 2]
+                .into_iter()
+                .chain(aliased_with_as.then_some(idx + 3))
+                .any(|start| {
+                    skip_relation(reversed, start)
+                        .is_some_and(|end| ends_relation_list(reversed, end))
+                })
+        }
+        _ => false,
+    }
+}
+
+/// Skip a possibly qualified table name or a parenthesized subquery in the reversed tokens
+/// starting at `idx`, returning the index of the token preceding it.
+fn skip_relation(reversed: &[&Token], idx: usize) -> Option<usize> {
+    match reversed.get(idx)? {
+        Token::Word(_) => {
+            let mut idx = idx + 1;
+            while matches!(reversed.get(idx), Some(Token::Period))
+                && matches!(reversed.get(idx + 1), Some(Token::Word(_)))
+            {
+                idx += 2;
+            }
+            Some(idx)
+        }
+        Token::RParen => {
+            let mut depth = 0;
+            for (offset, token) in reversed[idx..].iter().enumerate() {
+                match token {
+                    Token::RParen => depth += 1,
+                    Token::LParen if depth == 1 => return Some(idx + offset + 1),
+                    Token::LParen => depth -= 1,
+                    _ => (),
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Parse a `VERSION AS OF <version>` or `TIMESTAMP AS OF '<timestamp>'` clause starting at
+/// `idx`, returning the version and the index of the token following the clause.
+fn parse_time_travel(
+    tokens: &[Token],
+    idx: usize,
+) -> Result<Option<(TableVersion, usize)>, ParserError> {
+    let mut significant = tokens
+        .iter()
+        .enumerate()
+        .skip(idx)
+        .filter(|(_, token)| !matches!(token, Token::Whitespace(_)));
+    let keyword = match significant.next() {
+        Some((_, Token::Word(w))) if matches!(w.keyword, Keyword::VERSION | Keyword::TIMESTAMP) => {
+            w.keyword
+        }
+        _ => return Ok(None),
+    };
+    for expected in [Keyword::AS, Keyword::OF] {
+        match significant.next() {
+            Some((_, Token::Word(w))) if w.keyword == expected => (),
+            _ => return Ok(None),
+        }
+    }
+    let version = match (keyword, significant.next()) {
+        (Keyword::VERSION, Some((end, Token::Number(value, _)))) => value
+            .parse()
+            .map(|version| (TableVersion::Version(version), end + 1))
+            .map_err(|_| ParserError::ParserError(format!("Invalid version {value}")))?,
+        (Keyword::TIMESTAMP, Some((end, Token::SingleQuotedString(value)))) => {
+            (TableVersion::parse_timestamp(value)?, end + 1)
+        }
+        (Keyword::VERSION, token) => {
+            return parser_err!(format!(
+                "Expected version number after VERSION AS OF, found: {}",
+                token.map_or(Token::EOF, |(_, token)| token.clone())
+            ))
+        }
+        (_, token) => {
+            return parser_err!(format!(
+                "Expected timestamp string after TIMESTAMP AS OF, found: {}",
+                token.map_or(Token::EOF, |(_, token)| token.clone())
+            ))
+        }
+    };
+    Ok(Some(version))
 }
 
 #[cfg(test)]
@@ -291,4 +795,233 @@ mod tests {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn test_parse_describe() {
+        for (sql, operation) in [
+            ("DESCRIBE HISTORY data_table", DescribeOperation::History),
+            ("DESCRIBE DETAIL data_table", DescribeOperation::Detail),
+            ("describe files data_table", DescribeOperation::Files),
+        ] {
+            let stmt = Statement::Describe(DescribeStatement {
+                table: ObjectName(vec![Ident::new("data_table")]),
+                operation,
+            });
+            assert!(expect_parse_ok(sql, stmt).is_ok());
+        }
+
+        // tables named like an operation are described by datafusion
+        let statements = DeltaParser::parse_sql("DESCRIBE history").unwrap();
+        assert!(matches!(statements[0], Statement::Datafusion(_)));
+        let statements = DeltaParser::parse_sql("DESCRIBE data_table").unwrap();
+        assert!(matches!(statements[0], Statement::Datafusion(_)));
+    }
+
+    fn parse_expr(sql: &str) -> Expr {
+        Parser::new(&GenericDialect {})
+            .try_with_sql(sql)
+            .unwrap()
+            .parse_expr()
+            .unwrap()
+    }
+
+    fn assignment(column: &str, value: &str) -> Assignment {
+        Assignment {
+            id: vec![Ident::new(column)],
+            value: parse_expr(value),
+        }
+    }
+
+    #[test]
+    fn test_parse_delete_update() {
+        let stmt = Statement::Delete(DeleteStatement {
+            table: ObjectName(vec![Ident::new("data_table")]),
+            alias: None,
+            predicate: None,
+        });
+        assert!(expect_parse_ok("DELETE FROM data_table", stmt).is_ok());
+
+        let stmt = Statement::Delete(DeleteStatement {
+            table: ObjectName(vec![Ident::new("data_table")]),
+            alias: Some(Ident::new("t")),
+            predicate: Some(parse_expr("t.id > 1")),
+        });
+        assert!(expect_parse_ok("DELETE FROM data_table AS t WHERE t.id > 1", stmt).is_ok());
+
+        let stmt = Statement::Update(UpdateStatement {
+            table: ObjectName(vec![Ident::new("data_table")]),
+            alias: None,
+            assignments: vec![assignment("value", "'a'"), assignment("id", "id + 1")],
+            predicate: Some(parse_expr("id = 1")),
+        });
+        assert!(expect_parse_ok(
+            "UPDATE data_table SET value = 'a', id = id + 1 WHERE id = 1",
+            stmt
+        )
+        .is_ok());
+
+        assert!(DeltaParser::parse_sql("DELETE data_table").is_err());
+        assert!(DeltaParser::parse_sql("UPDATE data_table WHERE id = 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_merge() {
+        let sql = "MERGE INTO data_table t USING source s ON t.id = s.id
+            WHEN MATCHED AND s.deleted THEN DELETE
+            WHEN MATCHED THEN UPDATE SET value = s.value
+            WHEN NOT MATCHED THEN INSERT (id, value) VALUES (s.id, s.value)
+            WHEN NOT MATCHED BY SOURCE THEN UPDATE SET value = NULL";
+        let Statement::Merge(merge) = DeltaParser::parse_sql(sql).unwrap().remove(0).unwrap()
+        else {
+            panic!("Expected MERGE statement");
+        };
+        assert_eq!(merge.target, ObjectName(vec![Ident::new("data_table")]));
+        assert_eq!(merge.target_alias, Some(Ident::new("t")));
+        assert_eq!(merge.on, parse_expr("t.id = s.id"));
+        assert_eq!(
+            merge.clauses,
+            vec![
+                MergeClause {
+                    kind: MergeClauseKind::Matched,
+                    predicate: Some(parse_expr("s.deleted")),
+                    action: MergeAction::Delete,
+                },
+                MergeClause {
+                    kind: MergeClauseKind::Matched,
+                    predicate: None,
+                    action: MergeAction::Update(vec![assignment("value", "s.value")]),
+                },
+                MergeClause {
+                    kind: MergeClauseKind::NotMatched,
+                    predicate: None,
+                    action: MergeAction::Insert {
+                        columns: vec![Ident::new("id"), Ident::new("value")],
+                        values: vec![parse_expr("s.id"), parse_expr("s.value")],
+                    },
+                },
+                MergeClause {
+                    kind: MergeClauseKind::NotMatchedBySource,
+                    predicate: None,
+                    action: MergeAction::Update(vec![assignment("value", "NULL")]),
+                },
+            ]
+        );
+
+        let sql = "MERGE data_table USING (SELECT * FROM source) AS s ON data_table.id = s.id
+            WHEN MATCHED THEN UPDATE SET *
+            WHEN NOT MATCHED BY TARGET THEN INSERT *
+            WHEN NOT MATCHED BY SOURCE AND data_table.id > 10 THEN DELETE";
+        let Statement::Merge(merge) = DeltaParser::parse_sql(sql).unwrap().remove(0).unwrap()
+        else {
+            panic!("Expected MERGE statement");
+        };
+        assert_eq!(merge.target_alias, None);
+        assert!(matches!(merge.source, TableFactor::Derived { .. }));
+        let actions = merge
+            .clauses
+            .iter()
+            .map(|clause| (clause.kind, clause.action.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![
+                (MergeClauseKind::Matched, MergeAction::UpdateAll),
+                (MergeClauseKind::NotMatched, MergeAction::InsertAll),
+                (MergeClauseKind::NotMatchedBySource, MergeAction::Delete),
+            ]
+        );
+
+        // Error cases
+        for sql in [
+            "MERGE INTO data_table USING source ON data_table.id = source.id",
+            "MERGE INTO data_table USING source ON true WHEN MATCHED THEN INSERT *",
+            "MERGE INTO data_table USING source ON true WHEN NOT MATCHED THEN DELETE",
+            "MERGE INTO data_table USING source ON true WHEN NOT MATCHED BY SOURCE THEN INSERT *",
+            "MERGE INTO data_table USING source ON true WHEN NOT MATCHED BY SOURCE THEN UPDATE SET *",
+            "MERGE INTO data_table USING source ON true WHEN NOT MATCHED THEN INSERT (id) VALUES (1, 2)",
+        ] {
+            assert!(DeltaParser::parse_sql(sql).is_err(), "{sql}");
+        }
+    }
+
+    fn expect_same_parse(sql: &str, expected_sql: &str) {
+        let expected = DeltaParser::parse_sql(expected_sql).unwrap();
+        let statements = DeltaParser::parse_sql(sql).unwrap();
+        assert_eq!(statements, expected, "{sql}");
+    }
+
+    #[test]
+    fn test_parse_time_travel() {
+        expect_same_parse(
+            "SELECT * FROM data_table VERSION AS OF 12",
+            "SELECT * FROM data_table@v12 AS data_table",
+        );
+        expect_same_parse(
+            "SELECT * FROM data_table TIMESTAMP AS OF '2024-01-01'",
+            "SELECT * FROM data_table@20240101000000000 AS data_table",
+        );
+        expect_same_parse(
+            "SELECT * FROM data_table TIMESTAMP AS OF '2024-01-01 10:20:30.5'",
+            "SELECT * FROM data_table@20240101102030500 AS data_table",
+        );
+        expect_same_parse(
+            "SELECT * FROM data_table TIMESTAMP AS OF '2024-01-01T12:00:00+02:00'",
+            "SELECT * FROM data_table@20240101100000000 AS data_table",
+        );
+        expect_same_parse(
+            "SELECT t.id FROM db.data_table VERSION AS OF 1 t JOIN other VERSION AS OF 2 AS o ON t.id = o.id",
+            "SELECT t.id FROM db.data_table@v1 t JOIN other@v2 AS o ON t.id = o.id",
+        );
+        expect_same_parse(
+            "SELECT * FROM \"Data\" VERSION AS OF 3 WHERE id > 1",
+            "SELECT * FROM \"Data@v3\" AS \"Data\" WHERE id > 1",
+        );
+
+        let statements =
+            DeltaParser::parse_sql("SELECT 1; SELECT * FROM data_table VERSION AS OF 12").unwrap();
+        assert_eq!(statements.len(), 2);
+
+        expect_same_parse(
+            "SELECT * FROM a AS x, (SELECT 1) s, db.b VERSION AS OF 1",
+            "SELECT * FROM a AS x, (SELECT 1) s, db.b@v1 AS b",
+        );
+        expect_same_parse(
+            "MERGE INTO t USING s VERSION AS OF 1 ON t.id = s.id WHEN MATCHED THEN DELETE",
+            "MERGE INTO t USING s@v1 AS s ON t.id = s.id WHEN MATCHED THEN DELETE",
+        );
+        // clauses not following a relation are not rewritten
+        expect_same_parse(
+            "SELECT version AS of FROM data_table",
+            "SELECT version AS of FROM data_table",
+        );
+        expect_same_parse(
+            "SELECT a FROM t UNION SELECT timestamp AS of FROM u",
+            "SELECT a FROM t UNION SELECT timestamp AS of FROM u",
+        );
+
+        assert!(DeltaParser::parse_sql("SELECT * FROM data_table VERSION AS OF 'a'").is_err());
+        assert!(DeltaParser::parse_sql("SELECT * FROM data_table TIMESTAMP AS OF 1").is_err());
+        assert!(DeltaParser::parse_sql("SELECT * FROM data_table TIMESTAMP AS OF 'a'").is_err());
+    }
+
+    #[test]
+    fn test_split_table_name() {
+        assert_eq!(
+            TableVersion::split_table_name("data_table@v12"),
+            Some(("data_table", TableVersion::Version(12)))
+        );
+        let timestamp = DateTime::parse_from_rfc3339("2024-01-01T10:20:30.500Z").unwrap();
+        assert_eq!(
+            TableVersion::split_table_name("data_table@20240101102030500"),
+            Some(("data_table", TableVersion::Timestamp(timestamp.into())))
+        );
+        assert_eq!(
+            TableVersion::Timestamp(timestamp.into()).to_string(),
+            "20240101102030500"
+        );
+        assert_eq!(TableVersion::split_table_name("data_table"), None);
+        assert_eq!(TableVersion::split_table_name("data_table@vx"), None);
+        assert_eq!(TableVersion::split_table_name("data_table@2024"), None);
+        assert_eq!(TableVersion::split_table_name("@v1"), None);
+    }
 }
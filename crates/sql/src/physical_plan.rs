@@ -0,0 +1,870 @@
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Duration;
+use datafusion::arrow::array::{
+    ArrayRef, BooleanBuilder, Int32Array, Int64Array, Int64Builder, ListBuilder, MapBuilder,
+    StringArray, StringBuilder, TimestampMillisecondArray, TimestampMillisecondBuilder,
+    UInt64Array,
+};
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::catalog::schema::SchemaProvider;
+use datafusion::datasource::TableProvider;
+use datafusion::execution::context::{QueryPlanner, SessionState, TaskContext};
+use datafusion::execution::SendableRecordBatchStream;
+use datafusion::physical_expr::EquivalenceProperties;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::{
+    DisplayAs, DisplayFormatType, ExecutionMode, ExecutionPlan, Partitioning, PlanProperties,
+};
+use datafusion::physical_planner::{DefaultPhysicalPlanner, ExtensionPlanner, PhysicalPlanner};
+use datafusion::prelude::DataFrame;
+use datafusion_common::{
+    plan_datafusion_err, plan_err, Column, DataFusionError, OwnedTableReference, Result as DFResult,
+};
+use datafusion_expr::{LogicalPlan, UserDefinedLogicalNode};
+use deltalake_core::delta_datafusion::{DeltaScanConfig, DeltaTableProvider};
+use deltalake_core::operations::delete::DeleteBuilder;
+use deltalake_core::operations::merge::MergeBuilder;
+use deltalake_core::operations::update::UpdateBuilder;
+use deltalake_core::operations::vacuum::VacuumBuilder;
+use deltalake_core::DeltaTable;
+use futures::stream;
+use serde_json::Value;
+
+use crate::context::delta_table;
+use crate::logical_plan::{
+    Delete, DeltaStatement, DescribeDetails, DescribeFiles, DescribeHistory, Merge, MergeOperation,
+    Update, Vacuum,
+};
+
+/// Query planner executing [`DeltaStatement`] extension nodes next to regular DataFusion
+/// plans.
+///
+/// Register it with [`SessionState::with_query_planner`] to execute the plans created by
+/// [`DeltaSqlToRel`](crate::planner::DeltaSqlToRel).
+pub struct DeltaQueryPlanner {}
+
+#[async_trait]
+impl QueryPlanner for DeltaQueryPlanner {
+    async fn create_physical_plan(
+        &self,
+        logical_plan: &LogicalPlan,
+        session_state: &SessionState,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let planner = DefaultPhysicalPlanner::with_extension_planners(vec![Arc::new(
+            DeltaExtensionPlanner {},
+        )]);
+        planner
+            .create_physical_plan(logical_plan, session_state)
+            .await
+    }
+}
+
+/// Plans [`DeltaStatement`] extension nodes into a [`DeltaStatementExec`] operating on the
+/// Delta table registered in the session.
+pub struct DeltaExtensionPlanner {}
+
+#[async_trait]
+impl ExtensionPlanner for DeltaExtensionPlanner {
+    async fn plan_extension(
+        &self,
+        _planner: &dyn PhysicalPlanner,
+        node: &dyn UserDefinedLogicalNode,
+        _logical_inputs: &[&LogicalPlan],
+        _physical_inputs: &[Arc<dyn ExecutionPlan>],
+        session_state: &SessionState,
+    ) -> DFResult<Option<Arc<dyn ExecutionPlan>>> {
+        let Some(statement) = node.as_any().downcast_ref::<DeltaStatement>() else {
+            return Ok(None);
+        };
+        let table = match statement {
+            DeltaStatement::Vacuum(Vacuum { table, .. })
+            | DeltaStatement::DescribeHistory(DescribeHistory { table, .. })
+            | DeltaStatement::DescribeDetails(DescribeDetails { table, .. })
+            | DeltaStatement::DescribeFiles(DescribeFiles { table, .. })
+            | DeltaStatement::Delete(Delete { table, .. })
+            | DeltaStatement::Update(Update { table, .. })
+            | DeltaStatement::Merge(Merge { table, .. }) => table,
+        };
+        let (schema_provider, name, provider) = registered_table(session_state, table).await?;
+        let Some(table) = delta_table(provider.as_ref()) else {
+            return plan_err!("Table '{table}' is not a Delta table");
+        };
+        let scan_config = provider
+            .as_any()
+            .downcast_ref::<DeltaTableProvider>()
+            .map(|provider| provider.config().clone());
+        Ok(Some(Arc::new(DeltaStatementExec {
+            properties: PlanProperties::new(
+                EquivalenceProperties::new(SchemaRef::new(statement.schema().as_ref().into())),
+                Partitioning::UnknownPartitioning(1),
+                ExecutionMode::Bounded,
+            ),
+            statement: statement.clone(),
+            table,
+            name,
+            schema_provider,
+            scan_config,
+            state: session_state.clone(),
+        })))
+    }
+}
+
+/// Look up the table registered in the session as `table`, returning the schema provider it
+/// is registered in, its name and the table.
+async fn registered_table(
+    state: &SessionState,
+    table: &OwnedTableReference,
+) -> DFResult<(Arc<dyn SchemaProvider>, String, Arc<dyn TableProvider>)> {
+    let catalog = &state.config_options().catalog;
+    let reference = table
+        .clone()
+        .resolve(&catalog.default_catalog, &catalog.default_schema);
+    let schema_provider = state
+        .catalog_list()
+        .catalog(&reference.catalog)
+        .and_then(|catalog| catalog.schema(&reference.schema))
+        .ok_or_else(|| plan_datafusion_err!("table '{table}' not found"))?;
+    let provider = schema_provider
+        .table(&reference.table)
+        .await?
+        .ok_or_else(|| plan_datafusion_err!("table '{table}' not found"))?;
+    Ok((schema_provider, reference.table.to_string(), provider))
+}
+
+/// Executes a [`DeltaStatement`] on a Delta table, returning the result of the statement
+/// as a single batch.
+///
+/// When the statement commits to the table, the table registered in the session is replaced
+/// by the updated table.
+pub struct DeltaStatementExec {
+    statement: DeltaStatement,
+    table: DeltaTable,
+    /// Name of the table in the schema provider
+    name: String,
+    schema_provider: Arc<dyn SchemaProvider>,
+    /// Scan configuration of the table if it is registered as a [`DeltaTableProvider`], which
+    /// the updated table is registered with
+    scan_config: Option<DeltaScanConfig>,
+    state: SessionState,
+    properties: PlanProperties,
+}
+
+impl Debug for DeltaStatementExec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "DeltaStatementExec: {}", self.statement.display())
+    }
+}
+
+impl DisplayAs for DeltaStatementExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut Formatter) -> fmt::Result {
+        write!(f, "DeltaStatementExec: {}", self.statement.display())
+    }
+}
+
+impl ExecutionPlan for DeltaStatementExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        &self.properties
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        if !children.is_empty() {
+            return plan_err!("Children cannot be replaced in DeltaStatementExec");
+        }
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> DFResult<SendableRecordBatchStream> {
+        if partition != 0 {
+            return plan_err!("DeltaStatementExec has a single partition, got {partition}");
+        }
+        let statement = self.statement.clone();
+        let table = self.table.clone();
+        let state = self.state.clone();
+        let schema = self.schema();
+        let schema_provider = self.schema_provider.clone();
+        let name = self.name.clone();
+        let scan_config = self.scan_config.clone();
+        let batch = async move {
+            let (table, batch) = execute_statement(statement, table, state, schema).await?;
+            // Schemas which don't support replacing tables load the latest version of the
+            // table themselves
+            if let Some(table) = table {
+                let provider: Arc<dyn TableProvider> = match scan_config {
+                    Some(config) => Arc::new(DeltaTableProvider::try_new(
+                        table.snapshot()?.clone(),
+                        table.log_store(),
+                        config,
+                    )?),
+                    None => Arc::new(table),
+                };
+                if schema_provider.deregister_table(&name).is_ok() {
+                    schema_provider.register_table(name, provider)?;
+                }
+            }
+            Ok(batch)
+        };
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            stream::once(batch),
+        )))
+    }
+}
+
+/// Execute a statement with the operation builders of the table, returning the updated
+/// table if the statement changed it and the result of the statement
+async fn execute_statement(
+    statement: DeltaStatement,
+    table: DeltaTable,
+    state: SessionState,
+    schema: SchemaRef,
+) -> DFResult<(Option<DeltaTable>, RecordBatch)> {
+    let snapshot = table.snapshot()?.clone();
+    let log_store = table.log_store();
+    // the builders expect the object store of the table in the runtime of a provided session
+    state.runtime_env().register_object_store(
+        log_store.object_store_url().as_ref(),
+        log_store.object_store(),
+    );
+    let (table, metrics) = match statement {
+        DeltaStatement::Vacuum(vacuum) => {
+            let mut builder = VacuumBuilder::new(log_store, snapshot).with_dry_run(vacuum.dry_run);
+            if let Some(hours) = vacuum.retention_hours {
+                builder = builder.with_retention_period(Duration::hours(hours.into()));
+            }
+            let (table, metrics) = builder.await?;
+            let files = StringArray::from(metrics.files_deleted);
+            let batch = RecordBatch::try_new(schema, vec![Arc::new(files)])?;
+            return Ok((Some(table), batch));
+        }
+        DeltaStatement::DescribeHistory(_) => {
+            return Ok((None, history_batch(&table, schema).await?));
+        }
+        DeltaStatement::DescribeDetails(_) => {
+            return Ok((None, details_batch(&table, schema)?));
+        }
+        DeltaStatement::DescribeFiles(_) => {
+            let files = snapshot.add_actions_table(true)?;
+            return Ok((None, project_files(&files, schema)?));
+        }
+        DeltaStatement::Delete(delete) => {
+            let mut builder = DeleteBuilder::new(log_store, snapshot).with_session_state(state);
+            if let Some(predicate) = delete.predicate {
+                builder = builder.with_predicate(predicate);
+            }
+            let (table, metrics) = builder.await?;
+            (table, serde_json::to_value(metrics))
+        }
+        DeltaStatement::Update(update) => {
+            let mut builder = UpdateBuilder::new(log_store, snapshot).with_session_state(state);
+            if let Some(predicate) = update.predicate {
+                builder = builder.with_predicate(predicate);
+            }
+            for (column, value) in update.assignments {
+                builder = builder.with_update(Column::new_unqualified(column), value);
+            }
+            let (table, metrics) = builder.await?;
+            (table, serde_json::to_value(metrics))
+        }
+        DeltaStatement::Merge(merge) => {
+            let source = DataFrame::new(state.clone(), merge.source.as_ref().clone());
+            let mut builder = MergeBuilder::new(log_store, snapshot, merge.predicate, source)
+                .with_source_alias(merge.source_alias)
+                .with_target_alias(merge.target_alias)
+                .with_session_state(state);
+            for operation in merge.operations {
+                builder = match operation {
+                    MergeOperation::MatchedUpdate {
+                        predicate,
+                        assignments,
+                    } => builder.when_matched_update(|mut update| {
+                        if let Some(predicate) = predicate {
+                            update = update.predicate(predicate);
+                        }
+                        for (column, value) in assignments {
+                            update = update.update(Column::new_unqualified(column), value);
+                        }
+                        update
+                    })?,
+                    MergeOperation::MatchedDelete { predicate } => {
+                        builder.when_matched_delete(|mut delete| {
+                            if let Some(predicate) = predicate {
+                                delete = delete.predicate(predicate);
+                            }
+                            delete
+                        })?
+                    }
+                    MergeOperation::NotMatchedInsert { predicate, values } => builder
+                        .when_not_matched_insert(|mut insert| {
+                            if let Some(predicate) = predicate {
+                                insert = insert.predicate(predicate);
+                            }
+                            for (column, value) in values {
+                                insert = insert.set(Column::new_unqualified(column), value);
+                            }
+                            insert
+                        })?,
+                    MergeOperation::NotMatchedBySourceUpdate {
+                        predicate,
+                        assignments,
+                    } => builder.when_not_matched_by_source_update(|mut update| {
+                        if let Some(predicate) = predicate {
+                            update = update.predicate(predicate);
+                        }
+                        for (column, value) in assignments {
+                            update = update.update(Column::new_unqualified(column), value);
+                        }
+                        update
+                    })?,
+                    MergeOperation::NotMatchedBySourceDelete { predicate } => builder
+                        .when_not_matched_by_source_delete(|mut delete| {
+                            if let Some(predicate) = predicate {
+                                delete = delete.predicate(predicate);
+                            }
+                            delete
+                        })?,
+                };
+            }
+            let (table, metrics) = builder.await?;
+            (table, serde_json::to_value(metrics))
+        }
+    };
+    let metrics = metrics.map_err(|err| DataFusionError::External(Box::new(err)))?;
+    Ok((Some(table), metrics_batch(schema, &metrics)?))
+}
+
+/// The commits of the table, latest first, with one row per commit
+async fn history_batch(table: &DeltaTable, schema: SchemaRef) -> DFResult<RecordBatch> {
+    let history = table.versioned_history(None).await?;
+    let mut versions = Int64Builder::with_capacity(history.len());
+    let mut timestamps = TimestampMillisecondBuilder::with_capacity(history.len());
+    let mut user_ids = StringBuilder::new();
+    let mut user_names = StringBuilder::new();
+    let mut operations = StringBuilder::new();
+    let mut operation_parameters =
+        MapBuilder::new(None, StringBuilder::new(), StringBuilder::new());
+    let mut read_versions = Int64Builder::with_capacity(history.len());
+    let mut isolation_levels = StringBuilder::new();
+    let mut blind_appends = BooleanBuilder::with_capacity(history.len());
+    let mut operation_metrics = MapBuilder::new(None, StringBuilder::new(), StringBuilder::new());
+    let mut user_metadata = StringBuilder::new();
+    let mut engine_infos = StringBuilder::new();
+
+    // commits without commit info are listed with empty fields
+    for (version, commit) in history {
+        let commit = commit.unwrap_or_default();
+        versions.append_value(version);
+        timestamps.append_option(commit.timestamp);
+        user_ids.append_option(commit.user_id);
+        user_names.append_option(commit.user_name);
+        operations.append_option(commit.operation);
+        append_json_map(
+            &mut operation_parameters,
+            commit.operation_parameters.as_ref(),
+        )?;
+        read_versions.append_option(commit.read_version);
+        isolation_levels.append_option(commit.isolation_level.as_ref().map(AsRef::as_ref));
+        blind_appends.append_option(commit.is_blind_append);
+        let metrics = match commit.info.get("operationMetrics") {
+            Some(Value::Object(metrics)) => Some(metrics.clone().into_iter().collect()),
+            _ => None,
+        };
+        append_json_map(&mut operation_metrics, metrics.as_ref())?;
+        user_metadata.append_option(commit.info.get("userMetadata").and_then(Value::as_str));
+        engine_infos.append_option(commit.engine_info);
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(versions.finish()),
+        Arc::new(timestamps.finish().with_timezone("UTC")),
+        Arc::new(user_ids.finish()),
+        Arc::new(user_names.finish()),
+        Arc::new(operations.finish()),
+        Arc::new(operation_parameters.finish()),
+        Arc::new(read_versions.finish()),
+        Arc::new(isolation_levels.finish()),
+        Arc::new(blind_appends.finish()),
+        Arc::new(operation_metrics.finish()),
+        Arc::new(user_metadata.finish()),
+        Arc::new(engine_infos.finish()),
+    ];
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// Append a map of JSON values, with strings unquoted, or null if there is no map
+fn append_json_map(
+    builder: &mut MapBuilder<StringBuilder, StringBuilder>,
+    map: Option<&HashMap<String, Value>>,
+) -> DFResult<()> {
+    let Some(map) = map else {
+        return Ok(builder.append(false)?);
+    };
+    for (key, value) in map.iter().collect::<BTreeMap<_, _>>() {
+        builder.keys().append_value(key);
+        match value {
+            Value::Null => builder.values().append_null(),
+            Value::String(value) => builder.values().append_value(value),
+            value => builder.values().append_value(value.to_string()),
+        }
+    }
+    Ok(builder.append(true)?)
+}
+
+/// The details of the table as a single row
+fn details_batch(table: &DeltaTable, schema: SchemaRef) -> DFResult<RecordBatch> {
+    let snapshot = table.snapshot()?;
+    let metadata = snapshot.metadata();
+    let protocol = snapshot.protocol();
+
+    let mut partition_columns = ListBuilder::new(StringBuilder::new());
+    partition_columns.append_value(metadata.partition_columns.iter().map(Some));
+    let mut properties = MapBuilder::new(None, StringBuilder::new(), StringBuilder::new());
+    for (key, value) in metadata.configuration.iter().collect::<BTreeMap<_, _>>() {
+        properties.keys().append_value(key);
+        properties.values().append_option(value.as_ref());
+    }
+    properties.append(true)?;
+    let size: i64 = snapshot
+        .log_data()
+        .into_iter()
+        .map(|file| file.size())
+        .sum();
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(vec!["delta"])),
+        Arc::new(StringArray::from(vec![metadata.id.clone()])),
+        Arc::new(StringArray::from(vec![metadata.name.clone()])),
+        Arc::new(StringArray::from(vec![metadata.description.clone()])),
+        Arc::new(StringArray::from(vec![table.table_uri()])),
+        Arc::new(TimestampMillisecondArray::from(vec![metadata.created_time]).with_timezone("UTC")),
+        Arc::new(partition_columns.finish()),
+        Arc::new(StringArray::from(vec![metadata.schema_string.clone()])),
+        Arc::new(Int64Array::from(vec![snapshot.files_count() as i64])),
+        Arc::new(Int64Array::from(vec![size])),
+        Arc::new(properties.finish()),
+        Arc::new(Int32Array::from(vec![protocol.min_reader_version])),
+        Arc::new(Int32Array::from(vec![protocol.min_writer_version])),
+    ];
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// Project the files of the table onto the schema DESCRIBE FILES was planned with.
+///
+/// The columns of the files depend on the files of the table, e.g. statistics are only
+/// listed if a file has them, so the table may have changed since the statement was planned.
+fn project_files(files: &RecordBatch, schema: SchemaRef) -> DFResult<RecordBatch> {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match files.column_by_name(field.name()) {
+            Some(column) if column.data_type() == field.data_type() => Ok(column.clone()),
+            Some(column) => Err(DataFusionError::Execution(format!(
+                "The files of the table no longer match the planned schema of DESCRIBE FILES, \
+                 column '{}' changed from {} to {}, plan the statement again",
+                field.name(),
+                field.data_type(),
+                column.data_type()
+            ))),
+            None => Err(DataFusionError::Execution(format!(
+                "The files of the table no longer match the planned schema of DESCRIBE FILES, \
+                 column '{}' is missing, plan the statement again",
+                field.name()
+            ))),
+        })
+        .collect::<DFResult<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// A batch with a single row holding the metrics in `schema`
+fn metrics_batch(schema: SchemaRef, metrics: &Value) -> DFResult<RecordBatch> {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let value = metrics.get(field.name()).and_then(Value::as_u64);
+            Arc::new(UInt64Array::from(vec![value])) as ArrayRef
+        })
+        .collect();
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Int32Array, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use datafusion::datasource::MemTable;
+    use datafusion::execution::runtime_env::RuntimeEnv;
+    use datafusion::prelude::{SessionConfig, SessionContext};
+    use datafusion::{assert_batches_eq, assert_batches_sorted_eq};
+    use deltalake_core::kernel::{DataType as DeltaDataType, StructField};
+    use deltalake_core::table::config::DeltaConfigKey;
+    use deltalake_core::{DeltaOps, ObjectStore, Path};
+
+    use super::*;
+    use crate::context::DeltaContextProvider;
+    use crate::parser::DeltaParser;
+    use crate::planner::DeltaSqlToRel;
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, true),
+            Field::new("value", DataType::Utf8, true),
+        ]))
+    }
+
+    fn batch(ids: Vec<i32>, values: Vec<&str>) -> RecordBatch {
+        RecordBatch::try_new(
+            schema(),
+            vec![
+                Arc::new(Int32Array::from(ids)),
+                Arc::new(StringArray::from(values)),
+            ],
+        )
+        .unwrap()
+    }
+
+    async fn setup() -> SessionContext {
+        let table = DeltaOps::new_in_memory()
+            .create()
+            .with_columns(vec![
+                StructField::new("id", DeltaDataType::INTEGER, true),
+                StructField::new("value", DeltaDataType::STRING, true),
+            ])
+            .with_configuration_property(
+                DeltaConfigKey::DeletedFileRetentionDuration,
+                Some("interval 0 hours"),
+            )
+            .await
+            .unwrap();
+        let table = DeltaOps(table)
+            .write(vec![batch(vec![1, 2, 3], vec!["a", "b", "c"])])
+            .await
+            .unwrap();
+
+        let state = SessionState::new_with_config_rt(
+            SessionConfig::default(),
+            Arc::new(RuntimeEnv::default()),
+        )
+        .with_query_planner(Arc::new(DeltaQueryPlanner {}));
+        let ctx = SessionContext::new_with_state(state);
+        ctx.register_table("data", Arc::new(table)).unwrap();
+        ctx
+    }
+
+    async fn query(ctx: &SessionContext, sql: &str) -> DFResult<Vec<RecordBatch>> {
+        let state = ctx.state();
+        let statement = DeltaParser::parse_sql(sql)?.pop_front().unwrap();
+        let provider = DeltaContextProvider::try_new(&state, &statement).await?;
+        let plan = DeltaSqlToRel::new(&provider).statement_to_plan(statement)?;
+        ctx.execute_logical_plan(plan).await?.collect().await
+    }
+
+    async fn assert_data(ctx: &SessionContext, expected: &[&str]) {
+        let batches = query(ctx, "SELECT * FROM data").await.unwrap();
+        assert_batches_sorted_eq!(expected, &batches);
+    }
+
+    #[tokio::test]
+    async fn test_update_delete() {
+        let ctx = setup().await;
+
+        let batches = query(&ctx, "UPDATE data SET value = 'x' WHERE id = 1")
+            .await
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        let updated = batches[0]
+            .column_by_name("num_updated_rows")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        assert_eq!(updated.value(0), 1);
+
+        let batches = query(&ctx, "DELETE FROM data WHERE id = 2").await.unwrap();
+        let deleted = batches[0]
+            .column_by_name("num_deleted_rows")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        assert_eq!(deleted.value(0), 1);
+
+        let expected = [
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 1  | x     |",
+            "| 3  | c     |",
+            "+----+-------+",
+        ];
+        assert_data(&ctx, &expected).await;
+    }
+
+    #[tokio::test]
+    async fn test_merge() {
+        let ctx = setup().await;
+        let source = MemTable::try_new(
+            schema(),
+            vec![vec![batch(vec![2, 3, 4], vec!["B", "C", "D"])]],
+        )
+        .unwrap();
+        ctx.register_table("source", Arc::new(source)).unwrap();
+
+        let batches = query(
+            &ctx,
+            "MERGE INTO data t USING source s ON t.id = s.id \
+             WHEN MATCHED AND s.id = 3 THEN DELETE \
+             WHEN MATCHED THEN UPDATE SET value = s.value \
+             WHEN NOT MATCHED THEN INSERT * \
+             WHEN NOT MATCHED BY SOURCE THEN UPDATE SET value = 'z'",
+        )
+        .await
+        .unwrap();
+        let metrics = &batches[0];
+        let metric = |name: &str| {
+            metrics
+                .column_by_name(name)
+                .unwrap()
+                .as_any()
+                .downcast_ref::<UInt64Array>()
+                .unwrap()
+                .value(0)
+        };
+        assert_eq!(metric("num_source_rows"), 3);
+        assert_eq!(metric("num_target_rows_inserted"), 1);
+        assert_eq!(metric("num_target_rows_updated"), 2);
+        assert_eq!(metric("num_target_rows_deleted"), 1);
+
+        let expected = [
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 1  | z     |",
+            "| 2  | B     |",
+            "| 4  | D     |",
+            "+----+-------+",
+        ];
+        assert_data(&ctx, &expected).await;
+    }
+
+    #[tokio::test]
+    async fn test_merge_quoted_alias() {
+        let ctx = setup().await;
+        let source = MemTable::try_new(schema(), vec![vec![batch(vec![2], vec!["B"])]]).unwrap();
+        ctx.register_table("source", Arc::new(source)).unwrap();
+
+        query(
+            &ctx,
+            "MERGE INTO data AS \"T\" USING source s ON \"T\".id = s.id \
+             WHEN MATCHED THEN UPDATE SET value = s.value",
+        )
+        .await
+        .unwrap();
+        let expected = [
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 1  | a     |",
+            "| 2  | B     |",
+            "| 3  | c     |",
+            "+----+-------+",
+        ];
+        assert_data(&ctx, &expected).await;
+    }
+
+    #[tokio::test]
+    async fn test_vacuum() {
+        let ctx = setup().await;
+        // rewriting the data file removes the previous one
+        query(&ctx, "DELETE FROM data WHERE id = 1").await.unwrap();
+
+        let batches = query(&ctx, "VACUUM data RETAIN 0 HOURS DRY RUN")
+            .await
+            .unwrap();
+        assert_eq!(batches[0].num_rows(), 1);
+        let batches = query(&ctx, "VACUUM data RETAIN 0 HOURS").await.unwrap();
+        assert_eq!(batches[0].num_rows(), 1);
+        let batches = query(&ctx, "VACUUM data RETAIN 0 HOURS").await.unwrap();
+        assert_eq!(batches[0].num_rows(), 0);
+
+        let expected = [
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 2  | b     |",
+            "| 3  | c     |",
+            "+----+-------+",
+        ];
+        assert_data(&ctx, &expected).await;
+    }
+
+    #[tokio::test]
+    async fn test_describe() {
+        let ctx = setup().await;
+
+        let batches = query(&ctx, "DESCRIBE HISTORY data").await.unwrap();
+        let history = batches[0].project(&[0, 4]).unwrap();
+        let expected = [
+            "+---------+--------------+",
+            "| version | operation    |",
+            "+---------+--------------+",
+            "| 1       | WRITE        |",
+            "| 0       | CREATE TABLE |",
+            "+---------+--------------+",
+        ];
+        assert_batches_eq!(&expected, &[history]);
+
+        let batches = query(&ctx, "DESCRIBE DETAIL data").await.unwrap();
+        let details = batches[0].project(&[0, 6, 8, 10, 11, 12]).unwrap();
+        let expected = [
+            "+--------+------------------+----------+--------------------------------------------------------+------------------+------------------+",
+            "| format | partitionColumns | numFiles | properties                                             | minReaderVersion | minWriterVersion |",
+            "+--------+------------------+----------+--------------------------------------------------------+------------------+------------------+",
+            "| delta  | []               | 1        | {delta.deletedFileRetentionDuration: interval 0 hours} | 1                | 2                |",
+            "+--------+------------------+----------+--------------------------------------------------------+------------------+------------------+",
+        ];
+        assert_batches_eq!(&expected, &[details]);
+
+        let batches = query(&ctx, "DESCRIBE FILES data").await.unwrap();
+        let schema = batches[0].schema();
+        let files = batches[0]
+            .project(&[
+                schema.index_of("num_records").unwrap(),
+                schema.index_of("min.id").unwrap(),
+                schema.index_of("max.id").unwrap(),
+            ])
+            .unwrap();
+        let expected = [
+            "+-------------+--------+--------+",
+            "| num_records | min.id | max.id |",
+            "+-------------+--------+--------+",
+            "| 3           | 1      | 3      |",
+            "+-------------+--------+--------+",
+        ];
+        assert_batches_eq!(&expected, &[files]);
+    }
+
+    #[tokio::test]
+    async fn test_describe_history_without_commit_info() {
+        let ctx = setup().await;
+        let mut table = delta_table(ctx.table_provider("data").await.unwrap().as_ref()).unwrap();
+        // a commit written by a client which doesn't record commit info
+        table
+            .object_store()
+            .put(
+                &Path::from("_delta_log/00000000000000000002.json"),
+                r#"{"txn":{"appId":"app","version":1}}"#.into(),
+            )
+            .await
+            .unwrap();
+        table.update().await.unwrap();
+        table = DeltaOps(table)
+            .write(vec![batch(vec![4], vec!["d"])])
+            .await
+            .unwrap();
+        ctx.deregister_table("data").unwrap();
+        ctx.register_table("data", Arc::new(table)).unwrap();
+
+        let batches = query(&ctx, "DESCRIBE HISTORY data").await.unwrap();
+        let history = batches[0].project(&[0, 4]).unwrap();
+        let expected = [
+            "+---------+--------------+",
+            "| version | operation    |",
+            "+---------+--------------+",
+            "| 3       | WRITE        |",
+            "| 2       |              |",
+            "| 1       | WRITE        |",
+            "| 0       | CREATE TABLE |",
+            "+---------+--------------+",
+        ];
+        assert_batches_eq!(&expected, &[history]);
+    }
+
+    #[tokio::test]
+    async fn test_describe_files_changed() {
+        let ctx = setup().await;
+        let state = ctx.state();
+        let statement = DeltaParser::parse_sql("DESCRIBE FILES data")
+            .unwrap()
+            .pop_front()
+            .unwrap();
+        let provider = DeltaContextProvider::try_new(&state, &statement)
+            .await
+            .unwrap();
+        let plan = DeltaSqlToRel::new(&provider)
+            .statement_to_plan(statement)
+            .unwrap();
+
+        // removing the only file drops the statistics columns listed when planning
+        query(&ctx, "DELETE FROM data").await.unwrap();
+        let err = ctx
+            .execute_logical_plan(plan)
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("plan the statement again"));
+    }
+
+    #[tokio::test]
+    async fn test_table_provider() {
+        let ctx = setup().await;
+        let table = delta_table(ctx.table_provider("data").await.unwrap().as_ref()).unwrap();
+        let provider = DeltaTableProvider::try_new(
+            table.snapshot().unwrap().clone(),
+            table.log_store(),
+            DeltaScanConfig::default(),
+        )
+        .unwrap();
+        ctx.deregister_table("data").unwrap();
+        ctx.register_table("data", Arc::new(provider)).unwrap();
+
+        query(&ctx, "DELETE FROM data WHERE id = 2").await.unwrap();
+        let registered = ctx.table_provider("data").await.unwrap();
+        assert!(registered
+            .as_any()
+            .downcast_ref::<DeltaTableProvider>()
+            .is_some());
+        let expected = [
+            "+----+-------+",
+            "| id | value |",
+            "+----+-------+",
+            "| 1  | a     |",
+            "| 3  | c     |",
+            "+----+-------+",
+        ];
+        assert_data(&ctx, &expected).await;
+
+        let batches = query(&ctx, "DESCRIBE FILES data").await.unwrap();
+        assert_eq!(batches[0].num_rows(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_not_a_delta_table() {
+        let ctx = setup().await;
+        let source = MemTable::try_new(schema(), vec![vec![]]).unwrap();
+        ctx.register_table("source", Arc::new(source)).unwrap();
+        assert!(query(&ctx, "DELETE FROM source").await.is_err());
+    }
+}
@@ -1,20 +1,35 @@
 use std::sync::Arc;
 
-use datafusion_common::{OwnedTableReference, Result as DFResult};
+use crate::context::delta_table;
+use crate::logical_plan::{
+    Delete, DeltaStatement, DescribeDetails, DescribeFiles, DescribeHistory, Merge, MergeOperation,
+    Update, Vacuum,
+};
+use crate::parser::{
+    DeleteStatement, DescribeOperation, DescribeStatement, MergeAction, MergeClauseKind,
+    MergeStatement, Statement, UpdateStatement, VacuumStatement,
+};
+use datafusion::datasource::source_as_provider;
+use datafusion::optimizer::simplify_expressions::{ExprSimplifier, SimplifyContext};
+use datafusion_common::{plan_err, Column, DFSchema, OwnedTableReference, Result as DFResult};
+use datafusion_expr::execution_props::ExecutionProps;
+use datafusion_expr::expr_rewriter::unnormalize_col;
 use datafusion_expr::logical_plan::{Extension, LogicalPlan};
+use datafusion_expr::Expr;
 use datafusion_sql::planner::{
-    object_name_to_table_reference, ContextProvider, IdentNormalizer, ParserOptions, SqlToRel,
+    object_name_to_table_reference, ContextProvider, IdentNormalizer, ParserOptions,
+    PlannerContext, SqlToRel,
+};
+use datafusion_sql::sqlparser::ast::{
+    Assignment, Expr as SQLExpr, GroupByExpr, ObjectName, Query, Select, SelectItem, SetExpr,
+    Statement as SQLStatement, TableAlias, TableFactor, TableWithJoins, WildcardAdditionalOptions,
 };
-use datafusion_sql::sqlparser::ast::ObjectName;
-
-use crate::logical_plan::{DeltaStatement, DescribeFiles, Vacuum};
-use crate::parser::{DescribeStatement, Statement, VacuumStatement};
 
 /// Delta SQL query planner
 pub struct DeltaSqlToRel<'a, S: ContextProvider> {
     pub(crate) context_provider: &'a S,
     pub(crate) options: ParserOptions,
-    pub(crate) _normalizer: IdentNormalizer,
+    pub(crate) normalizer: IdentNormalizer,
 }
 
 impl<'a, S: ContextProvider> DeltaSqlToRel<'a, S> {
@@ -29,28 +44,32 @@ impl<'a, S: ContextProvider> DeltaSqlToRel<'a, S> {
         DeltaSqlToRel {
             context_provider: schema_provider,
             options,
-            _normalizer: IdentNormalizer::new(normalize),
+            normalizer: IdentNormalizer::new(normalize),
         }
     }
 
     /// Generate a logical plan from an Delta SQL statement
     pub fn statement_to_plan(&self, statement: Statement) -> DFResult<LogicalPlan> {
         match statement {
-            Statement::Datafusion(s) => {
-                let planner = SqlToRel::new_with_options(
-                    self.context_provider,
-                    ParserOptions {
-                        parse_float_as_decimal: self.options.parse_float_as_decimal,
-                        enable_ident_normalization: self.options.enable_ident_normalization,
-                    },
-                );
-                planner.statement_to_plan(s)
-            }
+            Statement::Datafusion(s) => self.sql_to_rel().statement_to_plan(s),
             Statement::Describe(describe) => self.describe_to_plan(describe),
             Statement::Vacuum(vacuum) => self.vacuum_to_plan(vacuum),
+            Statement::Delete(delete) => self.delete_to_plan(delete),
+            Statement::Update(update) => self.update_to_plan(update),
+            Statement::Merge(merge) => self.merge_to_plan(merge),
         }
     }
 
+    fn sql_to_rel(&self) -> SqlToRel<'a, S> {
+        SqlToRel::new_with_options(
+            self.context_provider,
+            ParserOptions {
+                parse_float_as_decimal: self.options.parse_float_as_decimal,
+                enable_ident_normalization: self.options.enable_ident_normalization,
+            },
+        )
+    }
+
     fn vacuum_to_plan(&self, vacuum: VacuumStatement) -> DFResult<LogicalPlan> {
         let table_ref = self.object_name_to_table_reference(vacuum.table)?;
         let plan = DeltaStatement::Vacuum(Vacuum::new(
@@ -65,13 +84,285 @@ impl<'a, S: ContextProvider> DeltaSqlToRel<'a, S> {
 
     fn describe_to_plan(&self, describe: DescribeStatement) -> DFResult<LogicalPlan> {
         let table_ref = self.object_name_to_table_reference(describe.table)?;
-        let plan =
-            DeltaStatement::DescribeFiles(DescribeFiles::new(table_ref.to_owned_reference()));
+        let plan = match describe.operation {
+            DescribeOperation::History => {
+                DeltaStatement::DescribeHistory(DescribeHistory::new(table_ref))
+            }
+            DescribeOperation::Detail => {
+                DeltaStatement::DescribeDetails(DescribeDetails::new(table_ref))
+            }
+            DescribeOperation::Files => {
+                // the columns of the file listing depend on the partition columns and
+                // statistics of the table
+                let source = self.context_provider.get_table_source(table_ref.clone())?;
+                let provider = source_as_provider(&source)?;
+                let Some(table) = delta_table(provider.as_ref()) else {
+                    return plan_err!("Table '{table_ref}' is not a Delta table");
+                };
+                let files = table.snapshot()?.add_actions_table(true)?;
+                DeltaStatement::DescribeFiles(DescribeFiles::try_new(
+                    table_ref,
+                    files.schema().as_ref().clone(),
+                )?)
+            }
+        };
+        Ok(LogicalPlan::Extension(Extension {
+            node: Arc::new(plan),
+        }))
+    }
+
+    fn delete_to_plan(&self, delete: DeleteStatement) -> DFResult<LogicalPlan> {
+        let table_ref = self.object_name_to_table_reference(delete.table)?;
+        let schema = self.table_schema(
+            &table_ref,
+            delete.alias.map(|alias| self.normalizer.normalize(alias)),
+        )?;
+        let predicate = delete
+            .predicate
+            .map(|predicate| self.table_expr(predicate, &schema))
+            .transpose()?;
+        let plan = DeltaStatement::Delete(Delete::new(table_ref, predicate));
+        Ok(LogicalPlan::Extension(Extension {
+            node: Arc::new(plan),
+        }))
+    }
+
+    fn update_to_plan(&self, update: UpdateStatement) -> DFResult<LogicalPlan> {
+        let table_ref = self.object_name_to_table_reference(update.table)?;
+        let schema = self.table_schema(
+            &table_ref,
+            update.alias.map(|alias| self.normalizer.normalize(alias)),
+        )?;
+        let assignments = update
+            .assignments
+            .into_iter()
+            .map(|assignment| {
+                let column = self.assigned_column(&assignment, &schema)?;
+                Ok((column, self.table_expr(assignment.value, &schema)?))
+            })
+            .collect::<DFResult<_>>()?;
+        let predicate = update
+            .predicate
+            .map(|predicate| self.table_expr(predicate, &schema))
+            .transpose()?;
+        let plan = DeltaStatement::Update(Update::new(table_ref, assignments, predicate));
+        Ok(LogicalPlan::Extension(Extension {
+            node: Arc::new(plan),
+        }))
+    }
+
+    fn merge_to_plan(&self, merge: MergeStatement) -> DFResult<LogicalPlan> {
+        let table_ref = self.object_name_to_table_reference(merge.target)?;
+        let target_alias = match merge.target_alias {
+            Some(alias) => self.normalizer.normalize(alias),
+            None => table_ref.table().to_string(),
+        };
+        let target_schema = self.table_schema(&table_ref, Some(target_alias.clone()))?;
+        let (source, source_alias) = self.merge_source_to_plan(merge.source)?;
+        let schema = target_schema.join(source.schema())?;
+
+        // all columns of the target, set from the source column of the same name
+        let all_columns = || {
+            target_schema
+                .fields()
+                .iter()
+                .map(|field| {
+                    let column = Column::new(Some(source_alias.clone()), field.name());
+                    if !source.schema().has_column(&column) {
+                        return plan_err!("MERGE source has no column {}", field.name());
+                    }
+                    Ok((field.name().clone(), Expr::Column(column)))
+                })
+                .collect::<DFResult<Vec<_>>>()
+        };
+        let assignments = |assignments: Vec<Assignment>| {
+            assignments
+                .into_iter()
+                .map(|assignment| {
+                    let column = self.assigned_column(&assignment, &target_schema)?;
+                    Ok((column, self.merge_expr(assignment.value, &schema)?))
+                })
+                .collect::<DFResult<Vec<_>>>()
+        };
+
+        let mut operations = Vec::with_capacity(merge.clauses.len());
+        for clause in merge.clauses {
+            let predicate = clause
+                .predicate
+                .map(|predicate| self.merge_expr(predicate, &schema))
+                .transpose()?;
+            let operation = match (clause.kind, clause.action) {
+                (MergeClauseKind::Matched, MergeAction::Update(values)) => {
+                    MergeOperation::MatchedUpdate {
+                        predicate,
+                        assignments: assignments(values)?,
+                    }
+                }
+                (MergeClauseKind::Matched, MergeAction::UpdateAll) => {
+                    MergeOperation::MatchedUpdate {
+                        predicate,
+                        assignments: all_columns()?,
+                    }
+                }
+                (MergeClauseKind::Matched, MergeAction::Delete) => {
+                    MergeOperation::MatchedDelete { predicate }
+                }
+                (MergeClauseKind::NotMatched, MergeAction::Insert { columns, values }) => {
+                    let values = columns
+                        .into_iter()
+                        .zip(values)
+                        .map(|(column, value)| {
+                            let column = self.normalizer.normalize(column);
+                            target_schema.field_with_unqualified_name(&column)?;
+                            Ok((column, self.merge_expr(value, &schema)?))
+                        })
+                        .collect::<DFResult<_>>()?;
+                    MergeOperation::NotMatchedInsert { predicate, values }
+                }
+                (MergeClauseKind::NotMatched, MergeAction::InsertAll) => {
+                    MergeOperation::NotMatchedInsert {
+                        predicate,
+                        values: all_columns()?,
+                    }
+                }
+                (MergeClauseKind::NotMatchedBySource, MergeAction::Update(values)) => {
+                    MergeOperation::NotMatchedBySourceUpdate {
+                        predicate,
+                        assignments: assignments(values)?,
+                    }
+                }
+                (MergeClauseKind::NotMatchedBySource, MergeAction::Delete) => {
+                    MergeOperation::NotMatchedBySourceDelete { predicate }
+                }
+                (kind, action) => {
+                    return plan_err!("Unsupported action {action:?} for {kind:?} clause of MERGE")
+                }
+            };
+            operations.push(operation);
+        }
+
+        let predicate = self.merge_expr(merge.on, &schema)?;
+        let plan = DeltaStatement::Merge(Merge::new(
+            table_ref,
+            target_alias,
+            source,
+            source_alias,
+            predicate,
+            operations,
+        ));
         Ok(LogicalPlan::Extension(Extension {
             node: Arc::new(plan),
         }))
     }
 
+    /// Plan the source of a merge, returning its plan and alias. Source tables without
+    /// alias are aliased by their name.
+    fn merge_source_to_plan(&self, mut source: TableFactor) -> DFResult<(LogicalPlan, String)> {
+        let alias = match &mut source {
+            TableFactor::Table { name, alias, .. } => {
+                let Some(table) = name.0.last().cloned() else {
+                    return plan_err!("Invalid MERGE source {name}");
+                };
+                alias
+                    .get_or_insert_with(|| TableAlias {
+                        name: table,
+                        columns: vec![],
+                    })
+                    .name
+                    .clone()
+            }
+            TableFactor::Derived {
+                alias: Some(alias), ..
+            } => alias.name.clone(),
+            TableFactor::Derived { alias: None, .. } => {
+                return plan_err!("MERGE source subquery requires an alias")
+            }
+            source => return plan_err!("Unsupported MERGE source {source}"),
+        };
+
+        let select = Select {
+            distinct: None,
+            top: None,
+            projection: vec![SelectItem::Wildcard(WildcardAdditionalOptions::default())],
+            into: None,
+            from: vec![TableWithJoins {
+                relation: source,
+                joins: vec![],
+            }],
+            lateral_views: vec![],
+            selection: None,
+            group_by: GroupByExpr::Expressions(vec![]),
+            cluster_by: vec![],
+            distribute_by: vec![],
+            sort_by: vec![],
+            having: None,
+            named_window: vec![],
+            qualify: None,
+            value_table_mode: None,
+        };
+        let query = Query {
+            with: None,
+            body: Box::new(SetExpr::Select(Box::new(select))),
+            order_by: vec![],
+            limit: None,
+            limit_by: vec![],
+            offset: None,
+            fetch: None,
+            locks: vec![],
+            for_clause: None,
+        };
+        let plan = self
+            .sql_to_rel()
+            .sql_statement_to_plan(SQLStatement::Query(Box::new(query)))?;
+        Ok((plan, self.normalizer.normalize(alias)))
+    }
+
+    /// Schema of a table with columns qualified by the normalized alias of the table or its name
+    fn table_schema(
+        &self,
+        table_ref: &OwnedTableReference,
+        alias: Option<String>,
+    ) -> DFResult<DFSchema> {
+        let source = self.context_provider.get_table_source(table_ref.clone())?;
+        match alias {
+            // the alias is already normalized, parsing it as a table reference would normalize
+            // it again
+            Some(alias) => DFSchema::try_from_qualified_schema(
+                OwnedTableReference::bare(alias),
+                &source.schema(),
+            ),
+            None => DFSchema::try_from_qualified_schema(table_ref.clone(), &source.schema()),
+        }
+    }
+
+    /// Name of the column of `schema` set by an assignment
+    fn assigned_column(&self, assignment: &Assignment, schema: &DFSchema) -> DFResult<String> {
+        let Some(column) = assignment.id.last().cloned() else {
+            return plan_err!("Invalid assignment {assignment}");
+        };
+        let column = self.normalizer.normalize(column);
+        schema.field_with_unqualified_name(&column)?;
+        Ok(column)
+    }
+
+    /// Plan an expression on the columns of a table, the columns are unqualified as
+    /// operations only operate on a single table
+    fn table_expr(&self, expr: SQLExpr, schema: &DFSchema) -> DFResult<Expr> {
+        Ok(unnormalize_col(self.merge_expr(expr, schema)?))
+    }
+
+    /// Plan an expression, coercing its types to the types of the columns it refers to
+    fn merge_expr(&self, expr: SQLExpr, schema: &DFSchema) -> DFResult<Expr> {
+        let expr = self
+            .sql_to_rel()
+            .sql_to_expr(expr, schema, &mut PlannerContext::new())?;
+        let schema = Arc::new(schema.clone());
+        let props = ExecutionProps::new();
+        let simplifier =
+            ExprSimplifier::new(SimplifyContext::new(&props).with_schema(schema.clone()));
+        simplifier.coerce(expr, schema)
+    }
+
     pub(crate) fn object_name_to_table_reference(
         &self,
         object_name: ObjectName,
@@ -113,6 +404,14 @@ mod tests {
                     false,
                 )]),
             );
+            tables.insert(
+                "table1@v1".to_string(),
+                create_table_source(vec![Field::new(
+                    "column1".to_string(),
+                    DataType::Utf8,
+                    false,
+                )]),
+            );
 
             Self {
                 options: Default::default(),
@@ -192,6 +491,15 @@ mod tests {
             &["Projection: table1.column1", "  TableScan: table1"],
         );
 
+        test_statement(
+            "SELECT table1.column1 FROM table1 VERSION AS OF 1",
+            &[
+                "Projection: table1.column1",
+                "  SubqueryAlias: table1",
+                "    TableScan: table1@v1",
+            ],
+        );
+
         test_statement("VACUUM table1", &["Vacuum: table1 dry_run=false"]);
         test_statement("VACUUM table1 DRY RUN", &["Vacuum: table1 dry_run=true"]);
         test_statement(
@@ -199,4 +507,53 @@ mod tests {
             &["Vacuum: table1 retention_hours=1234 dry_run=false"],
         );
     }
+
+    #[test]
+    fn test_plan_describe() {
+        test_statement("DESCRIBE HISTORY table1", &["DescribeHistory: table1"]);
+        test_statement("DESCRIBE DETAIL table1", &["DescribeDetails: table1"]);
+
+        // the files of tables which aren't Delta tables cannot be described
+        let cp = TestSchemaProvider::new();
+        let statement = DeltaParser::parse_sql("DESCRIBE FILES table1")
+            .unwrap()
+            .pop_front()
+            .unwrap();
+        assert!(DeltaSqlToRel::new(&cp)
+            .statement_to_plan(statement)
+            .is_err());
+    }
+
+    #[test]
+    fn test_plan_dml() {
+        test_statement(
+            "DELETE FROM table1 WHERE column1 = 'a'",
+            &["Delete: table1 predicate=column1 = Utf8(\"a\")"],
+        );
+        test_statement(
+            "UPDATE table1 SET column1 = 'b' WHERE column1 = 'a'",
+            &["Update: table1 set=[column1 = Utf8(\"b\")] predicate=column1 = Utf8(\"a\")"],
+        );
+        test_statement(
+            "MERGE INTO table1 t USING table1@v1 s ON t.column1 = s.column1 \
+             WHEN MATCHED THEN DELETE WHEN NOT MATCHED THEN INSERT *",
+            &[
+                "Merge: table1 target_alias=t source_alias=s predicate=t.column1 = s.column1 operations=2",
+                "  Projection: s.column1",
+                "    SubqueryAlias: s",
+                "      TableScan: table1@v1",
+            ],
+        );
+        // quoted aliases keep their case
+        test_statement(
+            "MERGE INTO table1 AS \"T\" USING table1@v1 s ON \"T\".column1 = s.column1 \
+             WHEN MATCHED THEN DELETE",
+            &[
+                "Merge: table1 target_alias=T source_alias=s predicate=T.column1 = s.column1 operations=1",
+                "  Projection: s.column1",
+                "    SubqueryAlias: s",
+                "      TableScan: table1@v1",
+            ],
+        );
+    }
 }